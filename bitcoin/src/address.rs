@@ -1,7 +1,8 @@
 use crate::format::BitcoinFormat;
 use crate::network::BitcoinNetwork;
 use crate::private_key::BitcoinPrivateKey;
-use crate::public_key::BitcoinPublicKey;
+use crate::public_key::{BitcoinPublicKey, PublicKeyPolicy};
+use crate::schnorr::{to_x_only, tweak_public_key};
 use crate::witness_program::WitnessProgram;
 use gyu_model::no_std::*;
 use gyu_model::{
@@ -41,8 +42,21 @@ impl<N: BitcoinNetwork> Address for BitcoinAddress<N> {
                     String::from("p2wsh address"),
                 ))
             }
+            BitcoinFormat::P2SH => {
+                return Err(AddressError::IncompatibleFormats(
+                    String::from("non-script"),
+                    String::from("p2sh address"),
+                ))
+            }
+            BitcoinFormat::P2SH_P2WSH => {
+                return Err(AddressError::IncompatibleFormats(
+                    String::from("non-script"),
+                    String::from("p2sh_p2wsh address"),
+                ))
+            }
             BitcoinFormat::P2SH_P2WPKH => Self::p2sh_p2wpkh(&public_key),
             BitcoinFormat::Bech32 => Self::bech32(&public_key),
+            BitcoinFormat::P2TR => Self::p2tr(&public_key),
         }
     }
 
@@ -58,8 +72,21 @@ impl<N: BitcoinNetwork> Address for BitcoinAddress<N> {
                     String::from("p2wsh address"),
                 ))
             }
+            BitcoinFormat::P2SH => {
+                return Err(AddressError::IncompatibleFormats(
+                    String::from("non-script"),
+                    String::from("p2sh address"),
+                ))
+            }
+            BitcoinFormat::P2SH_P2WSH => {
+                return Err(AddressError::IncompatibleFormats(
+                    String::from("non-script"),
+                    String::from("p2sh_p2wsh address"),
+                ))
+            }
             BitcoinFormat::P2SH_P2WPKH => Self::p2sh_p2wpkh(public_key),
             BitcoinFormat::Bech32 => Self::bech32(public_key),
+            BitcoinFormat::P2TR => Self::p2tr(public_key),
         }
     }
 }
@@ -112,8 +139,45 @@ impl<N: BitcoinNetwork> BitcoinAddress<N> {
         })
     }
 
+    /// Returns a P2SH address from a given Bitcoin redeem script.
+    pub fn p2sh(redeem_script: &Vec<u8>) -> Result<Self, AddressError> {
+        let mut address = [0u8; 25];
+        address[0] = N::to_address_prefix(&BitcoinFormat::P2SH)[0];
+        address[1..21].copy_from_slice(&hash160(redeem_script));
+
+        let sum = &checksum(&address[0..21])[0..4];
+        address[21..25].copy_from_slice(sum);
+
+        Ok(Self {
+            address: address.to_base58(),
+            format: BitcoinFormat::P2SH,
+            _network: PhantomData,
+        })
+    }
+
+    /// Returns a P2SH_P2WSH address from a given Bitcoin witness script.
+    pub fn p2sh_p2wsh(witness_script: &Vec<u8>) -> Result<Self, AddressError> {
+        let mut redeem_script = vec![0x00, 0x20];
+        redeem_script.extend(Sha256::digest(witness_script));
+
+        let mut address = [0u8; 25];
+        address[0] = N::to_address_prefix(&BitcoinFormat::P2SH_P2WSH)[0];
+        address[1..21].copy_from_slice(&hash160(&redeem_script));
+
+        let sum = &checksum(&address[0..21])[0..4];
+        address[21..25].copy_from_slice(sum);
+
+        Ok(Self {
+            address: address.to_base58(),
+            format: BitcoinFormat::P2SH_P2WSH,
+            _network: PhantomData,
+        })
+    }
+
     /// Returns a P2SH_P2WPKH address from a given Bitcoin public key.
     pub fn p2sh_p2wpkh(public_key: &<Self as Address>::PublicKey) -> Result<Self, AddressError> {
+        public_key.check_policy(&PublicKeyPolicy::SEGWIT)?;
+
         let mut address = [0u8; 25];
         address[0] = N::to_address_prefix(&BitcoinFormat::P2SH_P2WPKH)[0];
         address[1..21].copy_from_slice(&hash160(&Self::create_redeem_script(public_key)));
@@ -130,6 +194,8 @@ impl<N: BitcoinNetwork> BitcoinAddress<N> {
 
     /// Returns a Bech32 address from a given Bitcoin public key.
     pub fn bech32(public_key: &<Self as Address>::PublicKey) -> Result<Self, AddressError> {
+        public_key.check_policy(&PublicKeyPolicy::SEGWIT)?;
+
         let redeem_script = Self::create_redeem_script(public_key);
         let version = u5::try_from_u8(redeem_script[0])?;
 
@@ -148,6 +214,48 @@ impl<N: BitcoinNetwork> BitcoinAddress<N> {
         })
     }
 
+    /// Returns a P2TR address in Bech32m format from a given Bitcoin public key, committing
+    /// to the key with no script path as in BIP86.
+    pub fn p2tr(public_key: &<Self as Address>::PublicKey) -> Result<Self, AddressError> {
+        let internal_key = to_x_only(&public_key.to_secp256k1_public_key());
+        let (output_key, _) = tweak_public_key(&internal_key, None)?;
+        Self::p2tr_output_key(&output_key)
+    }
+
+    /// Returns a P2TR address in Bech32m format from a given x-only output key.
+    pub fn p2tr_output_key(output_key: &[u8; 32]) -> Result<Self, AddressError> {
+        let mut data = vec![u5::try_from_u8(1)?];
+        data.extend_from_slice(&output_key.to_vec().to_base32());
+
+        Ok(Self {
+            address: encode_bech32m(
+                &String::from_utf8(N::to_address_prefix(&BitcoinFormat::P2TR))?,
+                &data,
+            )?,
+            format: BitcoinFormat::P2TR,
+            _network: PhantomData,
+        })
+    }
+
+    /// Returns the witness program of a segwit address.
+    pub fn witness_program(&self) -> Result<WitnessProgram, AddressError> {
+        let data = match self.format {
+            BitcoinFormat::P2TR => decode_bech32m(&self.address)?.1,
+            BitcoinFormat::P2WSH | BitcoinFormat::Bech32 => {
+                Bech32::from_str(&self.address)?.data().to_vec()
+            }
+            _ => return Err(AddressError::InvalidAddress(self.address.clone())),
+        };
+        if data.is_empty() {
+            return Err(AddressError::InvalidAddress(self.address.clone()));
+        }
+
+        let mut program = Vec::from_base32(&data[1..])?;
+        let mut bytes = vec![data[0].to_u8(), program.len() as u8];
+        bytes.append(&mut program);
+        Ok(WitnessProgram::new(&bytes)?)
+    }
+
     /// Returns the format of the Bitcoin address.
     pub fn format(&self) -> BitcoinFormat {
         self.format.clone()
@@ -184,24 +292,36 @@ impl<N: BitcoinNetwork> FromStr for BitcoinAddress<N> {
 
         if let Ok(format) = BitcoinFormat::from_address_prefix(prefix.as_bytes()) {
             if BitcoinFormat::Bech32 == format {
-                let bech32 = Bech32::from_str(&address)?;
-                if bech32.data().is_empty() {
+                // Version 0 programs use Bech32 and later versions use Bech32m (BIP350)
+                let (data, is_bech32m) = match Bech32::from_str(&address) {
+                    Ok(bech32) => (bech32.data().to_vec(), false),
+                    Err(error) => match decode_bech32m(&address) {
+                        Ok((_, data)) => (data, true),
+                        Err(_) => return Err(error.into()),
+                    },
+                };
+                if data.is_empty() {
                     return Err(AddressError::InvalidAddress(address.to_owned()));
                 }
 
-                let data = bech32.data();
                 let version = data[0].to_u8();
                 let mut program = Vec::from_base32(&data[1..])?;
 
                 let mut data = vec![version, program.len() as u8];
                 data.append(&mut program);
 
-                let _ = WitnessProgram::new(data.as_slice())?;
+                let witness_program = WitnessProgram::new(data.as_slice())?;
                 let _ = N::from_address_prefix(prefix.as_bytes())?;
 
+                let format = match (version, is_bech32m, witness_program.program.len()) {
+                    (0, false, _) => BitcoinFormat::Bech32,
+                    (1, true, 32) => BitcoinFormat::P2TR,
+                    _ => return Err(AddressError::InvalidAddress(address.to_owned())),
+                };
+
                 return Ok(Self {
                     address: address.to_owned(),
-                    format: BitcoinFormat::Bech32,
+                    format,
                     _network: PhantomData,
                 });
             }
@@ -224,6 +344,95 @@ impl<N: BitcoinNetwork> FromStr for BitcoinAddress<N> {
     }
 }
 
+/// The Bech32 character set
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// The Bech32m checksum constant (BIP350)
+const BECH32M_CONSTANT: u32 = 0x2bc8_30a3;
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = (checksum & 0x01ff_ffff) << 5 ^ *value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn bech32_hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut expanded: Vec<u8> = hrp.bytes().map(|byte| byte >> 5).collect();
+    expanded.push(0);
+    expanded.extend(hrp.bytes().map(|byte| byte & 0x1f));
+    expanded
+}
+
+/// Returns the Bech32m encoding of the data with the human-readable part.
+pub(crate) fn encode_bech32m(hrp: &str, data: &[u5]) -> Result<String, AddressError> {
+    let mut values = bech32_hrp_expand(hrp);
+    values.extend(data.iter().map(|value| value.to_u8()));
+    values.extend(&[0u8; 6]);
+    let checksum = bech32_polymod(&values) ^ BECH32M_CONSTANT;
+
+    let mut encoded = format!("{}1", hrp);
+    for value in data {
+        encoded.push(BECH32_CHARSET[value.to_u8() as usize] as char);
+    }
+    for i in 0..6 {
+        encoded.push(BECH32_CHARSET[((checksum >> (5 * (5 - i))) & 0x1f) as usize] as char);
+    }
+    Ok(encoded)
+}
+
+/// Returns the human-readable part and data of a Bech32m string.
+pub(crate) fn decode_bech32m(encoded: &str) -> Result<(String, Vec<u5>), AddressError> {
+    let invalid = || AddressError::InvalidAddress(encoded.to_owned());
+    if encoded.to_lowercase() != encoded && encoded.to_uppercase() != encoded {
+        return Err(invalid());
+    }
+    let encoded_lowercase = encoded.to_lowercase();
+
+    let separator = encoded_lowercase.rfind('1').ok_or_else(invalid)?;
+    let (hrp, data) = (
+        &encoded_lowercase[..separator],
+        &encoded_lowercase[separator + 1..],
+    );
+    if hrp.is_empty() || data.len() < 6 {
+        return Err(invalid());
+    }
+
+    let mut values = vec![];
+    for character in data.bytes() {
+        match BECH32_CHARSET.iter().position(|c| *c == character) {
+            Some(value) => values.push(value as u8),
+            None => return Err(invalid()),
+        }
+    }
+
+    let mut checksum_values = bech32_hrp_expand(hrp);
+    checksum_values.extend(&values);
+    if bech32_polymod(&checksum_values) != BECH32M_CONSTANT {
+        return Err(invalid());
+    }
+
+    let data = values[..values.len() - 6]
+        .iter()
+        .map(|value| u5::try_from_u8(*value))
+        .collect::<Result<Vec<u5>, _>>()?;
+    Ok((hrp.to_owned(), data))
+}
+
 impl<N: BitcoinNetwork> fmt::Display for BitcoinAddress<N> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.address)
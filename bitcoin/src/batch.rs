@@ -0,0 +1,459 @@
+use crate::address::BitcoinAddress;
+use crate::derivation_path::BitcoinDerivationPath;
+use crate::extended_private_key::BitcoinExtendedPrivateKey;
+use crate::extended_public_key::BitcoinExtendedPublicKey;
+use crate::format::BitcoinFormat;
+use crate::network::BitcoinNetwork;
+use crate::public_key::BitcoinPublicKey;
+use crate::transaction::create_script_pub_key;
+use gyu_model::no_std::*;
+use gyu_model::{
+    address::{Address, AddressError},
+    derivation_path::{ChildIndex, DerivationPath, DerivationPathError},
+    extended_private_key::{ExtendedPrivateKey, ExtendedPrivateKeyError},
+    extended_public_key::{ExtendedPublicKey, ExtendedPublicKeyError},
+    transaction::TransactionError,
+};
+
+use core::{marker::PhantomData, ops::Range};
+use hmac::{Hmac, Mac};
+use secp256k1::SecretKey;
+use sha2::Sha512;
+use std::collections::BTreeMap;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+type HmacSha512 = Hmac<Sha512>;
+
+#[derive(Debug, Fail)]
+pub enum BatchError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "{}", _0)]
+    DerivationPathError(DerivationPathError),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPrivateKeyError(ExtendedPrivateKeyError),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPublicKeyError(ExtendedPublicKeyError),
+
+    #[fail(display = "invalid index range: {}..{}", _0, _1)]
+    InvalidRange(u32, u32),
+
+    #[fail(display = "the path {} needs an extended private key", _0)]
+    MissingExtendedPrivateKey(String),
+
+    #[fail(display = "{}", _0)]
+    TransactionError(TransactionError),
+}
+
+impl From<AddressError> for BatchError {
+    fn from(error: AddressError) -> Self {
+        BatchError::AddressError(error)
+    }
+}
+
+impl From<DerivationPathError> for BatchError {
+    fn from(error: DerivationPathError) -> Self {
+        BatchError::DerivationPathError(error)
+    }
+}
+
+impl From<ExtendedPrivateKeyError> for BatchError {
+    fn from(error: ExtendedPrivateKeyError) -> Self {
+        BatchError::ExtendedPrivateKeyError(error)
+    }
+}
+
+impl From<ExtendedPublicKeyError> for BatchError {
+    fn from(error: ExtendedPublicKeyError) -> Self {
+        BatchError::ExtendedPublicKeyError(error)
+    }
+}
+
+impl From<TransactionError> for BatchError {
+    fn from(error: TransactionError) -> Self {
+        BatchError::TransactionError(error)
+    }
+}
+
+/// The options of a batch derivation
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOptions {
+    /// The format of the derived addresses.
+    pub format: BitcoinFormat,
+    /// Derives the addresses on all cores. Has no effect without the `parallel` feature.
+    pub parallel: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            format: BitcoinFormat::P2PKH,
+            parallel: false,
+        }
+    }
+}
+
+/// An address derived in a batch, with its output script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchAddress<N: BitcoinNetwork> {
+    /// The normal child index of the address under the common parent.
+    pub index: u32,
+    pub address: BitcoinAddress<N>,
+    pub script_pub_key: Vec<u8>,
+}
+
+/// Derives many keys under a root extended key, caching the intermediate keys so that each
+/// parent is only derived once. Steps after the last hardened index of a path use public
+/// derivation, so a deriver over an extended public key serves any normal path.
+#[derive(Debug, Clone)]
+pub struct BatchDeriver<N: BitcoinNetwork> {
+    root_private_key: Option<BitcoinExtendedPrivateKey<N>>,
+    root_public_key: BitcoinExtendedPublicKey<N>,
+    private_keys: BTreeMap<Vec<ChildIndex>, BitcoinExtendedPrivateKey<N>>,
+    public_keys: BTreeMap<Vec<ChildIndex>, BitcoinExtendedPublicKey<N>>,
+}
+
+impl<N: BitcoinNetwork> BatchDeriver<N> {
+    pub fn from_extended_private_key(extended_private_key: &BitcoinExtendedPrivateKey<N>) -> Self {
+        Self {
+            root_private_key: Some(extended_private_key.clone()),
+            root_public_key: extended_private_key.to_extended_public_key(),
+            private_keys: BTreeMap::new(),
+            public_keys: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_extended_public_key(extended_public_key: &BitcoinExtendedPublicKey<N>) -> Self {
+        Self {
+            root_private_key: None,
+            root_public_key: extended_public_key.clone(),
+            private_keys: BTreeMap::new(),
+            public_keys: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of cached intermediate keys.
+    pub fn cached(&self) -> usize {
+        self.private_keys.len() + self.public_keys.len()
+    }
+
+    /// Drops the cached intermediate keys.
+    pub fn clear(&mut self) {
+        self.private_keys.clear();
+        self.public_keys.clear();
+    }
+
+    /// Returns the extended private key at the path from the root, deriving it from its
+    /// deepest cached ancestor.
+    pub fn extended_private_key(
+        &mut self,
+        path: &[ChildIndex],
+    ) -> Result<BitcoinExtendedPrivateKey<N>, BatchError> {
+        let root = match &self.root_private_key {
+            Some(root) => root,
+            None => return Err(BatchError::MissingExtendedPrivateKey(to_string(path))),
+        };
+        let start = (1..=path.len())
+            .rev()
+            .find(|end| self.private_keys.contains_key(&path[..*end]))
+            .unwrap_or(0);
+        let mut extended_private_key = match start {
+            0 => root.clone(),
+            _ => self.private_keys[&path[..start]].clone(),
+        };
+        for end in start + 1..=path.len() {
+            extended_private_key = extended_private_key.derive(&BitcoinDerivationPath::BIP32(
+                vec![path[end - 1]],
+                PhantomData,
+            ))?;
+            self.private_keys
+                .insert(path[..end].to_vec(), extended_private_key.clone());
+        }
+        Ok(extended_private_key)
+    }
+
+    /// Returns the extended public key at the path from the root, deriving it from its
+    /// deepest cached ancestor, and publicly after the last hardened index.
+    pub fn extended_public_key(
+        &mut self,
+        path: &[ChildIndex],
+    ) -> Result<BitcoinExtendedPublicKey<N>, BatchError> {
+        let hardened = path
+            .iter()
+            .rposition(ChildIndex::is_hardened)
+            .map_or(0, |position| position + 1);
+        let cached = (hardened.max(1)..=path.len())
+            .rev()
+            .find(|end| self.public_keys.contains_key(&path[..*end]));
+
+        let (start, mut extended_public_key) = match cached {
+            Some(end) => (end, self.public_keys[&path[..end]].clone()),
+            None if hardened == 0 => (0, self.root_public_key.clone()),
+            None => {
+                let extended_public_key = self
+                    .extended_private_key(&path[..hardened])?
+                    .to_extended_public_key();
+                self.public_keys
+                    .insert(path[..hardened].to_vec(), extended_public_key.clone());
+                (hardened, extended_public_key)
+            }
+        };
+        for end in start + 1..=path.len() {
+            extended_public_key = extended_public_key.derive(&BitcoinDerivationPath::BIP32(
+                vec![path[end - 1]],
+                PhantomData,
+            ))?;
+            self.public_keys
+                .insert(path[..end].to_vec(), extended_public_key.clone());
+        }
+        Ok(extended_public_key)
+    }
+
+    /// Returns the addresses of the normal children in the index range of the key at the
+    /// parent path, as in m/84'/0'/0'/0 for the receive addresses of an account.
+    pub fn derive_addresses(
+        &mut self,
+        parent: &BitcoinDerivationPath<N>,
+        indices: Range<u32>,
+        options: &BatchOptions,
+    ) -> Result<Vec<BatchAddress<N>>, BatchError> {
+        let parent = self.extended_public_key(&parent.to_vec()?)?;
+        derive_address_range(&parent, indices, options)
+    }
+}
+
+/// Returns the addresses of the normal children in the index range of the extended public
+/// key. The parent is hashed once, and each child costs one HMAC and one point addition.
+pub fn derive_address_range<N: BitcoinNetwork>(
+    parent: &BitcoinExtendedPublicKey<N>,
+    indices: Range<u32>,
+    options: &BatchOptions,
+) -> Result<Vec<BatchAddress<N>>, BatchError> {
+    if indices.start > indices.end || indices.end > 1 << 31 {
+        return Err(BatchError::InvalidRange(indices.start, indices.end));
+    }
+    if parent.depth() == 255 {
+        return Err(ExtendedPublicKeyError::MaximumChildDepthReached(parent.depth()).into());
+    }
+
+    let parent_public_key = parent.to_public_key().to_secp256k1_public_key();
+    let mut parent_mac =
+        HmacSha512::new_varkey(&parent.chain_code()).map_err(ExtendedPublicKeyError::from)?;
+    parent_mac.input(&parent_public_key.serialize_compressed());
+
+    let derive = |index: u32| -> Result<BatchAddress<N>, BatchError> {
+        // HMAC-SHA512(Key = cpar, Data = serP(Kpar) || ser32(i))
+        let mut mac = parent_mac.clone();
+        mac.input(&index.to_be_bytes());
+        let hmac = mac.result().code();
+
+        let mut public_key = parent_public_key.clone();
+        public_key
+            .tweak_add_assign(
+                &SecretKey::parse_slice(&hmac[..32]).map_err(ExtendedPublicKeyError::from)?,
+            )
+            .map_err(ExtendedPublicKeyError::from)?;
+        let public_key = BitcoinPublicKey::<N>::from_secp256k1_public_key(public_key, true);
+
+        let address = BitcoinAddress::from_public_key(&public_key, &options.format)?;
+        let script_pub_key = create_script_pub_key(&address)?;
+        Ok(BatchAddress {
+            index,
+            address,
+            script_pub_key,
+        })
+    };
+
+    #[cfg(feature = "parallel")]
+    {
+        if options.parallel {
+            return indices.into_par_iter().map(derive).collect();
+        }
+    }
+    indices.map(derive).collect()
+}
+
+/// Returns the path relative to the root of a batch deriver.
+fn to_string(path: &[ChildIndex]) -> String {
+    path.iter().fold(String::from("m"), |path, index| {
+        format!("{}/{}", path, index)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+    use crate::test_vectors::master;
+
+    use core::str::FromStr;
+
+    type N = Mainnet;
+
+    fn path(path: &str) -> BitcoinDerivationPath<N> {
+        BitcoinDerivationPath::from_str(path).unwrap()
+    }
+
+    fn bech32() -> BatchOptions {
+        BatchOptions {
+            format: BitcoinFormat::Bech32,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn derive_address_range() {
+        let receive = master(&BitcoinFormat::P2PKH)
+            .derive(&path("m/84'/0'/0'/0"))
+            .unwrap()
+            .to_extended_public_key();
+        let addresses = super::derive_address_range(&receive, 0..2, &bech32()).unwrap();
+        assert_eq!(
+            vec![
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+                "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g",
+            ],
+            addresses
+                .iter()
+                .map(|batch_address| batch_address.address.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "0014c0cebcd6c3d3ca8c75dc5ec62ebe55330ef910e2",
+            hex::encode(&addresses[0].script_pub_key)
+        );
+
+        // Each address matches a full derivation of its path.
+        for format in [
+            BitcoinFormat::P2PKH,
+            BitcoinFormat::P2SH_P2WPKH,
+            BitcoinFormat::P2TR,
+        ]
+        .iter()
+        {
+            let options = BatchOptions {
+                format: format.clone(),
+                ..Default::default()
+            };
+            let addresses = super::derive_address_range(&receive, 5..9, &options).unwrap();
+            assert_eq!(4, addresses.len());
+            for batch_address in addresses.iter() {
+                let child = receive
+                    .derive(&BitcoinDerivationPath::BIP32(
+                        vec![ChildIndex::Normal(batch_address.index)],
+                        PhantomData,
+                    ))
+                    .unwrap();
+                assert_eq!(child.to_address(format).unwrap(), batch_address.address);
+                assert_eq!(
+                    create_script_pub_key(&batch_address.address).unwrap(),
+                    batch_address.script_pub_key
+                );
+            }
+        }
+
+        assert!(super::derive_address_range(&receive, 3..3, &bech32())
+            .unwrap()
+            .is_empty());
+        let reversed = core::ops::Range { start: 3, end: 2 };
+        assert!(super::derive_address_range(&receive, reversed, &bech32()).is_err());
+        assert!(super::derive_address_range(&receive, 0..(1 << 31) + 1, &bech32()).is_err());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel() {
+        let receive = master(&BitcoinFormat::P2PKH)
+            .derive(&path("m/84'/0'/0'/0"))
+            .unwrap()
+            .to_extended_public_key();
+        let options = BatchOptions {
+            parallel: true,
+            ..bech32()
+        };
+        assert_eq!(
+            super::derive_address_range(&receive, 0..50, &bech32()).unwrap(),
+            super::derive_address_range(&receive, 0..50, &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn deriver_caches_parents() {
+        let master = master(&BitcoinFormat::P2PKH);
+        let mut deriver = BatchDeriver::from_extended_private_key(&master);
+
+        let receive = deriver
+            .derive_addresses(&path("m/84'/0'/0'/0"), 0..3, &bech32())
+            .unwrap();
+        assert_eq!(
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            receive[0].address.to_string()
+        );
+        // m/84', m/84'/0' and m/84'/0'/0' privately, then m/84'/0'/0' and m/84'/0'/0'/0
+        // publicly.
+        assert_eq!(5, deriver.cached());
+
+        let change = deriver
+            .derive_addresses(&path("m/84'/0'/0'/1"), 0..1, &bech32())
+            .unwrap();
+        assert_eq!(
+            "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el",
+            change[0].address.to_string()
+        );
+        assert_eq!(6, deriver.cached());
+
+        let indices = path("m/84'/0'/0'/0/2").to_vec().unwrap();
+        // The deriver keeps the format of the root, so compare the keys.
+        assert_eq!(
+            master
+                .derive(&path("m/84'/0'/0'/0/2"))
+                .unwrap()
+                .to_private_key(),
+            deriver
+                .extended_private_key(&indices)
+                .unwrap()
+                .to_private_key()
+        );
+        assert_eq!(
+            receive[2].address,
+            deriver
+                .extended_public_key(&indices)
+                .unwrap()
+                .to_address(&BitcoinFormat::Bech32)
+                .unwrap()
+        );
+
+        deriver.clear();
+        assert_eq!(0, deriver.cached());
+    }
+
+    #[test]
+    fn public_deriver() {
+        let master = master(&BitcoinFormat::P2PKH);
+        let account = master
+            .derive(&path("m/84'/0'/0'"))
+            .unwrap()
+            .to_extended_public_key();
+        let mut deriver = BatchDeriver::from_extended_public_key(&account);
+
+        let addresses = deriver
+            .derive_addresses(&path("m/1"), 0..1, &bech32())
+            .unwrap();
+        assert_eq!(
+            "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el",
+            addresses[0].address.to_string()
+        );
+
+        let hardened = [ChildIndex::Hardened(0), ChildIndex::Normal(0)];
+        assert!(deriver.extended_public_key(&hardened).is_err());
+        assert!(deriver.extended_private_key(&[]).is_err());
+    }
+}
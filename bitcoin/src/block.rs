@@ -0,0 +1,881 @@
+use crate::network::BitcoinNetwork;
+use crate::transaction::{variable_length_integer, BitcoinTransaction, BitcoinVector};
+use core::{fmt, str::FromStr};
+use gyu_model::no_std::{io::Read, *};
+use gyu_model::transaction::{Transaction, TransactionError};
+use sha2::{Digest, Sha256};
+
+/// The maximum number of transactions that fit in a block (max block weight / min transaction weight)
+const MAX_BLOCK_TRANSACTIONS: u32 = 4_000_000 / 240;
+
+/// The script prefix of the coinbase output committing to the witness data of a block
+const WITNESS_COMMITMENT_HEADER: [u8; 6] = [0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+
+#[derive(Debug, Fail)]
+pub enum BlockError {
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "block hash {} does not meet the target {}", _0, _1)]
+    InsufficientProofOfWork(String, String),
+
+    #[fail(display = "invalid compact target: {:#010x}", _0)]
+    InvalidCompactTarget(u32),
+
+    #[fail(
+        display = "invalid merkle root: {{ expected: {:?}, found: {:?} }}",
+        _0, _1
+    )]
+    InvalidMerkleRoot(String, String),
+
+    #[fail(display = "invalid partial merkle tree: {}", _0)]
+    InvalidPartialMerkleTree(String),
+
+    #[fail(
+        display = "invalid witness commitment: {{ expected: {:?}, found: {:?} }}",
+        _0, _1
+    )]
+    InvalidWitnessCommitment(String, String),
+
+    #[fail(display = "block has no coinbase transaction")]
+    MissingCoinbase,
+
+    #[fail(display = "coinbase is missing the witness reserved value")]
+    MissingWitnessReservedValue,
+
+    #[fail(display = "{} trailing bytes after the end of the data", _0)]
+    TrailingBytes(usize),
+
+    #[fail(display = "{}", _0)]
+    TransactionError(TransactionError),
+
+    #[fail(display = "transaction {} is not included in the block", _0)]
+    TransactionNotIncluded(String),
+}
+
+impl From<gyu_model::no_std::io::Error> for BlockError {
+    fn from(error: gyu_model::no_std::io::Error) -> Self {
+        BlockError::Crate("crate::no_std::io", format!("{:?}", error))
+    }
+}
+
+impl From<TransactionError> for BlockError {
+    fn from(error: TransactionError) -> Self {
+        BlockError::TransactionError(error)
+    }
+}
+
+impl From<hex::FromHexError> for BlockError {
+    fn from(error: hex::FromHexError) -> Self {
+        BlockError::Crate("hex", format!("{:?}", error))
+    }
+}
+
+/// Returns the double SHA256 hash of the given data.
+fn double_sha256(data: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Sha256::digest(&Sha256::digest(data)));
+    hash
+}
+
+/// Returns the hash of two merkle tree nodes.
+fn merkle_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut data = [0u8; 64];
+    data[0..32].copy_from_slice(left);
+    data[32..64].copy_from_slice(right);
+    double_sha256(&data)
+}
+
+/// Returns the merkle root of the given hashes in internal byte order,
+/// duplicating the last hash of each odd-length level.
+pub fn merkle_root(hashes: &[[u8; 32]]) -> Option<[u8; 32]> {
+    if hashes.is_empty() {
+        return None;
+    }
+
+    let mut level = hashes.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| merkle_node(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+    }
+    Some(level[0])
+}
+
+/// Converts a hash in display (big-endian) byte order to internal byte order, or vice versa.
+fn reverse_hash(hash: &[u8]) -> Result<[u8; 32], BlockError> {
+    if hash.len() != 32 {
+        return Err(TransactionError::InvalidTransactionId(hash.len()).into());
+    }
+    let mut reversed = [0u8; 32];
+    reversed.copy_from_slice(hash);
+    reversed.reverse();
+    Ok(reversed)
+}
+
+/// Reads a value that spans all of the given bytes, rejecting any bytes left over.
+fn read_all<T>(
+    mut bytes: &[u8],
+    read: impl FnOnce(&mut &[u8]) -> Result<T, BlockError>,
+) -> Result<T, BlockError> {
+    let value = read(&mut bytes)?;
+    match bytes.len() {
+        0 => Ok(value),
+        length => Err(BlockError::TrailingBytes(length)),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BitcoinBlockHeader {
+    pub version: u32,
+    /// The hash of the previous block header in internal byte order
+    pub previous_block_hash: [u8; 32],
+    /// The merkle root of the block transactions in internal byte order
+    pub merkle_root: [u8; 32],
+    pub time: u32,
+    /// The proof-of-work target in compact form
+    pub bits: u32,
+    pub nonce: u32,
+}
+
+impl BitcoinBlockHeader {
+    pub const SIZE: usize = 80;
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, BlockError> {
+        let mut version = [0u8; 4];
+        let mut previous_block_hash = [0u8; 32];
+        let mut merkle_root = [0u8; 32];
+        let mut time = [0u8; 4];
+        let mut bits = [0u8; 4];
+        let mut nonce = [0u8; 4];
+
+        reader.read_exact(&mut version)?;
+        reader.read_exact(&mut previous_block_hash)?;
+        reader.read_exact(&mut merkle_root)?;
+        reader.read_exact(&mut time)?;
+        reader.read_exact(&mut bits)?;
+        reader.read_exact(&mut nonce)?;
+
+        Ok(Self {
+            version: u32::from_le_bytes(version),
+            previous_block_hash,
+            merkle_root,
+            time: u32::from_le_bytes(time),
+            bits: u32::from_le_bytes(bits),
+            nonce: u32::from_le_bytes(nonce),
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(Self::SIZE);
+        header.extend(&self.version.to_le_bytes());
+        header.extend(&self.previous_block_hash);
+        header.extend(&self.merkle_root);
+        header.extend(&self.time.to_le_bytes());
+        header.extend(&self.bits.to_le_bytes());
+        header.extend(&self.nonce.to_le_bytes());
+        header
+    }
+
+    /// Returns the block hash in display (big-endian) byte order.
+    pub fn to_block_hash(&self) -> Vec<u8> {
+        let mut hash = double_sha256(&self.serialize()).to_vec();
+        hash.reverse();
+        hash
+    }
+
+    /// Returns the proof-of-work target encoded by `bits` as a 256-bit big-endian number.
+    pub fn target(&self) -> Result<[u8; 32], BlockError> {
+        let exponent = (self.bits >> 24) as usize;
+        let mantissa = self.bits & 0x007f_ffff;
+
+        // The sign bit is set or the target overflows 256 bits
+        if self.bits & 0x0080_0000 != 0
+            || (mantissa != 0
+                && (exponent > 34
+                    || (mantissa > 0xff && exponent > 33)
+                    || (mantissa > 0xffff && exponent > 32)))
+        {
+            return Err(BlockError::InvalidCompactTarget(self.bits));
+        }
+
+        let mut target = [0u8; 32];
+        for (i, byte) in mantissa.to_be_bytes()[1..].iter().enumerate() {
+            // The mantissa byte i is worth 256^(exponent - 1 - i)
+            if exponent > i && exponent - 1 - i < 32 {
+                target[31 - (exponent - 1 - i)] = *byte;
+            }
+        }
+
+        if target == [0u8; 32] {
+            return Err(BlockError::InvalidCompactTarget(self.bits));
+        }
+        Ok(target)
+    }
+
+    /// Checks that the block hash does not exceed the target of the header.
+    pub fn check_proof_of_work(&self) -> Result<(), BlockError> {
+        let target = self.target()?;
+        let hash = self.to_block_hash();
+        match hash.as_slice() <= &target[..] {
+            true => Ok(()),
+            false => Err(BlockError::InsufficientProofOfWork(
+                hex::encode(&hash),
+                hex::encode(target),
+            )),
+        }
+    }
+}
+
+impl FromStr for BitcoinBlockHeader {
+    type Err = BlockError;
+
+    fn from_str(header: &str) -> Result<Self, Self::Err> {
+        read_all(&hex::decode(header)?, |reader| Self::read(reader))
+    }
+}
+
+impl fmt::Display for BitcoinBlockHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.serialize()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BitcoinBlock<N: BitcoinNetwork> {
+    pub header: BitcoinBlockHeader,
+    pub transactions: Vec<BitcoinTransaction<N>>,
+}
+
+impl<N: BitcoinNetwork> BitcoinBlock<N> {
+    /// Reads a block, checking its merkle root and witness commitment against its transactions.
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, BlockError> {
+        let header = BitcoinBlockHeader::read(&mut reader)?;
+        let transactions =
+            BitcoinVector::read(&mut reader, |s| BitcoinTransaction::<N>::read(s))?;
+
+        let block = Self {
+            header,
+            transactions,
+        };
+        block.check_merkle_root()?;
+        block.check_witness_commitment()?;
+        Ok(block)
+    }
+
+    pub fn from_block_bytes(block: &[u8]) -> Result<Self, BlockError> {
+        read_all(block, |reader| Self::read(reader))
+    }
+
+    pub fn to_block_bytes(&self) -> Result<Vec<u8>, BlockError> {
+        let mut block = self.header.serialize();
+        block.extend(variable_length_integer(self.transactions.len() as u64)?);
+        for transaction in &self.transactions {
+            block.extend(transaction.to_transaction_bytes()?);
+        }
+        Ok(block)
+    }
+
+    /// Returns the block hash in display (big-endian) byte order.
+    pub fn to_block_hash(&self) -> Vec<u8> {
+        self.header.to_block_hash()
+    }
+
+    /// Returns the transaction ids of the block in internal byte order.
+    fn transaction_hashes(&self) -> Result<Vec<[u8; 32]>, BlockError> {
+        self.transactions
+            .iter()
+            .map(|transaction| reverse_hash(transaction.to_transaction_id()?.txid()))
+            .collect()
+    }
+
+    /// Returns the merkle root of the block transactions in internal byte order.
+    pub fn compute_merkle_root(&self) -> Result<[u8; 32], BlockError> {
+        merkle_root(&self.transaction_hashes()?).ok_or(BlockError::MissingCoinbase)
+    }
+
+    /// Checks that the merkle root of the transactions matches the block header.
+    pub fn check_merkle_root(&self) -> Result<(), BlockError> {
+        let merkle_root = self.compute_merkle_root()?;
+        match merkle_root == self.header.merkle_root {
+            true => Ok(()),
+            false => Err(BlockError::InvalidMerkleRoot(
+                hex::encode(self.header.merkle_root),
+                hex::encode(merkle_root),
+            )),
+        }
+    }
+
+    /// Returns the merkle root of the witness transaction ids in internal byte order,
+    /// where the coinbase witness transaction id is all zeros.
+    pub fn compute_witness_root(&self) -> Result<[u8; 32], BlockError> {
+        let mut hashes = vec![[0u8; 32]];
+        for transaction in self.transactions.iter().skip(1) {
+            hashes.push(reverse_hash(transaction.to_transaction_id()?.wtxid())?);
+        }
+        match self.transactions.is_empty() {
+            true => Err(BlockError::MissingCoinbase),
+            false => Ok(merkle_root(&hashes).unwrap_or([0u8; 32])),
+        }
+    }
+
+    /// Returns the witness reserved value stored in the coinbase witness.
+    pub fn witness_reserved_value(&self) -> Result<[u8; 32], BlockError> {
+        let coinbase = self
+            .transactions
+            .first()
+            .ok_or(BlockError::MissingCoinbase)?;
+        let input = coinbase
+            .parameters()
+            .inputs
+            .first()
+            .ok_or(BlockError::MissingCoinbase)?;
+
+        // Witness stack items are stored with their length prefix
+        match input.witnesses.as_slice() {
+            [item] if item.len() == 33 && item[0] == 0x20 => {
+                let mut value = [0u8; 32];
+                value.copy_from_slice(&item[1..]);
+                Ok(value)
+            }
+            _ => Err(BlockError::MissingWitnessReservedValue),
+        }
+    }
+
+    /// Returns the witness commitment computed from the block transactions.
+    pub fn compute_witness_commitment(&self) -> Result<[u8; 32], BlockError> {
+        let witness_root = self.compute_witness_root()?;
+        let reserved_value = self.witness_reserved_value()?;
+        Ok(merkle_node(&witness_root, &reserved_value))
+    }
+
+    /// Returns the witness commitment found in the coinbase outputs, if any.
+    pub fn witness_commitment(&self) -> Result<Option<[u8; 32]>, BlockError> {
+        let coinbase = self
+            .transactions
+            .first()
+            .ok_or(BlockError::MissingCoinbase)?;
+
+        // If multiple outputs match, the one with the highest index is the commitment
+        Ok(coinbase
+            .parameters()
+            .outputs
+            .iter()
+            .rev()
+            .find(|output| {
+                output.script_pub_key.len() >= 38
+                    && output.script_pub_key[0..6] == WITNESS_COMMITMENT_HEADER
+            })
+            .map(|output| {
+                let mut commitment = [0u8; 32];
+                commitment.copy_from_slice(&output.script_pub_key[6..38]);
+                commitment
+            }))
+    }
+
+    /// Checks the witness commitment of the block. Blocks without witness data
+    /// and without a commitment are valid.
+    pub fn check_witness_commitment(&self) -> Result<(), BlockError> {
+        let expected = match self.witness_commitment()? {
+            Some(commitment) => commitment,
+            None => {
+                return match self
+                    .transactions
+                    .iter()
+                    .any(|transaction| transaction.parameters().segwit_flag)
+                {
+                    true => Err(BlockError::InvalidWitnessCommitment(
+                        String::new(),
+                        hex::encode(self.compute_witness_commitment()?),
+                    )),
+                    false => Ok(()),
+                }
+            }
+        };
+
+        let found = self.compute_witness_commitment()?;
+        match expected == found {
+            true => Ok(()),
+            false => Err(BlockError::InvalidWitnessCommitment(
+                hex::encode(expected),
+                hex::encode(found),
+            )),
+        }
+    }
+}
+
+impl<N: BitcoinNetwork> FromStr for BitcoinBlock<N> {
+    type Err = BlockError;
+
+    fn from_str(block: &str) -> Result<Self, Self::Err> {
+        Self::from_block_bytes(&hex::decode(block)?)
+    }
+}
+
+/// A partial merkle tree as serialized in a BIP37 `merkleblock` message.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PartialMerkleTree {
+    pub total_transactions: u32,
+    /// The node hashes in depth-first order, in internal byte order
+    pub hashes: Vec<[u8; 32]>,
+    /// The traversal flag bits, least significant bit first
+    pub flags: Vec<u8>,
+}
+
+impl PartialMerkleTree {
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, BlockError> {
+        let mut total_transactions = [0u8; 4];
+        reader.read_exact(&mut total_transactions)?;
+
+        let hashes = BitcoinVector::read(&mut reader, |s| {
+            let mut hash = [0u8; 32];
+            s.read_exact(&mut hash)?;
+            Ok(hash)
+        })?;
+
+        let flags = BitcoinVector::read(&mut reader, |s| {
+            let mut byte = [0u8; 1];
+            s.read_exact(&mut byte)?;
+            Ok(byte[0])
+        })?;
+
+        Ok(Self {
+            total_transactions: u32::from_le_bytes(total_transactions),
+            hashes,
+            flags,
+        })
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>, BlockError> {
+        let mut tree = self.total_transactions.to_le_bytes().to_vec();
+        tree.extend(variable_length_integer(self.hashes.len() as u64)?);
+        for hash in &self.hashes {
+            tree.extend(hash);
+        }
+        tree.extend(variable_length_integer(self.flags.len() as u64)?);
+        tree.extend(&self.flags);
+        Ok(tree)
+    }
+
+    /// Returns a partial merkle tree over the given transaction ids, in internal byte order,
+    /// proving the inclusion of those whose `matches` flag is set.
+    pub fn from_transaction_hashes(
+        transaction_hashes: &[[u8; 32]],
+        matches: &[bool],
+    ) -> Result<Self, BlockError> {
+        if transaction_hashes.is_empty() || transaction_hashes.len() != matches.len() {
+            return Err(BlockError::InvalidPartialMerkleTree(format!(
+                "{} transactions with {} match flags",
+                transaction_hashes.len(),
+                matches.len()
+            )));
+        }
+
+        let mut tree = Self {
+            total_transactions: transaction_hashes.len() as u32,
+            hashes: vec![],
+            flags: vec![],
+        };
+
+        let mut height = 0;
+        while tree.tree_width(height) > 1 {
+            height += 1;
+        }
+
+        let mut bits = vec![];
+        tree.build(height, 0, transaction_hashes, matches, &mut bits);
+
+        tree.flags = vec![0u8; bits.len().div_ceil(8)];
+        for (i, bit) in bits.iter().enumerate() {
+            tree.flags[i / 8] |= (*bit as u8) << (i % 8);
+        }
+        Ok(tree)
+    }
+
+    fn build(
+        &mut self,
+        height: u32,
+        position: u32,
+        transaction_hashes: &[[u8; 32]],
+        matches: &[bool],
+        bits: &mut Vec<bool>,
+    ) {
+        let start = (position as usize) << height;
+        let end = (((position + 1) as usize) << height).min(matches.len());
+        let is_parent_of_match = matches[start..end].iter().any(|is_match| *is_match);
+        bits.push(is_parent_of_match);
+
+        if height == 0 || !is_parent_of_match {
+            let hash = self.node_hash(height, position, transaction_hashes);
+            self.hashes.push(hash);
+        } else {
+            self.build(height - 1, position * 2, transaction_hashes, matches, bits);
+            if position * 2 + 1 < self.tree_width(height - 1) {
+                self.build(height - 1, position * 2 + 1, transaction_hashes, matches, bits);
+            }
+        }
+    }
+
+    /// Returns the hash of the node at the given height and position of the full tree.
+    fn node_hash(&self, height: u32, position: u32, transaction_hashes: &[[u8; 32]]) -> [u8; 32] {
+        if height == 0 {
+            return transaction_hashes[position as usize];
+        }
+        let left = self.node_hash(height - 1, position * 2, transaction_hashes);
+        let right = match position * 2 + 1 < self.tree_width(height - 1) {
+            true => self.node_hash(height - 1, position * 2 + 1, transaction_hashes),
+            false => left,
+        };
+        merkle_node(&left, &right)
+    }
+
+    /// Returns the number of nodes at the given height of the tree.
+    fn tree_width(&self, height: u32) -> u32 {
+        ((self.total_transactions as u64 + (1 << height) - 1) >> height) as u32
+    }
+
+    fn traverse(
+        &self,
+        height: u32,
+        position: u32,
+        bits_used: &mut usize,
+        hashes_used: &mut usize,
+        matches: &mut Vec<[u8; 32]>,
+    ) -> Result<[u8; 32], BlockError> {
+        if *bits_used >= self.flags.len() * 8 {
+            return Err(BlockError::InvalidPartialMerkleTree(
+                "overflowed the flag bits".into(),
+            ));
+        }
+        let is_parent_of_match = (self.flags[*bits_used / 8] >> (*bits_used % 8)) & 1 == 1;
+        *bits_used += 1;
+
+        if height == 0 || !is_parent_of_match {
+            let hash = *self.hashes.get(*hashes_used).ok_or_else(|| {
+                BlockError::InvalidPartialMerkleTree("overflowed the hash array".into())
+            })?;
+            *hashes_used += 1;
+            if height == 0 && is_parent_of_match {
+                matches.push(hash);
+            }
+            return Ok(hash);
+        }
+
+        let left = self.traverse(height - 1, position * 2, bits_used, hashes_used, matches)?;
+        let right = match position * 2 + 1 < self.tree_width(height - 1) {
+            true => {
+                let right =
+                    self.traverse(height - 1, position * 2 + 1, bits_used, hashes_used, matches)?;
+                // Identical siblings allow forging a different transaction set (CVE-2012-2459)
+                if right == left {
+                    return Err(BlockError::InvalidPartialMerkleTree(
+                        "identical left and right branches".into(),
+                    ));
+                }
+                right
+            }
+            false => left,
+        };
+
+        Ok(merkle_node(&left, &right))
+    }
+
+    /// Returns the merkle root of the tree and the matched transaction ids,
+    /// both in internal byte order.
+    pub fn extract_matches(&self) -> Result<([u8; 32], Vec<[u8; 32]>), BlockError> {
+        if self.total_transactions == 0 || self.total_transactions > MAX_BLOCK_TRANSACTIONS {
+            return Err(BlockError::InvalidPartialMerkleTree(format!(
+                "invalid transaction count {}",
+                self.total_transactions
+            )));
+        }
+        if self.hashes.len() > self.total_transactions as usize {
+            return Err(BlockError::InvalidPartialMerkleTree(
+                "more hashes than transactions".into(),
+            ));
+        }
+        if self.flags.len() * 8 < self.hashes.len() {
+            return Err(BlockError::InvalidPartialMerkleTree(
+                "fewer flag bits than hashes".into(),
+            ));
+        }
+
+        let mut height = 0;
+        while self.tree_width(height) > 1 {
+            height += 1;
+        }
+
+        let mut bits_used = 0;
+        let mut hashes_used = 0;
+        let mut matches = vec![];
+        let root = self.traverse(height, 0, &mut bits_used, &mut hashes_used, &mut matches)?;
+
+        // All hashes and all flag bytes must be consumed
+        if bits_used.div_ceil(8) != self.flags.len() || hashes_used != self.hashes.len() {
+            return Err(BlockError::InvalidPartialMerkleTree(
+                "unused hashes or flag bits".into(),
+            ));
+        }
+
+        Ok((root, matches))
+    }
+}
+
+/// A block header with a partial merkle tree proving the inclusion of transactions (BIP37).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BitcoinMerkleBlock {
+    pub header: BitcoinBlockHeader,
+    pub partial_merkle_tree: PartialMerkleTree,
+}
+
+impl BitcoinMerkleBlock {
+    /// Returns a merkle block proving the inclusion of the given transaction ids,
+    /// in display (big-endian) byte order, in the block.
+    pub fn new<N: BitcoinNetwork>(
+        block: &BitcoinBlock<N>,
+        transaction_ids: &[Vec<u8>],
+    ) -> Result<Self, BlockError> {
+        let transaction_hashes = block.transaction_hashes()?;
+        let mut matches = vec![false; transaction_hashes.len()];
+        for transaction_id in transaction_ids {
+            let hash = reverse_hash(transaction_id)?;
+            match transaction_hashes.iter().position(|txid| *txid == hash) {
+                Some(index) => matches[index] = true,
+                None => {
+                    return Err(BlockError::TransactionNotIncluded(hex::encode(
+                        transaction_id,
+                    )))
+                }
+            }
+        }
+
+        Ok(Self {
+            header: block.header,
+            partial_merkle_tree: PartialMerkleTree::from_transaction_hashes(
+                &transaction_hashes,
+                &matches,
+            )?,
+        })
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, BlockError> {
+        Ok(Self {
+            header: BitcoinBlockHeader::read(&mut reader)?,
+            partial_merkle_tree: PartialMerkleTree::read(&mut reader)?,
+        })
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>, BlockError> {
+        Ok([self.header.serialize(), self.partial_merkle_tree.serialize()?].concat())
+    }
+
+    /// Returns the transaction ids proven by the merkle block in display (big-endian) byte order,
+    /// after checking the partial merkle tree against the header merkle root.
+    pub fn matched_transaction_ids(&self) -> Result<Vec<Vec<u8>>, BlockError> {
+        let (root, matches) = self.partial_merkle_tree.extract_matches()?;
+        if root != self.header.merkle_root {
+            return Err(BlockError::InvalidMerkleRoot(
+                hex::encode(self.header.merkle_root),
+                hex::encode(root),
+            ));
+        }
+
+        Ok(matches
+            .iter()
+            .map(|hash| {
+                let mut txid = hash.to_vec();
+                txid.reverse();
+                txid
+            })
+            .collect())
+    }
+
+    /// Verifies that the transaction id, given in display (big-endian) byte order,
+    /// is included in the block committed to by the header.
+    pub fn verify_transaction_id(&self, transaction_id: &[u8]) -> Result<(), BlockError> {
+        let _ = reverse_hash(transaction_id)?;
+        match self
+            .matched_transaction_ids()?
+            .iter()
+            .any(|txid| txid.as_slice() == transaction_id)
+        {
+            true => Ok(()),
+            false => Err(BlockError::TransactionNotIncluded(hex::encode(
+                transaction_id,
+            ))),
+        }
+    }
+}
+
+impl FromStr for BitcoinMerkleBlock {
+    type Err = BlockError;
+
+    fn from_str(merkle_block: &str) -> Result<Self, Self::Err> {
+        read_all(&hex::decode(merkle_block)?, |reader| Self::read(reader))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+
+    const GENESIS_BLOCK: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c0101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+    const GENESIS_HASH: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+    const GENESIS_TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    // A segwit transaction from the BIP143 examples
+    const SEGWIT_TRANSACTION: &str = "01000000000102fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f00000000494830450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f9281a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed01eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac000247304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee0121025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee635711000000";
+
+    // A coinbase with a witness reserved value and a zero witness commitment
+    const SEGWIT_COINBASE: &str = "010000000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0403e8030bffffffff0200f2052a010000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac0000000000000000266a24aa21a9ed00000000000000000000000000000000000000000000000000000000000000000120000000000000000000000000000000000000000000000000000000000000000000000000";
+
+    /// Returns a block of the transactions under the genesis header, with their merkle root.
+    fn block_with(transactions: &[Vec<u8>]) -> BitcoinBlock<Mainnet> {
+        let mut block = BitcoinBlock {
+            header: BitcoinBlock::<Mainnet>::from_str(GENESIS_BLOCK)
+                .unwrap()
+                .header,
+            transactions: transactions
+                .iter()
+                .map(|transaction| BitcoinTransaction::from_transaction_bytes(transaction))
+                .collect::<Result<_, _>>()
+                .unwrap(),
+        };
+        block.header.merkle_root = block.compute_merkle_root().unwrap();
+        block
+    }
+
+    #[test]
+    fn genesis_block() {
+        let block = BitcoinBlock::<Mainnet>::from_str(GENESIS_BLOCK).unwrap();
+        assert_eq!(GENESIS_HASH, hex::encode(block.to_block_hash()));
+        assert_eq!(
+            "00000000ffff0000000000000000000000000000000000000000000000000000",
+            hex::encode(block.header.target().unwrap())
+        );
+        assert_eq!(GENESIS_BLOCK, hex::encode(block.to_block_bytes().unwrap()));
+        block.check_merkle_root().unwrap();
+        block.check_witness_commitment().unwrap();
+        block.header.check_proof_of_work().unwrap();
+
+        let mut header = block.header;
+        header.nonce += 1;
+        assert!(header.check_proof_of_work().is_err());
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let block = format!("{}00", GENESIS_BLOCK);
+        assert!(matches!(
+            BitcoinBlock::<Mainnet>::from_str(&block),
+            Err(BlockError::TrailingBytes(1))
+        ));
+        let header = format!("{}00", &GENESIS_BLOCK[..160]);
+        assert!(matches!(
+            BitcoinBlockHeader::from_str(&header),
+            Err(BlockError::TrailingBytes(1))
+        ));
+
+        let block = BitcoinBlock::<Mainnet>::from_str(GENESIS_BLOCK).unwrap();
+        let txid = hex::decode(GENESIS_TXID).unwrap();
+        let merkle_block = BitcoinMerkleBlock::new(&block, &[txid]).unwrap();
+        let merkle_block = format!("{}00", hex::encode(merkle_block.serialize().unwrap()));
+        assert!(matches!(
+            BitcoinMerkleBlock::from_str(&merkle_block),
+            Err(BlockError::TrailingBytes(1))
+        ));
+    }
+
+    #[test]
+    fn invalid_merkle_root() {
+        let mut block = hex::decode(GENESIS_BLOCK).unwrap();
+        block[36] ^= 1;
+        assert!(matches!(
+            BitcoinBlock::<Mainnet>::from_block_bytes(&block),
+            Err(BlockError::InvalidMerkleRoot(..))
+        ));
+    }
+
+    #[test]
+    fn truncated_block() {
+        for length in [1, 10, 50, 100].iter() {
+            let block = &GENESIS_BLOCK[..GENESIS_BLOCK.len() - 2 * length];
+            assert!(BitcoinBlock::<Mainnet>::from_str(block).is_err());
+        }
+        assert!(BitcoinBlockHeader::from_str(&GENESIS_BLOCK[..158]).is_err());
+    }
+
+    #[test]
+    fn merkle_block() {
+        let coinbase = BitcoinBlock::<Mainnet>::from_str(GENESIS_BLOCK)
+            .unwrap()
+            .transactions[0]
+            .to_transaction_bytes()
+            .unwrap();
+        for count in 1..8usize {
+            // Distinct transactions with distinct lock times
+            let transactions = (0..count)
+                .map(|i| {
+                    let mut transaction = coinbase.clone();
+                    let length = transaction.len();
+                    transaction[length - 4] = i as u8;
+                    transaction
+                })
+                .collect::<Vec<_>>();
+            let bytes = block_with(&transactions).to_block_bytes().unwrap();
+            let block = BitcoinBlock::<Mainnet>::from_block_bytes(&bytes).unwrap();
+
+            let txids = block
+                .transactions
+                .iter()
+                .map(|transaction| transaction.to_transaction_id().unwrap().txid().to_vec())
+                .collect::<Vec<_>>();
+            for mask in 0..(1u32 << count) {
+                let matches = txids
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| mask >> i & 1 == 1)
+                    .map(|(_, txid)| txid.clone())
+                    .collect::<Vec<_>>();
+                let merkle_block = BitcoinMerkleBlock::new(&block, &matches).unwrap();
+                let merkle_block =
+                    BitcoinMerkleBlock::from_str(&hex::encode(merkle_block.serialize().unwrap()))
+                        .unwrap();
+                assert_eq!(matches, merkle_block.matched_transaction_ids().unwrap());
+                for (i, txid) in txids.iter().enumerate() {
+                    assert_eq!(
+                        mask >> i & 1 == 1,
+                        merkle_block.verify_transaction_id(txid).is_ok()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn witness_commitment() {
+        let transactions = [
+            hex::decode(SEGWIT_COINBASE).unwrap(),
+            hex::decode(SEGWIT_TRANSACTION).unwrap(),
+        ];
+        let block = block_with(&transactions);
+        assert!(block.check_witness_commitment().is_err());
+        assert!(matches!(
+            BitcoinBlock::<Mainnet>::from_block_bytes(&block.to_block_bytes().unwrap()),
+            Err(BlockError::InvalidWitnessCommitment(..))
+        ));
+
+        let commitment = block.compute_witness_commitment().unwrap();
+        let coinbase = SEGWIT_COINBASE.replace(
+            &format!("aa21a9ed{}", "00".repeat(32)),
+            &format!("aa21a9ed{}", hex::encode(commitment)),
+        );
+        let transactions = [hex::decode(coinbase).unwrap(), transactions[1].clone()];
+        let bytes = block_with(&transactions).to_block_bytes().unwrap();
+        let block = BitcoinBlock::<Mainnet>::from_block_bytes(&bytes).unwrap();
+        assert_eq!(bytes, block.to_block_bytes().unwrap());
+        block.check_witness_commitment().unwrap();
+    }
+}
@@ -0,0 +1,539 @@
+use crate::block::{BitcoinBlock, BlockError};
+use crate::network::BitcoinNetwork;
+use crate::transaction::{read_variable_length_integer, variable_length_integer};
+use core::{fmt, str::FromStr};
+use gyu_model::no_std::*;
+use gyu_model::transaction::TransactionError;
+use sha2::{Digest, Sha256};
+
+/// The Golomb-Rice coding parameter of basic filters (BIP158)
+const BASIC_FILTER_P: u8 = 19;
+
+/// The inverse false positive rate of basic filters (BIP158)
+const BASIC_FILTER_M: u64 = 784_931;
+
+/// The first byte of an OP_RETURN output script
+const OP_RETURN: u8 = 0x6a;
+
+#[derive(Debug, Fail)]
+pub enum BlockFilterError {
+    #[fail(display = "{}", _0)]
+    BlockError(BlockError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "invalid filter: {}", _0)]
+    InvalidFilter(String),
+
+    #[fail(
+        display = "invalid filter header at height offset {}: {{ expected: {:?}, found: {:?} }}",
+        _0, _1, _2
+    )]
+    InvalidFilterHeader(usize, String, String),
+
+    #[fail(
+        display = "mismatched filter header count: {{ expected: {:?}, found: {:?} }}",
+        _0, _1
+    )]
+    MismatchedFilterHeaderCount(usize, usize),
+}
+
+impl From<BlockError> for BlockFilterError {
+    fn from(error: BlockError) -> Self {
+        BlockFilterError::BlockError(error)
+    }
+}
+
+impl From<TransactionError> for BlockFilterError {
+    fn from(error: TransactionError) -> Self {
+        BlockFilterError::Crate("transaction", format!("{:?}", error))
+    }
+}
+
+impl From<hex::FromHexError> for BlockFilterError {
+    fn from(error: hex::FromHexError) -> Self {
+        BlockFilterError::Crate("hex", format!("{:?}", error))
+    }
+}
+
+/// Returns the SipHash-2-4 of the data with the given 128-bit key.
+fn siphash24(key: &[u8; 16], data: &[u8]) -> u64 {
+    let mut k0 = [0u8; 8];
+    let mut k1 = [0u8; 8];
+    k0.copy_from_slice(&key[0..8]);
+    k1.copy_from_slice(&key[8..16]);
+    let (k0, k1) = (u64::from_le_bytes(k0), u64::from_le_bytes(k1));
+
+    let mut v = [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ];
+
+    fn round(v: &mut [u64; 4]) {
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+    }
+
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(chunk);
+        let m = u64::from_le_bytes(word);
+        v[3] ^= m;
+        round(&mut v);
+        round(&mut v);
+        v[0] ^= m;
+    }
+
+    // The final word holds the remaining bytes and the data length in its top byte
+    let mut last = [0u8; 8];
+    last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    last[7] = data.len() as u8;
+    let m = u64::from_le_bytes(last);
+    v[3] ^= m;
+    round(&mut v);
+    round(&mut v);
+    v[0] ^= m;
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+/// Returns the double SHA256 hash of the given data.
+fn double_sha256(data: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Sha256::digest(&Sha256::digest(data)));
+    hash
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    length: usize,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: vec![],
+            length: 0,
+        }
+    }
+
+    fn write_bit(&mut self, bit: bool) {
+        if self.length.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        if bit {
+            let last = self.bytes.len() - 1;
+            self.bytes[last] |= 0x80 >> (self.length % 8);
+        }
+        self.length += 1;
+    }
+
+    /// Writes the lowest `count` bits of the value, most significant bit first.
+    fn write_bits(&mut self, value: u64, count: u8) {
+        for i in (0..count).rev() {
+            self.write_bit((value >> i) & 1 == 1);
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn read_bit(&mut self) -> Result<bool, BlockFilterError> {
+        let byte = self.bytes.get(self.position / 8).ok_or_else(|| {
+            BlockFilterError::InvalidFilter("unexpected end of the bit stream".into())
+        })?;
+        let bit = (byte >> (7 - self.position % 8)) & 1 == 1;
+        self.position += 1;
+        Ok(bit)
+    }
+
+    fn read_bits(&mut self, count: u8) -> Result<u64, BlockFilterError> {
+        let mut value = 0u64;
+        for _ in 0..count {
+            value = (value << 1) | self.read_bit()? as u64;
+        }
+        Ok(value)
+    }
+}
+
+/// A Golomb-coded set of hashed items, as specified in BIP158.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GolombCodedSet {
+    key: [u8; 16],
+    p: u8,
+    m: u64,
+}
+
+impl GolombCodedSet {
+    pub fn new(key: [u8; 16], p: u8, m: u64) -> Self {
+        Self { key, p, m }
+    }
+
+    /// Hashes the item into the range [0, n * m).
+    fn hash_to_range(&self, item: &[u8], n: u64) -> u64 {
+        let hash = siphash24(&self.key, item) as u128;
+        ((hash * (n as u128 * self.m as u128)) >> 64) as u64
+    }
+
+    /// Returns the sorted hashed values of the items for a set of size n.
+    fn hashed_items(&self, items: &[Vec<u8>], n: u64) -> Vec<u64> {
+        let mut values: Vec<u64> = items
+            .iter()
+            .map(|item| self.hash_to_range(item, n))
+            .collect();
+        values.sort_unstable();
+        values
+    }
+
+    /// Returns the serialized set (the item count followed by the Golomb-Rice bit stream)
+    /// of the given deduplicated items.
+    pub fn encode(&self, items: &[Vec<u8>]) -> Result<Vec<u8>, BlockFilterError> {
+        let n = items.len() as u64;
+        let mut writer = BitWriter::new();
+
+        let mut last = 0;
+        for value in self.hashed_items(items, n) {
+            let delta = value - last;
+            last = value;
+
+            // Quotient in unary, then the remainder in p bits
+            for _ in 0..(delta >> self.p) {
+                writer.write_bit(true);
+            }
+            writer.write_bit(false);
+            writer.write_bits(delta, self.p);
+        }
+
+        Ok([variable_length_integer(n)?, writer.bytes].concat())
+    }
+
+    /// Returns the item count and the sorted hashed values of the serialized set.
+    fn decode(&self, filter: &[u8]) -> Result<(u64, Vec<u64>), BlockFilterError> {
+        let mut reader = filter;
+        let n = read_variable_length_integer(&mut reader)? as u64;
+
+        // Each value takes at least a stop bit and p remainder bits, which bounds the
+        // number of values the remaining bytes can hold
+        if n > reader.len() as u64 * 8 / (self.p as u64 + 1) {
+            return Err(BlockFilterError::InvalidFilter(format!(
+                "{} items do not fit in {} bytes",
+                n,
+                reader.len()
+            )));
+        }
+        let mut bits = BitReader::new(reader);
+
+        let mut values = Vec::with_capacity(n as usize);
+        let mut last = 0u64;
+        for _ in 0..n {
+            let mut quotient = 0u64;
+            while bits.read_bit()? {
+                quotient += 1;
+            }
+            let delta = (quotient << self.p) | bits.read_bits(self.p)?;
+            last = last.checked_add(delta).ok_or_else(|| {
+                BlockFilterError::InvalidFilter("hashed value overflow".into())
+            })?;
+            values.push(last);
+        }
+        Ok((n, values))
+    }
+
+    /// Returns true if any of the queried items may be in the serialized set.
+    pub fn match_any(&self, filter: &[u8], queries: &[Vec<u8>]) -> Result<bool, BlockFilterError> {
+        let (n, values) = self.decode(filter)?;
+        if n == 0 || queries.is_empty() {
+            return Ok(false);
+        }
+
+        let queries = self.hashed_items(queries, n);
+        let (mut i, mut j) = (0, 0);
+        while i < values.len() && j < queries.len() {
+            match values[i].cmp(&queries[j]) {
+                core::cmp::Ordering::Less => i += 1,
+                core::cmp::Ordering::Greater => j += 1,
+                core::cmp::Ordering::Equal => return Ok(true),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns true if all of the queried items may be in the serialized set.
+    pub fn match_all(&self, filter: &[u8], queries: &[Vec<u8>]) -> Result<bool, BlockFilterError> {
+        let (n, values) = self.decode(filter)?;
+        if n == 0 {
+            return Ok(queries.is_empty());
+        }
+
+        let queries = self.hashed_items(queries, n);
+        Ok(queries
+            .iter()
+            .all(|query| values.binary_search(query).is_ok()))
+    }
+}
+
+/// A BIP158 basic block filter, committing to the output scripts of a block
+/// and the scripts spent by its inputs.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BitcoinBlockFilter {
+    /// The serialized Golomb-coded set
+    pub content: Vec<u8>,
+}
+
+impl BitcoinBlockFilter {
+    /// Returns the basic filter of the block, given the scripts of the outputs spent by
+    /// all non-coinbase inputs of the block in any order.
+    pub fn new_basic<N: BitcoinNetwork>(
+        block: &BitcoinBlock<N>,
+        spent_script_pub_keys: &[Vec<u8>],
+    ) -> Result<Self, BlockFilterError> {
+        let mut items: Vec<Vec<u8>> = vec![];
+        for transaction in &block.transactions {
+            for output in &transaction.parameters().outputs {
+                if !output.script_pub_key.is_empty() && output.script_pub_key[0] != OP_RETURN {
+                    items.push(output.script_pub_key.clone());
+                }
+            }
+        }
+        items.extend(
+            spent_script_pub_keys
+                .iter()
+                .filter(|script| !script.is_empty())
+                .cloned(),
+        );
+        items.sort();
+        items.dedup();
+
+        Ok(Self {
+            content: Self::basic_set(block)?.encode(&items)?,
+        })
+    }
+
+    /// Returns the Golomb-coded set parameters of basic filters for the block,
+    /// keyed by the first 16 bytes of the block hash in internal byte order.
+    fn basic_set<N: BitcoinNetwork>(
+        block: &BitcoinBlock<N>,
+    ) -> Result<GolombCodedSet, BlockFilterError> {
+        Self::basic_set_for_block_hash(&block.to_block_hash())
+    }
+
+    fn basic_set_for_block_hash(block_hash: &[u8]) -> Result<GolombCodedSet, BlockFilterError> {
+        if block_hash.len() != 32 {
+            return Err(BlockFilterError::InvalidFilter(format!(
+                "invalid block hash length {}",
+                block_hash.len()
+            )));
+        }
+        let mut key = [0u8; 16];
+        for (i, byte) in block_hash.iter().rev().take(16).enumerate() {
+            key[i] = *byte;
+        }
+        Ok(GolombCodedSet::new(key, BASIC_FILTER_P, BASIC_FILTER_M))
+    }
+
+    /// Returns true if any of the scripts may be in the filter of the block with the
+    /// given hash, in display (big-endian) byte order.
+    pub fn match_any(
+        &self,
+        block_hash: &[u8],
+        script_pub_keys: &[Vec<u8>],
+    ) -> Result<bool, BlockFilterError> {
+        Self::basic_set_for_block_hash(block_hash)?.match_any(&self.content, script_pub_keys)
+    }
+
+    /// Returns true if all of the scripts may be in the filter of the block with the
+    /// given hash, in display (big-endian) byte order.
+    pub fn match_all(
+        &self,
+        block_hash: &[u8],
+        script_pub_keys: &[Vec<u8>],
+    ) -> Result<bool, BlockFilterError> {
+        Self::basic_set_for_block_hash(block_hash)?.match_all(&self.content, script_pub_keys)
+    }
+
+    /// Returns the filter hash in internal byte order.
+    pub fn filter_hash(&self) -> [u8; 32] {
+        double_sha256(&self.content)
+    }
+
+    /// Returns the filter header chained to the previous filter header, both in internal byte order.
+    pub fn filter_header(&self, previous_filter_header: &[u8; 32]) -> [u8; 32] {
+        let mut data = [0u8; 64];
+        data[0..32].copy_from_slice(&self.filter_hash());
+        data[32..64].copy_from_slice(previous_filter_header);
+        double_sha256(&data)
+    }
+}
+
+impl FromStr for BitcoinBlockFilter {
+    type Err = BlockFilterError;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            content: hex::decode(filter)?,
+        })
+    }
+}
+
+impl fmt::Display for BitcoinBlockFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.content))
+    }
+}
+
+/// Verifies that the filters of consecutive blocks chain from the previous filter header
+/// to the expected filter headers, all in internal byte order.
+pub fn verify_filter_headers(
+    previous_filter_header: &[u8; 32],
+    filters: &[BitcoinBlockFilter],
+    filter_headers: &[[u8; 32]],
+) -> Result<(), BlockFilterError> {
+    if filters.len() != filter_headers.len() {
+        return Err(BlockFilterError::MismatchedFilterHeaderCount(
+            filter_headers.len(),
+            filters.len(),
+        ));
+    }
+
+    let mut previous = *previous_filter_header;
+    for (i, (filter, expected)) in filters.iter().zip(filter_headers.iter()).enumerate() {
+        let header = filter.filter_header(&previous);
+        if header != *expected {
+            return Err(BlockFilterError::InvalidFilterHeader(
+                i,
+                hex::encode(expected),
+                hex::encode(header),
+            ));
+        }
+        previous = header;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+
+    /// Blocks 0, 2 and 3 of the BIP158 testnet vectors, with their filters and filter headers
+    const VECTORS: [(&str, &str, &str, &str); 3] = [
+        (
+            "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943",
+            "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4adae5494dffff001d1aa4ae180101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000",
+            "019dfca8",
+            "21584579b7eb08997773e5aeff3a7f932700042d0ed2a6129012b7d7ae81b750",
+        ),
+        (
+            "000000006c02c8ea6e4ff69651f7fcde348fb9d557a06e6957b65552002a7820",
+            "0100000006128e87be8b1b4dea47a7247d5528d2702c96826c7a648497e773b800000000e241352e3bec0a95a6217e10c3abb54adfa05abb12c126695595580fb92e222032e7494dffff001d00d235340101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff0e0432e7494d010e062f503253482fffffffff0100f2052a010000002321038a7f6ef1c8ca0c588aa53fa860128077c9e6c11e6830f4d7ee4e763a56b7718fac00000000",
+            "0174a170",
+            "186afd11ef2b5e7e3504f2e8cbf8df28a1fd251fe53d60dff8b1467d1b386cf0",
+        ),
+        (
+            "000000008b896e272758da5297bcd98fdc6d97c9b765ecec401e286dc1fdbe10",
+            "0100000020782a005255b657696ea057d5b98f34defcf75196f64f6eeac8026c0000000041ba5afc532aae03151b8aa87b65e1594f97504a768e010c98c0add79216247186e7494dffff001d058dc2b60101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff0e0486e7494d0151062f503253482fffffffff0100f2052a01000000232103f6d9ff4c12959445ca5549c811683bf9c88e637b222dd2e0311154c4c85cf423ac00000000",
+            "016cf7a0",
+            "8d63aadf5ab7257cb6d2316a57b16f517bff1c6388f124ec4c04af1212729d2a",
+        ),
+    ];
+
+    fn to_internal(hash: &str) -> [u8; 32] {
+        let mut internal = [0u8; 32];
+        internal.copy_from_slice(&hex::decode(hash).unwrap());
+        internal.reverse();
+        internal
+    }
+
+    #[test]
+    fn basic_filter_vectors() {
+        let mut filters = vec![];
+        for (block_hash, block, filter, _) in VECTORS.iter() {
+            let block = BitcoinBlock::<Mainnet>::from_str(block).unwrap();
+            assert_eq!(*block_hash, hex::encode(block.to_block_hash()));
+
+            let basic_filter = BitcoinBlockFilter::new_basic(&block, &[]).unwrap();
+            assert_eq!(*filter, basic_filter.to_string());
+            assert_eq!(basic_filter, BitcoinBlockFilter::from_str(filter).unwrap());
+            filters.push(basic_filter);
+        }
+
+        let filter_headers = VECTORS
+            .iter()
+            .map(|(_, _, _, filter_header)| to_internal(filter_header))
+            .collect::<Vec<_>>();
+        // Block 1 is left out, so the chain restarts from its filter header
+        verify_filter_headers(&[0u8; 32], &filters[..1], &filter_headers[..1]).unwrap();
+        verify_filter_headers(
+            &to_internal("d7bdac13a59d745b1add0d2ce852f1a0442e8945fc1bf3848d3cbffd88c24fe1"),
+            &filters[1..],
+            &filter_headers[1..],
+        )
+        .unwrap();
+        assert!(verify_filter_headers(&[0u8; 32], &filters[1..], &filter_headers[1..]).is_err());
+    }
+
+    #[test]
+    fn match_scripts() {
+        let block = BitcoinBlock::<Mainnet>::from_str(VECTORS[1].1).unwrap();
+        let block_hash = block.to_block_hash();
+        let output_script = block.transactions[0].parameters().outputs[0]
+            .script_pub_key
+            .clone();
+        let spent_script = vec![0x00, 0x14, 0x01, 0x02];
+
+        let filter = BitcoinBlockFilter::new_basic(&block, &[spent_script.clone()]).unwrap();
+        assert!(filter
+            .match_any(&block_hash, &[vec![0x51], output_script.clone()])
+            .unwrap());
+        assert!(filter
+            .match_all(&block_hash, &[output_script, spent_script])
+            .unwrap());
+        assert!(!filter.match_any(&block_hash, &[vec![0x51]]).unwrap());
+        assert!(!filter.match_any(&[0u8; 32], &[vec![0x51]]).unwrap());
+
+        let items = (0..1000u32)
+            .map(|i| i.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let filter = BitcoinBlockFilter::new_basic(&block, &items).unwrap();
+        assert!(filter.match_all(&block_hash, &items).unwrap());
+    }
+
+    #[test]
+    fn invalid_filter() {
+        let set = GolombCodedSet::new([0u8; 16], BASIC_FILTER_P, BASIC_FILTER_M);
+        // An item count far beyond what the filter bytes can hold
+        let filter = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 0x00];
+        assert!(matches!(
+            set.match_any(&filter, &[vec![0x51]]),
+            Err(BlockFilterError::InvalidFilter(_))
+        ));
+        // Two items, but the bit stream ends after the first
+        let mut filter = set.encode(&[vec![0x51]]).unwrap();
+        filter[0] = 2;
+        assert!(set.match_any(&filter, &[vec![0x51]]).is_err());
+    }
+}
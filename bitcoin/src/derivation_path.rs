@@ -1,3 +1,4 @@
+use crate::format::BitcoinFormat;
 use crate::network::BitcoinNetwork;
 use gyu_model::derivation_path::{ChildIndex, DerivationPath, DerivationPathError};
 use gyu_model::no_std::*;
@@ -13,9 +14,22 @@ pub enum BitcoinDerivationPath<N: BitcoinNetwork> {
     /// BIP44 - m/44'/{0', 1'}/{account}'/{change}/{index} - Pay-to-Pubkey Hash
     /// https://github.com/bitcoin/bips/blob/master/bip-0044.mediawiki
     BIP44([ChildIndex; 3]),
+    /// BIP45 - m/45'/{cosigner}/{change}/{index} - Multisig Pay-to-Script Hash
+    /// https://github.com/bitcoin/bips/blob/master/bip-0045.mediawiki
+    BIP45([ChildIndex; 3]),
+    /// BIP48 - m/48'/{0', 1'}/{account}'/{1', 2'}/{change}/{index} - Multisig Pay-to-Witness-Script Hash,
+    /// nested in P2SH for script type 1' and native for script type 2'
+    /// https://github.com/bitcoin/bips/blob/master/bip-0048.mediawiki
+    BIP48([ChildIndex; 4]),
     /// BIP49 - m/49'/{0', 1'}/{account}'/{change}/{index} - SegWit Pay-to-Witness-Public-Key Hash
     /// https://github.com/bitcoin/bips/blob/master/bip-0049.mediawiki
     BIP49([ChildIndex; 3]),
+    /// BIP84 - m/84'/{0', 1'}/{account}'/{change}/{index} - Native SegWit Pay-to-Witness-Public-Key Hash
+    /// https://github.com/bitcoin/bips/blob/master/bip-0084.mediawiki
+    BIP84([ChildIndex; 3]),
+    /// BIP86 - m/86'/{0', 1'}/{account}'/{change}/{index} - Taproot Pay-to-Taproot key path
+    /// https://github.com/bitcoin/bips/blob/master/bip-0086.mediawiki
+    BIP86([ChildIndex; 3]),
 }
 
 impl<N: BitcoinNetwork> DerivationPath for BitcoinDerivationPath<N> {
@@ -38,6 +52,29 @@ impl<N: BitcoinNetwork> DerivationPath for BitcoinDerivationPath<N> {
                     false => Err(DerivationPathError::ExpectedBIP44Path),
                 }
             }
+            BitcoinDerivationPath::BIP45(path) => {
+                match path[0].is_normal() && is_change(path[1]) && path[2].is_normal() {
+                    true => Ok(vec![ChildIndex::Hardened(45), path[0], path[1], path[2]]),
+                    false => Err(DerivationPathError::ExpectedBIP45Path),
+                }
+            }
+            BitcoinDerivationPath::BIP48(path) => {
+                match path[0].is_hardened()
+                    && is_script_type(path[1])
+                    && is_change(path[2])
+                    && path[3].is_normal()
+                {
+                    true => Ok(vec![
+                        ChildIndex::Hardened(48),
+                        N::HD_COIN_TYPE,
+                        path[0],
+                        path[1],
+                        path[2],
+                        path[3],
+                    ]),
+                    false => Err(DerivationPathError::ExpectedBIP48Path),
+                }
+            }
             BitcoinDerivationPath::BIP49(path) => {
                 match path[0].is_hardened() && path[1].is_normal() && path[2].is_normal() {
                     true => Ok(vec![
@@ -50,12 +87,54 @@ impl<N: BitcoinNetwork> DerivationPath for BitcoinDerivationPath<N> {
                     false => Err(DerivationPathError::ExpectedBIP49Path),
                 }
             }
+            BitcoinDerivationPath::BIP84(path) => {
+                match path[0].is_hardened() && path[1].is_normal() && path[2].is_normal() {
+                    true => Ok(vec![
+                        ChildIndex::Hardened(84),
+                        N::HD_COIN_TYPE,
+                        path[0],
+                        path[1],
+                        path[2],
+                    ]),
+                    false => Err(DerivationPathError::ExpectedBIP84Path),
+                }
+            }
+            BitcoinDerivationPath::BIP86(path) => {
+                match path[0].is_hardened() && path[1].is_normal() && path[2].is_normal() {
+                    true => Ok(vec![
+                        ChildIndex::Hardened(86),
+                        N::HD_COIN_TYPE,
+                        path[0],
+                        path[1],
+                        path[2],
+                    ]),
+                    false => Err(DerivationPathError::ExpectedBIP86Path),
+                }
+            }
         }
     }
 
     /// Returns a derivation path given the child index vector.
     fn from_vec(path: &Vec<ChildIndex>) -> Result<Self, DerivationPathError> {
-        if path.len() == 5 {
+        if path.len() == 4
+            && path[0] == ChildIndex::Hardened(45)
+            && path[1].is_normal()
+            && is_change(path[2])
+            && path[3].is_normal()
+        {
+            // Path length 4 - BIP45
+            Ok(BitcoinDerivationPath::BIP45([path[1], path[2], path[3]]))
+        } else if path.len() == 6
+            && path[0] == ChildIndex::Hardened(48)
+            && path[1] == N::HD_COIN_TYPE
+            && path[2].is_hardened()
+            && is_script_type(path[3])
+            && is_change(path[4])
+            && path[5].is_normal()
+        {
+            // Path length 6 - BIP48
+            Ok(BitcoinDerivationPath::BIP48([path[2], path[3], path[4], path[5]]))
+        } else if path.len() == 5 {
             // Path length 5 - BIP44
             if path[0] == ChildIndex::Hardened(44)
                 && path[1] == N::HD_COIN_TYPE
@@ -74,7 +153,25 @@ impl<N: BitcoinNetwork> DerivationPath for BitcoinDerivationPath<N> {
             {
                 return Ok(BitcoinDerivationPath::BIP49([path[2], path[3], path[4]]));
             }
-            // Path length 5 - BIP32 (non-BIP44 & non-BIP49 compliant)
+            // Path length 5 - BIP84
+            if path[0] == ChildIndex::Hardened(84)
+                && path[1] == N::HD_COIN_TYPE
+                && path[2].is_hardened()
+                && path[3].is_normal()
+                && path[4].is_normal()
+            {
+                return Ok(BitcoinDerivationPath::BIP84([path[2], path[3], path[4]]));
+            }
+            // Path length 5 - BIP86
+            if path[0] == ChildIndex::Hardened(86)
+                && path[1] == N::HD_COIN_TYPE
+                && path[2].is_hardened()
+                && path[3].is_normal()
+                && path[4].is_normal()
+            {
+                return Ok(BitcoinDerivationPath::BIP86([path[2], path[3], path[4]]));
+            }
+            // Path length 5 - BIP32 (non-BIP44, BIP49, BIP84 & BIP86 compliant)
             return Ok(BitcoinDerivationPath::BIP32(path.to_vec(), PhantomData));
         } else {
             // Path length 0 - BIP32 root key
@@ -84,6 +181,65 @@ impl<N: BitcoinNetwork> DerivationPath for BitcoinDerivationPath<N> {
     }
 }
 
+impl<N: BitcoinNetwork> BitcoinDerivationPath<N> {
+    /// Returns the address format of keys derived along the path, if the path implies one.
+    pub fn format(&self) -> Option<BitcoinFormat> {
+        match self {
+            BitcoinDerivationPath::BIP32(..) => None,
+            BitcoinDerivationPath::BIP44(_) => Some(BitcoinFormat::P2PKH),
+            BitcoinDerivationPath::BIP45(_) => Some(BitcoinFormat::P2SH),
+            BitcoinDerivationPath::BIP48(path) => match path[1] {
+                ChildIndex::Hardened(1) => Some(BitcoinFormat::P2SH_P2WSH),
+                ChildIndex::Hardened(2) => Some(BitcoinFormat::P2WSH),
+                _ => None,
+            },
+            BitcoinDerivationPath::BIP49(_) => Some(BitcoinFormat::P2SH_P2WPKH),
+            BitcoinDerivationPath::BIP84(_) => Some(BitcoinFormat::Bech32),
+            BitcoinDerivationPath::BIP86(_) => Some(BitcoinFormat::P2TR),
+        }
+    }
+
+    /// Returns the path of the account of the format, the parent of its receive and change
+    /// chains: m/{44', 49', 84', 86'}/{coin}'/{account}' for single-key formats, and
+    /// m/48'/{coin}'/{account}'/{1', 2'} for multisig witness scripts.
+    pub fn account(format: &BitcoinFormat, account: u32) -> Result<Self, DerivationPathError> {
+        let purpose = match format {
+            BitcoinFormat::P2PKH => 44,
+            BitcoinFormat::P2SH_P2WPKH => 49,
+            BitcoinFormat::Bech32 => 84,
+            BitcoinFormat::P2TR => 86,
+            BitcoinFormat::P2SH_P2WSH | BitcoinFormat::P2WSH => 48,
+            BitcoinFormat::P2SH => {
+                return Err(DerivationPathError::InvalidDerivationPath(format!(
+                    "no account path for {} keys",
+                    format
+                )))
+            }
+        };
+        let mut path = vec![
+            ChildIndex::hardened(purpose)?,
+            N::HD_COIN_TYPE,
+            ChildIndex::hardened(account)?,
+        ];
+        match format {
+            BitcoinFormat::P2SH_P2WSH => path.push(ChildIndex::Hardened(1)),
+            BitcoinFormat::P2WSH => path.push(ChildIndex::Hardened(2)),
+            _ => {}
+        };
+        Ok(BitcoinDerivationPath::BIP32(path, PhantomData))
+    }
+}
+
+/// Returns true if the index is a BIP45 or BIP48 change index, 0 for receiving and 1 for change.
+fn is_change(index: ChildIndex) -> bool {
+    index == ChildIndex::Normal(0) || index == ChildIndex::Normal(1)
+}
+
+/// Returns true if the index is a BIP48 script type, 1' for P2SH-P2WSH and 2' for P2WSH.
+fn is_script_type(index: ChildIndex) -> bool {
+    index == ChildIndex::Hardened(1) || index == ChildIndex::Hardened(2)
+}
+
 impl<N: BitcoinNetwork> FromStr for BitcoinDerivationPath<N> {
     type Err = DerivationPathError;
 
@@ -136,3 +292,148 @@ impl<N: BitcoinNetwork> fmt::Display for BitcoinDerivationPath<N> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+
+    type N = Mainnet;
+
+    #[test]
+    fn bip84_bip86_paths() {
+        let path = BitcoinDerivationPath::<N>::from_str("m/84'/0'/0'/0/1").unwrap();
+        assert_eq!(
+            BitcoinDerivationPath::BIP84([
+                ChildIndex::Hardened(0),
+                ChildIndex::Normal(0),
+                ChildIndex::Normal(1)
+            ]),
+            path
+        );
+        assert_eq!("m/84'/0'/0'/0/1", path.to_string());
+        assert_eq!(Some(BitcoinFormat::Bech32), path.format());
+
+        let path = BitcoinDerivationPath::<N>::from_str("m/86'/0'/1'/1/0").unwrap();
+        assert_eq!(
+            BitcoinDerivationPath::BIP86([
+                ChildIndex::Hardened(1),
+                ChildIndex::Normal(1),
+                ChildIndex::Normal(0)
+            ]),
+            path
+        );
+        assert_eq!("m/86'/0'/1'/1/0", path.to_string());
+        assert_eq!(Some(BitcoinFormat::P2TR), path.format());
+
+        for (path, format) in [
+            ("m/44'/0'/0'/0/0", BitcoinFormat::P2PKH),
+            ("m/49'/0'/0'/0/0", BitcoinFormat::P2SH_P2WPKH),
+        ]
+        .iter()
+        {
+            let path = BitcoinDerivationPath::<N>::from_str(path).unwrap();
+            assert_eq!(Some(format.clone()), path.format());
+        }
+    }
+
+    #[test]
+    fn bip32_fallback() {
+        // Unhardened accounts and other coin types are not BIP84 or BIP86 paths.
+        for path in ["m/84'/0'/0/0/0", "m/86'/1'/0'/0/0", "m/84/0'/0'/0/0"].iter() {
+            let path = BitcoinDerivationPath::<N>::from_str(path).unwrap();
+            assert!(matches!(path, BitcoinDerivationPath::BIP32(..)));
+            assert_eq!(None, path.format());
+        }
+    }
+
+    #[test]
+    fn leading_m() {
+        // Relative paths are parsed as path templates, not as paths from the master key.
+        for path in ["", "84'/0'/0'/0/0", "0/1"].iter() {
+            assert!(BitcoinDerivationPath::<N>::from_str(path).is_err());
+        }
+        assert_eq!("m", BitcoinDerivationPath::<N>::from_str("m").unwrap().to_string());
+    }
+
+    #[test]
+    fn bip48_bip45_paths() {
+        for (path, format) in [
+            ("m/48'/0'/0'/1'/0/0", BitcoinFormat::P2SH_P2WSH),
+            ("m/48'/0'/3'/2'/1/7", BitcoinFormat::P2WSH),
+            ("m/45'/2/0/0", BitcoinFormat::P2SH),
+        ]
+        .iter()
+        {
+            let parsed = BitcoinDerivationPath::<N>::from_str(path).unwrap();
+            assert!(!matches!(parsed, BitcoinDerivationPath::BIP32(..)));
+            assert_eq!(*path, parsed.to_string());
+            assert_eq!(Some(format.clone()), parsed.format());
+        }
+
+        // Unknown script types, hardened cosigners and other change indices are BIP32 paths.
+        for path in [
+            "m/48'/0'/0'/3'/0/0",
+            "m/48'/0'/0'/2/0/0",
+            "m/48'/0'/0'/2'/2/0",
+            "m/45'/2'/0/0",
+            "m/45'/2/2/0",
+            "m/45'/2/0/0'",
+        ]
+        .iter()
+        {
+            let parsed = BitcoinDerivationPath::<N>::from_str(path).unwrap();
+            assert!(matches!(parsed, BitcoinDerivationPath::BIP32(..)));
+            assert_eq!(*path, parsed.to_string());
+            assert_eq!(None, parsed.format());
+        }
+        let path = BitcoinDerivationPath::<N>::BIP48([
+            ChildIndex::Hardened(0),
+            ChildIndex::Hardened(3),
+            ChildIndex::Normal(0),
+            ChildIndex::Normal(0),
+        ]);
+        assert!(path.to_vec().is_err());
+        let path = BitcoinDerivationPath::<N>::BIP45([
+            ChildIndex::Normal(2),
+            ChildIndex::Normal(2),
+            ChildIndex::Normal(0),
+        ]);
+        assert!(path.to_vec().is_err());
+
+        assert_eq!(
+            "m/48'/0'/1'/2'",
+            BitcoinDerivationPath::<N>::account(&BitcoinFormat::P2WSH, 1)
+                .unwrap()
+                .to_string()
+        );
+        assert!(BitcoinDerivationPath::<N>::account(&BitcoinFormat::P2SH, 0).is_err());
+    }
+
+    #[test]
+    fn invalid_paths() {
+        let unhardened_account = [
+            ChildIndex::Normal(0),
+            ChildIndex::Normal(0),
+            ChildIndex::Normal(0),
+        ];
+        assert!(BitcoinDerivationPath::<N>::BIP84(unhardened_account)
+            .to_vec()
+            .is_err());
+        assert!(BitcoinDerivationPath::<N>::BIP86(unhardened_account)
+            .to_vec()
+            .is_err());
+
+        let hardened_index = [
+            ChildIndex::Hardened(0),
+            ChildIndex::Normal(0),
+            ChildIndex::Hardened(0),
+        ];
+        assert!(BitcoinDerivationPath::<N>::BIP84(hardened_index)
+            .to_vec()
+            .is_err());
+        assert!(BitcoinDerivationPath::<N>::BIP86(hardened_index)
+            .to_vec()
+            .is_err());
+    }
+}
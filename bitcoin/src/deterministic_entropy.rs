@@ -0,0 +1,480 @@
+use crate::derivation_path::BitcoinDerivationPath;
+use crate::extended_private_key::BitcoinExtendedPrivateKey;
+use crate::format::BitcoinFormat;
+use crate::mnemonic::BitcoinMnemonic;
+use crate::network::BitcoinNetwork;
+use crate::private_key::BitcoinPrivateKey;
+use crate::wordlist::{
+    chinese_simplified::ChinessSimplified, chinese_traditional::ChineseTraditional,
+    english::English, french::French, italian::Italian, japanese::Japanese, korean::Korean,
+    spanish::Spanish, BitcoinWordlist,
+};
+use gyu_model::no_std::*;
+use gyu_model::{
+    derivation_path::{ChildIndex, DerivationPathError},
+    extended_private_key::{ExtendedPrivateKey, ExtendedPrivateKeyError},
+    mnemonic::MnemonicError,
+};
+
+use core::marker::PhantomData;
+use hmac::{Hmac, Mac};
+use secp256k1::SecretKey;
+use sha2::Sha512;
+use tiny_keccak::{Hasher, Shake, Xof};
+use zeroize::Zeroizing;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// The BIP85 purpose, the first index of every path
+pub const BIP85_PURPOSE: u32 = 83696968;
+
+/// The HMAC key that turns a derived private key into entropy
+const BIP85_HMAC_KEY: &[u8] = b"bip-entropy-from-k";
+
+/// The BIP85 application numbers
+const APPLICATION_BIP39: u32 = 39;
+const APPLICATION_WIF: u32 = 2;
+const APPLICATION_XPRV: u32 = 32;
+const APPLICATION_HEX: u32 = 128169;
+const APPLICATION_BASE64_PASSWORD: u32 = 707764;
+const APPLICATION_BASE85_PASSWORD: u32 = 707785;
+const APPLICATION_DICE: u32 = 89101;
+
+/// The base85 alphabet of RFC 1924
+const BASE85_ALPHABET: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+#[derive(Debug, Fail)]
+pub enum DeterministicEntropyError {
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "{}", _0)]
+    DerivationPathError(DerivationPathError),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPrivateKeyError(ExtendedPrivateKeyError),
+
+    #[fail(display = "invalid entropy byte length: {}", _0)]
+    InvalidByteLength(u32),
+
+    #[fail(display = "invalid number of dice rolls: {}", _0)]
+    InvalidDiceRolls(u32),
+
+    #[fail(display = "invalid number of dice sides: {}", _0)]
+    InvalidDiceSides(u32),
+
+    #[fail(display = "invalid password length: {}", _0)]
+    InvalidPasswordLength(u32),
+
+    #[fail(display = "{}", _0)]
+    MnemonicError(MnemonicError),
+}
+
+impl From<DerivationPathError> for DeterministicEntropyError {
+    fn from(error: DerivationPathError) -> Self {
+        DeterministicEntropyError::DerivationPathError(error)
+    }
+}
+
+impl From<ExtendedPrivateKeyError> for DeterministicEntropyError {
+    fn from(error: ExtendedPrivateKeyError) -> Self {
+        DeterministicEntropyError::ExtendedPrivateKeyError(error)
+    }
+}
+
+impl From<MnemonicError> for DeterministicEntropyError {
+    fn from(error: MnemonicError) -> Self {
+        DeterministicEntropyError::MnemonicError(error)
+    }
+}
+
+/// A BIP39 wordlist with a BIP85 language code
+pub trait Bip85Wordlist: BitcoinWordlist {
+    const BIP85_LANGUAGE: u32;
+}
+
+impl Bip85Wordlist for English {
+    const BIP85_LANGUAGE: u32 = 0;
+}
+
+impl Bip85Wordlist for Japanese {
+    const BIP85_LANGUAGE: u32 = 1;
+}
+
+impl Bip85Wordlist for Korean {
+    const BIP85_LANGUAGE: u32 = 2;
+}
+
+impl Bip85Wordlist for Spanish {
+    const BIP85_LANGUAGE: u32 = 3;
+}
+
+impl Bip85Wordlist for ChinessSimplified {
+    const BIP85_LANGUAGE: u32 = 4;
+}
+
+impl Bip85Wordlist for ChineseTraditional {
+    const BIP85_LANGUAGE: u32 = 5;
+}
+
+impl Bip85Wordlist for French {
+    const BIP85_LANGUAGE: u32 = 6;
+}
+
+impl Bip85Wordlist for Italian {
+    const BIP85_LANGUAGE: u32 = 7;
+}
+
+/// The BIP85 deterministic random number generator, SHAKE256 seeded with derived entropy
+pub struct Bip85Drng {
+    shake: Shake,
+}
+
+impl Bip85Drng {
+    pub fn new(entropy: &[u8; 64]) -> Self {
+        let mut shake = Shake::v256();
+        shake.update(entropy);
+        Self { shake }
+    }
+
+    /// Fills the buffer with the next bytes of the generator.
+    pub fn read(&mut self, buffer: &mut [u8]) {
+        self.shake.squeeze(buffer);
+    }
+}
+
+/// Returns the entropy of the path from the master key, the HMAC-SHA512 of the private key
+/// at the path under m/83696968'. Every index of the path must be hardened.
+/// https://github.com/bitcoin/bips/blob/master/bip-0085.mediawiki
+pub fn derive_entropy<N: BitcoinNetwork>(
+    master: &BitcoinExtendedPrivateKey<N>,
+    path: &[ChildIndex],
+) -> Result<Zeroizing<[u8; 64]>, DeterministicEntropyError> {
+    if let Some(index) = path.iter().find(|index| index.is_normal()) {
+        return Err(DerivationPathError::InvalidChildNumber(u32::from(*index)).into());
+    }
+    let mut full_path = vec![ChildIndex::hardened(BIP85_PURPOSE)?];
+    full_path.extend_from_slice(path);
+    let private_key = master
+        .derive(&BitcoinDerivationPath::BIP32(full_path, PhantomData))?
+        .to_private_key();
+
+    let mut mac = HmacSha512::new_varkey(BIP85_HMAC_KEY).map_err(ExtendedPrivateKeyError::from)?;
+    mac.input(&*Zeroizing::new(private_key.expose_secret().serialize()));
+    let mut entropy = Zeroizing::new([0u8; 64]);
+    entropy.copy_from_slice(&mac.result().code());
+    Ok(entropy)
+}
+
+/// Returns the BIP39 mnemonic of 12, 15, 18, 21 or 24 words in the language of the wordlist,
+/// at m/83696968'/39'/{language}'/{words}'/{index}'.
+pub fn derive_mnemonic<N: BitcoinNetwork, W: Bip85Wordlist>(
+    master: &BitcoinExtendedPrivateKey<N>,
+    word_count: u8,
+    index: u32,
+) -> Result<BitcoinMnemonic<N, W>, DeterministicEntropyError> {
+    let length = match word_count {
+        12 | 15 | 18 | 21 | 24 => word_count as usize * 4 / 3,
+        _ => return Err(MnemonicError::InvalidWordCount(word_count).into()),
+    };
+    let entropy = derive_entropy(
+        master,
+        &[
+            ChildIndex::hardened(APPLICATION_BIP39)?,
+            ChildIndex::hardened(W::BIP85_LANGUAGE)?,
+            ChildIndex::hardened(word_count as u32)?,
+            ChildIndex::hardened(index)?,
+        ],
+    )?;
+    Ok(BitcoinMnemonic::from_entropy(&entropy[..length])?)
+}
+
+/// Returns the compressed private key, for export as WIF, at m/83696968'/2'/{index}'.
+pub fn derive_private_key<N: BitcoinNetwork>(
+    master: &BitcoinExtendedPrivateKey<N>,
+    index: u32,
+) -> Result<BitcoinPrivateKey<N>, DeterministicEntropyError> {
+    let entropy = derive_entropy(
+        master,
+        &[
+            ChildIndex::hardened(APPLICATION_WIF)?,
+            ChildIndex::hardened(index)?,
+        ],
+    )?;
+    let secret_key =
+        SecretKey::parse_slice(&entropy[..32]).map_err(ExtendedPrivateKeyError::from)?;
+    Ok(BitcoinPrivateKey::from_secp256k1_secret_key(
+        &secret_key,
+        true,
+    ))
+}
+
+/// Returns the master extended private key at m/83696968'/32'/{index}', whose chain code is
+/// the first half of the entropy and whose private key is the second half.
+pub fn derive_extended_private_key<N: BitcoinNetwork>(
+    master: &BitcoinExtendedPrivateKey<N>,
+    index: u32,
+) -> Result<BitcoinExtendedPrivateKey<N>, DeterministicEntropyError> {
+    let entropy = derive_entropy(
+        master,
+        &[
+            ChildIndex::hardened(APPLICATION_XPRV)?,
+            ChildIndex::hardened(index)?,
+        ],
+    )?;
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&entropy[..32]);
+    let secret_key =
+        SecretKey::parse_slice(&entropy[32..]).map_err(ExtendedPrivateKeyError::from)?;
+    let private_key = BitcoinPrivateKey::from_secp256k1_secret_key(&secret_key, true);
+    Ok(BitcoinExtendedPrivateKey::from_master_parts(
+        chain_code,
+        &private_key,
+        &BitcoinFormat::P2PKH,
+    ))
+}
+
+/// Returns 16 to 64 bytes of entropy in hex, at m/83696968'/128169'/{bytes}'/{index}'.
+pub fn derive_hex<N: BitcoinNetwork>(
+    master: &BitcoinExtendedPrivateKey<N>,
+    bytes: u32,
+    index: u32,
+) -> Result<Zeroizing<String>, DeterministicEntropyError> {
+    if !(16..=64).contains(&bytes) {
+        return Err(DeterministicEntropyError::InvalidByteLength(bytes));
+    }
+    let entropy = derive_entropy(
+        master,
+        &[
+            ChildIndex::hardened(APPLICATION_HEX)?,
+            ChildIndex::hardened(bytes)?,
+            ChildIndex::hardened(index)?,
+        ],
+    )?;
+    Ok(Zeroizing::new(hex::encode(&entropy[..bytes as usize])))
+}
+
+/// Returns a password of 20 to 86 characters, the start of the base64 encoding of the
+/// entropy at m/83696968'/707764'/{length}'/{index}'.
+pub fn derive_base64_password<N: BitcoinNetwork>(
+    master: &BitcoinExtendedPrivateKey<N>,
+    length: u32,
+    index: u32,
+) -> Result<Zeroizing<String>, DeterministicEntropyError> {
+    if !(20..=86).contains(&length) {
+        return Err(DeterministicEntropyError::InvalidPasswordLength(length));
+    }
+    let entropy = derive_entropy(
+        master,
+        &[
+            ChildIndex::hardened(APPLICATION_BASE64_PASSWORD)?,
+            ChildIndex::hardened(length)?,
+            ChildIndex::hardened(index)?,
+        ],
+    )?;
+    let mut password = Zeroizing::new(base64::encode(&entropy[..]));
+    password.truncate(length as usize);
+    Ok(password)
+}
+
+/// Returns a password of 10 to 80 characters, the start of the RFC 1924 base85 encoding of
+/// the entropy at m/83696968'/707785'/{length}'/{index}'.
+pub fn derive_base85_password<N: BitcoinNetwork>(
+    master: &BitcoinExtendedPrivateKey<N>,
+    length: u32,
+    index: u32,
+) -> Result<Zeroizing<String>, DeterministicEntropyError> {
+    if !(10..=80).contains(&length) {
+        return Err(DeterministicEntropyError::InvalidPasswordLength(length));
+    }
+    let entropy = derive_entropy(
+        master,
+        &[
+            ChildIndex::hardened(APPLICATION_BASE85_PASSWORD)?,
+            ChildIndex::hardened(length)?,
+            ChildIndex::hardened(index)?,
+        ],
+    )?;
+    // Each 4 bytes encode as 5 big-endian base85 digits
+    let mut password = Zeroizing::new(String::with_capacity(80));
+    for chunk in entropy.chunks(4) {
+        let mut value = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        let mut digits = [0u8; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = BASE85_ALPHABET[(value % 85) as usize];
+            value /= 85;
+        }
+        password.extend(digits.iter().map(|digit| *digit as char));
+    }
+    password.truncate(length as usize);
+    Ok(password)
+}
+
+/// Returns the rolls, from 0 to sides - 1, of a die with 2 or more sides, drawn from the
+/// DRNG of the entropy at m/83696968'/89101'/{sides}'/{rolls}'/{index}'.
+pub fn derive_dice_rolls<N: BitcoinNetwork>(
+    master: &BitcoinExtendedPrivateKey<N>,
+    sides: u32,
+    rolls: u32,
+    index: u32,
+) -> Result<Vec<u32>, DeterministicEntropyError> {
+    if sides < 2 {
+        return Err(DeterministicEntropyError::InvalidDiceSides(sides));
+    }
+    if rolls == 0 {
+        return Err(DeterministicEntropyError::InvalidDiceRolls(rolls));
+    }
+    let entropy = derive_entropy(
+        master,
+        &[
+            ChildIndex::hardened(APPLICATION_DICE)?,
+            ChildIndex::hardened(sides)?,
+            ChildIndex::hardened(rolls)?,
+            ChildIndex::hardened(index)?,
+        ],
+    )?;
+
+    // Rejection sampling of the fewest bits that can hold every side. The results are not
+    // preallocated, as the number of rolls can be up to 2^31 - 1.
+    let bits_per_roll = 32 - (sides - 1).leading_zeros();
+    let bytes_per_roll = bits_per_roll.div_ceil(8) as usize;
+    let mut drng = Bip85Drng::new(&entropy);
+    let mut results = vec![];
+    while results.len() < rolls as usize {
+        let mut trial = [0u8; 4];
+        drng.read(&mut trial[4 - bytes_per_roll..]);
+        let trial = u32::from_be_bytes(trial) >> (bytes_per_roll as u32 * 8 - bits_per_roll);
+        if trial < sides {
+            results.push(trial);
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+    use gyu_model::mnemonic::Mnemonic;
+
+    use core::str::FromStr;
+
+    type N = Mainnet;
+
+    /// The master key of the BIP85 test vectors
+    /// https://github.com/bitcoin/bips/blob/master/bip-0085.mediawiki#test-vectors
+    const MASTER: &str = "xprv9s21ZrQH143K2LBWUUQRFXhucrQqBpKdRRxNVq2zBqsx8HVqFk2uYo8kmbaLLHRdqtQpUm98uKfu3vca1LqdGhUtyoFnCNkfmXRyPXLjbKb";
+
+    fn master() -> BitcoinExtendedPrivateKey<N> {
+        BitcoinExtendedPrivateKey::from_str(MASTER).unwrap()
+    }
+
+    #[test]
+    fn derive_entropy() {
+        let master = master();
+        let entropy =
+            |path: &[ChildIndex]| hex::encode(&super::derive_entropy(&master, path).unwrap()[..]);
+        assert_eq!(
+            "efecfbccffea313214232d29e71563d941229afb4338c21f9517c41aaa0d16f00b83d2a09ef747e7a64e8e2bd5a14869e693da66ce94ac2da570ab7ee48618f7",
+            entropy(&[ChildIndex::Hardened(0), ChildIndex::Hardened(0)])
+        );
+        assert_eq!(
+            "70c6e3e8ebee8dc4c0dbba66076819bb8c09672527c4277ca8729532ad711872218f826919f6b67218adde99018a6df9095ab2b58d803b5b93ec9802085a690e",
+            entropy(&[ChildIndex::Hardened(0), ChildIndex::Hardened(1)])
+        );
+        let normal = [ChildIndex::Hardened(0), ChildIndex::Normal(1)];
+        assert!(super::derive_entropy(&master, &normal).is_err());
+    }
+
+    #[test]
+    fn derive_mnemonic() {
+        let master = master();
+        for (word_count, phrase) in [
+            (12, "girl mad pet galaxy egg matter matrix prison refuse sense ordinary nose"),
+            (18, "near account window bike charge season chef number sketch tomorrow excuse sniff circle vital hockey outdoor supply token"),
+            (24, "puppy ocean match cereal symbol another shed magic wrap hammer bulb intact gadget divorce twin tonight reason outdoor destroy simple truth cigar social volcano"),
+        ]
+        .iter()
+        {
+            let mnemonic = super::derive_mnemonic::<N, English>(&master, *word_count, 0).unwrap();
+            assert_eq!(*phrase, mnemonic.to_phrase().unwrap());
+        }
+        assert!(super::derive_mnemonic::<N, English>(&master, 13, 0).is_err());
+
+        // Other languages derive other entropy, and phrases with their own separator.
+        let mnemonic = super::derive_mnemonic::<N, Japanese>(&master, 12, 0).unwrap();
+        let phrase = mnemonic.to_phrase().unwrap();
+        assert_eq!(12, phrase.split('\u{3000}').count());
+        assert_eq!(mnemonic, BitcoinMnemonic::from_phrase(&phrase).unwrap());
+        assert_ne!(
+            mnemonic.expose_secret(),
+            super::derive_mnemonic::<N, English>(&master, 12, 0)
+                .unwrap()
+                .expose_secret()
+        );
+    }
+
+    #[test]
+    fn derive_private_key() {
+        assert_eq!(
+            "Kzyv4uF39d4Jrw2W7UryTHwZr1zQVNk4dAFyqE6BuMrMh1Za7uhp",
+            super::derive_private_key(&master(), 0).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn derive_extended_private_key() {
+        assert_eq!(
+            "xprv9s21ZrQH143K2srSbCSg4m4kLvPMzcWydgmKEnMmoZUurYuBuYG46c6P71UGXMzmriLzCCBvKQWBUv3vPB3m1SATMhp3uEjXHJ42jFg7myX",
+            super::derive_extended_private_key(&master(), 0).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn derive_hex() {
+        let master = master();
+        assert_eq!(
+            "492db4698cf3b73a5a24998aa3e9d7fa96275d85724a91e71aa2d645442f878555d078fd1f1f67e368976f04137b1f7a0d19232136ca50c44614af72b5582a5c",
+            super::derive_hex(&master, 64, 0).unwrap().as_str()
+        );
+        assert!(super::derive_hex(&master, 15, 0).is_err());
+        assert!(super::derive_hex(&master, 65, 0).is_err());
+    }
+
+    #[test]
+    fn derive_passwords() {
+        let master = master();
+        assert_eq!(
+            "dKLoepugzdVJvdL56ogNV",
+            super::derive_base64_password(&master, 21, 0)
+                .unwrap()
+                .as_str()
+        );
+        assert_eq!(
+            "_s`{TW89)i4`",
+            super::derive_base85_password(&master, 12, 0)
+                .unwrap()
+                .as_str()
+        );
+        assert!(super::derive_base64_password(&master, 19, 0).is_err());
+        assert!(super::derive_base64_password(&master, 87, 0).is_err());
+        assert!(super::derive_base85_password(&master, 9, 0).is_err());
+        assert!(super::derive_base85_password(&master, 81, 0).is_err());
+    }
+
+    #[test]
+    fn derive_dice_rolls() {
+        let master = master();
+        assert_eq!(
+            vec![1, 0, 0, 2, 0, 1, 5, 5, 2, 4],
+            super::derive_dice_rolls(&master, 6, 10, 0).unwrap()
+        );
+        assert!(super::derive_dice_rolls(&master, 1, 10, 0).is_err());
+        assert!(super::derive_dice_rolls(&master, 6, 0, 0).is_err());
+
+        let rolls = super::derive_dice_rolls(&master, 1000, 50, 3).unwrap();
+        assert_eq!(50, rolls.len());
+        assert!(rolls.iter().all(|roll| *roll < 1000));
+    }
+}
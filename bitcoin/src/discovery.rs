@@ -0,0 +1,478 @@
+use crate::address::BitcoinAddress;
+use crate::batch::{derive_address_range, BatchError, BatchOptions};
+use crate::derivation_path::BitcoinDerivationPath;
+use crate::extended_private_key::BitcoinExtendedPrivateKey;
+use crate::extended_public_key::BitcoinExtendedPublicKey;
+use crate::format::BitcoinFormat;
+use crate::mnemonic::BitcoinMnemonic;
+use crate::network::BitcoinNetwork;
+use crate::wordlist::BitcoinWordlist;
+use gyu_model::no_std::*;
+use gyu_model::{
+    address::AddressError,
+    derivation_path::{ChildIndex, DerivationPathError},
+    extended_private_key::ExtendedPrivateKeyError,
+    extended_public_key::{ExtendedPublicKey, ExtendedPublicKeyError},
+    mnemonic::{MnemonicError, MnemonicExtended},
+    transaction::TransactionError,
+};
+
+use core::{marker::PhantomData, ops::Range};
+use std::collections::HashSet;
+
+/// The BIP44 gap limit, the number of consecutive unused addresses after which a chain
+/// is considered to have no more used addresses
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+#[derive(Debug, Fail)]
+pub enum DiscoveryError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}", _0)]
+    BatchError(BatchError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "{}", _0)]
+    DerivationPathError(DerivationPathError),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPrivateKeyError(ExtendedPrivateKeyError),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPublicKeyError(ExtendedPublicKeyError),
+
+    #[fail(display = "invalid gap limit: {}", _0)]
+    InvalidGapLimit(u32),
+
+    #[fail(display = "{}", _0)]
+    MnemonicError(MnemonicError),
+
+    #[fail(display = "address usage oracle error: {}", _0)]
+    OracleError(String),
+
+    #[fail(display = "{}", _0)]
+    TransactionError(TransactionError),
+
+    #[fail(display = "unsupported discovery format: {}", _0)]
+    UnsupportedFormat(BitcoinFormat),
+}
+
+impl From<AddressError> for DiscoveryError {
+    fn from(error: AddressError) -> Self {
+        DiscoveryError::AddressError(error)
+    }
+}
+
+impl From<BatchError> for DiscoveryError {
+    fn from(error: BatchError) -> Self {
+        DiscoveryError::BatchError(error)
+    }
+}
+
+impl From<DerivationPathError> for DiscoveryError {
+    fn from(error: DerivationPathError) -> Self {
+        DiscoveryError::DerivationPathError(error)
+    }
+}
+
+impl From<ExtendedPrivateKeyError> for DiscoveryError {
+    fn from(error: ExtendedPrivateKeyError) -> Self {
+        DiscoveryError::ExtendedPrivateKeyError(error)
+    }
+}
+
+impl From<ExtendedPublicKeyError> for DiscoveryError {
+    fn from(error: ExtendedPublicKeyError) -> Self {
+        DiscoveryError::ExtendedPublicKeyError(error)
+    }
+}
+
+impl From<MnemonicError> for DiscoveryError {
+    fn from(error: MnemonicError) -> Self {
+        DiscoveryError::MnemonicError(error)
+    }
+}
+
+impl From<TransactionError> for DiscoveryError {
+    fn from(error: TransactionError) -> Self {
+        DiscoveryError::TransactionError(error)
+    }
+}
+
+/// An oracle of whether addresses have transaction history, such as a block explorer,
+/// an Electrum server or a node with an address index
+pub trait AddressUsageOracle<N: BitcoinNetwork> {
+    /// Returns true if the address, whose output script is given, has transaction history.
+    fn is_used(
+        &self,
+        address: &BitcoinAddress<N>,
+        script_pub_key: &[u8],
+    ) -> Result<bool, DiscoveryError>;
+
+    /// Returns whether each of the addresses has transaction history. Oracles that can
+    /// answer for many addresses in one request should override this.
+    fn are_used(
+        &self,
+        addresses: &[(BitcoinAddress<N>, Vec<u8>)],
+    ) -> Result<Vec<bool>, DiscoveryError> {
+        addresses
+            .iter()
+            .map(|(address, script_pub_key)| self.is_used(address, script_pub_key))
+            .collect()
+    }
+}
+
+/// An address usage oracle over a set of used addresses and output scripts held in memory
+#[derive(Debug, Clone, Default)]
+pub struct MemoryAddressOracle {
+    addresses: HashSet<String>,
+    script_pub_keys: HashSet<Vec<u8>>,
+}
+
+impl MemoryAddressOracle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the address as used.
+    pub fn insert_address<N: BitcoinNetwork>(&mut self, address: &BitcoinAddress<N>) {
+        self.addresses.insert(address.to_string());
+    }
+
+    /// Marks addresses with the output script as used.
+    pub fn insert_script_pub_key(&mut self, script_pub_key: &[u8]) {
+        self.script_pub_keys.insert(script_pub_key.to_vec());
+    }
+}
+
+impl<N: BitcoinNetwork> AddressUsageOracle<N> for MemoryAddressOracle {
+    fn is_used(
+        &self,
+        address: &BitcoinAddress<N>,
+        script_pub_key: &[u8],
+    ) -> Result<bool, DiscoveryError> {
+        Ok(self.addresses.contains(&address.to_string())
+            || self.script_pub_keys.contains(script_pub_key))
+    }
+}
+
+/// The options of an account discovery
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveryOptions {
+    /// The address format, which selects the purpose: BIP44 for P2PKH, BIP49 for
+    /// P2SH_P2WPKH, BIP84 for Bech32 and BIP86 for P2TR.
+    pub format: BitcoinFormat,
+    /// The number of consecutive unused addresses that ends the scan of a chain.
+    pub gap_limit: u32,
+    /// The number of accounts after which discovery stops even if all were used, if any.
+    pub max_accounts: Option<u32>,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            format: BitcoinFormat::P2PKH,
+            gap_limit: DEFAULT_GAP_LIMIT,
+            max_accounts: None,
+        }
+    }
+}
+
+/// The usage of the external (receive) or internal (change) chain of an account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredChain<N: BitcoinNetwork> {
+    /// The index of the last used address, if any.
+    pub last_used_index: Option<u32>,
+    /// The index of the first address after the last used one.
+    pub next_unused_index: u32,
+    pub next_unused_address: BitcoinAddress<N>,
+}
+
+/// A used account found by account discovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredAccount<N: BitcoinNetwork> {
+    pub account: u32,
+    /// The account extended public key, which knows its origin.
+    pub extended_public_key: BitcoinExtendedPublicKey<N>,
+    pub external: DiscoveredChain<N>,
+    pub internal: DiscoveredChain<N>,
+}
+
+/// Returns the used accounts of the mnemonic, as in `discover_accounts`.
+pub fn discover_mnemonic_accounts<N: BitcoinNetwork, W: BitcoinWordlist>(
+    mnemonic: &BitcoinMnemonic<N, W>,
+    password: Option<&str>,
+    oracle: &dyn AddressUsageOracle<N>,
+    options: &DiscoveryOptions,
+) -> Result<Vec<DiscoveredAccount<N>>, DiscoveryError> {
+    let master = mnemonic.to_extended_private_key(password)?;
+    discover_accounts(&master, oracle, options)
+}
+
+/// Returns the used accounts of the master key, scanning accounts in order as in BIP44.
+/// Each chain is scanned until the gap limit of consecutive unused addresses, and
+/// discovery stops at the first account with no used address on its external chain.
+/// https://github.com/bitcoin/bips/blob/master/bip-0044.mediawiki#account-discovery
+pub fn discover_accounts<N: BitcoinNetwork>(
+    master: &BitcoinExtendedPrivateKey<N>,
+    oracle: &dyn AddressUsageOracle<N>,
+    options: &DiscoveryOptions,
+) -> Result<Vec<DiscoveredAccount<N>>, DiscoveryError> {
+    if options.gap_limit == 0 {
+        return Err(DiscoveryError::InvalidGapLimit(options.gap_limit));
+    }
+    match options.format {
+        BitcoinFormat::P2PKH
+        | BitcoinFormat::P2SH_P2WPKH
+        | BitcoinFormat::Bech32
+        | BitcoinFormat::P2TR => {}
+        _ => return Err(DiscoveryError::UnsupportedFormat(options.format.clone())),
+    };
+
+    let mut accounts = vec![];
+    for account in 0..options.max_accounts.unwrap_or(1 << 31) {
+        let extended_public_key =
+            master.to_account_extended_public_key(&options.format, account)?;
+
+        let external = scan_chain(&extended_public_key, 0, oracle, options)?;
+        if external.last_used_index.is_none() {
+            break;
+        }
+        let internal = scan_chain(&extended_public_key, 1, oracle, options)?;
+        accounts.push(DiscoveredAccount {
+            account,
+            extended_public_key,
+            external,
+            internal,
+        });
+    }
+    Ok(accounts)
+}
+
+/// Returns the usage of the chain of the account, asking the oracle about a gap limit of
+/// addresses at a time.
+pub fn scan_chain<N: BitcoinNetwork>(
+    extended_public_key: &BitcoinExtendedPublicKey<N>,
+    chain: u32,
+    oracle: &dyn AddressUsageOracle<N>,
+    options: &DiscoveryOptions,
+) -> Result<DiscoveredChain<N>, DiscoveryError> {
+    if options.gap_limit == 0 {
+        return Err(DiscoveryError::InvalidGapLimit(options.gap_limit));
+    }
+    let chain = extended_public_key.derive(&BitcoinDerivationPath::BIP32(
+        vec![ChildIndex::normal(chain)?],
+        PhantomData,
+    ))?;
+    let batch_options = BatchOptions {
+        format: options.format.clone(),
+        parallel: false,
+    };
+    let addresses = |indices: Range<u32>| derive_address_range(&chain, indices, &batch_options);
+
+    let (mut last_used_index, mut index, mut gap) = (None, 0u32, 0u32);
+    while gap < options.gap_limit {
+        let batch = addresses(index..index.saturating_add(options.gap_limit - gap))?
+            .into_iter()
+            .map(|batch_address| (batch_address.address, batch_address.script_pub_key))
+            .collect::<Vec<_>>();
+        let used = oracle.are_used(&batch)?;
+        if used.len() != batch.len() {
+            return Err(DiscoveryError::OracleError(format!(
+                "expected {} results, found {}",
+                batch.len(),
+                used.len()
+            )));
+        }
+        for used in used {
+            match used {
+                true => {
+                    last_used_index = Some(index);
+                    gap = 0;
+                }
+                false => gap += 1,
+            }
+            index += 1;
+        }
+    }
+
+    let next_unused_index = last_used_index.map_or(0, |index| index + 1);
+    Ok(DiscoveredChain {
+        last_used_index,
+        next_unused_index,
+        next_unused_address: addresses(next_unused_index..next_unused_index + 1)?
+            .remove(0)
+            .address,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+    use crate::test_vectors::master;
+    use crate::transaction::create_script_pub_key;
+    use crate::wordlist::english::English;
+    use gyu_model::extended_private_key::ExtendedPrivateKey;
+    use gyu_model::mnemonic::Mnemonic;
+
+    use core::str::FromStr;
+
+    type N = Mainnet;
+
+    fn bech32_options() -> DiscoveryOptions {
+        DiscoveryOptions {
+            format: BitcoinFormat::Bech32,
+            ..Default::default()
+        }
+    }
+
+    fn address(master: &BitcoinExtendedPrivateKey<N>, path: &str) -> BitcoinAddress<N> {
+        master
+            .derive(&BitcoinDerivationPath::from_str(path).unwrap())
+            .unwrap()
+            .to_address(&BitcoinFormat::Bech32)
+            .unwrap()
+    }
+
+    #[test]
+    fn discover_accounts() {
+        let master = master(&BitcoinFormat::P2PKH);
+        let mut oracle = MemoryAddressOracle::new();
+        for path in [
+            "m/84'/0'/0'/0/0",
+            "m/84'/0'/0'/0/15",
+            "m/84'/0'/0'/0/40",
+            "m/84'/0'/0'/1/3",
+            "m/84'/0'/1'/0/19",
+            "m/84'/0'/3'/0/0",
+        ]
+        .iter()
+        {
+            oracle.insert_address(&address(&master, path));
+        }
+
+        let accounts = super::discover_accounts(&master, &oracle, &bech32_options()).unwrap();
+        // Account 2 has no used address, so discovery stops before account 3.
+        assert_eq!(2, accounts.len());
+
+        let account = &accounts[0];
+        assert_eq!(0, account.account);
+        assert_eq!(
+            "[73c5da0a/84'/0'/0']",
+            account.extended_public_key.origin().unwrap().to_string()
+        );
+        // Index 40 is past the gap limit after index 15.
+        assert_eq!(Some(15), account.external.last_used_index);
+        assert_eq!(16, account.external.next_unused_index);
+        assert_eq!(
+            address(&master, "m/84'/0'/0'/0/16"),
+            account.external.next_unused_address
+        );
+        assert_eq!(Some(3), account.internal.last_used_index);
+        assert_eq!(4, account.internal.next_unused_index);
+
+        let account = &accounts[1];
+        assert_eq!(1, account.account);
+        assert_eq!(Some(19), account.external.last_used_index);
+        assert_eq!(None, account.internal.last_used_index);
+        assert_eq!(0, account.internal.next_unused_index);
+        assert_eq!(
+            address(&master, "m/84'/0'/1'/1/0"),
+            account.internal.next_unused_address
+        );
+
+        // A larger gap limit finds index 40, and max_accounts ends the scan early.
+        let options = DiscoveryOptions {
+            gap_limit: 25,
+            max_accounts: Some(1),
+            ..bech32_options()
+        };
+        let accounts = super::discover_accounts(&master, &oracle, &options).unwrap();
+        assert_eq!(1, accounts.len());
+        assert_eq!(Some(40), accounts[0].external.last_used_index);
+    }
+
+    #[test]
+    fn script_pub_key_oracle() {
+        let master = master(&BitcoinFormat::P2PKH);
+        let mut oracle = MemoryAddressOracle::new();
+        let used = master
+            .derive(&BitcoinDerivationPath::from_str("m/44'/0'/0'/0/2").unwrap())
+            .unwrap()
+            .to_address(&BitcoinFormat::P2PKH)
+            .unwrap();
+        oracle.insert_script_pub_key(&create_script_pub_key(&used).unwrap());
+
+        let accounts =
+            super::discover_accounts(&master, &oracle, &DiscoveryOptions::default()).unwrap();
+        assert_eq!(1, accounts.len());
+        assert_eq!(Some(2), accounts[0].external.last_used_index);
+    }
+
+    #[test]
+    fn discover_mnemonic_accounts() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = BitcoinMnemonic::<N, English>::from_phrase(phrase).unwrap();
+        let master = mnemonic.to_extended_private_key(None).unwrap();
+        let mut oracle = MemoryAddressOracle::new();
+        oracle.insert_address(&address(&master, "m/84'/0'/0'/0/1"));
+
+        let accounts =
+            super::discover_mnemonic_accounts(&mnemonic, None, &oracle, &bech32_options()).unwrap();
+        assert_eq!(1, accounts.len());
+        assert_eq!(Some(1), accounts[0].external.last_used_index);
+
+        let accounts = super::discover_mnemonic_accounts(
+            &mnemonic,
+            Some("password"),
+            &oracle,
+            &bech32_options(),
+        )
+        .unwrap();
+        assert!(accounts.is_empty());
+    }
+
+    #[test]
+    fn invalid_options() {
+        let master = master(&BitcoinFormat::P2PKH);
+        let oracle = MemoryAddressOracle::new();
+        let options = DiscoveryOptions {
+            gap_limit: 0,
+            ..bech32_options()
+        };
+        assert!(super::discover_accounts(&master, &oracle, &options).is_err());
+        let options = DiscoveryOptions {
+            format: BitcoinFormat::P2WSH,
+            ..Default::default()
+        };
+        assert!(super::discover_accounts(&master, &oracle, &options).is_err());
+    }
+
+    struct ShortOracle;
+
+    impl AddressUsageOracle<N> for ShortOracle {
+        fn is_used(&self, _: &BitcoinAddress<N>, _: &[u8]) -> Result<bool, DiscoveryError> {
+            Ok(false)
+        }
+
+        fn are_used(
+            &self,
+            _: &[(BitcoinAddress<N>, Vec<u8>)],
+        ) -> Result<Vec<bool>, DiscoveryError> {
+            Ok(vec![false])
+        }
+    }
+
+    #[test]
+    fn oracle_result_count() {
+        let account = master(&BitcoinFormat::P2PKH)
+            .derive(&BitcoinDerivationPath::from_str("m/84'/0'/0'").unwrap())
+            .unwrap()
+            .to_extended_public_key();
+        assert!(scan_chain(&account, 0, &ShortOracle, &bech32_options()).is_err());
+    }
+}
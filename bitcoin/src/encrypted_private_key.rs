@@ -0,0 +1,895 @@
+use crate::address::BitcoinAddress;
+use crate::network::BitcoinNetwork;
+use crate::private_key::BitcoinPrivateKey;
+use crate::public_key::BitcoinPublicKey;
+use gyu_model::no_std::*;
+use gyu_model::{address::AddressError, private_key::PrivateKeyError, utilities::crypto::checksum};
+
+use aes::cipher::generic_array::GenericArray;
+use aes::{Aes256, BlockDecrypt, BlockEncrypt, NewBlockCipher};
+use base58::{FromBase58, ToBase58};
+use core::{fmt, marker::PhantomData, str::FromStr};
+use rand::Rng;
+use scrypt::{scrypt, ScryptParams};
+use secp256k1::{PublicKey, SecretKey};
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+/// The prefix of encrypted private keys without EC multiplication
+const NON_EC_MULTIPLIED_PREFIX: [u8; 2] = [0x01, 0x42];
+/// The prefix of encrypted private keys with EC multiplication
+const EC_MULTIPLIED_PREFIX: [u8; 2] = [0x01, 0x43];
+/// The magic bytes of intermediate codes with and without a lot and sequence number
+const INTERMEDIATE_CODE_MAGIC: [u8; 7] = [0x2C, 0xE9, 0xB3, 0xE1, 0xFF, 0x39, 0xE2];
+const INTERMEDIATE_CODE_LOT_SEQUENCE: u8 = 0x51;
+const INTERMEDIATE_CODE_NO_LOT_SEQUENCE: u8 = 0x53;
+/// The magic bytes of confirmation codes
+const CONFIRMATION_CODE_MAGIC: [u8; 5] = [0x64, 0x3B, 0xF6, 0xA8, 0x9A];
+
+/// The flag bits of an encrypted private key
+const FLAG_NON_EC_MULTIPLIED: u8 = 0xC0;
+const FLAG_COMPRESSED: u8 = 0x20;
+const FLAG_LOT_SEQUENCE: u8 = 0x04;
+
+/// The maximum lot and sequence numbers of an intermediate code
+const MAX_LOT: u32 = 1_048_575;
+const MAX_SEQUENCE: u32 = 4095;
+
+#[derive(Debug, Fail)]
+pub enum EncryptedPrivateKeyError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "invalid byte length: {}", _0)]
+    InvalidByteLength(usize),
+
+    #[fail(
+        display = "invalid checksum: {{ expected: {:?}, found: {:?} }}",
+        _0, _1
+    )]
+    InvalidChecksum(String, String),
+
+    #[fail(display = "invalid flag byte: {:#04x}", _0)]
+    InvalidFlag(u8),
+
+    #[fail(display = "invalid lot {} or sequence {}", _0, _1)]
+    InvalidLotSequence(u32, u32),
+
+    #[fail(display = "invalid passphrase")]
+    InvalidPassphrase,
+
+    #[fail(display = "invalid prefix: {:?}", _0)]
+    InvalidPrefix(Vec<u8>),
+
+    #[fail(display = "{}", _0)]
+    PrivateKeyError(PrivateKeyError),
+}
+
+impl From<AddressError> for EncryptedPrivateKeyError {
+    fn from(error: AddressError) -> Self {
+        EncryptedPrivateKeyError::AddressError(error)
+    }
+}
+
+impl From<PrivateKeyError> for EncryptedPrivateKeyError {
+    fn from(error: PrivateKeyError) -> Self {
+        EncryptedPrivateKeyError::PrivateKeyError(error)
+    }
+}
+
+impl From<base58::FromBase58Error> for EncryptedPrivateKeyError {
+    fn from(error: base58::FromBase58Error) -> Self {
+        EncryptedPrivateKeyError::Crate("base58", format!("{:?}", error))
+    }
+}
+
+impl From<scrypt::errors::InvalidOutputLen> for EncryptedPrivateKeyError {
+    fn from(error: scrypt::errors::InvalidOutputLen) -> Self {
+        EncryptedPrivateKeyError::Crate("scrypt", format!("{:?}", error))
+    }
+}
+
+impl From<scrypt::errors::InvalidParams> for EncryptedPrivateKeyError {
+    fn from(error: scrypt::errors::InvalidParams) -> Self {
+        EncryptedPrivateKeyError::Crate("scrypt", format!("{:?}", error))
+    }
+}
+
+impl From<secp256k1::Error> for EncryptedPrivateKeyError {
+    fn from(error: secp256k1::Error) -> Self {
+        EncryptedPrivateKeyError::Crate("libsecp256k1", format!("{:?}", error))
+    }
+}
+
+/// Returns the base58 encoding of the data with a double SHA256 checksum.
+fn to_base58_check(data: &[u8]) -> String {
+    let mut data = data.to_vec();
+    data.extend(&checksum(&data)[0..4]);
+    data.to_base58()
+}
+
+/// Returns the data of a base58 string with a double SHA256 checksum of the expected length.
+fn from_base58_check(string: &str, length: usize) -> Result<Vec<u8>, EncryptedPrivateKeyError> {
+    let data = string.from_base58()?;
+    if data.len() != length + 4 {
+        return Err(EncryptedPrivateKeyError::InvalidByteLength(data.len()));
+    }
+
+    let (data, sum) = data.split_at(length);
+    let expected = &checksum(data)[0..4];
+    if expected != sum {
+        return Err(EncryptedPrivateKeyError::InvalidChecksum(
+            expected.to_base58(),
+            sum.to_base58(),
+        ));
+    }
+    Ok(data.to_vec())
+}
+
+/// Returns the passphrase in Unicode normalization form C, as BIP38 requires.
+fn normalize(passphrase: &str) -> Zeroizing<String> {
+    Zeroizing::new(passphrase.nfc().collect::<String>())
+}
+
+/// Returns the scrypt key of the given length, with N = 2^log_n.
+fn scrypt_key(
+    password: &[u8],
+    salt: &[u8],
+    log_n: u8,
+    r: u32,
+    p: u32,
+    length: usize,
+) -> Result<Zeroizing<Vec<u8>>, EncryptedPrivateKeyError> {
+    let mut key = Zeroizing::new(vec![0u8; length]);
+    scrypt(password, salt, &ScryptParams::new(log_n, r, p)?, &mut key)?;
+    Ok(key)
+}
+
+/// Returns the AES-256 encryption of the 16 byte block XORed with the mask.
+fn encrypt_block(block: &[u8], mask: &[u8], key: &[u8]) -> [u8; 16] {
+    let mut data = [0u8; 16];
+    for (i, byte) in data.iter_mut().enumerate() {
+        *byte = block[i] ^ mask[i];
+    }
+
+    let mut block = GenericArray::clone_from_slice(&data);
+    Aes256::new(GenericArray::from_slice(key)).encrypt_block(&mut block);
+    data.copy_from_slice(&block);
+    data
+}
+
+/// Returns the AES-256 decryption of the 16 byte block XORed with the mask.
+fn decrypt_block(block: &[u8], mask: &[u8], key: &[u8]) -> [u8; 16] {
+    let mut decrypted = GenericArray::clone_from_slice(block);
+    Aes256::new(GenericArray::from_slice(key)).decrypt_block(&mut decrypted);
+
+    let mut data = [0u8; 16];
+    for (i, byte) in data.iter_mut().enumerate() {
+        *byte = decrypted[i] ^ mask[i];
+    }
+    data
+}
+
+/// Returns the first 4 bytes of the double SHA256 of the P2PKH address of the public key.
+fn address_hash<N: BitcoinNetwork>(
+    public_key: &BitcoinPublicKey<N>,
+) -> Result<[u8; 4], EncryptedPrivateKeyError> {
+    let address = BitcoinAddress::<N>::p2pkh(public_key)?;
+    let mut hash = [0u8; 4];
+    hash.copy_from_slice(&checksum(address.to_string().as_bytes())[0..4]);
+    Ok(hash)
+}
+
+/// Returns the passfactor of the passphrase for the owner entropy, with or without a
+/// lot and sequence number.
+fn passfactor(
+    passphrase: &str,
+    owner_entropy: &[u8],
+    has_lot_sequence: bool,
+) -> Result<SecretKey, EncryptedPrivateKeyError> {
+    let passphrase = normalize(passphrase);
+    let passfactor = match has_lot_sequence {
+        true => {
+            let prefactor = scrypt_key(passphrase.as_bytes(), &owner_entropy[0..4], 14, 8, 8, 32)?;
+            let data = Zeroizing::new([&prefactor[..], owner_entropy].concat());
+            Zeroizing::new(checksum(&data))
+        }
+        false => scrypt_key(passphrase.as_bytes(), owner_entropy, 14, 8, 8, 32)?,
+    };
+    Ok(SecretKey::parse_slice(&passfactor)?)
+}
+
+/// Returns the point secret_key * point, multiplied in constant time.
+fn multiply<N: BitcoinNetwork>(
+    point: PublicKey,
+    secret_key: &SecretKey,
+) -> Result<PublicKey, EncryptedPrivateKeyError> {
+    Ok(BitcoinPrivateKey::<N>::from_secp256k1_secret_key(secret_key, true)
+        .shared_point(&BitcoinPublicKey::from_secp256k1_public_key(point, true))?
+        .to_secp256k1_public_key())
+}
+
+/// Returns the derived halves used to encrypt an EC multiplied key and its confirmation code.
+fn ec_derived_halves(
+    passpoint: &[u8],
+    address_hash: &[u8],
+    owner_entropy: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, EncryptedPrivateKeyError> {
+    scrypt_key(
+        passpoint,
+        &[address_hash, owner_entropy].concat(),
+        10,
+        1,
+        1,
+        64,
+    )
+}
+
+/// A BIP38 intermediate code, which lets a third party create encrypted private keys
+/// for the owner of the passphrase without learning the private keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinIntermediateCode {
+    owner_entropy: [u8; 8],
+    passpoint: [u8; 33],
+    has_lot_sequence: bool,
+}
+
+impl BitcoinIntermediateCode {
+    /// Returns an intermediate code for the passphrase with a random owner salt.
+    pub fn new<R: Rng>(passphrase: &str, rng: &mut R) -> Result<Self, EncryptedPrivateKeyError> {
+        let owner_entropy: [u8; 8] = rng.gen();
+        Self::from_owner_entropy(passphrase, owner_entropy, false)
+    }
+
+    /// Returns an intermediate code for the passphrase with a random owner salt, and the
+    /// lot and sequence numbers that the owner uses to tell encrypted keys apart.
+    pub fn with_lot_sequence<R: Rng>(
+        passphrase: &str,
+        lot: u32,
+        sequence: u32,
+        rng: &mut R,
+    ) -> Result<Self, EncryptedPrivateKeyError> {
+        if lot > MAX_LOT || sequence > MAX_SEQUENCE {
+            return Err(EncryptedPrivateKeyError::InvalidLotSequence(lot, sequence));
+        }
+
+        let owner_salt: [u8; 4] = rng.gen();
+        let mut owner_entropy = [0u8; 8];
+        owner_entropy[0..4].copy_from_slice(&owner_salt);
+        owner_entropy[4..8].copy_from_slice(&(lot * 4096 + sequence).to_be_bytes());
+        Self::from_owner_entropy(passphrase, owner_entropy, true)
+    }
+
+    fn from_owner_entropy(
+        passphrase: &str,
+        owner_entropy: [u8; 8],
+        has_lot_sequence: bool,
+    ) -> Result<Self, EncryptedPrivateKeyError> {
+        let passfactor = passfactor(passphrase, &owner_entropy, has_lot_sequence)?;
+        Ok(Self {
+            owner_entropy,
+            passpoint: PublicKey::from_secret_key(&passfactor).serialize_compressed(),
+            has_lot_sequence,
+        })
+    }
+
+    /// Returns the lot and sequence numbers of the intermediate code, if it has them.
+    pub fn lot_sequence(&self) -> Option<(u32, u32)> {
+        lot_sequence(&self.owner_entropy, self.has_lot_sequence)
+    }
+}
+
+/// Returns the lot and sequence numbers of the owner entropy, if it has them.
+fn lot_sequence(owner_entropy: &[u8], has_lot_sequence: bool) -> Option<(u32, u32)> {
+    match has_lot_sequence {
+        true => {
+            let mut number = [0u8; 4];
+            number.copy_from_slice(&owner_entropy[4..8]);
+            let number = u32::from_be_bytes(number);
+            Some((number / 4096, number % 4096))
+        }
+        false => None,
+    }
+}
+
+impl FromStr for BitcoinIntermediateCode {
+    type Err = EncryptedPrivateKeyError;
+
+    fn from_str(intermediate_code: &str) -> Result<Self, Self::Err> {
+        let data = from_base58_check(intermediate_code, 49)?;
+        let has_lot_sequence = match (data[0..7] == INTERMEDIATE_CODE_MAGIC[..], data[7]) {
+            (true, INTERMEDIATE_CODE_LOT_SEQUENCE) => true,
+            (true, INTERMEDIATE_CODE_NO_LOT_SEQUENCE) => false,
+            _ => return Err(EncryptedPrivateKeyError::InvalidPrefix(data[0..8].to_vec())),
+        };
+
+        let mut owner_entropy = [0u8; 8];
+        owner_entropy.copy_from_slice(&data[8..16]);
+        let mut passpoint = [0u8; 33];
+        passpoint.copy_from_slice(&data[16..49]);
+        let _ = PublicKey::parse_compressed(&passpoint)?;
+
+        Ok(Self {
+            owner_entropy,
+            passpoint,
+            has_lot_sequence,
+        })
+    }
+}
+
+impl fmt::Display for BitcoinIntermediateCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut data = INTERMEDIATE_CODE_MAGIC.to_vec();
+        data.push(match self.has_lot_sequence {
+            true => INTERMEDIATE_CODE_LOT_SEQUENCE,
+            false => INTERMEDIATE_CODE_NO_LOT_SEQUENCE,
+        });
+        data.extend(&self.owner_entropy);
+        data.extend(&self.passpoint[..]);
+        write!(f, "{}", to_base58_check(&data))
+    }
+}
+
+/// A BIP38 confirmation code, which proves to the owner of the passphrase that an
+/// encrypted private key created from their intermediate code belongs to an address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinConfirmationCode<N: BitcoinNetwork> {
+    flag: u8,
+    address_hash: [u8; 4],
+    owner_entropy: [u8; 8],
+    encrypted_pointb: [u8; 33],
+    _network: PhantomData<N>,
+}
+
+impl<N: BitcoinNetwork> BitcoinConfirmationCode<N> {
+    /// Returns the address of the encrypted private key if the confirmation code is valid
+    /// for the passphrase.
+    pub fn verify(&self, passphrase: &str) -> Result<BitcoinAddress<N>, EncryptedPrivateKeyError> {
+        let has_lot_sequence = self.flag & FLAG_LOT_SEQUENCE != 0;
+        let passfactor = passfactor(passphrase, &self.owner_entropy, has_lot_sequence)?;
+        let passpoint = PublicKey::from_secret_key(&passfactor).serialize_compressed();
+        let derived = ec_derived_halves(&passpoint, &self.address_hash, &self.owner_entropy)?;
+
+        let mut pointb = [0u8; 33];
+        pointb[0] = self.encrypted_pointb[0] ^ (derived[63] & 0x01);
+        pointb[1..17].copy_from_slice(&decrypt_block(
+            &self.encrypted_pointb[1..17],
+            &derived[0..16],
+            &derived[32..64],
+        ));
+        pointb[17..33].copy_from_slice(&decrypt_block(
+            &self.encrypted_pointb[17..33],
+            &derived[16..32],
+            &derived[32..64],
+        ));
+
+        let point = match PublicKey::parse_compressed(&pointb) {
+            Ok(point) => multiply::<N>(point, &passfactor)?,
+            Err(_) => return Err(EncryptedPrivateKeyError::InvalidPassphrase),
+        };
+
+        let public_key = BitcoinPublicKey::<N>::from_secp256k1_public_key(
+            point,
+            self.flag & FLAG_COMPRESSED != 0,
+        );
+        match address_hash(&public_key)? == self.address_hash {
+            true => Ok(BitcoinAddress::<N>::p2pkh(&public_key)?),
+            false => Err(EncryptedPrivateKeyError::InvalidPassphrase),
+        }
+    }
+
+    /// Returns the lot and sequence numbers of the confirmation code, if it has them.
+    pub fn lot_sequence(&self) -> Option<(u32, u32)> {
+        lot_sequence(&self.owner_entropy, self.flag & FLAG_LOT_SEQUENCE != 0)
+    }
+}
+
+impl<N: BitcoinNetwork> FromStr for BitcoinConfirmationCode<N> {
+    type Err = EncryptedPrivateKeyError;
+
+    fn from_str(confirmation_code: &str) -> Result<Self, Self::Err> {
+        let data = from_base58_check(confirmation_code, 51)?;
+        if data[0..5] != CONFIRMATION_CODE_MAGIC {
+            return Err(EncryptedPrivateKeyError::InvalidPrefix(data[0..5].to_vec()));
+        }
+
+        let mut address_hash = [0u8; 4];
+        address_hash.copy_from_slice(&data[6..10]);
+        let mut owner_entropy = [0u8; 8];
+        owner_entropy.copy_from_slice(&data[10..18]);
+        let mut encrypted_pointb = [0u8; 33];
+        encrypted_pointb.copy_from_slice(&data[18..51]);
+
+        Ok(Self {
+            flag: data[5],
+            address_hash,
+            owner_entropy,
+            encrypted_pointb,
+            _network: PhantomData,
+        })
+    }
+}
+
+impl<N: BitcoinNetwork> fmt::Display for BitcoinConfirmationCode<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut data = CONFIRMATION_CODE_MAGIC.to_vec();
+        data.push(self.flag);
+        data.extend(&self.address_hash);
+        data.extend(&self.owner_entropy);
+        data.extend(&self.encrypted_pointb[..]);
+        write!(f, "{}", to_base58_check(&data))
+    }
+}
+
+/// A BIP38 passphrase-encrypted private key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinEncryptedPrivateKey<N: BitcoinNetwork> {
+    prefix: [u8; 2],
+    flag: u8,
+    address_hash: [u8; 4],
+    encrypted: [u8; 32],
+    _network: PhantomData<N>,
+}
+
+impl<N: BitcoinNetwork> BitcoinEncryptedPrivateKey<N> {
+    /// Returns the private key encrypted with the passphrase, without EC multiplication.
+    /// The passphrase is normalized to Unicode normalization form C, as BIP38 requires.
+    pub fn encrypt(
+        private_key: &BitcoinPrivateKey<N>,
+        passphrase: &str,
+    ) -> Result<Self, EncryptedPrivateKeyError> {
+        let public_key = BitcoinPublicKey::<N>::from_secp256k1_public_key(
+            PublicKey::from_secret_key(&private_key.to_secp256k1_secret_key()),
+            private_key.is_compressed(),
+        );
+        let address_hash = address_hash(&public_key)?;
+        let derived = scrypt_key(normalize(passphrase).as_bytes(), &address_hash, 14, 8, 8, 64)?;
+        let secret_key = Zeroizing::new(private_key.to_secp256k1_secret_key().serialize());
+
+        let mut encrypted = [0u8; 32];
+        encrypted[0..16].copy_from_slice(&encrypt_block(
+            &secret_key[0..16],
+            &derived[0..16],
+            &derived[32..64],
+        ));
+        encrypted[16..32].copy_from_slice(&encrypt_block(
+            &secret_key[16..32],
+            &derived[16..32],
+            &derived[32..64],
+        ));
+
+        let mut flag = FLAG_NON_EC_MULTIPLIED;
+        if private_key.is_compressed() {
+            flag |= FLAG_COMPRESSED;
+        }
+
+        Ok(Self {
+            prefix: NON_EC_MULTIPLIED_PREFIX,
+            flag,
+            address_hash,
+            encrypted,
+            _network: PhantomData,
+        })
+    }
+
+    /// Returns a new encrypted private key for the owner of the intermediate code, with its
+    /// confirmation code. The seed is drawn from the random number generator.
+    pub fn from_intermediate_code<R: Rng>(
+        intermediate_code: &BitcoinIntermediateCode,
+        compressed: bool,
+        rng: &mut R,
+    ) -> Result<(Self, BitcoinConfirmationCode<N>), EncryptedPrivateKeyError> {
+        let mut seedb = Zeroizing::new([0u8; 24]);
+        rng.fill(&mut *seedb);
+        Self::from_intermediate_code_with_seed(intermediate_code, compressed, &seedb)
+    }
+
+    /// Returns the encrypted private key for the owner of the intermediate code generated
+    /// from the given 24 byte seed, with its confirmation code.
+    pub fn from_intermediate_code_with_seed(
+        intermediate_code: &BitcoinIntermediateCode,
+        compressed: bool,
+        seedb: &[u8; 24],
+    ) -> Result<(Self, BitcoinConfirmationCode<N>), EncryptedPrivateKeyError> {
+        let factorb = SecretKey::parse_slice(&Zeroizing::new(checksum(seedb)))?;
+        let point = multiply::<N>(
+            PublicKey::parse_compressed(&intermediate_code.passpoint)?,
+            &factorb,
+        )?;
+
+        let public_key = BitcoinPublicKey::<N>::from_secp256k1_public_key(point, compressed);
+        let address_hash = address_hash(&public_key)?;
+        let owner_entropy = intermediate_code.owner_entropy;
+        let derived =
+            ec_derived_halves(&intermediate_code.passpoint, &address_hash, &owner_entropy)?;
+
+        let encrypted_half1 = encrypt_block(&seedb[0..16], &derived[0..16], &derived[32..64]);
+        let encrypted_half2 = encrypt_block(
+            &[&encrypted_half1[8..16], &seedb[16..24]].concat(),
+            &derived[16..32],
+            &derived[32..64],
+        );
+
+        let mut flag = 0u8;
+        if compressed {
+            flag |= FLAG_COMPRESSED;
+        }
+        if intermediate_code.has_lot_sequence {
+            flag |= FLAG_LOT_SEQUENCE;
+        }
+
+        let mut encrypted = [0u8; 32];
+        encrypted[0..8].copy_from_slice(&owner_entropy);
+        encrypted[8..16].copy_from_slice(&encrypted_half1[0..8]);
+        encrypted[16..32].copy_from_slice(&encrypted_half2);
+
+        let pointb = PublicKey::from_secret_key(&factorb).serialize_compressed();
+        let mut encrypted_pointb = [0u8; 33];
+        encrypted_pointb[0] = pointb[0] ^ (derived[63] & 0x01);
+        encrypted_pointb[1..17].copy_from_slice(&encrypt_block(
+            &pointb[1..17],
+            &derived[0..16],
+            &derived[32..64],
+        ));
+        encrypted_pointb[17..33].copy_from_slice(&encrypt_block(
+            &pointb[17..33],
+            &derived[16..32],
+            &derived[32..64],
+        ));
+
+        let encrypted_private_key = Self {
+            prefix: EC_MULTIPLIED_PREFIX,
+            flag,
+            address_hash,
+            encrypted,
+            _network: PhantomData,
+        };
+        let confirmation_code = BitcoinConfirmationCode {
+            flag,
+            address_hash,
+            owner_entropy,
+            encrypted_pointb,
+            _network: PhantomData,
+        };
+        Ok((encrypted_private_key, confirmation_code))
+    }
+
+    /// Returns the private key decrypted with the passphrase, after the passphrase is
+    /// normalized to Unicode normalization form C.
+    pub fn decrypt(
+        &self,
+        passphrase: &str,
+    ) -> Result<BitcoinPrivateKey<N>, EncryptedPrivateKeyError> {
+        let secret_key = match self.is_ec_multiplied() {
+            true => {
+                let owner_entropy = &self.encrypted[0..8];
+                let has_lot_sequence = self.flag & FLAG_LOT_SEQUENCE != 0;
+                let passfactor = passfactor(passphrase, owner_entropy, has_lot_sequence)?;
+                let passpoint = PublicKey::from_secret_key(&passfactor).serialize_compressed();
+                let derived = ec_derived_halves(&passpoint, &self.address_hash, owner_entropy)?;
+
+                let decrypted_half2 = Zeroizing::new(decrypt_block(
+                    &self.encrypted[16..32],
+                    &derived[16..32],
+                    &derived[32..64],
+                ));
+                let encrypted_half1 = [&self.encrypted[8..16], &decrypted_half2[0..8]].concat();
+                let decrypted_half1 = Zeroizing::new(decrypt_block(
+                    &encrypted_half1,
+                    &derived[0..16],
+                    &derived[32..64],
+                ));
+
+                let seedb = Zeroizing::new([&decrypted_half1[..], &decrypted_half2[8..16]].concat());
+                let factorb = SecretKey::parse_slice(&Zeroizing::new(checksum(&seedb)))?;
+
+                let mut secret_key = passfactor;
+                secret_key.tweak_mul_assign(&factorb)?;
+                secret_key
+            }
+            false => {
+                let passphrase = normalize(passphrase);
+                let derived = scrypt_key(passphrase.as_bytes(), &self.address_hash, 14, 8, 8, 64)?;
+                let mut secret_key = Zeroizing::new([0u8; 32]);
+                secret_key[0..16].copy_from_slice(&decrypt_block(
+                    &self.encrypted[0..16],
+                    &derived[0..16],
+                    &derived[32..64],
+                ));
+                secret_key[16..32].copy_from_slice(&decrypt_block(
+                    &self.encrypted[16..32],
+                    &derived[16..32],
+                    &derived[32..64],
+                ));
+                match SecretKey::parse(&secret_key) {
+                    Ok(secret_key) => secret_key,
+                    Err(_) => return Err(EncryptedPrivateKeyError::InvalidPassphrase),
+                }
+            }
+        };
+
+        let private_key =
+            BitcoinPrivateKey::<N>::from_secp256k1_secret_key(&secret_key, self.is_compressed());
+        let public_key = BitcoinPublicKey::<N>::from_secp256k1_public_key(
+            PublicKey::from_secret_key(&secret_key),
+            self.is_compressed(),
+        );
+        match address_hash(&public_key)? == self.address_hash {
+            true => Ok(private_key),
+            false => Err(EncryptedPrivateKeyError::InvalidPassphrase),
+        }
+    }
+
+    /// Returns true if the private key was created from an intermediate code.
+    pub fn is_ec_multiplied(&self) -> bool {
+        self.prefix == EC_MULTIPLIED_PREFIX
+    }
+
+    /// Returns true if the private key corresponds to a compressed public key.
+    pub fn is_compressed(&self) -> bool {
+        self.flag & FLAG_COMPRESSED != 0
+    }
+
+    /// Returns the lot and sequence numbers of an EC multiplied private key, if it has them.
+    pub fn lot_sequence(&self) -> Option<(u32, u32)> {
+        lot_sequence(
+            &self.encrypted[0..8],
+            self.is_ec_multiplied() && self.flag & FLAG_LOT_SEQUENCE != 0,
+        )
+    }
+}
+
+impl<N: BitcoinNetwork> FromStr for BitcoinEncryptedPrivateKey<N> {
+    type Err = EncryptedPrivateKeyError;
+
+    fn from_str(encrypted_private_key: &str) -> Result<Self, Self::Err> {
+        let data = from_base58_check(encrypted_private_key, 39)?;
+
+        let mut prefix = [0u8; 2];
+        prefix.copy_from_slice(&data[0..2]);
+        let flag = data[2];
+        let valid_flag = match prefix {
+            NON_EC_MULTIPLIED_PREFIX => flag & !FLAG_COMPRESSED == FLAG_NON_EC_MULTIPLIED,
+            EC_MULTIPLIED_PREFIX => flag & !(FLAG_COMPRESSED | FLAG_LOT_SEQUENCE) == 0,
+            _ => return Err(EncryptedPrivateKeyError::InvalidPrefix(prefix.to_vec())),
+        };
+        if !valid_flag {
+            return Err(EncryptedPrivateKeyError::InvalidFlag(flag));
+        }
+
+        let mut address_hash = [0u8; 4];
+        address_hash.copy_from_slice(&data[3..7]);
+        let mut encrypted = [0u8; 32];
+        encrypted.copy_from_slice(&data[7..39]);
+
+        Ok(Self {
+            prefix,
+            flag,
+            address_hash,
+            encrypted,
+            _network: PhantomData,
+        })
+    }
+}
+
+impl<N: BitcoinNetwork> fmt::Display for BitcoinEncryptedPrivateKey<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut data = self.prefix.to_vec();
+        data.push(self.flag);
+        data.extend(&self.address_hash);
+        data.extend(&self.encrypted);
+        write!(f, "{}", to_base58_check(&data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::BitcoinFormat;
+    use crate::network::Mainnet;
+    use gyu_model::private_key::PrivateKey;
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    type N = Mainnet;
+
+    fn test_decrypt(passphrase: &str, encrypted_private_key: &str, private_key: &str) {
+        let encrypted = BitcoinEncryptedPrivateKey::<N>::from_str(encrypted_private_key).unwrap();
+        assert_eq!(encrypted_private_key, encrypted.to_string());
+        assert_eq!(
+            private_key,
+            encrypted.decrypt(passphrase).unwrap().to_string()
+        );
+        assert!(matches!(
+            encrypted.decrypt("wrong passphrase"),
+            Err(EncryptedPrivateKeyError::InvalidPassphrase)
+        ));
+    }
+
+    fn test_encrypt(passphrase: &str, encrypted_private_key: &str, private_key: &str) {
+        let private_key = BitcoinPrivateKey::<N>::from_str(private_key).unwrap();
+        assert_eq!(
+            encrypted_private_key,
+            BitcoinEncryptedPrivateKey::encrypt(&private_key, passphrase)
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    mod non_ec_multiplied {
+        use super::*;
+
+        // (passphrase, encrypted private key, private key)
+        const KEYPAIRS: [(&str, &str, &str); 5] = [
+            (
+                "TestingOneTwoThree",
+                "6PRVWUbkzzsbcVac2qwfssoUJAN1Xhrg6bNk8J7Nzm5H7kxEbn2Nh2ZoGg",
+                "5KN7MzqK5wt2TP1fQCYyHBtDrXdJuXbUzm4A9rKAteGu3Qi5CVR",
+            ),
+            (
+                "Satoshi",
+                "6PRNFFkZc2NZ6dJqFfhRoFNMR9Lnyj7dYGrzdgXXVMXcxoKTePPX1dWByq",
+                "5HtasZ6ofTHP6HCwTqTkLDuLQisYPah7aUnSKfC7h4hMUVw2gi5",
+            ),
+            (
+                "TestingOneTwoThree",
+                "6PYNKZ1EAgYgmQfmNVamxyXVWHzK5s6DGhwP4J5o44cvXdoY7sRzhtpUeo",
+                "L44B5gGEpqEDRS9vVPz7QT35jcBG2r3CZwSwQ4fCewXAhAhqGVpP",
+            ),
+            (
+                "Satoshi",
+                "6PYLtMnXvfG3oJde97zRyLYFZCYizPU5T3LwgdYJz1fRhh16bU7u6PPmY7",
+                "KwYgW8gcxj1JWJXhPSu4Fqwzfhp5Yfi42mdYmMa4XqK7NJxXUSK7",
+            ),
+            (
+                "\u{03D2}\u{0301}\u{0000}\u{10400}\u{1F4A9}",
+                "6PRW5o9FLp4gJDDVqJQKJFTpMvdsSGJxMYHtHaQBF3ooa8mwD69bapcDQn",
+                "5Jajm8eQ22H3pGWLEVCXyvND8dQZhiQhoLJNKjYXk9roUFTMSZ4",
+            ),
+        ];
+
+        #[test]
+        fn decrypt() {
+            KEYPAIRS
+                .iter()
+                .for_each(|(passphrase, encrypted, private_key)| {
+                    test_decrypt(passphrase, encrypted, private_key);
+                });
+        }
+
+        #[test]
+        fn encrypt() {
+            KEYPAIRS
+                .iter()
+                .for_each(|(passphrase, encrypted, private_key)| {
+                    test_encrypt(passphrase, encrypted, private_key);
+                });
+        }
+
+        #[test]
+        fn normalized_passphrase() {
+            let (_, encrypted, private_key) = KEYPAIRS[4];
+            let composed = "\u{03D3}\u{0000}\u{10400}\u{1F4A9}";
+            test_decrypt(composed, encrypted, private_key);
+            test_encrypt(composed, encrypted, private_key);
+        }
+    }
+
+    mod ec_multiplied {
+        use super::*;
+
+        // (passphrase, encrypted private key, private key, lot and sequence)
+        const KEYPAIRS: [(&str, &str, &str, Option<(u32, u32)>); 4] = [
+            (
+                "TestingOneTwoThree",
+                "6PfQu77ygVyJLZjfvMLyhLMQbYnu5uguoJJ4kMCLqWwPEdfpwANVS76gTX",
+                "5K4caxezwjGCGfnoPTZ8tMcJBLB7Jvyjv4xxeacadhq8nLisLR2",
+                None,
+            ),
+            (
+                "Satoshi",
+                "6PfLGnQs6VZnrNpmVKfjotbnQuaJK4KZoPFrAjx1JMJUa1Ft8gnf5WxfKd",
+                "5KJ51SgxWaAYR13zd9ReMhJpwrcX47xTJh2D3fGPG9CM8vkv5sH",
+                None,
+            ),
+            (
+                "MOLON LABE",
+                "6PgNBNNzDkKdhkT6uJntUXwwzQV8Rr2tZcbkDcuC9DZRsS6AtHts4Ypo1j",
+                "5JLdxTtcTHcfYcmJsNVy1v2PMDx432JPoYcBTVVRHpPaxUrdtf8",
+                Some((263183, 1)),
+            ),
+            (
+                "\u{039C}\u{039F}\u{039B}\u{03A9}\u{039D} \u{039B}\u{0391}\u{0392}\u{0395}",
+                "6PgGWtx25kUg8QWvwuJAgorN6k9FbE25rv5dMRwu5SKMnfpfVe5mar2ngH",
+                "5KMKKuUmAkiNbA3DazMQiLfDq47qs8MAEThm4yL8R2PhV1ov33D",
+                Some((806938, 1)),
+            ),
+        ];
+
+        #[test]
+        fn decrypt() {
+            KEYPAIRS
+                .iter()
+                .for_each(|(passphrase, encrypted, private_key, _)| {
+                    test_decrypt(passphrase, encrypted, private_key);
+                });
+        }
+
+        #[test]
+        fn lot_sequence() {
+            KEYPAIRS.iter().for_each(|(_, encrypted, _, lot_sequence)| {
+                let encrypted = BitcoinEncryptedPrivateKey::<N>::from_str(encrypted).unwrap();
+                assert!(encrypted.is_ec_multiplied());
+                assert_eq!(*lot_sequence, encrypted.lot_sequence());
+            });
+        }
+
+        #[test]
+        fn confirmation_code() {
+            let confirmation_code = BitcoinConfirmationCode::<N>::from_str(
+                "cfrm38V8aXBn7JWA1ESmFMUn6erxeBGZGAxJPY4e36S9QWkzZKtaVqLNMgnifETYw7BPwWC9aPD",
+            )
+            .unwrap();
+            assert_eq!(
+                "1Jscj8ALrYu2y9TD8NrpvDBugPedmbj4Yh",
+                confirmation_code.verify("MOLON LABE").unwrap().to_string()
+            );
+            assert!(confirmation_code.verify("wrong passphrase").is_err());
+        }
+
+        #[test]
+        fn intermediate_code() {
+            let mut rng = XorShiftRng::seed_from_u64(38);
+            for (lot_sequence, compressed) in
+                [(None, false), (None, true), (Some((7, 9)), true)].iter()
+            {
+                let intermediate_code = match lot_sequence {
+                    Some((lot, sequence)) => BitcoinIntermediateCode::with_lot_sequence(
+                        "passphrase",
+                        *lot,
+                        *sequence,
+                        &mut rng,
+                    ),
+                    None => BitcoinIntermediateCode::new("passphrase", &mut rng),
+                }
+                .unwrap();
+                let intermediate_code =
+                    BitcoinIntermediateCode::from_str(&intermediate_code.to_string()).unwrap();
+                assert!(intermediate_code.to_string().starts_with("passphrase"));
+                assert_eq!(*lot_sequence, intermediate_code.lot_sequence());
+
+                let (encrypted, confirmation_code) =
+                    BitcoinEncryptedPrivateKey::<N>::from_intermediate_code(
+                        &intermediate_code,
+                        *compressed,
+                        &mut rng,
+                    )
+                    .unwrap();
+                assert!(encrypted.to_string().starts_with("6P"));
+                assert!(confirmation_code.to_string().starts_with("cfrm38"));
+                assert_eq!(*lot_sequence, encrypted.lot_sequence());
+
+                let private_key = encrypted.decrypt("passphrase").unwrap();
+                assert_eq!(*compressed, private_key.is_compressed());
+                assert_eq!(
+                    private_key.to_address(&BitcoinFormat::P2PKH).unwrap(),
+                    confirmation_code.verify("passphrase").unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_encrypted_private_key() {
+        assert!(BitcoinEncryptedPrivateKey::<N>::from_str(
+            "6PRVWUbkzzsbcVac2qwfssoUJAN1Xhrg6bNk8J7Nzm5H7kxEbn2Nh2ZoGh"
+        )
+        .is_err());
+        assert!(
+            BitcoinEncryptedPrivateKey::<N>::from_str("6PRVWUbkzzsbcVac2qwfssoUJAN1X").is_err()
+        );
+    }
+}
@@ -1,4 +1,6 @@
+use gyu_model::no_std::*;
 use gyu_model::{
+    address::AddressError,
     derivation_path::{ChildIndex, DerivationPath},
     extended_private_key::{ExtendedPrivateKey, ExtendedPrivateKeyError},
     extended_public_key::ExtendedPublicKey,
@@ -11,8 +13,9 @@ use core::str::FromStr;
 
 use crate::{
     address::BitcoinAddress, derivation_path::BitcoinDerivationPath,
-    extended_public_key::BitcoinExtendedPublicKey, format::BitcoinFormat, network::BitcoinNetwork,
-    private_key::BitcoinPrivateKey, public_key::BitcoinPublicKey,
+    extended_public_key::BitcoinExtendedPublicKey, format::BitcoinFormat, key_origin::KeyOrigin,
+    network::BitcoinNetwork, private_key::BitcoinPrivateKey, public_key::BitcoinPublicKey,
+    slip132::Slip132Version,
 };
 
 use base58::{FromBase58, ToBase58};
@@ -21,10 +24,12 @@ use core::fmt::{self, Display};
 use hmac::{Hmac, Mac};
 use secp256k1::{PublicKey, SecretKey};
 use sha2::Sha512;
+use zeroize::{Zeroize, Zeroizing};
 
 type HmacSha512 = Hmac<Sha512>;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The chain code and private key are cleared on drop.
+#[derive(Clone)]
 pub struct BitcoinExtendedPrivateKey<N: BitcoinNetwork> {
     pub(super) format: BitcoinFormat,
     pub(super) depth: u8,
@@ -32,6 +37,7 @@ pub struct BitcoinExtendedPrivateKey<N: BitcoinNetwork> {
     pub(super) child_index: ChildIndex,
     pub(super) chain_code: [u8; 32],
     private_key: BitcoinPrivateKey<N>,
+    pub(super) origin: Option<KeyOrigin>,
 }
 
 impl<N: BitcoinNetwork> ExtendedPrivateKey for BitcoinExtendedPrivateKey<N> {
@@ -56,7 +62,8 @@ impl<N: BitcoinNetwork> ExtendedPrivateKey for BitcoinExtendedPrivateKey<N> {
     ) -> Result<Self, gyu_model::extended_private_key::ExtendedPrivateKeyError> {
         let mut mac = HmacSha512::new_varkey(b"Bitcoin seed")?;
         mac.input(seed);
-        let hmac = mac.result().code();
+        let mut hmac = Zeroizing::new([0u8; 64]);
+        hmac.copy_from_slice(&mac.result().code());
         let private_key = Self::PrivateKey::from_secp256k1_secret_key(
             &SecretKey::parse_slice(&hmac[0..32])?,
             true,
@@ -65,14 +72,7 @@ impl<N: BitcoinNetwork> ExtendedPrivateKey for BitcoinExtendedPrivateKey<N> {
         let mut chain_code = [0u8; 32];
         chain_code[0..32].copy_from_slice(&hmac[32..]);
 
-        Ok(Self {
-            format: format.clone(),
-            depth: 0,
-            parent_fingerprint: [0u8; 4],
-            child_index: ChildIndex::Normal(0),
-            chain_code,
-            private_key,
-        })
+        Ok(Self::from_master_parts(chain_code, &private_key, format))
     }
 
     fn derive(
@@ -88,29 +88,25 @@ impl<N: BitcoinNetwork> ExtendedPrivateKey for BitcoinExtendedPrivateKey<N> {
         let mut extended_private_key = self.clone();
 
         for index in path.to_vec()?.into_iter() {
-            let public_key = &PublicKey::from_secret_key(
-                &extended_private_key.private_key.to_secp256k1_secret_key(),
-            )
-            .serialize_compressed()[..];
+            let public_key =
+                &PublicKey::from_secret_key(extended_private_key.private_key.expose_secret())
+                    .serialize_compressed()[..];
             let mut mac = HmacSha512::new_varkey(&extended_private_key.chain_code)?;
             match index {
                 ChildIndex::Normal(_) => mac.input(public_key),
                 ChildIndex::Hardened(_) => {
                     mac.input(&[0u8]);
-                    mac.input(
-                        &extended_private_key
-                            .private_key
-                            .to_secp256k1_secret_key()
-                            .serialize(),
-                    );
+                    mac.input(&*Zeroizing::new(
+                        extended_private_key.private_key.expose_secret().serialize(),
+                    ));
                 }
             }
             mac.input(&u32::from(index).to_be_bytes());
-            let hmac = mac.result().code();
+            let mut hmac = Zeroizing::new([0u8; 64]);
+            hmac.copy_from_slice(&mac.result().code());
 
             let mut secret_key = SecretKey::parse_slice(&hmac[0..32])?;
-            secret_key
-                .tweak_add_assign(&extended_private_key.private_key.to_secp256k1_secret_key())?;
+            secret_key.tweak_add_assign(extended_private_key.private_key.expose_secret())?;
             let private_key = Self::PrivateKey::from_secp256k1_secret_key(&secret_key, true);
 
             let mut chain_code = [0u8; 32];
@@ -119,10 +115,9 @@ impl<N: BitcoinNetwork> ExtendedPrivateKey for BitcoinExtendedPrivateKey<N> {
             let mut parent_fingerprint = [0u8; 4];
             parent_fingerprint.copy_from_slice(&hash160(public_key)[0..4]);
 
-            let format = match path {
-                BitcoinDerivationPath::BIP49(_) => BitcoinFormat::P2SH_P2WPKH,
-                _ => extended_private_key.format.clone(),
-            };
+            let format = path
+                .format()
+                .unwrap_or_else(|| extended_private_key.format.clone());
 
             extended_private_key = Self {
                 format,
@@ -131,6 +126,10 @@ impl<N: BitcoinNetwork> ExtendedPrivateKey for BitcoinExtendedPrivateKey<N> {
                 child_index: index,
                 chain_code,
                 private_key,
+                origin: extended_private_key
+                    .origin
+                    .as_ref()
+                    .map(|origin| origin.child(index)),
             }
         }
         Ok(extended_private_key)
@@ -156,11 +155,158 @@ impl<N: BitcoinNetwork> ExtendedPrivateKey for BitcoinExtendedPrivateKey<N> {
     }
 }
 
+impl<N: BitcoinNetwork> BitcoinExtendedPrivateKey<N> {
+    /// Returns the master extended private key of the chain code and private key.
+    pub fn from_master_parts(
+        chain_code: [u8; 32],
+        private_key: &BitcoinPrivateKey<N>,
+        format: &BitcoinFormat,
+    ) -> Self {
+        let mut master = Self {
+            format: format.clone(),
+            depth: 0,
+            parent_fingerprint: [0u8; 4],
+            child_index: ChildIndex::Normal(0),
+            chain_code,
+            private_key: private_key.clone(),
+            origin: None,
+        };
+        master.origin = Some(KeyOrigin::new(master.fingerprint(), &[]));
+        master
+    }
+
+    pub fn format(&self) -> BitcoinFormat {
+        self.format.clone()
+    }
+
+    /// Returns the address of the private key in the format of the extended private key.
+    pub fn to_default_address(&self) -> Result<BitcoinAddress<N>, AddressError> {
+        self.private_key.to_address(&self.format)
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    pub fn child_index(&self) -> ChildIndex {
+        self.child_index
+    }
+
+    /// Returns the BIP32 identifier, the hash160 of the compressed public key.
+    pub fn identifier(&self) -> [u8; 20] {
+        self.to_extended_public_key().identifier()
+    }
+
+    /// Returns the BIP32 fingerprint, the first 4 bytes of the identifier.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        self.to_extended_public_key().fingerprint()
+    }
+
+    /// Returns the master fingerprint and path the key was derived along, if known. Master
+    /// keys and keys derived from them know their origin, while keys parsed at a depth
+    /// above 0 only know it once it is set with `with_origin`.
+    pub fn origin(&self) -> Option<&KeyOrigin> {
+        self.origin.as_ref()
+    }
+
+    /// Returns the extended private key with the origin, if it fits the depth and child
+    /// index of the key.
+    pub fn with_origin(&self, origin: &KeyOrigin) -> Result<Self, ExtendedPrivateKeyError> {
+        if !origin.is_consistent(self.depth, self.child_index, self.fingerprint()) {
+            return Err(ExtendedPrivateKeyError::InvalidKeyOrigin(
+                origin.to_string(),
+            ));
+        }
+        let mut extended_private_key = self.clone();
+        extended_private_key.origin = Some(origin.clone());
+        Ok(extended_private_key)
+    }
+
+    /// Returns a reference to the chain code. The chain code of a private key is secret, so it
+    /// is borrowed rather than copied out of the zeroized key.
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    /// Returns the SLIP-132 registry entry of the version bytes of the extended private key.
+    pub fn slip132_version(&self) -> Option<&'static Slip132Version> {
+        Slip132Version::from_format(N::NAME, &self.format)
+    }
+
+    /// Returns the extended private key with the version bytes of the format, as in
+    /// converting an xprv to a zprv.
+    pub fn to_format(&self, format: &BitcoinFormat) -> Result<Self, ExtendedPrivateKeyError> {
+        let _ = N::to_extended_private_key_version_bytes(format)?;
+        let mut extended_private_key = self.clone();
+        extended_private_key.format = format.clone();
+        Ok(extended_private_key)
+    }
+
+    /// Returns the account extended public key of the master key, at the account path of
+    /// the format and with its version bytes, as in the zpub at m/84'/0'/0'. The key knows
+    /// its origin, so it can be handed to a watch-only wallet.
+    pub fn to_account_extended_public_key(
+        &self,
+        format: &BitcoinFormat,
+        account: u32,
+    ) -> Result<BitcoinExtendedPublicKey<N>, ExtendedPrivateKeyError> {
+        if self.depth != 0 {
+            return Err(ExtendedPrivateKeyError::Message(format!(
+                "expected a master key, found depth {}",
+                self.depth
+            )));
+        }
+        Ok(self
+            .derive(&BitcoinDerivationPath::account(format, account)?)?
+            .to_format(format)?
+            .to_extended_public_key())
+    }
+}
+
+impl<N: BitcoinNetwork> Drop for BitcoinExtendedPrivateKey<N> {
+    fn drop(&mut self) {
+        self.chain_code.zeroize();
+    }
+}
+
+impl<N: BitcoinNetwork> fmt::Debug for BitcoinExtendedPrivateKey<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BitcoinExtendedPrivateKey")
+            .field("format", &self.format)
+            .field("depth", &self.depth)
+            .field("parent_fingerprint", &self.parent_fingerprint)
+            .field("child_index", &self.child_index)
+            .field("origin", &self.origin)
+            .field("chain_code", &format_args!("[REDACTED]"))
+            .field("private_key", &format_args!("[REDACTED]"))
+            .finish()
+    }
+}
+
+/// Keys compare by their serialized fields. The origin is left out, as a derived key and
+/// the same key parsed from its string are equal whether or not the origin is known.
+impl<N: BitcoinNetwork> PartialEq for BitcoinExtendedPrivateKey<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.format == other.format
+            && self.depth == other.depth
+            && self.parent_fingerprint == other.parent_fingerprint
+            && self.child_index == other.child_index
+            && self.chain_code == other.chain_code
+            && self.private_key == other.private_key
+    }
+}
+
+impl<N: BitcoinNetwork> Eq for BitcoinExtendedPrivateKey<N> {}
+
 impl<N: BitcoinNetwork> FromStr for BitcoinExtendedPrivateKey<N> {
     type Err = ExtendedPrivateKeyError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let data = s.from_base58()?;
+        let data = Zeroizing::new(s.from_base58()?);
         if data.len() != 82 {
             return Err(ExtendedPrivateKeyError::InvalidByteLength(data.len()));
         }
@@ -192,20 +338,27 @@ impl<N: BitcoinNetwork> FromStr for BitcoinExtendedPrivateKey<N> {
             return Err(ExtendedPrivateKeyError::InvalidChecksum(expected, found));
         }
 
-        Ok(Self {
+        let mut extended_private_key = Self {
             format,
             depth,
             parent_fingerprint,
             child_index,
             chain_code,
             private_key,
-        })
+            origin: None,
+        };
+        // A master key is its own origin
+        if depth == 0 {
+            extended_private_key.origin =
+                Some(KeyOrigin::new(extended_private_key.fingerprint(), &[]));
+        }
+        Ok(extended_private_key)
     }
 }
 
 impl<N: BitcoinNetwork> Display for BitcoinExtendedPrivateKey<N> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let mut result = [0u8; 82];
+        let mut result = Zeroizing::new([0u8; 82]);
         result[0..4].copy_from_slice(
             match &N::to_extended_private_key_version_bytes(&self.format) {
                 Ok(version) => version,
@@ -217,11 +370,236 @@ impl<N: BitcoinNetwork> Display for BitcoinExtendedPrivateKey<N> {
         result[9..13].copy_from_slice(&u32::from(self.child_index).to_be_bytes());
         result[13..45].copy_from_slice(&self.chain_code[..]);
         result[45] = 0;
-        result[46..78].copy_from_slice(&self.private_key.to_secp256k1_secret_key().serialize());
+        result[46..78].copy_from_slice(&*Zeroizing::new(
+            self.private_key.expose_secret().serialize(),
+        ));
 
         let checksum = &checksum(&result[0..78])[0..4];
         result[78..82].copy_from_slice(&checksum);
 
-        fmt.write_str(&result.to_base58())
+        fmt.write_str(&Zeroizing::new(result.to_base58()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+    use crate::test_vectors::master;
+
+    type N = Mainnet;
+
+    fn derive(path: &str) -> BitcoinExtendedPrivateKey<N> {
+        derive_with_format(path, &BitcoinFormat::P2PKH)
+    }
+
+    fn derive_with_format(path: &str, format: &BitcoinFormat) -> BitcoinExtendedPrivateKey<N> {
+        master(format)
+            .derive(&BitcoinDerivationPath::from_str(path).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn accessors() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master =
+            BitcoinExtendedPrivateKey::<N>::new_master(&seed, &BitcoinFormat::P2PKH).unwrap();
+        assert_eq!(0, master.depth());
+        assert_eq!([0x34, 0x42, 0x19, 0x3e], master.fingerprint());
+        assert_eq!("[3442193e]", master.origin().unwrap().to_string());
+
+        // BIP32 test vector 1, m/0'
+        let child = master
+            .derive(&BitcoinDerivationPath::from_str("m/0'").unwrap())
+            .unwrap();
+        assert_eq!(1, child.depth());
+        assert_eq!(ChildIndex::Hardened(0), child.child_index());
+        assert_eq!(master.fingerprint(), child.parent_fingerprint());
+        assert_eq!(
+            "5c1bd648ed23aa5fd50ba52b2457c11e9e80a6a7",
+            hex::encode(child.identifier())
+        );
+        assert_eq!([0x5c, 0x1b, 0xd6, 0x48], child.fingerprint());
+        assert_eq!(
+            "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141",
+            hex::encode(child.chain_code())
+        );
+        assert_eq!(
+            child.chain_code(),
+            &child.to_extended_public_key().chain_code()
+        );
+    }
+
+    #[test]
+    fn key_origin() {
+        let key = derive("m/84'/0'/0'/0/1");
+        let origin = key.origin().unwrap();
+        assert_eq!(
+            master(&BitcoinFormat::P2PKH).fingerprint(),
+            origin.master_fingerprint
+        );
+        assert_eq!("[73c5da0a/84'/0'/0'/0/1]", origin.to_string());
+        assert_eq!(Some(origin), key.to_extended_public_key().origin());
+
+        // A key parsed at depth above 0 has no origin until one is set, but equals the
+        // derived key either way.
+        let parsed = BitcoinExtendedPrivateKey::<N>::from_str(&key.to_string()).unwrap();
+        assert_eq!(None, parsed.origin());
+        assert_eq!(key, parsed);
+        assert_eq!(
+            key.to_extended_public_key(),
+            parsed.to_extended_public_key()
+        );
+
+        let parsed = parsed.with_origin(origin).unwrap();
+        assert_eq!(Some(origin), parsed.origin());
+        assert_eq!(
+            "[73c5da0a/84'/0'/0'/0/1/7]",
+            parsed
+                .derive(&BitcoinDerivationPath::from_str("m/7").unwrap())
+                .unwrap()
+                .origin()
+                .unwrap()
+                .to_string()
+        );
+
+        let wrong_depth = KeyOrigin::from_str("[73c5da0a/84'/0'/0'/1]").unwrap();
+        assert!(key.with_origin(&wrong_depth).is_err());
+        let wrong_index = KeyOrigin::from_str("[73c5da0a/84'/0'/0'/0/2]").unwrap();
+        assert!(key.with_origin(&wrong_index).is_err());
+        let master_origin = KeyOrigin::from_str("[00000000]").unwrap();
+        assert!(master(&BitcoinFormat::P2PKH)
+            .with_origin(&master_origin)
+            .is_err());
+    }
+
+    #[test]
+    fn bip84_vectors() {
+        assert_eq!(
+            "zprvAWgYBBk7JR8Gjrh4UJQ2uJdG1r3WNRRfURiABBE3RvMXYSrRJL62XuezvGdPvG6GFBZduosCc1YP5wixPox7zhZLfiUm8aunE96BBa4Kei5",
+            master(&BitcoinFormat::Bech32).to_string()
+        );
+        assert_eq!(
+            "zpub6jftahH18ngZxLmXaKw3GSZzZsszmt9WqedkyZdezFtWRFBZqsQH5hyUmb4pCEeZGmVfQuP5bedXTB8is6fTv19U1GQRyQUKQGUTzyHACMF",
+            master(&BitcoinFormat::Bech32).to_extended_public_key().to_string()
+        );
+
+        // Account paths are not full BIP84 paths, so they keep the format of the master key.
+        let account = derive_with_format("m/84'/0'/0'", &BitcoinFormat::Bech32);
+        assert_eq!(BitcoinFormat::Bech32, account.format());
+        assert_eq!(
+            "zprvAdG4iTXWBoARxkkzNpNh8r6Qag3irQB8PzEMkAFeTRXxHpbF9z4QgEvBRmfvqWvGp42t42nvgGpNgYSJA9iefm1yYNZKEm7z6qUWCroSQnE",
+            account.to_string()
+        );
+        assert_eq!(
+            "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs",
+            account.to_extended_public_key().to_string()
+        );
+
+        for (path, address) in [
+            (
+                "m/84'/0'/0'/0/0",
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            ),
+            (
+                "m/84'/0'/0'/0/1",
+                "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g",
+            ),
+            (
+                "m/84'/0'/0'/1/0",
+                "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el",
+            ),
+        ]
+        .iter()
+        {
+            let key = derive(path);
+            assert_eq!(*address, key.to_default_address().unwrap().to_string());
+            assert_eq!(
+                *address,
+                key.to_extended_public_key()
+                    .to_default_address()
+                    .unwrap()
+                    .to_string()
+            );
+        }
+
+        // Keys derived along a BIP84 path take its format, and serialize as zprv and zpub.
+        let key = derive("m/84'/0'/0'/0/0");
+        assert_eq!(BitcoinFormat::Bech32, key.format());
+        assert!(key.to_string().starts_with("zprv"));
+        let zpub = key.to_extended_public_key().to_string();
+        assert!(zpub.starts_with("zpub"));
+        assert_eq!(
+            BitcoinFormat::Bech32,
+            BitcoinExtendedPublicKey::<N>::from_str(&zpub)
+                .unwrap()
+                .format()
+        );
+        assert_eq!(
+            "KyZpNDKnfs94vbrwhJneDi77V6jF64PWPF8x5cdJb8ifgg2DUc9d",
+            derive("m/84'/0'/0'/0/0").to_private_key().to_string()
+        );
+    }
+
+    #[test]
+    fn bip86_vectors() {
+        let account = derive_with_format("m/86'/0'/0'", &BitcoinFormat::P2TR);
+        assert_eq!(BitcoinFormat::P2TR, account.format());
+        assert_eq!(
+            "xprv9xgqHN7yz9MwCkxsBPN5qetuNdQSUttZNKw1dcYTV4mkaAFiBVGQziHs3NRSWMkCzvgjEe3n9xV8oYywvM8at9yRqyaZVz6TYYhX98VjsUk",
+            account.to_string()
+        );
+        assert_eq!(
+            "xpub6BgBgsespWvERF3LHQu6CnqdvfEvtMcQjYrcRzx53QJjSxarj2afYWcLteoGVky7D3UKDP9QyrLprQ3VCECoY49yfdDEHGCtMMj92pReUsQ",
+            account.to_extended_public_key().to_string()
+        );
+
+        for (path, address) in [
+            (
+                "m/86'/0'/0'/0/0",
+                "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            ),
+            (
+                "m/86'/0'/0'/0/1",
+                "bc1p4qhjn9zdvkux4e44uhx8tc55attvtyu358kutcqkudyccelu0was9fqzwh",
+            ),
+            (
+                "m/86'/0'/0'/1/0",
+                "bc1p3qkhfews2uk44qtvauqyr2ttdsw7svhkl9nkm9s9c3x4ax5h60wqwruhk7",
+            ),
+        ]
+        .iter()
+        {
+            let key = derive(path);
+            assert_eq!(*address, key.to_default_address().unwrap().to_string());
+            assert_eq!(
+                *address,
+                key.to_extended_public_key()
+                    .to_default_address()
+                    .unwrap()
+                    .to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn redacted_debug() {
+        let xprv = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        let extended_private_key = BitcoinExtendedPrivateKey::<Mainnet>::from_str(xprv).unwrap();
+        let chain_code = hex::encode(extended_private_key.chain_code());
+        let secret_key = hex::encode(
+            extended_private_key
+                .to_private_key()
+                .expose_secret()
+                .serialize(),
+        );
+
+        let debug = format!("{:?}", extended_private_key);
+        assert!(debug.contains("chain_code: [REDACTED]"));
+        assert!(debug.contains("private_key: [REDACTED]"));
+        assert!(!debug.contains(&chain_code));
+        assert!(!debug.contains(&secret_key));
+        assert!(!debug.contains(xprv));
+        assert_eq!(xprv, extended_private_key.to_string());
     }
 }
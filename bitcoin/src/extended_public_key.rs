@@ -2,7 +2,9 @@ use core::fmt;
 use core::str::FromStr;
 
 use base58::{FromBase58, ToBase58};
+use gyu_model::no_std::*;
 use gyu_model::{
+    address::AddressError,
     derivation_path::{ChildIndex, DerivationPath},
     extended_private_key::ExtendedPrivateKey,
     extended_public_key::{ExtendedPublicKey, ExtendedPublicKeyError},
@@ -11,25 +13,31 @@ use gyu_model::{
 };
 
 use hmac::{Hmac, Mac};
-use secp256k1::{PublicKey as Secp256k1_PublicKey, SecretKey};
+use secp256k1::SecretKey;
 use sha2::Sha512;
 
 use crate::{
-    address::BitcoinAddress, derivation_path::BitcoinDerivationPath,
-    extended_private_key::BitcoinExtendedPrivateKey, format::BitcoinFormat,
-    network::BitcoinNetwork, public_key::BitcoinPublicKey,
+    address::BitcoinAddress,
+    derivation_path::BitcoinDerivationPath,
+    extended_private_key::BitcoinExtendedPrivateKey,
+    format::BitcoinFormat,
+    key_origin::KeyOrigin,
+    network::BitcoinNetwork,
+    public_key::{BitcoinPublicKey, PublicKeyPolicy},
+    slip132::Slip132Version,
 };
 
 type HmacSha512 = Hmac<Sha512>;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct BitcoinExtendedPublicKey<N: BitcoinNetwork> {
-    format: BitcoinFormat,
-    depth: u8,
-    parent_fingerprint: [u8; 4],
-    child_index: ChildIndex,
-    chain_code: [u8; 32],
-    public_key: BitcoinPublicKey<N>,
+    pub(super) format: BitcoinFormat,
+    pub(super) depth: u8,
+    pub(super) parent_fingerprint: [u8; 4],
+    pub(super) child_index: ChildIndex,
+    pub(super) chain_code: [u8; 32],
+    pub(super) public_key: BitcoinPublicKey<N>,
+    pub(super) origin: Option<KeyOrigin>,
 }
 
 impl<N: BitcoinNetwork> ExtendedPublicKey for BitcoinExtendedPublicKey<N> {
@@ -47,6 +55,7 @@ impl<N: BitcoinNetwork> ExtendedPublicKey for BitcoinExtendedPublicKey<N> {
             child_index: extended_private_key.child_index,
             chain_code: extended_private_key.chain_code,
             public_key: extended_private_key.to_public_key(),
+            origin: extended_private_key.origin.clone(),
         }
     }
 
@@ -54,26 +63,28 @@ impl<N: BitcoinNetwork> ExtendedPublicKey for BitcoinExtendedPublicKey<N> {
         &self,
         path: &Self::DerivationPath,
     ) -> Result<Self, gyu_model::extended_public_key::ExtendedPublicKeyError> {
-        if self.depth == 255 {
-            return Err(ExtendedPublicKeyError::MaximumChildDepthReached(self.depth));
-        }
         let mut extended_public_key = self.clone();
 
         for index in path.to_vec()?.into_iter() {
-            let public_key_serialized = &self
+            if extended_public_key.depth == 255 {
+                return Err(ExtendedPublicKeyError::MaximumChildDepthReached(
+                    extended_public_key.depth,
+                ));
+            }
+
+            let public_key_serialized = &extended_public_key
                 .public_key
                 .to_secp256k1_public_key()
                 .serialize_compressed()[..];
 
-            let mut mac = HmacSha512::new_varkey(&self.chain_code)?;
+            let mut mac = HmacSha512::new_varkey(&extended_public_key.chain_code)?;
             match index {
                 // HMAC-SHA512(Key = cpar, Data = serP(Kpar) || ser32(i))
                 ChildIndex::Normal(_) => mac.input(public_key_serialized),
                 // Return failure
                 ChildIndex::Hardened(_) => {
-                    return Err(ExtendedPublicKeyError::InvalidChildNumber(
-                        1 << 31,
-                        u32::from(index),
+                    return Err(ExtendedPublicKeyError::HardenedChildIndex(
+                        index.to_string(),
                     ))
                 }
             }
@@ -84,7 +95,7 @@ impl<N: BitcoinNetwork> ExtendedPublicKey for BitcoinExtendedPublicKey<N> {
             let mut chain_code = [0u8; 32];
             chain_code[0..32].copy_from_slice(&hmac[32..]);
 
-            let mut public_key = self.public_key.to_secp256k1_public_key();
+            let mut public_key = extended_public_key.public_key.to_secp256k1_public_key();
             public_key.tweak_add_assign(&SecretKey::parse_slice(&hmac[..32])?)?;
             let public_key = Self::PublicKey::from_secp256k1_public_key(public_key, true);
 
@@ -98,6 +109,10 @@ impl<N: BitcoinNetwork> ExtendedPublicKey for BitcoinExtendedPublicKey<N> {
                 child_index: index,
                 chain_code,
                 public_key,
+                origin: extended_public_key
+                    .origin
+                    .as_ref()
+                    .map(|origin| origin.child(index)),
             };
         }
 
@@ -120,8 +135,95 @@ impl<N: BitcoinNetwork> BitcoinExtendedPublicKey<N> {
     pub fn format(&self) -> BitcoinFormat {
         self.format.clone()
     }
+
+    /// Returns the address of the public key in the format of the extended public key.
+    pub fn to_default_address(&self) -> Result<BitcoinAddress<N>, AddressError> {
+        self.public_key.to_address(&self.format)
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    pub fn child_index(&self) -> ChildIndex {
+        self.child_index
+    }
+
+    pub fn chain_code(&self) -> [u8; 32] {
+        self.chain_code
+    }
+
+    /// Returns the BIP32 identifier, the hash160 of the compressed public key.
+    pub fn identifier(&self) -> [u8; 20] {
+        let mut identifier = [0u8; 20];
+        identifier.copy_from_slice(&hash160(
+            &self
+                .public_key
+                .to_secp256k1_public_key()
+                .serialize_compressed(),
+        ));
+        identifier
+    }
+
+    /// Returns the BIP32 fingerprint, the first 4 bytes of the identifier.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        let mut fingerprint = [0u8; 4];
+        fingerprint.copy_from_slice(&self.identifier()[0..4]);
+        fingerprint
+    }
+
+    /// Returns the master fingerprint and path the key was derived along, if known. Master
+    /// keys and keys derived from them know their origin, while keys parsed at a depth
+    /// above 0 only know it once it is set with `with_origin`.
+    pub fn origin(&self) -> Option<&KeyOrigin> {
+        self.origin.as_ref()
+    }
+
+    /// Returns the extended public key with the origin, if it fits the depth and child
+    /// index of the key.
+    pub fn with_origin(&self, origin: &KeyOrigin) -> Result<Self, ExtendedPublicKeyError> {
+        if !origin.is_consistent(self.depth, self.child_index, self.fingerprint()) {
+            return Err(ExtendedPublicKeyError::InvalidKeyOrigin(origin.to_string()));
+        }
+        let mut extended_public_key = self.clone();
+        extended_public_key.origin = Some(origin.clone());
+        Ok(extended_public_key)
+    }
+
+    /// Returns the SLIP-132 registry entry of the version bytes of the extended public key.
+    pub fn slip132_version(&self) -> Option<&'static Slip132Version> {
+        Slip132Version::from_format(N::NAME, &self.format)
+    }
+
+    /// Returns the extended public key with the version bytes of the format, as in
+    /// converting an xpub to a zpub.
+    pub fn to_format(&self, format: &BitcoinFormat) -> Result<Self, ExtendedPublicKeyError> {
+        let _ = N::to_extended_public_key_version_bytes(format)?;
+        let mut extended_public_key = self.clone();
+        extended_public_key.format = format.clone();
+        Ok(extended_public_key)
+    }
+}
+
+/// Keys compare by their serialized fields. The origin is left out, as a derived key and
+/// the same key parsed from its string are equal whether or not the origin is known.
+impl<N: BitcoinNetwork> PartialEq for BitcoinExtendedPublicKey<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.format == other.format
+            && self.depth == other.depth
+            && self.parent_fingerprint == other.parent_fingerprint
+            && self.child_index == other.child_index
+            && self.chain_code == other.chain_code
+            && self.public_key == other.public_key
+    }
 }
 
+impl<N: BitcoinNetwork> Eq for BitcoinExtendedPublicKey<N> {}
+
 impl<N: BitcoinNetwork> FromStr for BitcoinExtendedPublicKey<N> {
     type Err = ExtendedPublicKeyError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -144,8 +246,7 @@ impl<N: BitcoinNetwork> FromStr for BitcoinExtendedPublicKey<N> {
         let mut chain_code = [0u8; 32];
         chain_code.copy_from_slice(&data[13..45]);
 
-        let secp256k1_public_key = Secp256k1_PublicKey::parse_slice(&data[45..78], None)?;
-        let public_key = BitcoinPublicKey::from_secp256k1_public_key(secp256k1_public_key, true);
+        let public_key = BitcoinPublicKey::from_slice(&data[45..78], &PublicKeyPolicy::SEGWIT)?;
 
         let expected = &data[78..82];
         let checksum = &checksum(&data[0..78])[0..4];
@@ -155,14 +256,21 @@ impl<N: BitcoinNetwork> FromStr for BitcoinExtendedPublicKey<N> {
             return Err(ExtendedPublicKeyError::InvalidChecksum(expected, found));
         }
 
-        Ok(Self {
+        let mut extended_public_key = Self {
             format,
             depth,
             parent_fingerprint,
             child_index,
             chain_code,
             public_key,
-        })
+            origin: None,
+        };
+        // A master key is its own origin
+        if depth == 0 {
+            extended_public_key.origin =
+                Some(KeyOrigin::new(extended_public_key.fingerprint(), &[]));
+        }
+        Ok(extended_public_key)
     }
 }
 
@@ -192,3 +300,97 @@ impl<N: BitcoinNetwork> fmt::Display for BitcoinExtendedPublicKey<N> {
         f.write_str(&result.to_base58())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+
+    type N = Mainnet;
+
+    fn derive(extended_public_key: &str, path: &str) -> String {
+        BitcoinExtendedPublicKey::<N>::from_str(extended_public_key)
+            .unwrap()
+            .derive(&BitcoinDerivationPath::from_str(path).unwrap())
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn bip32_vector_1() {
+        // m/0'/1/2'
+        let parent = "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5";
+        // m/0'/1/2'/2
+        let child = "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV";
+        // m/0'/1/2'/2/1000000000
+        let grandchild = "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy";
+
+        assert_eq!(child, derive(parent, "m/2"));
+        assert_eq!(grandchild, derive(child, "m/1000000000"));
+        // Each step derives from the previous child, not from the starting key.
+        assert_eq!(grandchild, derive(parent, "m/2/1000000000"));
+    }
+
+    #[test]
+    fn key_origin() {
+        let master = BitcoinExtendedPrivateKey::<N>::new_master(&[7u8; 32], &BitcoinFormat::P2PKH)
+            .unwrap()
+            .to_extended_public_key();
+        let key = master
+            .derive(&BitcoinDerivationPath::from_str("m/0/1").unwrap())
+            .unwrap();
+        let origin = key.origin().unwrap();
+        assert_eq!(master.fingerprint(), origin.master_fingerprint);
+        assert_eq!(
+            vec![ChildIndex::Normal(0), ChildIndex::Normal(1)],
+            origin.path
+        );
+
+        // A key parsed at depth above 0 has no origin, but equals the derived key.
+        let parsed = BitcoinExtendedPublicKey::<N>::from_str(&key.to_string()).unwrap();
+        assert_eq!(None, parsed.origin());
+        assert_eq!(key, parsed);
+        assert_eq!(Some(origin), parsed.with_origin(origin).unwrap().origin());
+        assert!(parsed
+            .with_origin(&origin.child(ChildIndex::Normal(2)))
+            .is_err());
+    }
+
+    #[test]
+    fn bip32_vector_2() {
+        // m
+        let master = "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB";
+        // m/0
+        let child = "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH";
+        assert_eq!(child, derive(master, "m/0"));
+
+        let master = BitcoinExtendedPublicKey::<N>::from_str(master).unwrap();
+        assert!(master
+            .derive(&BitcoinDerivationPath::from_str("m/0/2147483647'").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn matches_private_derivation() {
+        let master =
+            BitcoinExtendedPrivateKey::<N>::new_master(&[1u8; 32], &BitcoinFormat::P2PKH).unwrap();
+        let account = master
+            .derive(&BitcoinDerivationPath::from_str("m/44'/0'/0'").unwrap())
+            .unwrap();
+        for path in ["m/0", "m/0/1", "m/1/5/9", "m/7/0/3/2"].iter() {
+            let path = BitcoinDerivationPath::from_str(path).unwrap();
+            assert_eq!(
+                account
+                    .derive(&path)
+                    .unwrap()
+                    .to_extended_public_key()
+                    .to_string(),
+                account
+                    .to_extended_public_key()
+                    .derive(&path)
+                    .unwrap()
+                    .to_string()
+            );
+        }
+    }
+}
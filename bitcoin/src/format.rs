@@ -8,14 +8,18 @@ use gyu_model::{
 use serde::Serialize;
 
 use crate::network::BitcoinNetwork;
+use crate::slip132::Slip132Version;
 
 #[derive(Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[allow(non_camel_case_types)]
 pub enum BitcoinFormat {
     P2PKH,
     P2WSH,
+    P2SH,
     P2SH_P2WPKH,
+    P2SH_P2WSH,
     Bech32,
+    P2TR,
 }
 
 impl Format for BitcoinFormat {}
@@ -41,10 +45,9 @@ impl BitcoinFormat {
     pub fn from_extended_private_key_version_bytes(
         prefix: &[u8],
     ) -> Result<Self, ExtendedPrivateKeyError> {
-        match prefix[0..4] {
-            [0x04, 0x88, 0xAD, 0xE4] | [0x04, 0x35, 0x83, 0x94] => Ok(BitcoinFormat::P2PKH),
-            [0x04, 0x9D, 0x7C, 0xB2] | [0x04, 0x4A, 0x52, 0x62] => Ok(BitcoinFormat::P2SH_P2WPKH),
-            _ => Err(ExtendedPrivateKeyError::InvalidVersionBytes(
+        match Slip132Version::from_private_version(prefix) {
+            Some(version) => Ok(version.format.clone()),
+            None => Err(ExtendedPrivateKeyError::InvalidVersionBytes(
                 prefix.to_vec(),
             )),
         }
@@ -53,10 +56,9 @@ impl BitcoinFormat {
     pub fn from_extended_public_key_version_bytes(
         prefix: &[u8],
     ) -> Result<Self, ExtendedPublicKeyError> {
-        match prefix[0..4] {
-            [0x04, 0x88, 0xB2, 0x1E] | [0x04, 0x35, 0x87, 0xCF] => Ok(BitcoinFormat::P2PKH),
-            [0x04, 0x9D, 0x7C, 0xB2] | [0x04, 0x4A, 0x52, 0x62] => Ok(BitcoinFormat::P2SH_P2WPKH),
-            _ => Err(ExtendedPublicKeyError::InvalidVersionBytes(prefix.to_vec())),
+        match Slip132Version::from_public_version(prefix) {
+            Some(version) => Ok(version.format.clone()),
+            None => Err(ExtendedPublicKeyError::InvalidVersionBytes(prefix.to_vec())),
         }
     }
 }
@@ -66,8 +68,11 @@ impl fmt::Display for BitcoinFormat {
         match self {
             BitcoinFormat::P2PKH => write!(f, "p2pkh"),
             BitcoinFormat::P2WSH => write!(f, "p2wsh"),
+            BitcoinFormat::P2SH => write!(f, "p2sh"),
             BitcoinFormat::P2SH_P2WPKH => write!(f, "p2sh_p2wpkh"),
+            BitcoinFormat::P2SH_P2WSH => write!(f, "p2sh_p2wsh"),
             BitcoinFormat::Bech32 => write!(f, "bech32"),
+            BitcoinFormat::P2TR => write!(f, "p2tr"),
         }
     }
 }
@@ -0,0 +1,1118 @@
+use crate::address::BitcoinAddress;
+use crate::format::BitcoinFormat;
+use crate::network::BitcoinNetwork;
+use crate::private_key::BitcoinPrivateKey;
+use crate::public_key::BitcoinPublicKey;
+use crate::schnorr::{
+    self, infinity, parse_point, scalar_from_bytes, serialize_point, tagged_hash, tap_tweak,
+};
+use gyu_model::no_std::*;
+use gyu_model::{address::AddressError, public_key::PublicKey};
+
+use core::{fmt, marker::PhantomData};
+use rand::Rng;
+use secp256k1::curve::{Jacobian, Scalar, ECMULT_CONTEXT, ECMULT_GEN_CONTEXT};
+use zeroize::Zeroizing;
+
+#[derive(Debug, Fail)]
+pub enum FrostError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "duplicate participant identifier {}", _0)]
+    DuplicateIdentifier(u16),
+
+    #[fail(display = "the group nonce commitment is the point at infinity")]
+    InfiniteNonceCommitment,
+
+    #[fail(display = "invalid commitment of participant {}", _0)]
+    InvalidCommitment(u16),
+
+    #[fail(display = "invalid participant identifier {}", _0)]
+    InvalidIdentifier(u16),
+
+    #[fail(display = "invalid proof of knowledge of participant {}", _0)]
+    InvalidProofOfKnowledge(u16),
+
+    #[fail(display = "invalid secret share from participant {}", _0)]
+    InvalidSecretShare(u16),
+
+    #[fail(display = "invalid aggregate signature")]
+    InvalidSignature,
+
+    #[fail(display = "invalid signature share of participant {}", _0)]
+    InvalidSignatureShare(u16),
+
+    #[fail(display = "invalid threshold {} of {} participants", _0, _1)]
+    InvalidThreshold(u16, u16),
+
+    #[fail(display = "invalid tweak: {}", _0)]
+    InvalidTweak(String),
+
+    #[fail(display = "missing package of participant {}", _0)]
+    MissingPackage(u16),
+
+    #[fail(display = "missing signature share of participant {}", _0)]
+    MissingSignatureShare(u16),
+
+    #[fail(display = "the nonces of participant {} were not committed to", _0)]
+    NonceMismatch(u16),
+
+    #[fail(display = "{} signers are fewer than the threshold {}", _0, _1)]
+    NotEnoughSigners(usize, u16),
+
+    #[fail(display = "unknown participant identifier {}", _0)]
+    UnknownIdentifier(u16),
+}
+
+impl From<AddressError> for FrostError {
+    fn from(error: AddressError) -> Self {
+        FrostError::AddressError(error)
+    }
+}
+
+impl From<secp256k1::Error> for FrostError {
+    fn from(error: secp256k1::Error) -> Self {
+        FrostError::Crate("libsecp256k1", format!("{:?}", error))
+    }
+}
+
+/// Represents the public keys of a FROST group, the group public key with any tweaks
+/// applied and the verifying share of each participant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrostPublicKeyPackage<N: BitcoinNetwork> {
+    threshold: u16,
+    verifying_shares: Vec<(u16, BitcoinPublicKey<N>)>,
+    group_public_key: BitcoinPublicKey<N>,
+    gacc: Scalar,
+    tacc: Scalar,
+}
+
+impl<N: BitcoinNetwork> FrostPublicKeyPackage<N> {
+    fn new(
+        threshold: u16,
+        verifying_shares: Vec<(u16, BitcoinPublicKey<N>)>,
+        group_public_key: &Jacobian,
+    ) -> Result<Self, FrostError> {
+        Ok(Self {
+            threshold,
+            verifying_shares,
+            group_public_key: BitcoinPublicKey::from_point(group_public_key)
+                .ok_or(FrostError::Crate("libsecp256k1", "InvalidPublicKey".into()))?,
+            gacc: Scalar::from_int(1),
+            tacc: Scalar::default(),
+        })
+    }
+
+    pub fn threshold(&self) -> u16 {
+        self.threshold
+    }
+
+    /// Returns the group public key, with any tweaks applied.
+    pub fn group_public_key(&self) -> BitcoinPublicKey<N> {
+        self.group_public_key.clone()
+    }
+
+    /// Returns the x-only (BIP340) group public key, with any tweaks applied.
+    pub fn x_only_public_key(&self) -> [u8; 32] {
+        self.group_public_key.to_x_only()
+    }
+
+    /// Returns the verifying share of the participant.
+    pub fn verifying_share(&self, identifier: u16) -> Option<&BitcoinPublicKey<N>> {
+        self.verifying_shares
+            .iter()
+            .find(|(id, _)| *id == identifier)
+            .map(|(_, verifying_share)| verifying_share)
+    }
+
+    /// Returns the P2TR address with the group public key as its internal key.
+    pub fn to_address(&self) -> Result<BitcoinAddress<N>, FrostError> {
+        Ok(self.group_public_key.to_address(&BitcoinFormat::P2TR)?)
+    }
+
+    /// Returns the package with the plain or x-only tweak added to the group public key.
+    pub fn apply_tweak(&self, tweak: &[u8; 32], x_only: bool) -> Result<Self, FrostError> {
+        let mut t = Scalar::default();
+        if bool::from(t.set_b32(tweak)) {
+            return Err(FrostError::InvalidTweak(hex::encode(tweak)));
+        }
+
+        let g = match x_only && !self.group_public_key.has_even_y() {
+            true => -Scalar::from_int(1),
+            false => Scalar::from_int(1),
+        };
+
+        // Q' = g * Q + t * G
+        let mut group_public_key = Jacobian::default();
+        ECMULT_CONTEXT.ecmult(
+            &mut group_public_key,
+            &self.group_public_key.to_point(),
+            &g,
+            &t,
+        );
+
+        let mut package = self.clone();
+        package.group_public_key = BitcoinPublicKey::from_point(&group_public_key)
+            .ok_or_else(|| FrostError::InvalidTweak(hex::encode(tweak)))?;
+        package.gacc = &g * &self.gacc;
+        package.tacc = &t + &(&g * &self.tacc);
+        Ok(package)
+    }
+
+    /// Returns the package with the BIP341 TapTweak of the group public key applied, for
+    /// signing a key path spend of its P2TR output committing to the given script tree.
+    pub fn apply_taproot_tweak(&self, merkle_root: Option<&[u8; 32]>) -> Result<Self, FrostError> {
+        self.apply_tweak(&tap_tweak(&self.x_only_public_key(), merkle_root), true)
+    }
+
+    /// Returns 1 if the group public key has an even y coordinate, or -1 otherwise,
+    /// multiplied by the accumulated tweak parity.
+    fn parity(&self) -> Scalar {
+        match self.group_public_key.has_even_y() {
+            true => self.gacc.clone(),
+            false => -self.gacc.clone(),
+        }
+    }
+}
+
+/// Represents the secret share of a FROST participant
+#[derive(Clone)]
+pub struct FrostKeyShare<N: BitcoinNetwork> {
+    identifier: u16,
+    secret_share: Scalar,
+    _network: PhantomData<N>,
+}
+
+impl<N: BitcoinNetwork> FrostKeyShare<N> {
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+
+    /// Returns the public key of the secret share.
+    pub fn verifying_share(&self) -> BitcoinPublicKey<N> {
+        BitcoinPublicKey::from_point(&multiply_generator(&self.secret_share))
+            .expect("secret shares are nonzero")
+    }
+
+    fn clear_secrets(&mut self) {
+        self.secret_share.clear();
+    }
+}
+
+impl<N: BitcoinNetwork> Drop for FrostKeyShare<N> {
+    fn drop(&mut self) {
+        self.clear_secrets();
+    }
+}
+
+impl<N: BitcoinNetwork> fmt::Debug for FrostKeyShare<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrostKeyShare")
+            .field("identifier", &self.identifier)
+            .field("secret_share", &format_args!("[REDACTED]"))
+            .finish()
+    }
+}
+
+/// Splits the private key into the shares of participants 1 to n, any threshold of which
+/// can sign for its public key, acting as a trusted dealer.
+pub fn trusted_dealer_keygen<N: BitcoinNetwork, R: Rng>(
+    rng: &mut R,
+    private_key: &BitcoinPrivateKey<N>,
+    threshold: u16,
+    participants: u16,
+) -> Result<(Vec<FrostKeyShare<N>>, FrostPublicKeyPackage<N>), FrostError> {
+    check_threshold(threshold, participants)?;
+
+    let mut coefficients = vec![private_key.expose_secret().clone().into()];
+    for _ in 1..threshold {
+        coefficients.push(random_scalar(rng));
+    }
+
+    let key_shares: Vec<FrostKeyShare<N>> = (1..=participants)
+        .map(|identifier| FrostKeyShare {
+            identifier,
+            secret_share: evaluate_polynomial(&coefficients, identifier),
+            _network: PhantomData,
+        })
+        .collect();
+    let verifying_shares = key_shares
+        .iter()
+        .map(|key_share| (key_share.identifier, key_share.verifying_share()))
+        .collect();
+
+    let package = FrostPublicKeyPackage::new(
+        threshold,
+        verifying_shares,
+        &multiply_generator(&coefficients[0]),
+    );
+    coefficients.iter_mut().for_each(Scalar::clear);
+    Ok((key_shares, package?))
+}
+
+/// Represents a participant of a FROST distributed key generation, holding its secret
+/// polynomial
+pub struct FrostDkgParticipant<N: BitcoinNetwork> {
+    identifier: u16,
+    threshold: u16,
+    participants: u16,
+    coefficients: Vec<Scalar>,
+    _network: PhantomData<N>,
+}
+
+/// Represents the package broadcast by a participant in the first round of the distributed
+/// key generation, the commitment to its polynomial and a proof of knowledge of its secret
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrostDkgCommitment {
+    identifier: u16,
+    commitment: Vec<[u8; 33]>,
+    proof_nonce: [u8; 33],
+    proof_response: [u8; 32],
+}
+
+impl FrostDkgCommitment {
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+}
+
+/// Represents the secret share sent privately from one participant to another in the
+/// second round of the distributed key generation
+#[derive(Clone)]
+pub struct FrostDkgShare {
+    sender: u16,
+    receiver: u16,
+    share: Scalar,
+}
+
+impl FrostDkgShare {
+    pub fn sender(&self) -> u16 {
+        self.sender
+    }
+
+    pub fn receiver(&self) -> u16 {
+        self.receiver
+    }
+}
+
+impl Drop for FrostDkgShare {
+    fn drop(&mut self) {
+        self.share.clear();
+    }
+}
+
+impl fmt::Debug for FrostDkgShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrostDkgShare")
+            .field("sender", &self.sender)
+            .field("receiver", &self.receiver)
+            .field("share", &format_args!("[REDACTED]"))
+            .finish()
+    }
+}
+
+impl<N: BitcoinNetwork> FrostDkgParticipant<N> {
+    /// Returns the participant with a fresh secret polynomial and its first round package.
+    pub fn new<R: Rng>(
+        rng: &mut R,
+        identifier: u16,
+        threshold: u16,
+        participants: u16,
+    ) -> Result<(Self, FrostDkgCommitment), FrostError> {
+        check_threshold(threshold, participants)?;
+        if identifier == 0 || identifier > participants {
+            return Err(FrostError::InvalidIdentifier(identifier));
+        }
+
+        let coefficients: Vec<Scalar> = (0..threshold).map(|_| random_scalar(rng)).collect();
+        let commitment: Vec<[u8; 33]> = coefficients
+            .iter()
+            .map(|coefficient| serialize_nonzero_point(&multiply_generator(coefficient)))
+            .collect();
+
+        // Schnorr proof of knowledge of the secret a_0, mu = k + a_0 * c
+        let mut k = random_scalar(rng);
+        let proof_nonce = serialize_nonzero_point(&multiply_generator(&k));
+        let challenge = dkg_challenge(identifier, &commitment[0], &proof_nonce);
+        let proof_response = (&k + &(&coefficients[0] * &challenge)).b32();
+        k.clear();
+
+        let participant = Self {
+            identifier,
+            threshold,
+            participants,
+            coefficients,
+            _network: PhantomData,
+        };
+        let package = FrostDkgCommitment {
+            identifier,
+            commitment,
+            proof_nonce,
+            proof_response,
+        };
+        Ok((participant, package))
+    }
+
+    /// Returns the secret shares for the other participants, after verifying the first
+    /// round packages of all participants.
+    pub fn shares(
+        &self,
+        commitments: &[FrostDkgCommitment],
+    ) -> Result<Vec<FrostDkgShare>, FrostError> {
+        self.verify_commitments(commitments)?;
+        Ok((1..=self.participants)
+            .filter(|receiver| *receiver != self.identifier)
+            .map(|receiver| FrostDkgShare {
+                sender: self.identifier,
+                receiver,
+                share: evaluate_polynomial(&self.coefficients, receiver),
+            })
+            .collect())
+    }
+
+    /// Returns the key share of the participant and the public key package of the group,
+    /// after verifying the secret shares received from the other participants.
+    pub fn finish(
+        self,
+        commitments: &[FrostDkgCommitment],
+        shares: &[FrostDkgShare],
+    ) -> Result<(FrostKeyShare<N>, FrostPublicKeyPackage<N>), FrostError> {
+        let commitments = self.verify_commitments(commitments)?;
+
+        let mut secret_share = evaluate_polynomial(&self.coefficients, self.identifier);
+        for (sender, commitment) in &commitments {
+            if *sender == self.identifier {
+                continue;
+            }
+            let share = shares
+                .iter()
+                .find(|share| share.sender == *sender && share.receiver == self.identifier)
+                .ok_or(FrostError::MissingPackage(*sender))?;
+
+            let expected = evaluate_commitment(commitment, self.identifier);
+            if serialize_point(&multiply_generator(&share.share)) != serialize_point(&expected) {
+                return Err(FrostError::InvalidSecretShare(*sender));
+            }
+            secret_share += &share.share;
+        }
+
+        let mut group_public_key = infinity();
+        for (_, commitment) in &commitments {
+            group_public_key = group_public_key.add_var(&commitment[0], None);
+        }
+
+        let mut verifying_shares = vec![];
+        for identifier in 1..=self.participants {
+            let mut verifying_share = infinity();
+            for (_, commitment) in &commitments {
+                verifying_share =
+                    verifying_share.add_var(&evaluate_commitment(commitment, identifier), None);
+            }
+            let verifying_share = BitcoinPublicKey::from_point(&verifying_share)
+                .ok_or(FrostError::InvalidSecretShare(identifier))?;
+            verifying_shares.push((identifier, verifying_share));
+        }
+
+        let key_share = FrostKeyShare {
+            identifier: self.identifier,
+            secret_share,
+            _network: PhantomData,
+        };
+        let package =
+            FrostPublicKeyPackage::new(self.threshold, verifying_shares, &group_public_key)?;
+        Ok((key_share, package))
+    }
+
+    /// Returns the commitments of all participants by identifier, after verifying their
+    /// proofs of knowledge.
+    fn verify_commitments(
+        &self,
+        commitments: &[FrostDkgCommitment],
+    ) -> Result<Vec<(u16, Vec<Jacobian>)>, FrostError> {
+        let mut points = vec![];
+        for identifier in 1..=self.participants {
+            let mut matching = commitments
+                .iter()
+                .filter(|commitment| commitment.identifier == identifier);
+            let package = matching
+                .next()
+                .ok_or(FrostError::MissingPackage(identifier))?;
+            if matching.next().is_some() {
+                return Err(FrostError::DuplicateIdentifier(identifier));
+            }
+            if package.commitment.len() != self.threshold as usize {
+                return Err(FrostError::InvalidCommitment(identifier));
+            }
+
+            let commitment = package
+                .commitment
+                .iter()
+                .map(|point| parse_point(point))
+                .collect::<Result<Vec<Jacobian>, _>>()
+                .map_err(|_| FrostError::InvalidCommitment(identifier))?;
+
+            // mu * G - c * C_0 == R
+            let mut response = Scalar::default();
+            if bool::from(response.set_b32(&package.proof_response)) {
+                return Err(FrostError::InvalidProofOfKnowledge(identifier));
+            }
+            let challenge = dkg_challenge(identifier, &package.commitment[0], &package.proof_nonce);
+            let mut proof_nonce = Jacobian::default();
+            ECMULT_CONTEXT.ecmult(&mut proof_nonce, &commitment[0], &-challenge, &response);
+            if serialize_point(&proof_nonce) != Some(package.proof_nonce) {
+                return Err(FrostError::InvalidProofOfKnowledge(identifier));
+            }
+
+            points.push((identifier, commitment));
+        }
+        Ok(points)
+    }
+}
+
+impl<N: BitcoinNetwork> Drop for FrostDkgParticipant<N> {
+    fn drop(&mut self) {
+        self.coefficients.iter_mut().for_each(Scalar::clear);
+    }
+}
+
+impl<N: BitcoinNetwork> fmt::Debug for FrostDkgParticipant<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrostDkgParticipant")
+            .field("identifier", &self.identifier)
+            .field("threshold", &self.threshold)
+            .field("participants", &self.participants)
+            .field("coefficients", &format_args!("[REDACTED]"))
+            .finish()
+    }
+}
+
+/// Represents the secret nonces of a signer, which are consumed by signing so that they
+/// are never used twice
+pub struct FrostSigningNonces {
+    hiding: Scalar,
+    binding: Scalar,
+    commitment: FrostSigningCommitment,
+}
+
+/// Represents the commitment of a signer to its secret nonces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrostSigningCommitment {
+    identifier: u16,
+    hiding: [u8; 33],
+    binding: [u8; 33],
+}
+
+impl FrostSigningCommitment {
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+}
+
+impl FrostSigningNonces {
+    /// Returns fresh secret nonces of the signer and its commitment to them.
+    pub fn generate<N: BitcoinNetwork, R: Rng>(
+        rng: &mut R,
+        key_share: &FrostKeyShare<N>,
+    ) -> (Self, FrostSigningCommitment) {
+        let mut nonce = || loop {
+            let rand: [u8; 32] = rng.gen();
+            let secret_share = Zeroizing::new(key_share.secret_share.b32());
+            let nonce = scalar_from_bytes(&tagged_hash("FROST/nonce", &[&rand, &*secret_share]));
+            if !nonce.is_zero() {
+                return nonce;
+            }
+        };
+        let (hiding, binding) = (nonce(), nonce());
+
+        let commitment = FrostSigningCommitment {
+            identifier: key_share.identifier,
+            hiding: serialize_nonzero_point(&multiply_generator(&hiding)),
+            binding: serialize_nonzero_point(&multiply_generator(&binding)),
+        };
+        let nonces = Self {
+            hiding,
+            binding,
+            commitment: commitment.clone(),
+        };
+        (nonces, commitment)
+    }
+
+    fn clear_secrets(&mut self) {
+        self.hiding.clear();
+        self.binding.clear();
+    }
+}
+
+impl Drop for FrostSigningNonces {
+    fn drop(&mut self) {
+        self.clear_secrets();
+    }
+}
+
+impl fmt::Debug for FrostSigningNonces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrostSigningNonces")
+            .field("hiding", &format_args!("[REDACTED]"))
+            .field("binding", &format_args!("[REDACTED]"))
+            .field("commitment", &self.commitment)
+            .finish()
+    }
+}
+
+/// Represents the signature share of a signer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrostSignatureShare {
+    identifier: u16,
+    share: [u8; 32],
+}
+
+impl FrostSignatureShare {
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.share
+    }
+}
+
+/// Represents a FROST signing session of a 32 byte message by a set of signers, whose
+/// aggregate signature is a BIP340 signature by the group public key. Nonces, binding
+/// factors and proofs of knowledge hash with BIP340 tagged hashes under FROST/ tags rather
+/// than an RFC 9591 ciphersuite, so signers do not interoperate with RFC 9591 implementations.
+#[derive(Debug, Clone)]
+pub struct FrostSigningSession<N: BitcoinNetwork> {
+    package: FrostPublicKeyPackage<N>,
+    commitments: Vec<FrostSigningCommitment>,
+    binding_factors: Vec<Scalar>,
+    nonce_point: BitcoinPublicKey<N>,
+    challenge: Scalar,
+    message: [u8; 32],
+}
+
+impl<N: BitcoinNetwork> FrostSigningSession<N> {
+    pub fn new(
+        package: &FrostPublicKeyPackage<N>,
+        commitments: &[FrostSigningCommitment],
+        message: &[u8; 32],
+    ) -> Result<Self, FrostError> {
+        let mut commitments = commitments.to_vec();
+        commitments.sort_by_key(|commitment| commitment.identifier);
+        for (i, commitment) in commitments.iter().enumerate() {
+            if package.verifying_share(commitment.identifier).is_none() {
+                return Err(FrostError::UnknownIdentifier(commitment.identifier));
+            }
+            if i > 0 && commitments[i - 1].identifier == commitment.identifier {
+                return Err(FrostError::DuplicateIdentifier(commitment.identifier));
+            }
+        }
+        if commitments.len() < package.threshold as usize {
+            return Err(FrostError::NotEnoughSigners(
+                commitments.len(),
+                package.threshold,
+            ));
+        }
+
+        let public_key = package.x_only_public_key();
+        let encoded_commitments: Vec<u8> = commitments
+            .iter()
+            .flat_map(|commitment| {
+                [
+                    &commitment.identifier.to_be_bytes()[..],
+                    &commitment.hiding,
+                    &commitment.binding,
+                ]
+                .concat()
+            })
+            .collect();
+
+        // R = sum of D_i + rho_i * E_i
+        let mut binding_factors = vec![];
+        let mut nonce_point = infinity();
+        for commitment in &commitments {
+            let binding_factor = scalar_from_bytes(&tagged_hash(
+                "FROST/binding",
+                &[
+                    &public_key,
+                    message,
+                    &encoded_commitments,
+                    &commitment.identifier.to_be_bytes(),
+                ],
+            ));
+            nonce_point =
+                nonce_point.add_var(&signer_nonce_point(commitment, &binding_factor)?, None);
+            binding_factors.push(binding_factor);
+        }
+        let nonce_point = BitcoinPublicKey::from_point(&nonce_point)
+            .ok_or(FrostError::InfiniteNonceCommitment)?;
+
+        let challenge = scalar_from_bytes(&tagged_hash(
+            "BIP0340/challenge",
+            &[&nonce_point.to_x_only(), &public_key, message],
+        ));
+
+        Ok(Self {
+            package: package.clone(),
+            commitments,
+            binding_factors,
+            nonce_point,
+            challenge,
+            message: *message,
+        })
+    }
+
+    /// Returns the signature share of the key share, consuming its secret nonces. The
+    /// signature share is verified before it is returned.
+    pub fn sign(
+        &self,
+        nonces: FrostSigningNonces,
+        key_share: &FrostKeyShare<N>,
+    ) -> Result<FrostSignatureShare, FrostError> {
+        let identifier = key_share.identifier;
+        let index = self.signer_index(identifier)?;
+        if nonces.commitment != self.commitments[index] {
+            return Err(FrostError::NonceMismatch(identifier));
+        }
+
+        // z_i = d_i + e_i * rho_i + lambda_i * s_i * c, with the nonces and secret share
+        // negated as needed for the even y nonce commitment and group public key
+        let mut nonce = &nonces.hiding + &(&nonces.binding * &self.binding_factors[index]);
+        if !self.nonce_point.has_even_y() {
+            nonce = -nonce;
+        }
+        let mut secret_share = &self.key_coefficient(identifier) * &key_share.secret_share;
+        let share = &nonce + &secret_share;
+        nonce.clear();
+        secret_share.clear();
+
+        let signature_share = FrostSignatureShare {
+            identifier,
+            share: share.b32(),
+        };
+        self.verify_share(&signature_share)?;
+        Ok(signature_share)
+    }
+
+    /// Verifies the signature share against the verifying share of its signer.
+    pub fn verify_share(&self, signature_share: &FrostSignatureShare) -> Result<(), FrostError> {
+        let identifier = signature_share.identifier;
+        let index = self.signer_index(identifier)?;
+        let invalid = || FrostError::InvalidSignatureShare(identifier);
+
+        let mut share = Scalar::default();
+        if bool::from(share.set_b32(&signature_share.share)) {
+            return Err(invalid());
+        }
+
+        let mut nonce_point =
+            signer_nonce_point(&self.commitments[index], &self.binding_factors[index])?;
+        if !self.nonce_point.has_even_y() {
+            nonce_point = nonce_point.neg();
+        }
+
+        // z_i * G - lambda_i * c * Y_i == R_i
+        let verifying_share = self
+            .package
+            .verifying_share(identifier)
+            .ok_or(FrostError::UnknownIdentifier(identifier))?;
+        let mut result = Jacobian::default();
+        ECMULT_CONTEXT.ecmult(
+            &mut result,
+            &verifying_share.to_point(),
+            &-self.key_coefficient(identifier),
+            &share,
+        );
+
+        match serialize_point(&result) == serialize_point(&nonce_point) {
+            true => Ok(()),
+            false => Err(invalid()),
+        }
+    }
+
+    /// Returns the BIP340 signature aggregating the signature shares of all signers of the
+    /// session, after verifying each of them.
+    pub fn aggregate(
+        &self,
+        signature_shares: &[FrostSignatureShare],
+    ) -> Result<[u8; 64], FrostError> {
+        // s = sum of z_i + c * g * tacc
+        let mut s = match self.package.group_public_key.has_even_y() {
+            true => &self.challenge * &self.package.tacc,
+            false => -(&self.challenge * &self.package.tacc),
+        };
+        for commitment in &self.commitments {
+            let signature_share = signature_shares
+                .iter()
+                .find(|share| share.identifier == commitment.identifier)
+                .ok_or(FrostError::MissingSignatureShare(commitment.identifier))?;
+            self.verify_share(signature_share)?;
+            s += scalar_from_bytes(&signature_share.share);
+        }
+
+        let mut signature = [0u8; 64];
+        signature[0..32].copy_from_slice(&self.nonce_point.to_x_only());
+        signature[32..64].copy_from_slice(&s.b32());
+
+        match schnorr::verify(&self.message, &self.package.x_only_public_key(), &signature) {
+            true => Ok(signature),
+            false => Err(FrostError::InvalidSignature),
+        }
+    }
+
+    /// Returns the position of the signer in the session.
+    fn signer_index(&self, identifier: u16) -> Result<usize, FrostError> {
+        self.commitments
+            .iter()
+            .position(|commitment| commitment.identifier == identifier)
+            .ok_or(FrostError::UnknownIdentifier(identifier))
+    }
+
+    /// Returns the coefficient lambda_i * c of the secret share of the signer in its
+    /// signature share, negated as needed for the even y group public key.
+    fn key_coefficient(&self, identifier: u16) -> Scalar {
+        let signers: Vec<u16> = self
+            .commitments
+            .iter()
+            .map(|commitment| commitment.identifier)
+            .collect();
+        let coefficient = &lagrange_coefficient(identifier, &signers) * &self.challenge;
+        &coefficient * &self.package.parity()
+    }
+}
+
+/// Returns an error unless the threshold is between 2 and the number of participants.
+fn check_threshold(threshold: u16, participants: u16) -> Result<(), FrostError> {
+    match threshold >= 2 && threshold <= participants {
+        true => Ok(()),
+        false => Err(FrostError::InvalidThreshold(threshold, participants)),
+    }
+}
+
+/// Returns the challenge of the proof of knowledge of the secret of a participant.
+fn dkg_challenge(identifier: u16, commitment: &[u8; 33], proof_nonce: &[u8; 33]) -> Scalar {
+    scalar_from_bytes(&tagged_hash(
+        "FROST/dkg",
+        &[&identifier.to_be_bytes(), commitment, proof_nonce],
+    ))
+}
+
+/// Returns the nonce commitment D_i + rho_i * E_i of the signer.
+fn signer_nonce_point(
+    commitment: &FrostSigningCommitment,
+    binding_factor: &Scalar,
+) -> Result<Jacobian, FrostError> {
+    let invalid = |_| FrostError::InvalidCommitment(commitment.identifier);
+    let mut nonce_point = Jacobian::default();
+    ECMULT_CONTEXT.ecmult(
+        &mut nonce_point,
+        &parse_point(&commitment.binding).map_err(invalid)?,
+        binding_factor,
+        &Scalar::default(),
+    );
+    Ok(nonce_point.add_var(&parse_point(&commitment.hiding).map_err(invalid)?, None))
+}
+
+/// Returns the Lagrange coefficient at zero of the signer among the signers.
+fn lagrange_coefficient(identifier: u16, signers: &[u16]) -> Scalar {
+    let x = Scalar::from_int(identifier as u32);
+    let mut numerator = Scalar::from_int(1);
+    let mut denominator = Scalar::from_int(1);
+    for signer in signers.iter().filter(|signer| **signer != identifier) {
+        let x_j = Scalar::from_int(*signer as u32);
+        denominator *= &x_j + &(-x.clone());
+        numerator *= x_j;
+    }
+    &numerator * &denominator.inv()
+}
+
+/// Returns the value at x of the polynomial with the given coefficients.
+fn evaluate_polynomial(coefficients: &[Scalar], x: u16) -> Scalar {
+    let x = Scalar::from_int(x as u32);
+    let mut value = Scalar::default();
+    for coefficient in coefficients.iter().rev() {
+        value = &(&value * &x) + coefficient;
+    }
+    value
+}
+
+/// Returns the commitment to the value at x of the polynomial with the given coefficient
+/// commitments.
+fn evaluate_commitment(commitment: &[Jacobian], x: u16) -> Jacobian {
+    let x = Scalar::from_int(x as u32);
+    let mut value = infinity();
+    for point in commitment.iter().rev() {
+        if !value.is_infinity() {
+            let mut product = Jacobian::default();
+            ECMULT_CONTEXT.ecmult(&mut product, &value, &x, &Scalar::default());
+            value = product;
+        }
+        value = value.add_var(point, None);
+    }
+    value
+}
+
+/// Returns a uniformly random nonzero scalar.
+fn random_scalar<R: Rng>(rng: &mut R) -> Scalar {
+    loop {
+        let bytes: [u8; 32] = rng.gen();
+        let mut scalar = Scalar::default();
+        if !bool::from(scalar.set_b32(&bytes)) && !scalar.is_zero() {
+            return scalar;
+        }
+    }
+}
+
+/// Returns the point scalar * G.
+fn multiply_generator(scalar: &Scalar) -> Jacobian {
+    let mut point = Jacobian::default();
+    ECMULT_GEN_CONTEXT.ecmult_gen(&mut point, scalar);
+    point
+}
+
+/// Returns the compressed serialization of the product of a nonzero scalar and G.
+fn serialize_nonzero_point(point: &Jacobian) -> [u8; 33] {
+    serialize_point(point).expect("the product of a nonzero scalar and G is not infinity")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+    use gyu_model::private_key::PrivateKey;
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    type N = Mainnet;
+
+    const MESSAGE: [u8; 32] = [0x42; 32];
+
+    fn private_key() -> BitcoinPrivateKey<N> {
+        BitcoinPrivateKey::from_secp256k1_secret_key(
+            &secp256k1::SecretKey::parse(&[7u8; 32]).unwrap(),
+            true,
+        )
+    }
+
+    /// Runs the distributed key generation of all participants in process.
+    fn simulate_dkg<N: BitcoinNetwork, R: Rng>(
+        rng: &mut R,
+        threshold: u16,
+        participants: u16,
+    ) -> Result<(Vec<FrostKeyShare<N>>, FrostPublicKeyPackage<N>), FrostError> {
+        let mut dkg_participants = vec![];
+        let mut commitments = vec![];
+        for identifier in 1..=participants {
+            let (participant, commitment) =
+                FrostDkgParticipant::<N>::new(rng, identifier, threshold, participants)?;
+            dkg_participants.push(participant);
+            commitments.push(commitment);
+        }
+
+        let mut shares = vec![];
+        for participant in &dkg_participants {
+            shares.extend(participant.shares(&commitments)?);
+        }
+
+        let mut key_shares = vec![];
+        let mut packages = vec![];
+        for participant in dkg_participants {
+            let (key_share, package) = participant.finish(&commitments, &shares)?;
+            key_shares.push(key_share);
+            packages.push(package);
+        }
+        if packages.iter().any(|package| *package != packages[0]) {
+            return Err(FrostError::InvalidCommitment(0));
+        }
+        Ok((key_shares, packages.remove(0)))
+    }
+
+    /// Runs both signing rounds of the signers in process, returning the BIP340 signature of the
+    /// message.
+    fn simulate_signing<N: BitcoinNetwork, R: Rng>(
+        rng: &mut R,
+        key_shares: &[FrostKeyShare<N>],
+        package: &FrostPublicKeyPackage<N>,
+        message: &[u8; 32],
+    ) -> Result<[u8; 64], FrostError> {
+        let (nonces, commitments): (Vec<_>, Vec<_>) = key_shares
+            .iter()
+            .map(|key_share| FrostSigningNonces::generate(rng, key_share))
+            .unzip();
+
+        let session = FrostSigningSession::new(package, &commitments, message)?;
+        let signature_shares = nonces
+            .into_iter()
+            .zip(key_shares)
+            .map(|(nonces, key_share)| session.sign(nonces, key_share))
+            .collect::<Result<Vec<_>, _>>()?;
+        session.aggregate(&signature_shares)
+    }
+
+    fn assert_signs(
+        rng: &mut XorShiftRng,
+        key_shares: &[FrostKeyShare<N>],
+        package: &FrostPublicKeyPackage<N>,
+    ) {
+        let signature = simulate_signing(rng, key_shares, package, &MESSAGE).unwrap();
+        assert!(schnorr::verify(
+            &MESSAGE,
+            &package.x_only_public_key(),
+            &signature
+        ));
+    }
+
+    #[test]
+    fn trusted_dealer() {
+        let mut rng = XorShiftRng::seed_from_u64(3);
+        let (key_shares, package) = trusted_dealer_keygen(&mut rng, &private_key(), 2, 3).unwrap();
+        assert_eq!(private_key().to_public_key(), package.group_public_key());
+
+        for signers in [[0, 1], [0, 2], [1, 2]].iter() {
+            let signers: Vec<_> = signers.iter().map(|i| key_shares[*i].clone()).collect();
+            assert_signs(&mut rng, &signers, &package);
+        }
+        assert_signs(&mut rng, &key_shares, &package);
+
+        assert!(simulate_signing(&mut rng, &key_shares[..1], &package, &MESSAGE).is_err());
+        assert!(trusted_dealer_keygen(&mut rng, &private_key(), 1, 3).is_err());
+        assert!(trusted_dealer_keygen(&mut rng, &private_key(), 4, 3).is_err());
+    }
+
+    #[test]
+    fn dkg() {
+        let mut rng = XorShiftRng::seed_from_u64(5);
+        let (key_shares, package) = simulate_dkg::<N, _>(&mut rng, 3, 5).unwrap();
+        for key_share in &key_shares {
+            assert_eq!(
+                &key_share.verifying_share(),
+                package.verifying_share(key_share.identifier()).unwrap()
+            );
+        }
+        assert_signs(&mut rng, &key_shares[1..4], &package);
+        assert!(simulate_signing(&mut rng, &key_shares[..2], &package, &MESSAGE).is_err());
+    }
+
+    #[test]
+    fn invalid_dkg() {
+        let mut rng = XorShiftRng::seed_from_u64(7);
+        assert!(FrostDkgParticipant::<N>::new(&mut rng, 0, 2, 2).is_err());
+        assert!(FrostDkgParticipant::<N>::new(&mut rng, 3, 2, 2).is_err());
+
+        let (first, first_commitment) = FrostDkgParticipant::<N>::new(&mut rng, 1, 2, 2).unwrap();
+        let (second, second_commitment) = FrostDkgParticipant::<N>::new(&mut rng, 2, 2, 2).unwrap();
+        let commitments = [first_commitment.clone(), second_commitment];
+        assert!(first.shares(&[first_commitment.clone()]).is_err());
+        assert!(first
+            .shares(&[first_commitment.clone(), first_commitment.clone()])
+            .is_err());
+
+        let mut forged = commitments[1].clone();
+        forged.proof_response = [1u8; 32];
+        assert!(first.shares(&[first_commitment.clone(), forged]).is_err());
+
+        // Shares from a participant who committed to another polynomial are rejected.
+        let (_, other_commitment) = FrostDkgParticipant::<N>::new(&mut rng, 2, 2, 2).unwrap();
+        let second_shares = second.shares(&commitments).unwrap();
+        assert!(first
+            .finish(&[first_commitment, other_commitment], &second_shares)
+            .is_err());
+        assert!(second.finish(&commitments, &[]).is_err());
+    }
+
+    #[test]
+    fn tweaks() {
+        let mut rng = XorShiftRng::seed_from_u64(11);
+        let (key_shares, package) = simulate_dkg::<N, _>(&mut rng, 2, 3).unwrap();
+
+        let tweaked = package
+            .apply_tweak(&[5u8; 32], false)
+            .unwrap()
+            .apply_taproot_tweak(None)
+            .unwrap();
+        assert_signs(&mut rng, &key_shares[1..], &tweaked);
+        assert_signs(
+            &mut rng,
+            &key_shares[..2],
+            &package.apply_tweak(&[9u8; 32], true).unwrap(),
+        );
+        assert_eq!(
+            package
+                .group_public_key()
+                .to_address(&BitcoinFormat::P2TR)
+                .unwrap(),
+            package.to_address().unwrap()
+        );
+    }
+
+    #[test]
+    fn signing_session() {
+        let mut rng = XorShiftRng::seed_from_u64(13);
+        let (key_shares, package) = simulate_dkg::<N, _>(&mut rng, 3, 4).unwrap();
+        let (nonces, commitments): (Vec<_>, Vec<_>) = key_shares[..3]
+            .iter()
+            .map(|key_share| FrostSigningNonces::generate(&mut rng, key_share))
+            .unzip();
+
+        assert!(FrostSigningSession::new(&package, &commitments[..2], &MESSAGE).is_err());
+        let duplicate = [
+            commitments[0].clone(),
+            commitments[0].clone(),
+            commitments[1].clone(),
+        ];
+        assert!(FrostSigningSession::new(&package, &duplicate, &MESSAGE).is_err());
+
+        let session = FrostSigningSession::new(&package, &commitments, &MESSAGE).unwrap();
+        let (other_nonces, _) = FrostSigningNonces::generate(&mut rng, &key_shares[3]);
+        assert!(session.sign(other_nonces, &key_shares[3]).is_err());
+
+        let mut signature_shares = vec![];
+        for (nonces, key_share) in nonces.into_iter().zip(&key_shares) {
+            signature_shares.push(session.sign(nonces, key_share).unwrap());
+        }
+        assert!(session.aggregate(&signature_shares[..2]).is_err());
+
+        let mut forged = signature_shares.clone();
+        forged[1].share = forged[0].share;
+        assert!(session.verify_share(&forged[1]).is_err());
+        assert!(session.aggregate(&forged).is_err());
+
+        let signature = session.aggregate(&signature_shares).unwrap();
+        assert!(schnorr::verify(
+            &MESSAGE,
+            &package.x_only_public_key(),
+            &signature
+        ));
+    }
+
+    #[test]
+    fn zeroize() {
+        // Drop clears the secrets of key shares and nonces through clear_secrets.
+        let mut rng = XorShiftRng::seed_from_u64(17);
+        let (mut key_shares, _) = trusted_dealer_keygen(&mut rng, &private_key(), 2, 2).unwrap();
+        let (mut nonces, _) = FrostSigningNonces::generate(&mut rng, &key_shares[0]);
+        nonces.clear_secrets();
+        assert!(nonces.hiding.is_zero() && nonces.binding.is_zero());
+        key_shares[0].clear_secrets();
+        assert!(key_shares[0].secret_share.is_zero());
+    }
+
+    #[test]
+    fn redacted_debug() {
+        let mut rng = XorShiftRng::seed_from_u64(19);
+        let (key_shares, _) = trusted_dealer_keygen(&mut rng, &private_key(), 2, 2).unwrap();
+        assert_eq!(
+            "FrostKeyShare { identifier: 1, secret_share: [REDACTED] }",
+            format!("{:?}", key_shares[0])
+        );
+
+        let (nonces, _) = FrostSigningNonces::generate(&mut rng, &key_shares[0]);
+        assert!(format!("{:?}", nonces).contains("hiding: [REDACTED], binding: [REDACTED]"));
+
+        let (participant, _) = FrostDkgParticipant::<N>::new(&mut rng, 1, 2, 2).unwrap();
+        assert!(format!("{:?}", participant).contains("coefficients: [REDACTED]"));
+    }
+}
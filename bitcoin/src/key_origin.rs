@@ -0,0 +1,145 @@
+use crate::derivation_path::BitcoinDerivationPath;
+use crate::network::BitcoinNetwork;
+use gyu_model::derivation_path::{ChildIndex, DerivationPath, DerivationPathError};
+use gyu_model::no_std::*;
+
+use core::{fmt, str::FromStr};
+
+/// Represents the origin of an extended key, the fingerprint of its master key and the
+/// path it was derived along, written [fingerprint/path] in PSBTs and descriptors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyOrigin {
+    pub master_fingerprint: [u8; 4],
+    pub path: Vec<ChildIndex>,
+}
+
+impl KeyOrigin {
+    pub fn new(master_fingerprint: [u8; 4], path: &[ChildIndex]) -> Self {
+        Self {
+            master_fingerprint,
+            path: path.to_vec(),
+        }
+    }
+
+    /// Returns the origin of the child at the index of a key with this origin.
+    pub fn child(&self, index: ChildIndex) -> Self {
+        let mut path = self.path.clone();
+        path.push(index);
+        Self {
+            master_fingerprint: self.master_fingerprint,
+            path,
+        }
+    }
+
+    /// Returns true if the origin can be that of a key at the depth and child index with
+    /// the fingerprint.
+    pub(crate) fn is_consistent(
+        &self,
+        depth: u8,
+        child_index: ChildIndex,
+        fingerprint: [u8; 4],
+    ) -> bool {
+        match self.path.last() {
+            Some(index) => self.path.len() == depth as usize && *index == child_index,
+            None => depth == 0 && self.master_fingerprint == fingerprint,
+        }
+    }
+
+    /// Returns the path of the origin as a derivation path from the master key.
+    pub fn to_derivation_path<N: BitcoinNetwork>(
+        &self,
+    ) -> Result<BitcoinDerivationPath<N>, DerivationPathError> {
+        BitcoinDerivationPath::from_vec(&self.path)
+    }
+}
+
+impl FromStr for KeyOrigin {
+    type Err = DerivationPathError;
+
+    fn from_str(origin: &str) -> Result<Self, Self::Err> {
+        let invalid = || DerivationPathError::InvalidDerivationPath(origin.to_string());
+        let inner = match origin.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Some(inner) => inner,
+            None => origin,
+        };
+
+        let mut parts = inner.split('/');
+        let fingerprint = parts.next().ok_or_else(invalid)?;
+        let mut master_fingerprint = [0u8; 4];
+        if fingerprint.len() != 8 {
+            return Err(invalid());
+        }
+        hex::decode_to_slice(fingerprint, &mut master_fingerprint).map_err(|_| invalid())?;
+
+        let path: Result<Vec<ChildIndex>, Self::Err> = parts.map(str::parse).collect();
+        Ok(Self {
+            master_fingerprint,
+            path: path?,
+        })
+    }
+}
+
+impl fmt::Display for KeyOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}", hex::encode(self.master_fingerprint))?;
+        for index in self.path.iter() {
+            write!(f, "/{}", index)?;
+        }
+        f.write_str("]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+
+    #[test]
+    fn from_str() {
+        let origin = KeyOrigin::from_str("[d34db33f/48'/0'/0'/2']").unwrap();
+        assert_eq!([0xd3, 0x4d, 0xb3, 0x3f], origin.master_fingerprint);
+        assert_eq!(
+            vec![
+                ChildIndex::Hardened(48),
+                ChildIndex::Hardened(0),
+                ChildIndex::Hardened(0),
+                ChildIndex::Hardened(2),
+            ],
+            origin.path
+        );
+        assert_eq!("[d34db33f/48'/0'/0'/2']", origin.to_string());
+        assert_eq!(
+            origin,
+            KeyOrigin::from_str("d34db33f/48'/0'/0'/2'").unwrap()
+        );
+        assert_eq!(
+            "m/48'/0'/0'/2'",
+            origin.to_derivation_path::<Mainnet>().unwrap().to_string()
+        );
+
+        let master = KeyOrigin::from_str("[d34db33f]").unwrap();
+        assert!(master.path.is_empty());
+        assert_eq!("[d34db33f]", master.to_string());
+    }
+
+    #[test]
+    fn invalid_origins() {
+        for origin in ["", "[d34db33]", "[d34db33f0]", "[g34db33f]", "[d34db33f/x]"].iter() {
+            assert!(KeyOrigin::from_str(origin).is_err());
+        }
+    }
+
+    #[test]
+    fn child() {
+        let master = KeyOrigin::new([1, 2, 3, 4], &[]);
+        let child = master
+            .child(ChildIndex::Hardened(84))
+            .child(ChildIndex::Normal(1));
+        assert_eq!("[01020304/84'/1]", child.to_string());
+        assert!(master.is_consistent(0, ChildIndex::Normal(0), [1, 2, 3, 4]));
+        assert!(!master.is_consistent(0, ChildIndex::Normal(0), [4, 3, 2, 1]));
+        assert!(child.is_consistent(2, ChildIndex::Normal(1), [0; 4]));
+        assert!(!child.is_consistent(2, ChildIndex::Hardened(1), [0; 4]));
+        assert!(!child.is_consistent(3, ChildIndex::Normal(1), [0; 4]));
+    }
+}
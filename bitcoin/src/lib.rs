@@ -6,14 +6,41 @@ extern crate failure;
 
 pub mod address;
 pub mod amount;
+#[cfg(feature = "std")]
+pub mod batch;
+pub mod block;
+pub mod block_filter;
 pub mod derivation_path;
+pub mod deterministic_entropy;
+#[cfg(feature = "std")]
+pub mod discovery;
+pub mod encrypted_private_key;
 pub mod extended_private_key;
 pub mod extended_public_key;
 pub mod format;
+pub mod frost;
+pub mod key_origin;
+pub mod message;
 pub mod mnemonic;
+pub mod multisig;
+pub mod musig;
 pub mod network;
+pub mod path_template;
+pub mod payment_code;
 pub mod private_key;
 pub mod public_key;
+pub mod schnorr;
+pub mod signature;
+#[cfg(feature = "std")]
+pub mod signer;
+pub mod silent_payment;
+pub mod slip132;
+#[cfg(test)]
+mod test_vectors;
 pub mod transaction;
+#[cfg(feature = "std")]
+pub mod vanity;
+#[cfg(feature = "std")]
+pub mod watch_only;
 pub mod witness_program;
 pub mod wordlist;
@@ -0,0 +1,711 @@
+use crate::address::BitcoinAddress;
+use crate::amount::BitcoinAmount;
+use crate::format::BitcoinFormat;
+use crate::network::BitcoinNetwork;
+use crate::private_key::BitcoinPrivateKey;
+use crate::public_key::{BitcoinPublicKey, PublicKeyPolicy};
+use crate::schnorr::{self, tagged_hash};
+use crate::signature::verify;
+use crate::transaction::{
+    create_script_pub_key, variable_length_integer, BitcoinTransaction, BitcoinTransactionInput,
+    BitcoinTransactionOutput, BitcoinTransactionParameters, Outpoint, SignatureHash,
+};
+use gyu_model::no_std::*;
+use gyu_model::{
+    address::AddressError,
+    private_key::PrivateKey,
+    public_key::PublicKeyError,
+    transaction::{Transaction, TransactionError},
+    utilities::crypto::{checksum, hash160},
+};
+
+use core::fmt;
+use secp256k1::{Message, RecoveryId, Signature};
+
+/// The prefix of messages signed in the legacy (BIP137) format
+const BITCOIN_SIGNED_MESSAGE_PREFIX: &[u8] = b"Bitcoin Signed Message:\n";
+
+#[derive(Debug, Fail)]
+pub enum MessageError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "invalid message signature encoding")]
+    InvalidEncoding,
+
+    #[fail(display = "invalid message signature header {}", _0)]
+    InvalidHeader(u8),
+
+    #[fail(display = "invalid message signature for address {}", _0)]
+    InvalidSignature(String),
+
+    #[fail(display = "invalid BIP322 to_sign transaction: {}", _0)]
+    InvalidToSignTransaction(String),
+
+    #[fail(display = "{}", _0)]
+    PublicKeyError(PublicKeyError),
+
+    #[fail(display = "{}", _0)]
+    TransactionError(TransactionError),
+
+    #[fail(
+        display = "unsupported address format {} for {} message signatures",
+        _0, _1
+    )]
+    UnsupportedFormat(String, String),
+}
+
+impl From<AddressError> for MessageError {
+    fn from(error: AddressError) -> Self {
+        MessageError::AddressError(error)
+    }
+}
+
+impl From<PublicKeyError> for MessageError {
+    fn from(error: PublicKeyError) -> Self {
+        MessageError::PublicKeyError(error)
+    }
+}
+
+impl From<TransactionError> for MessageError {
+    fn from(error: TransactionError) -> Self {
+        MessageError::TransactionError(error)
+    }
+}
+
+impl From<base64::DecodeError> for MessageError {
+    fn from(error: base64::DecodeError) -> Self {
+        MessageError::Crate("base64", format!("{:?}", error))
+    }
+}
+
+impl From<secp256k1::Error> for MessageError {
+    fn from(error: secp256k1::Error) -> Self {
+        MessageError::Crate("libsecp256k1", format!("{:?}", error))
+    }
+}
+
+/// The encoding of a message signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BitcoinMessageSignatureFormat {
+    /// The BIP137 compact recoverable signature, for P2PKH, P2SH_P2WPKH and Bech32 addresses
+    Legacy,
+    /// The BIP322 witness stack of the to_sign transaction, for Bech32 and P2TR addresses
+    Simple,
+    /// The BIP322 serialized to_sign transaction, for P2PKH, P2SH_P2WPKH, Bech32 and P2TR addresses
+    Full,
+}
+
+impl fmt::Display for BitcoinMessageSignatureFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitcoinMessageSignatureFormat::Legacy => write!(f, "legacy"),
+            BitcoinMessageSignatureFormat::Simple => write!(f, "simple"),
+            BitcoinMessageSignatureFormat::Full => write!(f, "full"),
+        }
+    }
+}
+
+/// Returns the BIP137 hash of the message, the double SHA256 of the prefixed message.
+pub fn legacy_message_hash(message: &[u8]) -> Result<[u8; 32], MessageError> {
+    let mut data = variable_length_integer(BITCOIN_SIGNED_MESSAGE_PREFIX.len() as u64)?;
+    data.extend(BITCOIN_SIGNED_MESSAGE_PREFIX);
+    data.extend(variable_length_integer(message.len() as u64)?);
+    data.extend(message);
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&checksum(&data));
+    Ok(hash)
+}
+
+/// Returns the BIP322 tagged hash of the message.
+pub fn bip322_message_hash(message: &[u8]) -> [u8; 32] {
+    tagged_hash("BIP0322-signed-message", &[message])
+}
+
+/// Returns the BIP322 to_spend transaction, the virtual transaction whose only output
+/// pays to the address and whose input commits to the message.
+pub fn bip322_to_spend<N: BitcoinNetwork>(
+    address: &BitcoinAddress<N>,
+    message: &[u8],
+) -> Result<BitcoinTransaction<N>, MessageError> {
+    let mut script_sig = vec![0x00, 0x20];
+    script_sig.extend(&bip322_message_hash(message));
+
+    let input = BitcoinTransactionInput::<N> {
+        outpoint: Outpoint::<N>::new(vec![0u8; 32], 0xFFFFFFFF, None, None, None, None)?,
+        script_sig,
+        sequence: vec![0u8; 4],
+        sighash_code: SignatureHash::SIG_ALL,
+        witnesses: vec![],
+        is_signed: true,
+        additional_witness: None,
+        witness_script_data: None,
+    };
+    let output = BitcoinTransactionOutput {
+        amount: BitcoinAmount(0),
+        script_pub_key: create_script_pub_key(address)?,
+    };
+
+    Ok(BitcoinTransaction::new(&BitcoinTransactionParameters {
+        version: 0,
+        inputs: vec![input],
+        outputs: vec![output],
+        lock_time: 0,
+        segwit_flag: false,
+    })?)
+}
+
+/// Returns the unsigned BIP322 to_sign transaction, the virtual transaction spending the
+/// output of the to_spend transaction to a single OP_RETURN output. P2SH_P2WPKH addresses
+/// take their redeem script.
+pub fn bip322_to_sign<N: BitcoinNetwork>(
+    address: &BitcoinAddress<N>,
+    message: &[u8],
+    redeem_script: Option<Vec<u8>>,
+) -> Result<BitcoinTransaction<N>, MessageError> {
+    let to_spend = bip322_to_spend(address, message)?;
+    let sighash = match address.format() {
+        BitcoinFormat::P2TR => SignatureHash::SIGHASH_DEFAULT,
+        _ => SignatureHash::SIG_ALL,
+    };
+
+    let input = BitcoinTransactionInput::<N>::new(
+        to_spend.to_transaction_id()?.txid().to_vec(),
+        0,
+        Some(address.clone()),
+        Some(BitcoinAmount(0)),
+        redeem_script,
+        None,
+        Some(vec![0u8; 4]),
+        sighash,
+    )?;
+    let output = BitcoinTransactionOutput {
+        amount: BitcoinAmount(0),
+        script_pub_key: vec![0x6a],
+    };
+
+    Ok(BitcoinTransaction::new(&BitcoinTransactionParameters {
+        version: 0,
+        inputs: vec![input],
+        outputs: vec![output],
+        lock_time: 0,
+        segwit_flag: false,
+    })?)
+}
+
+impl<N: BitcoinNetwork> BitcoinPrivateKey<N> {
+    /// Returns the base64 signature of the message by the address of the given format.
+    pub fn sign_message(
+        &self,
+        message: &[u8],
+        format: &BitcoinFormat,
+        signature_format: BitcoinMessageSignatureFormat,
+    ) -> Result<String, MessageError> {
+        let unsupported = || {
+            Err(MessageError::UnsupportedFormat(
+                format.to_string(),
+                signature_format.to_string(),
+            ))
+        };
+
+        match (signature_format, format) {
+            (BitcoinMessageSignatureFormat::Legacy, _) => {
+                let header = match (format, self.is_compressed()) {
+                    (BitcoinFormat::P2PKH, false) => 27,
+                    (BitcoinFormat::P2PKH, true) => 31,
+                    (BitcoinFormat::P2SH_P2WPKH, _) => 35,
+                    (BitcoinFormat::Bech32, _) => 39,
+                    _ => return unsupported(),
+                };
+
+                let signature = self.sign_recoverable(&legacy_message_hash(message)?);
+
+                let mut compact = vec![header + signature[64]];
+                compact.extend(&signature[..64]);
+                Ok(base64::encode(&compact))
+            }
+            (BitcoinMessageSignatureFormat::Simple, BitcoinFormat::Bech32)
+            | (BitcoinMessageSignatureFormat::Simple, BitcoinFormat::P2TR) => {
+                let to_sign = self.sign_bip322(message, format)?;
+                let input = &to_sign.parameters().inputs[0];
+                let mut witness = variable_length_integer(input.witnesses.len() as u64)?;
+                for item in &input.witnesses {
+                    witness.extend(item);
+                }
+                Ok(base64::encode(&witness))
+            }
+            (BitcoinMessageSignatureFormat::Full, BitcoinFormat::P2PKH)
+            | (BitcoinMessageSignatureFormat::Full, BitcoinFormat::P2SH_P2WPKH)
+            | (BitcoinMessageSignatureFormat::Full, BitcoinFormat::Bech32)
+            | (BitcoinMessageSignatureFormat::Full, BitcoinFormat::P2TR) => {
+                let to_sign = self.sign_bip322(message, format)?;
+                Ok(base64::encode(&to_sign.to_transaction_bytes()?))
+            }
+            _ => unsupported(),
+        }
+    }
+
+    /// Returns the signed BIP322 to_sign transaction of the message.
+    fn sign_bip322(
+        &self,
+        message: &[u8],
+        format: &BitcoinFormat,
+    ) -> Result<BitcoinTransaction<N>, MessageError> {
+        let address = self.to_address(format)?;
+        let to_sign = bip322_to_sign(&address, message, redeem_script(self, format))?.sign(self)?;
+        match to_sign.parameters().inputs[0].is_signed {
+            true => Ok(to_sign),
+            false => Err(MessageError::InvalidSignature(address.to_string())),
+        }
+    }
+}
+
+/// Returns the redeem script of the P2SH_P2WPKH address of the private key.
+fn redeem_script<N: BitcoinNetwork>(
+    private_key: &BitcoinPrivateKey<N>,
+    format: &BitcoinFormat,
+) -> Option<Vec<u8>> {
+    match format {
+        BitcoinFormat::P2SH_P2WPKH => Some(p2wpkh_redeem_script(&private_key.to_public_key())),
+        _ => None,
+    }
+}
+
+/// Returns the P2SH_P2WPKH redeem script of the public key.
+fn p2wpkh_redeem_script<N: BitcoinNetwork>(public_key: &BitcoinPublicKey<N>) -> Vec<u8> {
+    let mut redeem_script = vec![0x00, 0x14];
+    redeem_script.extend(&hash160(
+        &public_key.to_secp256k1_public_key().serialize_compressed(),
+    ));
+    redeem_script
+}
+
+impl<N: BitcoinNetwork> BitcoinAddress<N> {
+    /// Verifies the base64 signature of the message by the address. Legacy (BIP137),
+    /// BIP322 simple and BIP322 full signatures are distinguished by their encoding.
+    pub fn verify_message(&self, message: &[u8], signature: &str) -> Result<(), MessageError> {
+        let signature = base64::decode(signature)?;
+
+        if signature.len() == 65 {
+            return self.verify_legacy_message(message, &signature);
+        }
+
+        if let Some(witness) = read_witness_stack(&signature) {
+            if !witness.is_empty() {
+                return self.verify_bip322(message, &[], &witness);
+            }
+        }
+
+        let to_sign = BitcoinTransaction::<N>::read(&signature[..])
+            .map_err(|_| MessageError::InvalidEncoding)?;
+        if to_sign.to_transaction_bytes()? != signature {
+            return Err(MessageError::InvalidEncoding);
+        }
+        self.verify_bip322_to_sign(message, &to_sign)
+    }
+
+    /// Verifies the BIP137 compact recoverable signature of the message by the address.
+    fn verify_legacy_message(&self, message: &[u8], signature: &[u8]) -> Result<(), MessageError> {
+        let header = signature[0];
+        if !(27..=42).contains(&header) {
+            return Err(MessageError::InvalidHeader(header));
+        }
+
+        let recovery_id = RecoveryId::parse((header - 27) % 4)?;
+        let message = Message::parse(&legacy_message_hash(message)?);
+        let public_key = secp256k1::recover(
+            &message,
+            &Signature::parse_slice(&signature[1..])?,
+            &recovery_id,
+        )?;
+
+        // Compressed P2PKH headers are also accepted for segwit addresses, as some
+        // wallets sign with them.
+        let compressed = BitcoinPublicKey::<N>::from_secp256k1_public_key(public_key.clone(), true);
+        let candidates = match (header - 27) / 4 {
+            0 => vec![BitcoinAddress::<N>::p2pkh(
+                &BitcoinPublicKey::from_secp256k1_public_key(public_key, false),
+            )?],
+            1 => vec![
+                BitcoinAddress::<N>::p2pkh(&compressed)?,
+                BitcoinAddress::<N>::p2sh_p2wpkh(&compressed)?,
+                BitcoinAddress::<N>::bech32(&compressed)?,
+            ],
+            2 => vec![BitcoinAddress::<N>::p2sh_p2wpkh(&compressed)?],
+            _ => vec![BitcoinAddress::<N>::bech32(&compressed)?],
+        };
+
+        let script_pub_key = create_script_pub_key(self)?;
+        for candidate in &candidates {
+            if create_script_pub_key(candidate)? == script_pub_key {
+                return Ok(());
+            }
+        }
+        Err(MessageError::InvalidSignature(self.to_string()))
+    }
+
+    /// Verifies the signed BIP322 to_sign transaction of the message by the address.
+    fn verify_bip322_to_sign(
+        &self,
+        message: &[u8],
+        to_sign: &BitcoinTransaction<N>,
+    ) -> Result<(), MessageError> {
+        let invalid = |reason: &str| Err(MessageError::InvalidToSignTransaction(reason.into()));
+        let parameters = to_sign.parameters();
+        if parameters.version != 0 || parameters.lock_time != 0 {
+            return invalid("version and lock time must be zero");
+        }
+        if parameters.inputs.len() != 1 {
+            return invalid("expected a single input");
+        }
+        if parameters.outputs.len() != 1
+            || parameters.outputs[0].amount.0 != 0
+            || parameters.outputs[0].script_pub_key != vec![0x6a]
+        {
+            return invalid("expected a single empty OP_RETURN output");
+        }
+
+        let input = &parameters.inputs[0];
+        let mut to_spend_id = bip322_to_spend(self, message)?
+            .to_transaction_id()?
+            .txid()
+            .to_vec();
+        to_spend_id.reverse();
+        if input.outpoint.reverse_transaction_id != to_spend_id
+            || input.outpoint.index != 0
+            || input.sequence != vec![0u8; 4]
+        {
+            return invalid("the input must spend the to_spend transaction");
+        }
+
+        let mut witness = vec![];
+        for item in &input.witnesses {
+            match read_witness_stack(&[vec![0x01], item.clone()].concat()) {
+                Some(mut items) => witness.append(&mut items),
+                None => return Err(MessageError::InvalidEncoding),
+            }
+        }
+        self.verify_bip322(message, &input.script_sig, &witness)
+    }
+
+    /// Verifies the script signature and witness of the BIP322 to_sign transaction of the
+    /// message for the standard script of the address.
+    fn verify_bip322(
+        &self,
+        message: &[u8],
+        script_sig: &[u8],
+        witness: &[Vec<u8>],
+    ) -> Result<(), MessageError> {
+        let invalid = || Err(MessageError::InvalidSignature(self.to_string()));
+        let script_pub_key = create_script_pub_key(self)?;
+
+        let (signature, public_key, redeem_script) = match self.format() {
+            BitcoinFormat::P2PKH => match (read_pushes(script_sig), witness.len()) {
+                (Some(pushes), 0) if pushes.len() == 2 => {
+                    (pushes[0].clone(), pushes[1].clone(), None)
+                }
+                _ => return invalid(),
+            },
+            BitcoinFormat::P2SH_P2WPKH => match (read_pushes(script_sig), witness.len()) {
+                (Some(pushes), 2) if pushes.len() == 1 => (
+                    witness[0].clone(),
+                    witness[1].clone(),
+                    Some(pushes[0].clone()),
+                ),
+                _ => return invalid(),
+            },
+            BitcoinFormat::Bech32 if script_pub_key.len() == 22 => {
+                match (script_sig.len(), witness.len()) {
+                    (0, 2) => (witness[0].clone(), witness[1].clone(), None),
+                    _ => return invalid(),
+                }
+            }
+            BitcoinFormat::P2TR => {
+                let sighash = match (script_sig.len(), witness) {
+                    (0, [signature]) if signature.len() == 64 => SignatureHash::SIGHASH_DEFAULT,
+                    (0, [signature]) if signature.len() == 65 && signature[64] == 0x01 => {
+                        SignatureHash::SIG_ALL
+                    }
+                    _ => return invalid(),
+                };
+
+                let to_sign = bip322_to_sign(self, message, None)?;
+                let preimage = to_sign.taproot_hash_preimage(0, sighash)?;
+                let transaction_hash = tagged_hash("TapSighash", &[&preimage]);
+
+                let mut output_key = [0u8; 32];
+                output_key.copy_from_slice(&script_pub_key[2..]);
+                let mut signature = [0u8; 64];
+                signature.copy_from_slice(&witness[0][..64]);
+
+                return match schnorr::verify(&transaction_hash, &output_key, &signature) {
+                    true => Ok(()),
+                    false => invalid(),
+                };
+            }
+            format => {
+                return Err(MessageError::UnsupportedFormat(
+                    format.to_string(),
+                    "BIP322".into(),
+                ))
+            }
+        };
+
+        // The public key must hash to the address, and the signature must use SIGHASH_ALL
+        let public_key = BitcoinPublicKey::<N>::from_slice(&public_key, &PublicKeyPolicy::P2PKH)?;
+        let address = match (self.format(), &redeem_script) {
+            (BitcoinFormat::P2SH_P2WPKH, Some(redeem_script))
+                if public_key.is_compressed()
+                    && *redeem_script == p2wpkh_redeem_script(&public_key) =>
+            {
+                BitcoinAddress::<N>::p2sh_p2wpkh(&public_key)?
+            }
+            (BitcoinFormat::P2SH_P2WPKH, _) => return invalid(),
+            (BitcoinFormat::Bech32, _) if public_key.is_compressed() => {
+                BitcoinAddress::<N>::bech32(&public_key)?
+            }
+            (BitcoinFormat::Bech32, _) => return invalid(),
+            _ => BitcoinAddress::<N>::p2pkh(&public_key)?,
+        };
+        if create_script_pub_key(&address)? != script_pub_key {
+            return invalid();
+        }
+
+        let (signature, sighash) = match signature.split_last() {
+            Some((0x01, signature)) => (Signature::parse_der(signature)?, SignatureHash::SIG_ALL),
+            _ => return invalid(),
+        };
+
+        let to_sign = bip322_to_sign(self, message, redeem_script)?;
+        let preimage = match self.format() {
+            BitcoinFormat::P2PKH => to_sign.p2pkh_hash_preimage(0, sighash)?,
+            _ => to_sign.segwit_hash_preimage(0, sighash)?,
+        };
+        let transaction_hash = Message::parse_slice(&checksum(&preimage))?;
+
+        match verify(
+            &transaction_hash,
+            &signature,
+            &public_key.to_secp256k1_public_key(),
+        ) {
+            true => Ok(()),
+            false => invalid(),
+        }
+    }
+}
+
+/// Returns the items of a serialized witness stack, if the bytes are exactly one.
+fn read_witness_stack(bytes: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut position = 0;
+    let count = read_compact_size(bytes, &mut position)?;
+
+    let mut items = vec![];
+    for _ in 0..count {
+        let length = read_compact_size(bytes, &mut position)?;
+        items.push(bytes.get(position..position.checked_add(length)?)?.to_vec());
+        position += length;
+    }
+
+    match position == bytes.len() {
+        true => Some(items),
+        false => None,
+    }
+}
+
+/// Returns the compact size integer at the position, advancing the position past it.
+pub(crate) fn read_compact_size(bytes: &[u8], position: &mut usize) -> Option<usize> {
+    let flag = *bytes.get(*position)?;
+    *position += 1;
+
+    let size = match flag {
+        0xfd => 2,
+        0xfe => 4,
+        0xff => 8,
+        _ => return Some(flag as usize),
+    };
+    let mut value = [0u8; 8];
+    value[..size].copy_from_slice(bytes.get(*position..*position + size)?);
+    *position += size;
+    Some(u64::from_le_bytes(value) as usize)
+}
+
+/// Returns the data pushed by a script that consists only of data pushes.
+pub(crate) fn read_pushes(script: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut position = 0;
+    let mut pushes = vec![];
+    while position < script.len() {
+        let opcode = script[position];
+        position += 1;
+
+        let length = match opcode {
+            0x01..=0x4b => opcode as usize,
+            // OP_PUSHDATA1
+            0x4c => {
+                position += 1;
+                *script.get(position - 1)? as usize
+            }
+            // OP_PUSHDATA2
+            0x4d => {
+                position += 2;
+                let length = script.get(position - 2..position)?;
+                u16::from_le_bytes([length[0], length[1]]) as usize
+            }
+            _ => return None,
+        };
+
+        pushes.push(script.get(position..position + length)?.to_vec());
+        position += length;
+    }
+    Some(pushes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+    use core::str::FromStr;
+
+    const PRIVATE_KEY: &str = "L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k";
+    const HELLO_WORLD_SIGNATURE: &str = "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+
+    #[test]
+    fn bip322_message_hash_vectors() {
+        assert_eq!(
+            "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1",
+            hex::encode(bip322_message_hash(b""))
+        );
+        assert_eq!(
+            "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a",
+            hex::encode(bip322_message_hash(b"Hello World"))
+        );
+    }
+
+    #[test]
+    fn bip322_simple_vectors() {
+        let private_key = BitcoinPrivateKey::<Mainnet>::from_str(PRIVATE_KEY).unwrap();
+        let address = private_key.to_address(&BitcoinFormat::Bech32).unwrap();
+        assert_eq!(
+            "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l",
+            address.to_string()
+        );
+
+        assert_eq!(
+            "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=",
+            private_key
+                .sign_message(b"", &BitcoinFormat::Bech32, BitcoinMessageSignatureFormat::Simple)
+                .unwrap()
+        );
+        assert_eq!(
+            HELLO_WORLD_SIGNATURE,
+            private_key
+                .sign_message(
+                    b"Hello World",
+                    &BitcoinFormat::Bech32,
+                    BitcoinMessageSignatureFormat::Simple
+                )
+                .unwrap()
+        );
+        address
+            .verify_message(b"Hello World", HELLO_WORLD_SIGNATURE)
+            .unwrap();
+        assert!(address
+            .verify_message(b"Hello World!", HELLO_WORLD_SIGNATURE)
+            .is_err());
+    }
+
+    #[test]
+    fn bip322_taproot_vector() {
+        let private_key = BitcoinPrivateKey::<Mainnet>::from_str(PRIVATE_KEY).unwrap();
+        let address = private_key.to_address(&BitcoinFormat::P2TR).unwrap();
+        assert_eq!(
+            "bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3",
+            address.to_string()
+        );
+
+        let signature = "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==";
+        address.verify_message(b"Hello World", signature).unwrap();
+        assert!(address.verify_message(b"Hello", signature).is_err());
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let private_key = BitcoinPrivateKey::<Mainnet>::from_str(PRIVATE_KEY).unwrap();
+        let other = BitcoinPrivateKey::<Mainnet>::from_str(
+            "KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn",
+        )
+        .unwrap();
+        let message = b"proof of reserves";
+
+        let supported = [
+            (BitcoinFormat::P2PKH, BitcoinMessageSignatureFormat::Legacy),
+            (BitcoinFormat::P2PKH, BitcoinMessageSignatureFormat::Full),
+            (
+                BitcoinFormat::P2SH_P2WPKH,
+                BitcoinMessageSignatureFormat::Legacy,
+            ),
+            (
+                BitcoinFormat::P2SH_P2WPKH,
+                BitcoinMessageSignatureFormat::Full,
+            ),
+            (BitcoinFormat::Bech32, BitcoinMessageSignatureFormat::Legacy),
+            (BitcoinFormat::Bech32, BitcoinMessageSignatureFormat::Simple),
+            (BitcoinFormat::Bech32, BitcoinMessageSignatureFormat::Full),
+            (BitcoinFormat::P2TR, BitcoinMessageSignatureFormat::Simple),
+            (BitcoinFormat::P2TR, BitcoinMessageSignatureFormat::Full),
+        ];
+        for (format, signature_format) in supported.iter() {
+            let address = private_key.to_address(format).unwrap();
+            let signature = private_key
+                .sign_message(message, format, *signature_format)
+                .unwrap();
+            address.verify_message(message, &signature).unwrap();
+            assert!(address
+                .verify_message(b"proof of reserve", &signature)
+                .is_err());
+            assert!(other
+                .to_address(format)
+                .unwrap()
+                .verify_message(message, &signature)
+                .is_err());
+        }
+
+        assert!(private_key
+            .sign_message(
+                message,
+                &BitcoinFormat::P2PKH,
+                BitcoinMessageSignatureFormat::Simple
+            )
+            .is_err());
+        assert!(private_key
+            .sign_message(
+                message,
+                &BitcoinFormat::P2TR,
+                BitcoinMessageSignatureFormat::Legacy
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn uncompressed_legacy() {
+        let private_key = BitcoinPrivateKey::<Mainnet>::from_str(
+            "5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ",
+        )
+        .unwrap();
+        let address = private_key.to_address(&BitcoinFormat::P2PKH).unwrap();
+        for signature_format in [
+            BitcoinMessageSignatureFormat::Legacy,
+            BitcoinMessageSignatureFormat::Full,
+        ]
+        .iter()
+        {
+            let signature = private_key
+                .sign_message(b"message", &BitcoinFormat::P2PKH, *signature_format)
+                .unwrap();
+            address.verify_message(b"message", &signature).unwrap();
+        }
+    }
+}
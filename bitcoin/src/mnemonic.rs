@@ -21,11 +21,13 @@ use crate::public_key::BitcoinPublicKey;
 use crate::wordlist::BitcoinWordlist;
 use bitvec::prelude::*;
 use pbkdf2::pbkdf2;
+use zeroize::{Zeroize, Zeroizing};
 
 const PBKDF2_ROUNDS: usize = 64;
 const PBKDF2_BYTES: usize = 2048;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The entropy is cleared on drop.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct BitcoinMnemonic<N: BitcoinNetwork, W: BitcoinWordlist> {
     entropy: Vec<u8>,
     _network: PhantomData<N>,
@@ -43,7 +45,7 @@ impl<N: BitcoinNetwork, W: BitcoinWordlist> MnemonicCount for BitcoinMnemonic<N,
             wc => return Err(MnemonicError::InvalidWordCount(wc)),
         };
 
-        let entropy: [u8; 32] = rng.gen();
+        let entropy = Zeroizing::new(rng.gen::<[u8; 32]>());
 
         Ok(Self {
             entropy: entropy[0..length].to_vec(),
@@ -60,7 +62,7 @@ impl<N: BitcoinNetwork, W: BitcoinWordlist> Mnemonic for BitcoinMnemonic<N, W> {
     type PublicKey = BitcoinPublicKey<N>;
 
     fn new<R: Rng>(rng: &mut R) -> Result<Self, MnemonicError> {
-        let entropy: [u8; 16] = rng.gen();
+        let entropy = Zeroizing::new(rng.gen::<[u8; 16]>());
         Ok(Self {
             entropy: entropy.to_vec(),
             _network: PhantomData,
@@ -69,7 +71,7 @@ impl<N: BitcoinNetwork, W: BitcoinWordlist> Mnemonic for BitcoinMnemonic<N, W> {
     }
 
     fn from_phrase(phrase: &str) -> Result<Self, MnemonicError> {
-        let mnemonic = phrase.split(" ").collect::<Vec<&str>>();
+        let mnemonic = phrase.split(W::SEPARATOR).collect::<Vec<&str>>();
 
         let length = match mnemonic.len() {
             12 => 128,
@@ -95,8 +97,9 @@ impl<N: BitcoinNetwork, W: BitcoinWordlist> Mnemonic for BitcoinMnemonic<N, W> {
             _network: PhantomData,
             _wordlist: PhantomData,
         };
+        entropy.as_mut_slice().zeroize();
 
-        match phrase == mnemonic.to_phrase()? {
+        match phrase == Zeroizing::new(mnemonic.to_phrase()?).as_str() {
             true => Ok(mnemonic),
             false => Err(MnemonicError::InvalidPhrase(phrase.into())),
         }
@@ -139,8 +142,9 @@ impl<N: BitcoinNetwork, W: BitcoinWordlist> Mnemonic for BitcoinMnemonic<N, W> {
                 wordlist[index as usize]
             })
             .collect::<Vec<&str>>();
+        encoding.as_mut_slice().zeroize();
 
-        Ok(phrase.join(" "))
+        Ok(phrase.join(W::SEPARATOR))
     }
 
     fn to_private_key(&self, password: Option<&str>) -> Result<Self::PrivateKey, MnemonicError> {
@@ -190,17 +194,63 @@ impl<N: BitcoinNetwork, W: BitcoinWordlist> BitcoinMnemonic<N, W> {
         Self::from_phrase(phrase).is_ok()
     }
 
-    fn to_seed(&self, password: Option<&str>) -> Result<Vec<u8>, MnemonicError> {
-        let mut seed = vec![0u8; PBKDF2_BYTES];
-        let salt = format!("mnemonic{}", password.unwrap_or(""));
+    /// Returns the mnemonic of the entropy, which is 16, 20, 24, 28 or 32 bytes long.
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, MnemonicError> {
+        match entropy.len() {
+            16 | 20 | 24 | 28 | 32 => Ok(Self {
+                entropy: entropy.to_vec(),
+                _network: PhantomData,
+                _wordlist: PhantomData,
+            }),
+            length => Err(MnemonicError::InvalidEntropyLength(length)),
+        }
+    }
+
+    /// Returns a reference to the entropy, for callers that must handle the raw secret.
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.entropy
+    }
+
+    /// Returns the seed of the phrase and password. The seed also derives the keys of other
+    /// curves, as with SLIP-10.
+    pub fn to_seed(&self, password: Option<&str>) -> Result<Zeroizing<Vec<u8>>, MnemonicError> {
+        let mut seed = Zeroizing::new(vec![0u8; PBKDF2_BYTES]);
+        let salt = Zeroizing::new(format!("mnemonic{}", password.unwrap_or("")));
         pbkdf2::<Hmac<Sha512>>(
-            &self.to_phrase()?.as_bytes(),
+            Zeroizing::new(self.to_phrase()?).as_bytes(),
             salt.as_bytes(),
             PBKDF2_ROUNDS,
             &mut seed,
         );
         Ok(seed)
     }
+
+    /// Returns the account extended public key of the format, as in
+    /// `BitcoinExtendedPrivateKey::to_account_extended_public_key`.
+    pub fn to_account_extended_public_key(
+        &self,
+        password: Option<&str>,
+        format: &BitcoinFormat,
+        account: u32,
+    ) -> Result<BitcoinExtendedPublicKey<N>, MnemonicError> {
+        Ok(self
+            .to_extended_private_key(password)?
+            .to_account_extended_public_key(format, account)?)
+    }
+}
+
+impl<N: BitcoinNetwork, W: BitcoinWordlist> Drop for BitcoinMnemonic<N, W> {
+    fn drop(&mut self) {
+        self.entropy.zeroize();
+    }
+}
+
+impl<N: BitcoinNetwork, W: BitcoinWordlist> fmt::Debug for BitcoinMnemonic<N, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BitcoinMnemonic")
+            .field("entropy", &format_args!("[REDACTED]"))
+            .finish()
+    }
 }
 
 impl<N: BitcoinNetwork, W: BitcoinWordlist> FromStr for BitcoinMnemonic<N, W> {
@@ -212,13 +262,32 @@ impl<N: BitcoinNetwork, W: BitcoinWordlist> FromStr for BitcoinMnemonic<N, W> {
 
 impl<N: BitcoinNetwork, W: BitcoinWordlist> fmt::Display for BitcoinMnemonic<N, W> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self.to_phrase() {
-                Ok(phrase) => phrase,
-                _ => return Err(fmt::Error),
-            }
-        )
+        let phrase = match self.to_phrase() {
+            Ok(phrase) => Zeroizing::new(phrase),
+            _ => return Err(fmt::Error),
+        };
+        f.write_str(&phrase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+    use crate::wordlist::english::English;
+
+    type N = Mainnet;
+    type W = English;
+
+    #[test]
+    fn redacted_debug() {
+        let phrase = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let mnemonic = BitcoinMnemonic::<N, W>::from_phrase(phrase).unwrap();
+        assert_eq!([0x7fu8; 16], mnemonic.expose_secret());
+
+        let debug = format!("{:?}", mnemonic);
+        assert_eq!("BitcoinMnemonic { entropy: [REDACTED] }", debug);
+        assert!(!debug.contains("legal"));
+        assert_eq!(phrase, mnemonic.to_string());
     }
 }
@@ -0,0 +1,423 @@
+use crate::address::BitcoinAddress;
+use crate::derivation_path::BitcoinDerivationPath;
+use crate::extended_public_key::BitcoinExtendedPublicKey;
+use crate::format::BitcoinFormat;
+use crate::network::BitcoinNetwork;
+use crate::public_key::{BitcoinPublicKey, PublicKeyPolicy};
+use crate::transaction::Opcode;
+use gyu_model::no_std::*;
+use gyu_model::{
+    address::AddressError,
+    derivation_path::{ChildIndex, DerivationPathError},
+    extended_public_key::{ExtendedPublicKey, ExtendedPublicKeyError},
+    public_key::PublicKeyError,
+};
+
+use core::{marker::PhantomData, ops::Range};
+
+/// The largest number of public keys in a P2SH redeem script of at most 520 bytes
+const MAX_P2SH_PUBLIC_KEYS: usize = 15;
+/// The largest number of public keys accepted by OP_CHECKMULTISIG
+const MAX_PUBLIC_KEYS: usize = 20;
+
+#[derive(Debug, Fail)]
+pub enum MultisigError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "{}", _0)]
+    DerivationPathError(DerivationPathError),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPublicKeyError(ExtendedPublicKeyError),
+
+    #[fail(display = "invalid threshold {} of {} public keys", _0, _1)]
+    InvalidThreshold(usize, usize),
+
+    #[fail(display = "{}", _0)]
+    PublicKeyError(PublicKeyError),
+
+    #[fail(display = "{} public keys exceed the limit of {} for {}", _0, _1, _2)]
+    TooManyPublicKeys(usize, usize, BitcoinFormat),
+
+    #[fail(display = "unsupported multisig format: {}", _0)]
+    UnsupportedFormat(BitcoinFormat),
+}
+
+impl From<AddressError> for MultisigError {
+    fn from(error: AddressError) -> Self {
+        MultisigError::AddressError(error)
+    }
+}
+
+impl From<DerivationPathError> for MultisigError {
+    fn from(error: DerivationPathError) -> Self {
+        MultisigError::DerivationPathError(error)
+    }
+}
+
+impl From<ExtendedPublicKeyError> for MultisigError {
+    fn from(error: ExtendedPublicKeyError) -> Self {
+        MultisigError::ExtendedPublicKeyError(error)
+    }
+}
+
+impl From<PublicKeyError> for MultisigError {
+    fn from(error: PublicKeyError) -> Self {
+        MultisigError::PublicKeyError(error)
+    }
+}
+
+/// Returns the threshold-of-n OP_CHECKMULTISIG script of the public keys, sorted by their
+/// compressed serialization as in BIP67.
+/// https://github.com/bitcoin/bips/blob/master/bip-0067.mediawiki
+pub fn sorted_multisig_script<N: BitcoinNetwork>(
+    threshold: usize,
+    public_keys: &[BitcoinPublicKey<N>],
+) -> Result<Vec<u8>, MultisigError> {
+    if threshold == 0 || threshold > public_keys.len() {
+        return Err(MultisigError::InvalidThreshold(
+            threshold,
+            public_keys.len(),
+        ));
+    }
+    if public_keys.len() > MAX_PUBLIC_KEYS {
+        return Err(MultisigError::TooManyPublicKeys(
+            public_keys.len(),
+            MAX_PUBLIC_KEYS,
+            BitcoinFormat::P2WSH,
+        ));
+    }
+
+    let mut serialized = Vec::with_capacity(public_keys.len());
+    for public_key in public_keys {
+        public_key.check_policy(&PublicKeyPolicy::SEGWIT)?;
+        serialized.push(public_key.to_secp256k1_public_key().serialize_compressed());
+    }
+    serialized.sort();
+
+    let mut script = push_number(threshold);
+    for public_key in serialized.iter() {
+        script.push(public_key.len() as u8);
+        script.extend(public_key.iter());
+    }
+    script.extend(push_number(public_keys.len()));
+    script.push(Opcode::OP_CHECKMULTISIG as u8);
+    Ok(script)
+}
+
+/// Returns the address in the format of the sorted multisig script of the public keys.
+pub fn sorted_multisig_address<N: BitcoinNetwork>(
+    threshold: usize,
+    public_keys: &[BitcoinPublicKey<N>],
+    format: &BitcoinFormat,
+) -> Result<BitcoinAddress<N>, MultisigError> {
+    check_format(format, public_keys.len())?;
+    let script = sorted_multisig_script(threshold, public_keys)?;
+    Ok(match format {
+        BitcoinFormat::P2SH => BitcoinAddress::p2sh(&script)?,
+        BitcoinFormat::P2SH_P2WSH => BitcoinAddress::p2sh_p2wsh(&script)?,
+        _ => BitcoinAddress::p2wsh(&script)?,
+    })
+}
+
+/// Represents a sorted multisig account of cosigner extended public keys, such as the
+/// BIP48 account keys m/48'/{0', 1'}/{account}'/{1', 2'} or the BIP45 purpose keys m/45'
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinMultisigAccount<N: BitcoinNetwork> {
+    threshold: usize,
+    format: BitcoinFormat,
+    cosigners: Vec<BitcoinExtendedPublicKey<N>>,
+}
+
+impl<N: BitcoinNetwork> BitcoinMultisigAccount<N> {
+    /// Returns a threshold-of-n multisig account of the cosigners with addresses in the
+    /// format, one of P2SH, P2SH_P2WSH and P2WSH.
+    pub fn new(
+        threshold: usize,
+        cosigners: &[BitcoinExtendedPublicKey<N>],
+        format: &BitcoinFormat,
+    ) -> Result<Self, MultisigError> {
+        check_format(format, cosigners.len())?;
+        if threshold == 0 || threshold > cosigners.len() {
+            return Err(MultisigError::InvalidThreshold(threshold, cosigners.len()));
+        }
+        Ok(Self {
+            threshold,
+            format: format.clone(),
+            cosigners: cosigners.to_vec(),
+        })
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn format(&self) -> BitcoinFormat {
+        self.format.clone()
+    }
+
+    pub fn cosigners(&self) -> &[BitcoinExtendedPublicKey<N>] {
+        &self.cosigners
+    }
+
+    /// Returns the BIP45 cosigner index of the cosigner, its position among the cosigner
+    /// public keys in lexicographic order.
+    pub fn cosigner_index(&self, cosigner: &BitcoinExtendedPublicKey<N>) -> Option<u32> {
+        let serialize = |cosigner: &BitcoinExtendedPublicKey<N>| {
+            cosigner
+                .to_public_key()
+                .to_secp256k1_public_key()
+                .serialize_compressed()
+        };
+        let mut public_keys: Vec<_> = self.cosigners.iter().map(serialize).collect();
+        public_keys.sort();
+        let public_key = serialize(cosigner);
+        public_keys
+            .iter()
+            .position(|candidate| *candidate == public_key)
+            .map(|index| index as u32)
+    }
+
+    /// Returns the public keys of the cosigners derived along the relative path, in the
+    /// order of the cosigners.
+    pub fn public_keys(
+        &self,
+        path: &[ChildIndex],
+    ) -> Result<Vec<BitcoinPublicKey<N>>, MultisigError> {
+        let path = BitcoinDerivationPath::<N>::BIP32(path.to_vec(), PhantomData);
+        self.cosigners
+            .iter()
+            .map(|cosigner| Ok(cosigner.derive(&path)?.to_public_key()))
+            .collect()
+    }
+
+    /// Returns the sorted multisig script of the cosigner public keys at the relative path,
+    /// the redeem script of P2SH addresses and the witness script otherwise.
+    pub fn script(&self, path: &[ChildIndex]) -> Result<Vec<u8>, MultisigError> {
+        sorted_multisig_script(self.threshold, &self.public_keys(path)?)
+    }
+
+    /// Returns the address of the cosigner public keys at the relative path, such as
+    /// {change}/{index} for BIP48 or {cosigner}/{change}/{index} for BIP45.
+    pub fn to_address(&self, path: &[ChildIndex]) -> Result<BitcoinAddress<N>, MultisigError> {
+        sorted_multisig_address(self.threshold, &self.public_keys(path)?, &self.format)
+    }
+
+    /// Returns the addresses at each index of the range under the relative path.
+    pub fn to_addresses(
+        &self,
+        path: &[ChildIndex],
+        indices: Range<u32>,
+    ) -> Result<Vec<BitcoinAddress<N>>, MultisigError> {
+        indices
+            .map(|index| {
+                let mut path = path.to_vec();
+                path.push(ChildIndex::normal(index)?);
+                self.to_address(&path)
+            })
+            .collect()
+    }
+}
+
+/// Returns an error unless the format is a multisig format that fits the number of keys.
+fn check_format(format: &BitcoinFormat, public_keys: usize) -> Result<(), MultisigError> {
+    let max = match format {
+        BitcoinFormat::P2SH => MAX_P2SH_PUBLIC_KEYS,
+        BitcoinFormat::P2SH_P2WSH | BitcoinFormat::P2WSH => MAX_PUBLIC_KEYS,
+        _ => return Err(MultisigError::UnsupportedFormat(format.clone())),
+    };
+    match public_keys > max {
+        true => Err(MultisigError::TooManyPublicKeys(
+            public_keys,
+            max,
+            format.clone(),
+        )),
+        false => Ok(()),
+    }
+}
+
+/// Returns the script that pushes the number, OP_1 to OP_16 or a one byte push above.
+fn push_number(number: usize) -> Vec<u8> {
+    match number {
+        1..=16 => vec![0x50 + number as u8],
+        _ => vec![0x01, number as u8],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extended_private_key::BitcoinExtendedPrivateKey;
+    use crate::network::Mainnet;
+    use gyu_model::extended_private_key::ExtendedPrivateKey;
+
+    use core::str::FromStr;
+
+    type N = Mainnet;
+
+    fn public_keys(public_keys: &[&str]) -> Vec<BitcoinPublicKey<N>> {
+        public_keys
+            .iter()
+            .map(|public_key| BitcoinPublicKey::from_str(public_key).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn bip67_vectors() {
+        let vectors = [
+            (
+                vec![
+                    "02ff12471208c14bd580709cb2358d98975247d8765f92bc25eab3b2763ed605f8",
+                    "02fe6f0a5a297eb38c391581c4413e084773ea23954d93f7753db7dc0adc188b2f",
+                ],
+                "522102fe6f0a5a297eb38c391581c4413e084773ea23954d93f7753db7dc0adc188b2f2102ff12471208c14bd580709cb2358d98975247d8765f92bc25eab3b2763ed605f852ae",
+                "39bgKC7RFbpoCRbtD5KEdkYKtNyhpsNa3Z",
+            ),
+            (
+                vec![
+                    "02632b12f4ac5b1d1b72b2a3b508c19172de44f6f46bcee50ba33f3f9291e47ed0",
+                    "027735a29bae7780a9755fae7a1c4374c656ac6a69ea9f3697fda61bb99a4f3e77",
+                    "02e2cc6bd5f45edd43bebe7cb9b675f0ce9ed3efe613b177588290ad188d11b404",
+                ],
+                "522102632b12f4ac5b1d1b72b2a3b508c19172de44f6f46bcee50ba33f3f9291e47ed021027735a29bae7780a9755fae7a1c4374c656ac6a69ea9f3697fda61bb99a4f3e772102e2cc6bd5f45edd43bebe7cb9b675f0ce9ed3efe613b177588290ad188d11b40453ae",
+                "3CKHTjBKxCARLzwABMu9yD85kvtm7WnMfH",
+            ),
+            (
+                vec![
+                    "022df8750480ad5b26950b25c7ba79d3e37d75f640f8e5d9bcd5b150a0f85014da",
+                    "03e3818b65bcc73a7d64064106a859cc1a5a728c4345ff0b641209fba0d90de6e9",
+                    "021f2f6e1e50cb6a953935c3601284925decd3fd21bc445712576873fb8c6ebc18",
+                ],
+                "5221021f2f6e1e50cb6a953935c3601284925decd3fd21bc445712576873fb8c6ebc1821022df8750480ad5b26950b25c7ba79d3e37d75f640f8e5d9bcd5b150a0f85014da2103e3818b65bcc73a7d64064106a859cc1a5a728c4345ff0b641209fba0d90de6e953ae",
+                "3Q4sF6tv9wsdqu2NtARzNCpQgwifm2rAba",
+            ),
+        ];
+
+        for (keys, script, address) in vectors.iter() {
+            let keys = public_keys(keys);
+            assert_eq!(
+                *script,
+                hex::encode(sorted_multisig_script(2, &keys).unwrap())
+            );
+            assert_eq!(
+                *address,
+                sorted_multisig_address(2, &keys, &BitcoinFormat::P2SH)
+                    .unwrap()
+                    .to_string()
+            );
+
+            let mut reversed = keys.clone();
+            reversed.reverse();
+            assert_eq!(
+                *script,
+                hex::encode(sorted_multisig_script(2, &reversed).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_multisig() {
+        let keys = public_keys(&[
+            "02ff12471208c14bd580709cb2358d98975247d8765f92bc25eab3b2763ed605f8",
+            "02fe6f0a5a297eb38c391581c4413e084773ea23954d93f7753db7dc0adc188b2f",
+        ]);
+        assert!(sorted_multisig_script(0, &keys).is_err());
+        assert!(sorted_multisig_script(3, &keys).is_err());
+        assert!(sorted_multisig_address(1, &keys, &BitcoinFormat::Bech32).is_err());
+        assert!(sorted_multisig_address(1, &keys, &BitcoinFormat::P2PKH).is_err());
+
+        let many = vec![keys[0].clone(); 16];
+        assert!(sorted_multisig_address(2, &many, &BitcoinFormat::P2SH).is_err());
+        assert!(sorted_multisig_address(2, &many, &BitcoinFormat::P2WSH).is_ok());
+        let too_many = vec![keys[0].clone(); 21];
+        assert!(sorted_multisig_script(2, &too_many).is_err());
+
+        let uncompressed = BitcoinPublicKey::<N>::from_str("0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8").unwrap();
+        assert!(sorted_multisig_script(1, &[uncompressed]).is_err());
+    }
+
+    #[test]
+    fn bip48_account() {
+        let masters: Vec<_> = (1u8..=3)
+            .map(|byte| {
+                BitcoinExtendedPrivateKey::<N>::new_master(&[byte; 32], &BitcoinFormat::P2PKH)
+                    .unwrap()
+            })
+            .collect();
+        let account_path = BitcoinDerivationPath::from_str("m/48'/0'/0'/2'").unwrap();
+        let cosigners: Vec<_> = masters
+            .iter()
+            .map(|master| {
+                master
+                    .derive(&account_path)
+                    .unwrap()
+                    .to_extended_public_key()
+            })
+            .collect();
+
+        for format in [BitcoinFormat::P2WSH, BitcoinFormat::P2SH_P2WSH].iter() {
+            let account = BitcoinMultisigAccount::new(2, &cosigners, format).unwrap();
+            let addresses = account
+                .to_addresses(&[ChildIndex::Normal(0)], 0..3)
+                .unwrap();
+            assert_eq!(3, addresses.len());
+
+            for (index, address) in addresses.iter().enumerate() {
+                let path = BitcoinDerivationPath::from_str(&format!("m/48'/0'/0'/2'/0/{}", index))
+                    .unwrap();
+                let keys: Vec<_> = masters
+                    .iter()
+                    .map(|master| master.derive(&path).unwrap().to_public_key())
+                    .collect();
+                assert_eq!(*address, sorted_multisig_address(2, &keys, format).unwrap());
+            }
+        }
+
+        assert!(BitcoinMultisigAccount::new(0, &cosigners, &BitcoinFormat::P2WSH).is_err());
+        assert!(BitcoinMultisigAccount::new(4, &cosigners, &BitcoinFormat::P2WSH).is_err());
+        assert!(BitcoinMultisigAccount::new(2, &cosigners, &BitcoinFormat::Bech32).is_err());
+    }
+
+    #[test]
+    fn bip45_cosigner_index() {
+        let cosigners: Vec<_> = (1u8..=3)
+            .map(|byte| {
+                BitcoinExtendedPrivateKey::<N>::new_master(&[byte; 32], &BitcoinFormat::P2PKH)
+                    .unwrap()
+                    .derive(&BitcoinDerivationPath::from_str("m/45'").unwrap())
+                    .unwrap()
+                    .to_extended_public_key()
+            })
+            .collect();
+        let account = BitcoinMultisigAccount::new(2, &cosigners, &BitcoinFormat::P2SH).unwrap();
+
+        let mut indices: Vec<u32> = cosigners
+            .iter()
+            .map(|cosigner| account.cosigner_index(cosigner).unwrap())
+            .collect();
+        indices.sort();
+        assert_eq!(vec![0, 1, 2], indices);
+
+        let other = BitcoinExtendedPrivateKey::<N>::new_master(&[9u8; 32], &BitcoinFormat::P2PKH)
+            .unwrap()
+            .to_extended_public_key();
+        assert_eq!(None, account.cosigner_index(&other));
+
+        let index = account.cosigner_index(&cosigners[0]).unwrap();
+        let path = [
+            ChildIndex::Normal(index),
+            ChildIndex::Normal(0),
+            ChildIndex::Normal(5),
+        ];
+        let script = account.script(&path).unwrap();
+        assert_eq!(
+            BitcoinAddress::p2sh(&script).unwrap(),
+            account.to_address(&path).unwrap()
+        );
+        assert!(account.to_address(&[ChildIndex::Hardened(0)]).is_err());
+    }
+}
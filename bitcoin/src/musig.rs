@@ -0,0 +1,909 @@
+use crate::address::BitcoinAddress;
+use crate::format::BitcoinFormat;
+use crate::network::BitcoinNetwork;
+use crate::private_key::BitcoinPrivateKey;
+use crate::public_key::BitcoinPublicKey;
+use crate::schnorr::{
+    infinity, parse_point, scalar_from_bytes, serialize_point, tagged_hash, tap_tweak,
+};
+use gyu_model::no_std::*;
+use gyu_model::{
+    address::AddressError,
+    private_key::PrivateKey,
+    public_key::{PublicKey, PublicKeyError},
+};
+
+use core::fmt;
+use rand::Rng;
+use secp256k1::curve::{Jacobian, Scalar, AFFINE_G, ECMULT_CONTEXT, ECMULT_GEN_CONTEXT};
+use zeroize::{Zeroize, Zeroizing};
+
+#[derive(Debug, Fail)]
+pub enum MuSigError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "the aggregate public key is the point at infinity")]
+    InfiniteAggregatePublicKey,
+
+    #[fail(display = "invalid aggregate nonce: {}", _0)]
+    InvalidAggregateNonce(String),
+
+    #[fail(display = "invalid secret nonce")]
+    InvalidNonce,
+
+    #[fail(display = "invalid partial signature: {}", _0)]
+    InvalidPartialSignature(String),
+
+    #[fail(display = "invalid public nonce: {}", _0)]
+    InvalidPublicNonce(String),
+
+    #[fail(display = "invalid tweak: {}", _0)]
+    InvalidTweak(String),
+
+    #[fail(display = "no public keys to aggregate")]
+    NoPublicKeys,
+
+    #[fail(display = "{}", _0)]
+    PublicKeyError(PublicKeyError),
+
+    #[fail(display = "the secret nonce does not belong to the public key {}", _0)]
+    SecretNonceMismatch(String),
+
+    #[fail(display = "the public key {} is not one of the aggregated keys", _0)]
+    UnknownPublicKey(String),
+}
+
+impl From<AddressError> for MuSigError {
+    fn from(error: AddressError) -> Self {
+        MuSigError::AddressError(error)
+    }
+}
+
+impl From<PublicKeyError> for MuSigError {
+    fn from(error: PublicKeyError) -> Self {
+        MuSigError::PublicKeyError(error)
+    }
+}
+
+impl From<secp256k1::Error> for MuSigError {
+    fn from(error: secp256k1::Error) -> Self {
+        MuSigError::Crate("libsecp256k1", format!("{:?}", error))
+    }
+}
+
+/// Returns the public keys sorted by their compressed serialization (BIP327 KeySort).
+pub fn key_sort<N: BitcoinNetwork>(
+    public_keys: &[BitcoinPublicKey<N>],
+) -> Vec<BitcoinPublicKey<N>> {
+    let mut public_keys = public_keys.to_vec();
+    public_keys.sort_by_key(serialize_public_key);
+    public_keys
+}
+
+/// Represents the BIP327 key aggregation context of a set of public keys, with any tweaks applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MuSigKeyAggContext<N: BitcoinNetwork> {
+    public_keys: Vec<[u8; 33]>,
+    list_hash: [u8; 32],
+    second_public_key: Option<[u8; 33]>,
+    aggregate_public_key: BitcoinPublicKey<N>,
+    gacc: Scalar,
+    tacc: Scalar,
+}
+
+impl<N: BitcoinNetwork> MuSigKeyAggContext<N> {
+    /// Returns the key aggregation context of the public keys, in the given order.
+    pub fn new(public_keys: &[BitcoinPublicKey<N>]) -> Result<Self, MuSigError> {
+        if public_keys.is_empty() {
+            return Err(MuSigError::NoPublicKeys);
+        }
+
+        let public_keys: Vec<[u8; 33]> = public_keys.iter().map(serialize_public_key).collect();
+        let list_hash = tagged_hash("KeyAgg list", &[&public_keys.concat()]);
+        let second_public_key = public_keys
+            .iter()
+            .find(|public_key| **public_key != public_keys[0])
+            .cloned();
+
+        // Q = a_1 * P_1 + ... + a_u * P_u
+        let mut aggregate = infinity();
+        for public_key in &public_keys {
+            let mut term = Jacobian::default();
+            ECMULT_CONTEXT.ecmult(
+                &mut term,
+                &parse_point(public_key)?,
+                &coefficient(&list_hash, second_public_key.as_ref(), public_key),
+                &Scalar::default(),
+            );
+            aggregate = aggregate.add_var(&term, None);
+        }
+
+        Ok(Self {
+            public_keys,
+            list_hash,
+            second_public_key,
+            aggregate_public_key: BitcoinPublicKey::from_point(&aggregate)
+                .ok_or(MuSigError::InfiniteAggregatePublicKey)?,
+            gacc: Scalar::from_int(1),
+            tacc: Scalar::default(),
+        })
+    }
+
+    /// Returns the aggregate public key, with any tweaks applied.
+    pub fn aggregate_public_key(&self) -> BitcoinPublicKey<N> {
+        self.aggregate_public_key.clone()
+    }
+
+    /// Returns the x-only (BIP340) aggregate public key, with any tweaks applied.
+    pub fn x_only_public_key(&self) -> [u8; 32] {
+        self.aggregate_public_key.to_x_only()
+    }
+
+    /// Returns the P2TR address with the aggregate public key as its internal key.
+    pub fn to_address(&self) -> Result<BitcoinAddress<N>, MuSigError> {
+        Ok(self.aggregate_public_key.to_address(&BitcoinFormat::P2TR)?)
+    }
+
+    /// Returns the context with the plain or x-only tweak added to the aggregate public key.
+    pub fn apply_tweak(&self, tweak: &[u8; 32], x_only: bool) -> Result<Self, MuSigError> {
+        let mut t = Scalar::default();
+        if bool::from(t.set_b32(tweak)) {
+            return Err(MuSigError::InvalidTweak(hex::encode(tweak)));
+        }
+
+        let g = match x_only && !self.aggregate_public_key.has_even_y() {
+            true => -Scalar::from_int(1),
+            false => Scalar::from_int(1),
+        };
+
+        // Q' = g * Q + t * G
+        let mut aggregate = Jacobian::default();
+        ECMULT_CONTEXT.ecmult(
+            &mut aggregate,
+            &self.aggregate_public_key.to_point(),
+            &g,
+            &t,
+        );
+
+        let mut context = self.clone();
+        context.aggregate_public_key = match BitcoinPublicKey::from_point(&aggregate) {
+            Some(aggregate_public_key) => aggregate_public_key,
+            None => return Err(MuSigError::InvalidTweak(hex::encode(tweak))),
+        };
+        context.gacc = &g * &self.gacc;
+        context.tacc = &t + &(&g * &self.tacc);
+        Ok(context)
+    }
+
+    /// Returns the context with the BIP341 TapTweak of the aggregate public key applied, for
+    /// signing a key path spend of its P2TR output committing to the given script tree.
+    pub fn apply_taproot_tweak(&self, merkle_root: Option<&[u8; 32]>) -> Result<Self, MuSigError> {
+        self.apply_tweak(&tap_tweak(&self.x_only_public_key(), merkle_root), true)
+    }
+
+    /// Returns the key aggregation coefficient of the public key if it was aggregated.
+    fn session_coefficient(&self, public_key: &[u8; 33]) -> Result<Scalar, MuSigError> {
+        match self.public_keys.contains(public_key) {
+            true => Ok(coefficient(
+                &self.list_hash,
+                self.second_public_key.as_ref(),
+                public_key,
+            )),
+            false => Err(MuSigError::UnknownPublicKey(hex::encode(&public_key[..]))),
+        }
+    }
+
+    /// Returns 1 if the aggregate public key has an even y coordinate, or -1 otherwise.
+    fn parity(&self) -> Scalar {
+        match self.aggregate_public_key.has_even_y() {
+            true => Scalar::from_int(1),
+            false => -Scalar::from_int(1),
+        }
+    }
+}
+
+/// Represents the secret nonce of a signer, which is consumed by signing so that it is
+/// never used twice
+pub struct MuSigSecretNonce {
+    k1: [u8; 32],
+    k2: [u8; 32],
+    public_key: [u8; 33],
+}
+
+impl MuSigSecretNonce {
+    /// Returns a fresh secret nonce and its public nonce for signing the message with the
+    /// private key under the aggregate public key of the context.
+    pub fn generate<N: BitcoinNetwork, R: Rng>(
+        rng: &mut R,
+        private_key: &BitcoinPrivateKey<N>,
+        key_agg_context: &MuSigKeyAggContext<N>,
+        message: &[u8],
+    ) -> Result<(Self, MuSigPublicNonce), MuSigError> {
+        let mut rand: [u8; 32] = rng.gen();
+        let nonce = Self::new(
+            &rand,
+            &private_key.to_public_key(),
+            Some(private_key),
+            Some(&key_agg_context.x_only_public_key()),
+            Some(message),
+            None,
+        );
+        rand.zeroize();
+        nonce
+    }
+
+    /// Returns the secret nonce and its public nonce (BIP327 NonceGen). The random bytes must
+    /// be uniformly random and never reused, the other inputs only add defense in depth.
+    pub fn new<N: BitcoinNetwork>(
+        rand: &[u8; 32],
+        public_key: &BitcoinPublicKey<N>,
+        private_key: Option<&BitcoinPrivateKey<N>>,
+        aggregate_public_key: Option<&[u8; 32]>,
+        message: Option<&[u8]>,
+        extra_input: Option<&[u8]>,
+    ) -> Result<(Self, MuSigPublicNonce), MuSigError> {
+        let mut rand = *rand;
+        if let Some(private_key) = private_key {
+            rand = tagged_hash("MuSig/aux", &[&rand]);
+            let secret = Zeroizing::new(private_key.expose_secret().serialize());
+            for (byte, secret_byte) in rand.iter_mut().zip(secret.iter()) {
+                *byte ^= secret_byte;
+            }
+        }
+
+        let public_key = serialize_public_key(public_key);
+        let aggregate_public_key: &[u8] = match aggregate_public_key {
+            Some(aggregate_public_key) => aggregate_public_key,
+            None => &[],
+        };
+        let message = match message {
+            Some(message) => [&[1u8][..], &(message.len() as u64).to_be_bytes(), message].concat(),
+            None => vec![0u8],
+        };
+        let extra_input = extra_input.unwrap_or(&[]);
+
+        let nonce = |index: u8| {
+            scalar_from_bytes(&tagged_hash(
+                "MuSig/nonce",
+                &[
+                    &rand,
+                    &[public_key.len() as u8],
+                    &public_key,
+                    &[aggregate_public_key.len() as u8],
+                    aggregate_public_key,
+                    &message,
+                    &(extra_input.len() as u32).to_be_bytes(),
+                    extra_input,
+                    &[index],
+                ],
+            ))
+        };
+        let (k1, k2) = (nonce(0), nonce(1));
+        rand.zeroize();
+        if k1.is_zero() || k2.is_zero() {
+            return Err(MuSigError::InvalidNonce);
+        }
+
+        let secret_nonce = Self {
+            k1: k1.b32(),
+            k2: k2.b32(),
+            public_key,
+        };
+        let public_nonce = secret_nonce.public_nonce()?;
+        Ok((secret_nonce, public_nonce))
+    }
+
+    /// Returns the public nonce of the secret nonce.
+    fn public_nonce(&self) -> Result<MuSigPublicNonce, MuSigError> {
+        let mut public_nonce = [0u8; 66];
+        for (k, chunk) in [&self.k1, &self.k2].iter().zip(public_nonce.chunks_mut(33)) {
+            let mut nonce_point = Jacobian::default();
+            ECMULT_GEN_CONTEXT.ecmult_gen(&mut nonce_point, &secret_scalar(k)?);
+            match serialize_point(&nonce_point) {
+                Some(nonce_point) => chunk.copy_from_slice(&nonce_point),
+                None => return Err(MuSigError::InvalidNonce),
+            }
+        }
+        Ok(MuSigPublicNonce(public_nonce))
+    }
+}
+
+impl Drop for MuSigSecretNonce {
+    fn drop(&mut self) {
+        self.k1.zeroize();
+        self.k2.zeroize();
+    }
+}
+
+impl fmt::Debug for MuSigSecretNonce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MuSigSecretNonce")
+            .field("k1", &format_args!("[REDACTED]"))
+            .field("k2", &format_args!("[REDACTED]"))
+            .field("public_key", &hex::encode(&self.public_key[..]))
+            .finish()
+    }
+}
+
+/// Represents the public nonce of a signer, the two compressed nonce points R1 and R2
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MuSigPublicNonce([u8; 66]);
+
+impl MuSigPublicNonce {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MuSigError> {
+        let invalid = || MuSigError::InvalidPublicNonce(hex::encode(bytes));
+        if bytes.len() != 66 {
+            return Err(invalid());
+        }
+        let mut public_nonce = [0u8; 66];
+        public_nonce.copy_from_slice(bytes);
+        for chunk in public_nonce.chunks(33) {
+            parse_point(chunk).map_err(|_| invalid())?;
+        }
+        Ok(Self(public_nonce))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 66] {
+        self.0
+    }
+}
+
+/// Represents the aggregate of the public nonces of all signers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MuSigAggregateNonce([u8; 66]);
+
+impl MuSigAggregateNonce {
+    /// Returns the aggregate of the public nonces (BIP327 NonceAgg).
+    pub fn new(public_nonces: &[MuSigPublicNonce]) -> Result<Self, MuSigError> {
+        let mut aggregate_nonce = [0u8; 66];
+        for (j, chunk) in aggregate_nonce.chunks_mut(33).enumerate() {
+            let mut sum = infinity();
+            for public_nonce in public_nonces {
+                sum = sum.add_var(&parse_point(&public_nonce.0[33 * j..33 * (j + 1)])?, None);
+            }
+            // The point at infinity is encoded as 33 zero bytes.
+            if let Some(sum) = serialize_point(&sum) {
+                chunk.copy_from_slice(&sum);
+            }
+        }
+        Ok(Self(aggregate_nonce))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MuSigError> {
+        let invalid = || MuSigError::InvalidAggregateNonce(hex::encode(bytes));
+        if bytes.len() != 66 {
+            return Err(invalid());
+        }
+        let mut aggregate_nonce = [0u8; 66];
+        aggregate_nonce.copy_from_slice(bytes);
+        for chunk in aggregate_nonce.chunks(33) {
+            point_ext_from_slice(chunk).map_err(|_| invalid())?;
+        }
+        Ok(Self(aggregate_nonce))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 66] {
+        self.0
+    }
+}
+
+/// Represents the partial signature of a signer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MuSigPartialSignature([u8; 32]);
+
+impl MuSigPartialSignature {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MuSigError> {
+        let invalid = || MuSigError::InvalidPartialSignature(hex::encode(bytes));
+        if bytes.len() != 32 {
+            return Err(invalid());
+        }
+        let mut partial_signature = [0u8; 32];
+        partial_signature.copy_from_slice(bytes);
+        if bool::from(Scalar::default().set_b32(&partial_signature)) {
+            return Err(invalid());
+        }
+        Ok(Self(partial_signature))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+/// Represents a BIP327 signing session of a message under the aggregate public key and
+/// aggregate nonce
+#[derive(Debug, Clone)]
+pub struct MuSigSession<N: BitcoinNetwork> {
+    key_agg_context: MuSigKeyAggContext<N>,
+    b: Scalar,
+    e: Scalar,
+    nonce_point: BitcoinPublicKey<N>,
+}
+
+impl<N: BitcoinNetwork> MuSigSession<N> {
+    pub fn new(
+        key_agg_context: &MuSigKeyAggContext<N>,
+        aggregate_nonce: &MuSigAggregateNonce,
+        message: &[u8],
+    ) -> Result<Self, MuSigError> {
+        let public_key = key_agg_context.x_only_public_key();
+        let b = scalar_from_bytes(&tagged_hash(
+            "MuSig/noncecoef",
+            &[&aggregate_nonce.0, &public_key, message],
+        ));
+
+        // R = R1 + b * R2, or G if it is the point at infinity
+        let mut nonce_point = point_ext_from_slice(&aggregate_nonce.0[0..33])?;
+        let second_nonce_point = point_ext_from_slice(&aggregate_nonce.0[33..66])?;
+        if !second_nonce_point.is_infinity() {
+            let mut term = Jacobian::default();
+            ECMULT_CONTEXT.ecmult(&mut term, &second_nonce_point, &b, &Scalar::default());
+            nonce_point = nonce_point.add_var(&term, None);
+        }
+        let nonce_point = match BitcoinPublicKey::from_point(&nonce_point) {
+            Some(nonce_point) => nonce_point,
+            None => BitcoinPublicKey::from_point(&Jacobian::from_ge(&AFFINE_G))
+                .expect("the generator is not the point at infinity"),
+        };
+
+        let e = scalar_from_bytes(&tagged_hash(
+            "BIP0340/challenge",
+            &[&nonce_point.to_x_only(), &public_key, message],
+        ));
+
+        Ok(Self {
+            key_agg_context: key_agg_context.clone(),
+            b,
+            e,
+            nonce_point,
+        })
+    }
+
+    /// Returns the partial signature of the private key, consuming its secret nonce. The
+    /// partial signature is verified before it is returned.
+    pub fn partial_sign(
+        &self,
+        secret_nonce: MuSigSecretNonce,
+        private_key: &BitcoinPrivateKey<N>,
+    ) -> Result<MuSigPartialSignature, MuSigError> {
+        let public_key = serialize_public_key(&private_key.to_public_key());
+        if public_key != secret_nonce.public_key {
+            return Err(MuSigError::SecretNonceMismatch(hex::encode(
+                &public_key[..],
+            )));
+        }
+        let a = self.key_agg_context.session_coefficient(&public_key)?;
+
+        let (mut k1, mut k2) = (
+            secret_scalar(&secret_nonce.k1)?,
+            secret_scalar(&secret_nonce.k2)?,
+        );
+        if !self.nonce_point.has_even_y() {
+            k1 = -k1;
+            k2 = -k2;
+        }
+
+        let secret: Scalar = private_key.expose_secret().clone().into();
+        let d = &(&self.key_agg_context.parity() * &self.key_agg_context.gacc) * &secret;
+        let s = &(&k1 + &(&self.b * &k2)) + &(&(&self.e * &a) * &d);
+        let partial_signature = MuSigPartialSignature(s.b32());
+
+        self.partial_verify(
+            &partial_signature,
+            &secret_nonce.public_nonce()?,
+            &private_key.to_public_key(),
+        )?;
+        Ok(partial_signature)
+    }
+
+    /// Verifies the partial signature of the signer with the public nonce and public key.
+    pub fn partial_verify(
+        &self,
+        partial_signature: &MuSigPartialSignature,
+        public_nonce: &MuSigPublicNonce,
+        public_key: &BitcoinPublicKey<N>,
+    ) -> Result<(), MuSigError> {
+        let public_key = serialize_public_key(public_key);
+        let a = self.key_agg_context.session_coefficient(&public_key)?;
+
+        // Re = R1 + b * R2, negated if R has an odd y coordinate
+        let mut nonce_point = Jacobian::default();
+        ECMULT_CONTEXT.ecmult(
+            &mut nonce_point,
+            &parse_point(&public_nonce.0[33..66])?,
+            &self.b,
+            &Scalar::default(),
+        );
+        let mut nonce_point = nonce_point.add_var(&parse_point(&public_nonce.0[0..33])?, None);
+        if !self.nonce_point.has_even_y() {
+            nonce_point = nonce_point.neg();
+        }
+
+        // s * G - e * a * g * gacc * P == Re
+        let g = &self.key_agg_context.parity() * &self.key_agg_context.gacc;
+        let mut result = Jacobian::default();
+        ECMULT_CONTEXT.ecmult(
+            &mut result,
+            &parse_point(&public_key)?,
+            &-(&(&self.e * &a) * &g),
+            &scalar_from_bytes(&partial_signature.0),
+        );
+
+        match serialize_point(&result) == serialize_point(&nonce_point) {
+            true => Ok(()),
+            false => Err(MuSigError::InvalidPartialSignature(hex::encode(
+                &partial_signature.0[..],
+            ))),
+        }
+    }
+
+    /// Returns the BIP340 signature aggregating the partial signatures of all signers
+    /// (BIP327 PartialSigAgg). Partial signatures should be verified beforehand.
+    pub fn aggregate(&self, partial_signatures: &[MuSigPartialSignature]) -> [u8; 64] {
+        let mut s = &(&self.e * &self.key_agg_context.parity()) * &self.key_agg_context.tacc;
+        for partial_signature in partial_signatures {
+            s += scalar_from_bytes(&partial_signature.0);
+        }
+
+        let mut signature = [0u8; 64];
+        signature[0..32].copy_from_slice(&self.nonce_point.to_x_only());
+        signature[32..64].copy_from_slice(&s.b32());
+        signature
+    }
+}
+
+/// Returns the key aggregation coefficient of the public key, which is 1 for the second
+/// distinct public key of the list.
+fn coefficient(
+    list_hash: &[u8; 32],
+    second_public_key: Option<&[u8; 33]>,
+    public_key: &[u8; 33],
+) -> Scalar {
+    match second_public_key {
+        Some(second_public_key) if second_public_key == public_key => Scalar::from_int(1),
+        _ => scalar_from_bytes(&tagged_hash("KeyAgg coefficient", &[list_hash, public_key])),
+    }
+}
+
+/// Returns the compressed serialization of the public key.
+fn serialize_public_key<N: BitcoinNetwork>(public_key: &BitcoinPublicKey<N>) -> [u8; 33] {
+    public_key.to_secp256k1_public_key().serialize_compressed()
+}
+
+/// Returns the point of the 33 byte compressed public key, or the point at infinity for
+/// 33 zero bytes.
+fn point_ext_from_slice(bytes: &[u8]) -> Result<Jacobian, MuSigError> {
+    match bytes.iter().all(|byte| *byte == 0) {
+        true => Ok(infinity()),
+        false => Ok(parse_point(bytes)?),
+    }
+}
+
+/// Returns the nonzero scalar of the 32 bytes of a secret nonce.
+fn secret_scalar(bytes: &[u8; 32]) -> Result<Scalar, MuSigError> {
+    let mut scalar = Scalar::default();
+    if bool::from(scalar.set_b32(bytes)) || scalar.is_zero() {
+        return Err(MuSigError::InvalidNonce);
+    }
+    Ok(scalar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+    use core::str::FromStr;
+
+    type N = Mainnet;
+
+    fn public_keys(public_keys: &[&str]) -> Vec<BitcoinPublicKey<N>> {
+        public_keys
+            .iter()
+            .map(|public_key| BitcoinPublicKey::from_str(public_key).unwrap())
+            .collect()
+    }
+
+    fn bytes32(bytes: &str) -> [u8; 32] {
+        let mut array = [0u8; 32];
+        array.copy_from_slice(&hex::decode(bytes).unwrap());
+        array
+    }
+
+    mod key_agg {
+        use super::*;
+
+        const PUBLIC_KEYS: [&str; 3] = [
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+        ];
+
+        const VECTORS: [(&[usize], &str); 4] = [
+            (
+                &[0, 1, 2],
+                "90539EEDE565F5D054F32CC0C220126889ED1E5D193BAF15AEF344FE59D4610C",
+            ),
+            (
+                &[2, 1, 0],
+                "6204DE8B083426DC6EAF9502D27024D53FC826BF7D2012148A0575435DF54B2B",
+            ),
+            (
+                &[0, 0, 0],
+                "B436E3BAD62B8CD409969A224731C193D051162D8C5AE8B109306127DA3AA935",
+            ),
+            (
+                &[0, 0, 1, 1],
+                "69BC22BFA5D106306E48A20679DE1D7389386124D07571D0D872686028C26A3E",
+            ),
+        ];
+
+        #[test]
+        fn bip327_key_agg_vectors() {
+            let keys = public_keys(&PUBLIC_KEYS);
+            for (indices, expected) in VECTORS.iter() {
+                let public_keys: Vec<_> = indices.iter().map(|i| keys[*i].clone()).collect();
+                let context = MuSigKeyAggContext::new(&public_keys).unwrap();
+                assert_eq!(
+                    expected.to_lowercase(),
+                    hex::encode(context.x_only_public_key())
+                );
+            }
+        }
+
+        #[test]
+        fn key_sort() {
+            let keys = public_keys(&PUBLIC_KEYS);
+            let sorted = super::super::key_sort(&keys);
+            assert_eq!(
+                vec![keys[2].clone(), keys[0].clone(), keys[1].clone()],
+                sorted
+            );
+        }
+
+        #[test]
+        fn invalid_key_agg() {
+            assert!(MuSigKeyAggContext::<N>::new(&[]).is_err());
+
+            let context = MuSigKeyAggContext::new(&public_keys(&PUBLIC_KEYS)).unwrap();
+            let order = "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141";
+            assert!(context.apply_tweak(&bytes32(order), true).is_err());
+        }
+    }
+
+    mod sign {
+        use super::*;
+
+        const SECRET_KEY: &str = "7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671";
+        const SECRET_NONCE: &str = "508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F7";
+
+        const PUBLIC_KEYS: [&str; 3] = [
+            "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA661",
+        ];
+
+        const TWEAK_PUBLIC_KEYS: [&str; 3] = [
+            "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        ];
+
+        const PUBLIC_NONCES: [&str; 3] = [
+            "0337C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0287BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480",
+            "0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F817980279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            "032DE2662628C90B03F5E720284EB52FF7D71F4284F627B68A853D78C78E1FFE9303E4C5524E83FFE1493B9077CF1CA6BEB2090C93D930321071AD40B2F44E599046",
+        ];
+
+        const AGGREGATE_NONCE: &str = "028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61037496A3CC86926D452CAFCFD55D25972CA1675D549310DE296BFF42F72EEEA8C9";
+
+        const MESSAGE: &str = "F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF";
+
+        fn private_key() -> BitcoinPrivateKey<N> {
+            BitcoinPrivateKey::from_secp256k1_secret_key(
+                &secp256k1::SecretKey::parse(&bytes32(SECRET_KEY)).unwrap(),
+                true,
+            )
+        }
+
+        fn secret_nonce() -> MuSigSecretNonce {
+            let secret_nonce = hex::decode(SECRET_NONCE).unwrap();
+            let mut k1 = [0u8; 32];
+            let mut k2 = [0u8; 32];
+            k1.copy_from_slice(&secret_nonce[..32]);
+            k2.copy_from_slice(&secret_nonce[32..]);
+            MuSigSecretNonce {
+                k1,
+                k2,
+                public_key: serialize_public_key(&private_key().to_public_key()),
+            }
+        }
+
+        fn public_nonce(index: usize) -> MuSigPublicNonce {
+            MuSigPublicNonce::from_bytes(&hex::decode(PUBLIC_NONCES[index]).unwrap()).unwrap()
+        }
+
+        fn sign(
+            keys: &[&str],
+            key_indices: &[usize],
+            tweaks: &[(&str, bool)],
+        ) -> (MuSigSession<N>, MuSigPartialSignature) {
+            let keys = public_keys(keys);
+            let public_keys: Vec<_> = key_indices.iter().map(|i| keys[*i].clone()).collect();
+            let mut context = MuSigKeyAggContext::new(&public_keys).unwrap();
+            for (tweak, x_only) in tweaks {
+                context = context.apply_tweak(&bytes32(tweak), *x_only).unwrap();
+            }
+
+            let public_nonces: Vec<_> = key_indices.iter().map(|i| public_nonce(*i)).collect();
+            let aggregate_nonce = MuSigAggregateNonce::new(&public_nonces).unwrap();
+            assert_eq!(
+                AGGREGATE_NONCE.to_lowercase(),
+                hex::encode(&aggregate_nonce.to_bytes()[..])
+            );
+
+            let message = hex::decode(MESSAGE).unwrap();
+            let session = MuSigSession::new(&context, &aggregate_nonce, &message).unwrap();
+            let partial_signature = session
+                .partial_sign(secret_nonce(), &private_key())
+                .unwrap();
+            (session, partial_signature)
+        }
+
+        #[test]
+        fn bip327_sign_vectors() {
+            assert_eq!(
+                PUBLIC_KEYS[0].to_lowercase(),
+                private_key().to_public_key().to_string()
+            );
+            assert_eq!(
+                PUBLIC_NONCES[0].to_lowercase(),
+                hex::encode(&secret_nonce().public_nonce().unwrap().to_bytes()[..])
+            );
+
+            let vectors: [(&[usize], &str); 3] = [
+                (
+                    &[0, 1, 2],
+                    "012ABBCB52B3016AC03AD82395A1A415C48B93DEF78718E62A7A90052FE224FB",
+                ),
+                (
+                    &[1, 0, 2],
+                    "9FF2F7AAA856150CC8819254218D3ADEEB0535269051897724F9DB3789513A52",
+                ),
+                (
+                    &[1, 2, 0],
+                    "FA23C359F6FAC4E7796BB93BC9F0532A95468C539BA20FF86D7C76ED92227900",
+                ),
+            ];
+            for (key_indices, expected) in vectors.iter() {
+                let (session, partial_signature) = sign(&PUBLIC_KEYS, key_indices, &[]);
+                assert_eq!(
+                    expected.to_lowercase(),
+                    hex::encode(partial_signature.to_bytes())
+                );
+                assert!(session
+                    .partial_verify(
+                        &partial_signature,
+                        &public_nonce(0),
+                        &private_key().to_public_key()
+                    )
+                    .is_ok());
+                assert!(session
+                    .partial_verify(
+                        &partial_signature,
+                        &public_nonce(1),
+                        &public_keys(&PUBLIC_KEYS)[1]
+                    )
+                    .is_err());
+            }
+        }
+
+        #[test]
+        fn bip327_tweak_vectors() {
+            let tweaks = [
+                "E8F791FF9225A2AF0102AFFF4A9A723D9612A682A25EBE79802B263CDFCD83BB",
+                "AE2EA797CC0FE72AC5B97B97F3C6957D7E4199A167A58EB08BCAFFDA70AC0455",
+                "F52ECBC565B3D8BEA2DFD5B75A4F457E54369809322E4120831626F290FA87E0",
+                "1969AD73CC177FA0B4FCED6DF1F7BF9907E665FDE9BA196A74FED0A3CF5AEF9D",
+            ];
+            let vectors: [(&[(&str, bool)], &str); 5] = [
+                (
+                    &[(tweaks[0], true)],
+                    "E28A5C66E61E178C2BA19DB77B6CF9F7E2F0F56C17918CD13135E60CC848FE91",
+                ),
+                (
+                    &[(tweaks[0], false)],
+                    "38B0767798252F21BF5702C48028B095428320F73A4B14DB1E25DE58543D2D2D",
+                ),
+                (
+                    &[(tweaks[0], false), (tweaks[1], true)],
+                    "408A0A21C4A0F5DACAF9646AD6EB6FECD7F7A11F03ED1F48DFFF2185BC2C2408",
+                ),
+                (
+                    &[
+                        (tweaks[0], false),
+                        (tweaks[1], false),
+                        (tweaks[2], true),
+                        (tweaks[3], true),
+                    ],
+                    "45ABD206E61E3DF2EC9E264A6FEC8292141A633C28586388235541F9ADE75435",
+                ),
+                (
+                    &[
+                        (tweaks[0], true),
+                        (tweaks[1], false),
+                        (tweaks[2], true),
+                        (tweaks[3], false),
+                    ],
+                    "B255FDCAC27B40C7CE7848E2D3B7BF5EA0ED756DA81565AC804CCCA3E1D5D239",
+                ),
+            ];
+            for (tweaks, expected) in vectors.iter() {
+                let (_, partial_signature) = sign(&TWEAK_PUBLIC_KEYS, &[1, 2, 0], tweaks);
+                assert_eq!(
+                    expected.to_lowercase(),
+                    hex::encode(partial_signature.to_bytes())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sign_and_aggregate() {
+        let private_keys: Vec<BitcoinPrivateKey<N>> = (1u8..=3)
+            .map(|byte| {
+                BitcoinPrivateKey::from_secp256k1_secret_key(
+                    &secp256k1::SecretKey::parse(&[byte; 32]).unwrap(),
+                    true,
+                )
+            })
+            .collect();
+        let public_keys: Vec<_> = private_keys.iter().map(|key| key.to_public_key()).collect();
+        let context = MuSigKeyAggContext::new(&key_sort(&public_keys))
+            .unwrap()
+            .apply_taproot_tweak(None)
+            .unwrap();
+
+        let message = [0x42u8; 32];
+        let (secret_nonces, public_nonces): (Vec<_>, Vec<_>) = private_keys
+            .iter()
+            .enumerate()
+            .map(|(i, private_key)| {
+                MuSigSecretNonce::new(
+                    &[0x10 + i as u8; 32],
+                    &private_key.to_public_key(),
+                    Some(private_key),
+                    Some(&context.x_only_public_key()),
+                    Some(&message),
+                    None,
+                )
+                .unwrap()
+            })
+            .unzip();
+
+        let aggregate_nonce = MuSigAggregateNonce::new(&public_nonces).unwrap();
+        let session = MuSigSession::new(&context, &aggregate_nonce, &message).unwrap();
+        let partial_signatures: Vec<_> = secret_nonces
+            .into_iter()
+            .zip(&private_keys)
+            .map(|(secret_nonce, private_key)| {
+                session.partial_sign(secret_nonce, private_key).unwrap()
+            })
+            .collect();
+
+        let signature = session.aggregate(&partial_signatures);
+        assert!(crate::schnorr::verify(
+            &message,
+            &context.x_only_public_key(),
+            &signature
+        ));
+        assert!(!crate::schnorr::verify(
+            &message,
+            &context.x_only_public_key(),
+            &session.aggregate(&partial_signatures[1..])
+        ));
+    }
+}
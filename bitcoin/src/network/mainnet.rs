@@ -4,6 +4,7 @@ use gyu_model::no_std::*;
 
 use crate::format::BitcoinFormat;
 use crate::network::BitcoinNetwork;
+use crate::slip132::Slip132Version;
 
 use gyu_model::{
     address::AddressError,
@@ -29,8 +30,10 @@ impl BitcoinNetwork for Mainnet {
         match format {
             BitcoinFormat::P2PKH => vec![0x00],
             BitcoinFormat::P2WSH => vec![0x00],
-            BitcoinFormat::P2SH_P2WPKH => vec![0x05],
-            BitcoinFormat::Bech32 => vec![0x62, 0x63],
+            BitcoinFormat::P2SH | BitcoinFormat::P2SH_P2WPKH | BitcoinFormat::P2SH_P2WSH => {
+                vec![0x05]
+            }
+            BitcoinFormat::Bech32 | BitcoinFormat::P2TR => vec![0x62, 0x63],
         }
     }
 
@@ -41,6 +44,17 @@ impl BitcoinNetwork for Mainnet {
         }
     }
 
+    fn to_silent_payment_prefix() -> &'static str {
+        "sp"
+    }
+
+    fn from_silent_payment_prefix(prefix: &str) -> Result<Self, AddressError> {
+        match prefix {
+            "sp" => Ok(Self),
+            _ => Err(AddressError::InvalidPrefix(prefix.as_bytes().to_owned())),
+        }
+    }
+
     fn to_private_key_prefix() -> u8 {
         0x80
     }
@@ -57,10 +71,9 @@ impl BitcoinNetwork for Mainnet {
     fn to_extended_private_key_version_bytes(
         format: &BitcoinFormat,
     ) -> Result<Vec<u8>, ExtendedPrivateKeyError> {
-        match format {
-            BitcoinFormat::P2PKH => Ok(vec![0x04, 0x88, 0xAD, 0xE4]),
-            BitcoinFormat::P2SH_P2WPKH => Ok(vec![0x04, 0x9D, 0x78, 0x78]),
-            _ => Err(ExtendedPrivateKeyError::UnsupportedFormat(
+        match Slip132Version::from_format(Self::NAME, format) {
+            Some(version) => Ok(version.private_version.to_vec()),
+            None => Err(ExtendedPrivateKeyError::UnsupportedFormat(
                 format.to_string(),
             )),
         }
@@ -69,8 +82,8 @@ impl BitcoinNetwork for Mainnet {
     fn from_extended_private_key_version_bytes(
         prefix: &[u8],
     ) -> Result<Self, ExtendedPrivateKeyError> {
-        match prefix[0..4] {
-            [0x04, 0x88, 0xAD, 0xE4] | [0x04, 0x9D, 0x78, 0x78] => Ok(Self),
+        match Slip132Version::from_private_version(prefix) {
+            Some(version) if version.network == Self::NAME => Ok(Self),
             _ => Err(ExtendedPrivateKeyError::InvalidVersionBytes(
                 prefix.to_vec(),
             )),
@@ -80,10 +93,9 @@ impl BitcoinNetwork for Mainnet {
     fn to_extended_public_key_version_bytes(
         format: &BitcoinFormat,
     ) -> Result<Vec<u8>, ExtendedPublicKeyError> {
-        match format {
-            BitcoinFormat::P2PKH => Ok(vec![0x04, 0x88, 0xB2, 0x1E]),
-            BitcoinFormat::P2SH_P2WPKH => Ok(vec![0x04, 0x9D, 0x7C, 0xB2]),
-            _ => Err(ExtendedPublicKeyError::UnsupportedFormat(
+        match Slip132Version::from_format(Self::NAME, format) {
+            Some(version) => Ok(version.public_version.to_vec()),
+            None => Err(ExtendedPublicKeyError::UnsupportedFormat(
                 format.to_string(),
             )),
         }
@@ -92,8 +104,8 @@ impl BitcoinNetwork for Mainnet {
     fn from_extended_public_key_version_bytes(
         prefix: &[u8],
     ) -> Result<Self, ExtendedPublicKeyError> {
-        match prefix[0..4] {
-            [0x04, 0x88, 0xB2, 0x1E] | [0x04, 0x9D, 0x7C, 0xB2] => Ok(Self),
+        match Slip132Version::from_public_version(prefix) {
+            Some(version) if version.network == Self::NAME => Ok(Self),
             _ => Err(ExtendedPublicKeyError::InvalidVersionBytes(prefix.to_vec())),
         }
     }
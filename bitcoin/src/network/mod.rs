@@ -16,6 +16,10 @@ pub trait BitcoinNetwork: Network {
 
     fn from_address_prefix(prefix: &[u8]) -> Result<Self, AddressError>;
 
+    fn to_silent_payment_prefix() -> &'static str;
+
+    fn from_silent_payment_prefix(prefix: &str) -> Result<Self, AddressError>;
+
     fn to_private_key_prefix() -> u8;
 
     fn from_private_key_prefix(prefix: u8) -> Result<Self, PrivateKeyError>;
@@ -0,0 +1,645 @@
+use crate::address::BitcoinAddress;
+use crate::derivation_path::BitcoinDerivationPath;
+use crate::extended_private_key::BitcoinExtendedPrivateKey;
+use crate::extended_public_key::BitcoinExtendedPublicKey;
+use crate::format::BitcoinFormat;
+use crate::key_origin::KeyOrigin;
+use crate::network::BitcoinNetwork;
+use gyu_model::no_std::*;
+use gyu_model::{
+    address::AddressError,
+    derivation_path::{ChildIndex, DerivationPath, DerivationPathError},
+    extended_private_key::{ExtendedPrivateKey, ExtendedPrivateKeyError},
+    extended_public_key::{ExtendedPublicKey, ExtendedPublicKeyError},
+};
+
+use core::{fmt, marker::PhantomData, str::FromStr};
+
+/// The number of indices of each kind, normal or hardened
+const INDICES: u64 = 1 << 31;
+
+/// A concrete path of a template and the key or address derived along it
+type Derived<N, T> = Result<(BitcoinDerivationPath<N>, T), PathTemplateError>;
+
+#[derive(Debug, Fail)]
+pub enum PathTemplateError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "{}", _0)]
+    DerivationPathError(DerivationPathError),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPrivateKeyError(ExtendedPrivateKeyError),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPublicKeyError(ExtendedPublicKeyError),
+
+    #[fail(display = "the extended key is not on the path {}", _0)]
+    KeyNotOnPath(String),
+}
+
+impl From<AddressError> for PathTemplateError {
+    fn from(error: AddressError) -> Self {
+        PathTemplateError::AddressError(error)
+    }
+}
+
+impl From<DerivationPathError> for PathTemplateError {
+    fn from(error: DerivationPathError) -> Self {
+        PathTemplateError::DerivationPathError(error)
+    }
+}
+
+impl From<ExtendedPrivateKeyError> for PathTemplateError {
+    fn from(error: ExtendedPrivateKeyError) -> Self {
+        PathTemplateError::ExtendedPrivateKeyError(error)
+    }
+}
+
+impl From<ExtendedPublicKeyError> for PathTemplateError {
+    fn from(error: ExtendedPublicKeyError) -> Self {
+        PathTemplateError::ExtendedPublicKeyError(error)
+    }
+}
+
+/// Represents a component of a derivation path template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathComponent {
+    /// A concrete child index, such as 0 or 44'
+    Index(ChildIndex),
+    /// An inclusive range of child indices, such as 0-999
+    Range(ChildIndex, ChildIndex),
+    /// Every normal or, if true, every hardened child index, written * or *'
+    Wildcard(bool),
+    /// One child index per path of a multipath template, such as <0;1>
+    Multipath(Vec<ChildIndex>),
+}
+
+impl PathComponent {
+    /// Returns the number of child indices of the component, for components other than
+    /// multipath components.
+    fn len(&self) -> u64 {
+        match self {
+            PathComponent::Index(_) | PathComponent::Multipath(_) => 1,
+            PathComponent::Range(start, end) => (end.to_index() - start.to_index()) as u64 + 1,
+            PathComponent::Wildcard(_) => INDICES,
+        }
+    }
+
+    /// Returns the child index at the position among the child indices of the component.
+    fn get(&self, position: u64, branch: usize) -> ChildIndex {
+        match self {
+            PathComponent::Index(index) => *index,
+            PathComponent::Range(start, _) => ChildIndex::from(start.to_index() + position as u32),
+            PathComponent::Wildcard(false) => ChildIndex::Normal(position as u32),
+            PathComponent::Wildcard(true) => ChildIndex::Hardened(position as u32),
+            PathComponent::Multipath(indices) => indices[branch],
+        }
+    }
+}
+
+impl FromStr for PathComponent {
+    type Err = DerivationPathError;
+
+    fn from_str(component: &str) -> Result<Self, Self::Err> {
+        let invalid = || DerivationPathError::InvalidDerivationPath(component.to_string());
+
+        if let Some(hardened) = component.strip_prefix('*') {
+            return match hardened {
+                "" => Ok(PathComponent::Wildcard(false)),
+                "'" | "h" | "H" => Ok(PathComponent::Wildcard(true)),
+                _ => Err(invalid()),
+            };
+        }
+
+        if let Some(indices) = component
+            .strip_prefix('<')
+            .and_then(|component| component.strip_suffix('>'))
+        {
+            let indices: Vec<ChildIndex> = indices
+                .split(';')
+                .map(str::parse)
+                .collect::<Result<_, _>>()?;
+            return match indices.len() < 2 {
+                true => Err(invalid()),
+                false => Ok(PathComponent::Multipath(indices)),
+            };
+        }
+
+        if let Some((start, end)) = component.split_once('-') {
+            let (start, end): (ChildIndex, ChildIndex) = (start.parse()?, end.parse()?);
+            return match start.is_hardened() == end.is_hardened() && start <= end {
+                true => Ok(PathComponent::Range(start, end)),
+                false => Err(invalid()),
+            };
+        }
+
+        Ok(PathComponent::Index(component.parse()?))
+    }
+}
+
+impl fmt::Display for PathComponent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathComponent::Index(index) => fmt::Display::fmt(index, f),
+            PathComponent::Range(start, end) => write!(f, "{}-{}", start, end),
+            PathComponent::Wildcard(false) => f.write_str("*"),
+            PathComponent::Wildcard(true) => f.write_str("*'"),
+            PathComponent::Multipath(indices) => {
+                f.write_str("<")?;
+                for (i, index) in indices.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(";")?;
+                    }
+                    fmt::Display::fmt(index, f)?;
+                }
+                f.write_str(">")
+            }
+        }
+    }
+}
+
+/// Represents a derivation path template, such as m/84'/0'/0'/<0;1>/* or 0/0-999, that
+/// expands lazily into concrete derivation paths
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinDerivationPathTemplate<N: BitcoinNetwork> {
+    /// True for paths from the master key, written with a leading m
+    absolute: bool,
+    components: Vec<PathComponent>,
+    _network: PhantomData<N>,
+}
+
+impl<N: BitcoinNetwork> BitcoinDerivationPathTemplate<N> {
+    pub fn new(absolute: bool, components: &[PathComponent]) -> Result<Self, DerivationPathError> {
+        let template = Self {
+            absolute,
+            components: components.to_vec(),
+            _network: PhantomData,
+        };
+        template.check()?;
+        Ok(template)
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        self.absolute
+    }
+
+    pub fn components(&self) -> &[PathComponent] {
+        &self.components
+    }
+
+    /// Returns the number of paths of a multipath template, or 1.
+    pub fn branches(&self) -> usize {
+        self.components
+            .iter()
+            .find_map(|component| match component {
+                PathComponent::Multipath(indices) => Some(indices.len()),
+                _ => None,
+            })
+            .unwrap_or(1)
+    }
+
+    /// Returns the templates of each path of a multipath template, as in BIP389, such as
+    /// m/84'/0'/0'/0/* and m/84'/0'/0'/1/* for m/84'/0'/0'/<0;1>/*.
+    pub fn split_multipath(&self) -> Vec<Self> {
+        (0..self.branches())
+            .map(|branch| Self {
+                absolute: self.absolute,
+                components: self
+                    .components
+                    .iter()
+                    .map(|component| match component {
+                        PathComponent::Multipath(indices) => PathComponent::Index(indices[branch]),
+                        component => component.clone(),
+                    })
+                    .collect(),
+                _network: PhantomData,
+            })
+            .collect()
+    }
+
+    /// Returns the number of concrete paths of the template, if it fits in a u64.
+    pub fn path_count(&self) -> Option<u64> {
+        self.components
+            .iter()
+            .try_fold(self.branches() as u64, |count, component| {
+                count.checked_mul(component.len())
+            })
+    }
+
+    /// Returns an iterator over the concrete paths of the template, the paths of each
+    /// multipath branch in turn, with the last component varying fastest.
+    pub fn iter(&self) -> PathTemplateIter<N> {
+        PathTemplateIter {
+            template: self.clone(),
+            branch: 0,
+            positions: vec![0; self.components.len()],
+            done: false,
+        }
+    }
+
+    /// Returns the derived extended public keys of the concrete paths of the template,
+    /// lazily. Absolute templates derive from the master key or, for keys that know their
+    /// origin, from the key along the rest of the path.
+    pub fn derive_public_keys<'a>(
+        &'a self,
+        extended_public_key: &BitcoinExtendedPublicKey<N>,
+    ) -> Result<impl Iterator<Item = Derived<N, BitcoinExtendedPublicKey<N>>> + 'a, PathTemplateError>
+    {
+        self.derive_with(
+            extended_public_key,
+            extended_public_key.depth(),
+            extended_public_key.origin(),
+            |key, path| key.derive(path),
+            |key, format| key.to_format(format),
+        )
+    }
+
+    /// Returns the derived extended private keys of the concrete paths of the template,
+    /// lazily, as for `derive_public_keys`.
+    pub fn derive_private_keys<'a>(
+        &'a self,
+        extended_private_key: &BitcoinExtendedPrivateKey<N>,
+    ) -> Result<
+        impl Iterator<Item = Derived<N, BitcoinExtendedPrivateKey<N>>> + 'a,
+        PathTemplateError,
+    > {
+        self.derive_with(
+            extended_private_key,
+            extended_private_key.depth(),
+            extended_private_key.origin(),
+            |key, path| key.derive(path),
+            |key, format| key.to_format(format),
+        )
+    }
+
+    /// Returns the addresses in the format of the concrete paths of the template, lazily.
+    pub fn derive_addresses<'a>(
+        &'a self,
+        extended_public_key: &BitcoinExtendedPublicKey<N>,
+        format: &'a BitcoinFormat,
+    ) -> Result<impl Iterator<Item = Derived<N, BitcoinAddress<N>>> + 'a, PathTemplateError> {
+        Ok(self
+            .derive_public_keys(extended_public_key)?
+            .map(move |result| {
+                let (path, extended_public_key) = result?;
+                Ok((path, extended_public_key.to_address(format)?))
+            }))
+    }
+
+    /// Returns the derived keys of the concrete paths of the template, deriving the leading
+    /// concrete components once and the rest of each path from there. Keys take the format
+    /// of their path, if it implies one.
+    fn derive_with<'a, K, E, F, G>(
+        &'a self,
+        key: &K,
+        depth: u8,
+        origin: Option<&KeyOrigin>,
+        derive: F,
+        with_format: G,
+    ) -> Result<impl Iterator<Item = Derived<N, K>> + 'a, PathTemplateError>
+    where
+        K: Clone + 'a,
+        F: Fn(&K, &BitcoinDerivationPath<N>) -> Result<K, E> + 'a,
+        G: Fn(&K, &BitcoinFormat) -> Result<K, E> + 'a,
+        PathTemplateError: From<E>,
+    {
+        let prefix: Vec<ChildIndex> = self
+            .components
+            .iter()
+            .map_while(|component| match component {
+                PathComponent::Index(index) => Some(*index),
+                _ => None,
+            })
+            .collect();
+
+        // The number of leading components the key was already derived along
+        let skip = match (self.absolute, origin) {
+            (false, _) => 0,
+            (true, _) if depth == 0 => 0,
+            (true, Some(origin)) if prefix.starts_with(&origin.path) => origin.path.len(),
+            (true, _) => return Err(PathTemplateError::KeyNotOnPath(self.to_string())),
+        };
+
+        let base = derive(
+            key,
+            &BitcoinDerivationPath::BIP32(prefix[skip..].to_vec(), PhantomData),
+        )?;
+        let fixed = prefix.len();
+
+        Ok(self.iter().map(move |path| {
+            let path = path?;
+            let indices = path.to_vec()?;
+            let relative = BitcoinDerivationPath::BIP32(indices[fixed..].to_vec(), PhantomData);
+            let key = derive(&base, &relative)?;
+            let key = match path.format() {
+                Some(format) => with_format(&key, &format)?,
+                None => key,
+            };
+            Ok((path, key))
+        }))
+    }
+
+    /// Returns an error unless the multipath components have the same number of indices.
+    fn check(&self) -> Result<(), DerivationPathError> {
+        let branches = self.branches();
+        for component in self.components.iter() {
+            if let PathComponent::Multipath(indices) = component {
+                if indices.len() != branches {
+                    return Err(DerivationPathError::InvalidDerivationPath(self.to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<N: BitcoinNetwork> FromStr for BitcoinDerivationPathTemplate<N> {
+    type Err = DerivationPathError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let (absolute, components) = match template {
+            "m" | "M" => (true, ""),
+            _ => match template
+                .strip_prefix("m/")
+                .or_else(|| template.strip_prefix("M/"))
+            {
+                Some(components) => (true, components),
+                None => (false, template),
+            },
+        };
+        let components: Vec<PathComponent> = match components {
+            "" => vec![],
+            components => components
+                .split('/')
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
+        };
+        Self::new(absolute, &components)
+    }
+}
+
+impl<N: BitcoinNetwork> fmt::Display for BitcoinDerivationPathTemplate<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.absolute {
+            f.write_str("m")?;
+        }
+        for (i, component) in self.components.iter().enumerate() {
+            if self.absolute || i > 0 {
+                f.write_str("/")?;
+            }
+            fmt::Display::fmt(component, f)?;
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the concrete paths of a derivation path template
+pub struct PathTemplateIter<N: BitcoinNetwork> {
+    template: BitcoinDerivationPathTemplate<N>,
+    branch: usize,
+    positions: Vec<u64>,
+    done: bool,
+}
+
+impl<N: BitcoinNetwork> Iterator for PathTemplateIter<N> {
+    type Item = Result<BitcoinDerivationPath<N>, DerivationPathError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let components = &self.template.components;
+        let indices: Vec<ChildIndex> = components
+            .iter()
+            .zip(self.positions.iter())
+            .map(|(component, position)| component.get(*position, self.branch))
+            .collect();
+
+        // Advance the positions, the last component fastest, then the branch
+        let mut carry = true;
+        for (component, position) in components.iter().zip(self.positions.iter_mut()).rev() {
+            *position += 1;
+            if *position < component.len() {
+                carry = false;
+                break;
+            }
+            *position = 0;
+        }
+        if carry {
+            self.branch += 1;
+            self.done = self.branch == self.template.branches();
+        }
+
+        Some(match self.template.absolute {
+            true => BitcoinDerivationPath::from_vec(&indices),
+            false => Ok(BitcoinDerivationPath::BIP32(indices, PhantomData)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+    use crate::test_vectors::master;
+
+    type N = Mainnet;
+
+    fn template(template: &str) -> BitcoinDerivationPathTemplate<N> {
+        BitcoinDerivationPathTemplate::from_str(template).unwrap()
+    }
+
+    fn paths(template: &BitcoinDerivationPathTemplate<N>) -> Vec<String> {
+        template
+            .iter()
+            .map(|path| path.unwrap().to_string())
+            .collect()
+    }
+
+    fn account(master: &BitcoinExtendedPrivateKey<N>) -> BitcoinExtendedPublicKey<N> {
+        master
+            .derive(&BitcoinDerivationPath::from_str("m/84'/0'/0'").unwrap())
+            .unwrap()
+            .to_extended_public_key()
+    }
+
+    #[test]
+    fn from_str() {
+        let parsed = template("m/84h/0H/0'/<0;1>/*");
+        assert!(parsed.is_absolute());
+        assert_eq!(
+            &[
+                PathComponent::Index(ChildIndex::Hardened(84)),
+                PathComponent::Index(ChildIndex::Hardened(0)),
+                PathComponent::Index(ChildIndex::Hardened(0)),
+                PathComponent::Multipath(vec![ChildIndex::Normal(0), ChildIndex::Normal(1)]),
+                PathComponent::Wildcard(false),
+            ],
+            parsed.components()
+        );
+        assert_eq!("m/84'/0'/0'/<0;1>/*", parsed.to_string());
+
+        for (input, output) in [
+            ("m", "m"),
+            ("0/0-999", "0/0-999"),
+            ("m/44h/0h/0h/0/0-999", "m/44'/0'/0'/0/0-999"),
+            ("M/0'-3'/*h", "m/0'-3'/*'"),
+            ("<0;1;2>/5", "<0;1;2>/5"),
+        ]
+        .iter()
+        {
+            let parsed = template(input);
+            assert_eq!(*output, parsed.to_string());
+            assert_eq!(parsed, template(output));
+        }
+    }
+
+    #[test]
+    fn invalid_templates() {
+        for invalid in [
+            "m/x",
+            "m/**",
+            "m/*x",
+            "m/<0>",
+            "m/<0;x>",
+            "m/5-3",
+            "m/0-3'",
+            "m/<0;1>/<0;1;2>",
+            "m/2147483648",
+        ]
+        .iter()
+        {
+            assert!(
+                BitcoinDerivationPathTemplate::<N>::from_str(invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn iter() {
+        assert_eq!(
+            vec!["m/0/0", "m/0/1", "m/0/2", "m/1/0", "m/1/1", "m/1/2"],
+            paths(&template("m/0-1/0-2"))
+        );
+        assert_eq!(
+            vec!["m/0/5'", "m/0/6'", "m/1/5'", "m/1/6'"],
+            paths(&template("m/<0;1>/5'-6'"))
+        );
+        // Relative paths expand into paths from the key they are applied to
+        assert_eq!(vec!["m/0/7"], paths(&template("0/7")));
+        assert_eq!(vec!["m"], paths(&template("m")));
+
+        let wildcard = template("m/0/*'");
+        assert_eq!(Some(INDICES), wildcard.path_count());
+        assert_eq!(
+            vec!["m/0/0'", "m/0/1'", "m/0/2'"],
+            wildcard
+                .iter()
+                .take(3)
+                .map(|path| path.unwrap().to_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(2000), template("m/<0;1>/0-999").path_count());
+        assert_eq!(None, template("*/*/*").path_count());
+    }
+
+    #[test]
+    fn split_multipath() {
+        let parsed = template("m/84'/0'/0'/<0;1>/*");
+        assert_eq!(2, parsed.branches());
+        let split: Vec<String> = parsed
+            .split_multipath()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(vec!["m/84'/0'/0'/0/*", "m/84'/0'/0'/1/*"], split);
+        assert_eq!(1, template("m/0/*").split_multipath().len());
+    }
+
+    #[test]
+    fn derive_addresses() {
+        let master = master(&BitcoinFormat::P2PKH);
+        let expected = [
+            (
+                "m/84'/0'/0'/0/0",
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            ),
+            (
+                "m/84'/0'/0'/0/1",
+                "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g",
+            ),
+            (
+                "m/84'/0'/0'/1/0",
+                "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el",
+            ),
+            (
+                "m/84'/0'/0'/1/1",
+                "bc1qggnasd834t54yulsep6fta8lpjekv4zj6gv5rf",
+            ),
+        ];
+        let parsed = template("m/84'/0'/0'/<0;1>/0-1");
+
+        // From the account key, which knows its origin
+        let account = account(&master);
+        let addresses: Vec<(String, String)> = parsed
+            .derive_addresses(&account, &BitcoinFormat::Bech32)
+            .unwrap()
+            .map(|result| {
+                let (path, address) = result.unwrap();
+                (path.to_string(), address.to_string())
+            })
+            .collect();
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(path, address)| (path.to_string(), address.to_string()))
+            .collect();
+        assert_eq!(expected, addresses);
+
+        // From the master key, with the format of the BIP84 paths
+        for (result, (path, address)) in parsed
+            .derive_private_keys(&master)
+            .unwrap()
+            .zip(expected.iter())
+        {
+            let (derived_path, key) = result.unwrap();
+            assert_eq!(*path, derived_path.to_string());
+            assert_eq!(BitcoinFormat::Bech32, key.format());
+            assert_eq!(*address, key.to_default_address().unwrap().to_string());
+        }
+
+        // A relative template derives from the key itself
+        let relative = template("1/0-1");
+        let addresses: Vec<String> = relative
+            .derive_addresses(&account, &BitcoinFormat::Bech32)
+            .unwrap()
+            .map(|result| result.unwrap().1.to_string())
+            .collect();
+        assert_eq!(
+            vec![expected[2].1.clone(), expected[3].1.clone()],
+            addresses
+        );
+    }
+
+    #[test]
+    fn key_not_on_path() {
+        let master = master(&BitcoinFormat::P2PKH);
+        let account = account(&master);
+        assert!(template("m/84'/0'/1'/0/*")
+            .derive_public_keys(&account)
+            .is_err());
+
+        // A key parsed at depth above 0 has no origin to place it on an absolute path
+        let parsed = BitcoinExtendedPublicKey::<N>::from_str(&account.to_string()).unwrap();
+        assert!(template("m/84'/0'/0'/0/*")
+            .derive_public_keys(&parsed)
+            .is_err());
+    }
+}
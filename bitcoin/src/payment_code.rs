@@ -0,0 +1,619 @@
+use crate::address::BitcoinAddress;
+use crate::amount::BitcoinAmount;
+use crate::derivation_path::BitcoinDerivationPath;
+use crate::extended_private_key::BitcoinExtendedPrivateKey;
+use crate::extended_public_key::BitcoinExtendedPublicKey;
+use crate::format::BitcoinFormat;
+use crate::message::{read_compact_size, read_pushes};
+use crate::network::BitcoinNetwork;
+use crate::private_key::{BitcoinPrivateKey, BitcoinSharedSecretFormat};
+use crate::public_key::{BitcoinPublicKey, PublicKeyPolicy};
+use crate::transaction::{BitcoinTransaction, BitcoinTransactionOutput, Outpoint};
+use gyu_model::no_std::*;
+use gyu_model::{
+    address::AddressError,
+    amount::AmountError,
+    derivation_path::{ChildIndex, DerivationPathError},
+    extended_private_key::{ExtendedPrivateKey, ExtendedPrivateKeyError},
+    extended_public_key::{ExtendedPublicKey, ExtendedPublicKeyError},
+    private_key::{PrivateKey, PrivateKeyError},
+    public_key::{PublicKey, PublicKeyError},
+    transaction::TransactionError,
+    utilities::crypto::checksum,
+};
+
+use base58::{FromBase58, ToBase58};
+use core::{fmt, marker::PhantomData, str::FromStr};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256, Sha512};
+use zeroize::Zeroizing;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// The base58 prefix of serialized payment codes
+const PAYMENT_CODE_PREFIX: u8 = 0x47;
+
+/// The version of payment codes that are supported
+const PAYMENT_CODE_VERSION: u8 = 0x01;
+
+#[derive(Debug, Fail)]
+pub enum PaymentCodeError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}", _0)]
+    AmountError(AmountError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "{}", _0)]
+    DerivationPathError(DerivationPathError),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPrivateKeyError(ExtendedPrivateKeyError),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPublicKeyError(ExtendedPublicKeyError),
+
+    #[fail(display = "invalid byte length: {}", _0)]
+    InvalidByteLength(usize),
+
+    #[fail(
+        display = "invalid checksum: {{ expected: {:?}, found: {:?} }}",
+        _0, _1
+    )]
+    InvalidChecksum(String, String),
+
+    #[fail(display = "invalid payment code prefix: {}", _0)]
+    InvalidPrefix(u8),
+
+    #[fail(display = "invalid payment code public key sign: {}", _0)]
+    InvalidSign(u8),
+
+    #[fail(display = "unsupported payment code version: {}", _0)]
+    InvalidVersion(u8),
+
+    #[fail(display = "the transaction has no input that exposes a public key")]
+    MissingDesignatedInput,
+
+    #[fail(display = "the transaction has no payment code notification payload")]
+    MissingNotificationPayload,
+
+    #[fail(display = "{}", _0)]
+    PrivateKeyError(PrivateKeyError),
+
+    #[fail(display = "{}", _0)]
+    PublicKeyError(PublicKeyError),
+
+    #[fail(display = "{}", _0)]
+    TransactionError(TransactionError),
+}
+
+impl From<AddressError> for PaymentCodeError {
+    fn from(error: AddressError) -> Self {
+        PaymentCodeError::AddressError(error)
+    }
+}
+
+impl From<AmountError> for PaymentCodeError {
+    fn from(error: AmountError) -> Self {
+        PaymentCodeError::AmountError(error)
+    }
+}
+
+impl From<DerivationPathError> for PaymentCodeError {
+    fn from(error: DerivationPathError) -> Self {
+        PaymentCodeError::DerivationPathError(error)
+    }
+}
+
+impl From<ExtendedPrivateKeyError> for PaymentCodeError {
+    fn from(error: ExtendedPrivateKeyError) -> Self {
+        PaymentCodeError::ExtendedPrivateKeyError(error)
+    }
+}
+
+impl From<ExtendedPublicKeyError> for PaymentCodeError {
+    fn from(error: ExtendedPublicKeyError) -> Self {
+        PaymentCodeError::ExtendedPublicKeyError(error)
+    }
+}
+
+impl From<PrivateKeyError> for PaymentCodeError {
+    fn from(error: PrivateKeyError) -> Self {
+        PaymentCodeError::PrivateKeyError(error)
+    }
+}
+
+impl From<PublicKeyError> for PaymentCodeError {
+    fn from(error: PublicKeyError) -> Self {
+        PaymentCodeError::PublicKeyError(error)
+    }
+}
+
+impl From<TransactionError> for PaymentCodeError {
+    fn from(error: TransactionError) -> Self {
+        PaymentCodeError::TransactionError(error)
+    }
+}
+
+impl From<base58::FromBase58Error> for PaymentCodeError {
+    fn from(error: base58::FromBase58Error) -> Self {
+        PaymentCodeError::Crate("base58", format!("{:?}", error))
+    }
+}
+
+impl From<secp256k1::Error> for PaymentCodeError {
+    fn from(error: secp256k1::Error) -> Self {
+        PaymentCodeError::Crate("libsecp256k1", format!("{:?}", error))
+    }
+}
+
+/// Represents a BIP47 reusable payment code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinPaymentCode<N: BitcoinNetwork> {
+    features: u8,
+    public_key: BitcoinPublicKey<N>,
+    chain_code: [u8; 32],
+}
+
+impl<N: BitcoinNetwork> BitcoinPaymentCode<N> {
+    /// Returns the payment code of the extended public key at m/47'/coin'/account'.
+    pub fn from_extended_public_key(extended_public_key: &BitcoinExtendedPublicKey<N>) -> Self {
+        Self {
+            features: 0,
+            public_key: extended_public_key.to_public_key(),
+            chain_code: extended_public_key.chain_code,
+        }
+    }
+
+    /// Returns the payment code of its 80 byte binary serialization.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PaymentCodeError> {
+        if bytes.len() != 80 {
+            return Err(PaymentCodeError::InvalidByteLength(bytes.len()));
+        }
+        if bytes[0] != PAYMENT_CODE_VERSION {
+            return Err(PaymentCodeError::InvalidVersion(bytes[0]));
+        }
+        if bytes[2] != 0x02 && bytes[2] != 0x03 {
+            return Err(PaymentCodeError::InvalidSign(bytes[2]));
+        }
+
+        let mut public_key = [0u8; 33];
+        public_key.copy_from_slice(&bytes[2..35]);
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&bytes[35..67]);
+
+        Ok(Self {
+            features: bytes[1],
+            public_key: BitcoinPublicKey::from_secp256k1_public_key(
+                secp256k1::PublicKey::parse_compressed(&public_key)?,
+                true,
+            ),
+            chain_code,
+        })
+    }
+
+    /// Returns the 80 byte binary serialization of the payment code.
+    pub fn to_bytes(&self) -> [u8; 80] {
+        let mut bytes = [0u8; 80];
+        bytes[0] = PAYMENT_CODE_VERSION;
+        bytes[1] = self.features;
+        bytes[2..35].copy_from_slice(
+            &self
+                .public_key
+                .to_secp256k1_public_key()
+                .serialize_compressed(),
+        );
+        bytes[35..67].copy_from_slice(&self.chain_code);
+        bytes
+    }
+
+    pub fn public_key(&self) -> &BitcoinPublicKey<N> {
+        &self.public_key
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    /// Returns the public key of the payment code at the child index.
+    pub fn derive_public_key(&self, index: u32) -> Result<BitcoinPublicKey<N>, PaymentCodeError> {
+        let extended_public_key = BitcoinExtendedPublicKey {
+            format: BitcoinFormat::P2PKH,
+            depth: 3,
+            parent_fingerprint: [0u8; 4],
+            child_index: ChildIndex::Hardened(0),
+            chain_code: self.chain_code,
+            public_key: self.public_key.clone(),
+            origin: None,
+        };
+        let path = BitcoinDerivationPath::BIP32(vec![ChildIndex::normal(index)?], PhantomData);
+        Ok(extended_public_key.derive(&path)?.to_public_key())
+    }
+
+    /// Returns the P2PKH notification address of the payment code.
+    pub fn notification_address(&self) -> Result<BitcoinAddress<N>, PaymentCodeError> {
+        Ok(self
+            .derive_public_key(0)?
+            .to_address(&BitcoinFormat::P2PKH)?)
+    }
+}
+
+impl<N: BitcoinNetwork> FromStr for BitcoinPaymentCode<N> {
+    type Err = PaymentCodeError;
+
+    fn from_str(payment_code: &str) -> Result<Self, Self::Err> {
+        let data = payment_code.from_base58()?;
+        if data.len() != 85 {
+            return Err(PaymentCodeError::InvalidByteLength(data.len()));
+        }
+
+        let expected = &data[81..85];
+        let checksum = &checksum(&data[0..81])[0..4];
+        if *expected != *checksum {
+            let expected = expected.to_base58();
+            let found = checksum.to_base58();
+            return Err(PaymentCodeError::InvalidChecksum(expected, found));
+        }
+
+        if data[0] != PAYMENT_CODE_PREFIX {
+            return Err(PaymentCodeError::InvalidPrefix(data[0]));
+        }
+        Self::from_bytes(&data[1..81])
+    }
+}
+
+impl<N: BitcoinNetwork> fmt::Display for BitcoinPaymentCode<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut data = [0u8; 85];
+        data[0] = PAYMENT_CODE_PREFIX;
+        data[1..81].copy_from_slice(&self.to_bytes());
+        let checksum = &checksum(&data[0..81])[0..4];
+        data[81..85].copy_from_slice(checksum);
+        write!(f, "{}", data.to_base58())
+    }
+}
+
+/// Represents the private keys of a BIP47 payment code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinPrivatePaymentCode<N: BitcoinNetwork> {
+    extended_private_key: BitcoinExtendedPrivateKey<N>,
+}
+
+impl<N: BitcoinNetwork> BitcoinPrivatePaymentCode<N> {
+    /// Returns the private payment code at m/47'/coin'/account' of the master extended private key.
+    pub fn from_extended_private_key(
+        extended_private_key: &BitcoinExtendedPrivateKey<N>,
+        account: u32,
+    ) -> Result<Self, PaymentCodeError> {
+        // Reject account indices that are already hardened.
+        ChildIndex::normal(account)?;
+
+        let path = BitcoinDerivationPath::BIP32(
+            vec![
+                ChildIndex::Hardened(47),
+                N::HD_COIN_TYPE,
+                ChildIndex::Hardened(account),
+            ],
+            PhantomData,
+        );
+        Ok(Self {
+            extended_private_key: extended_private_key.derive(&path)?,
+        })
+    }
+
+    pub fn to_payment_code(&self) -> BitcoinPaymentCode<N> {
+        BitcoinPaymentCode::from_extended_public_key(
+            &self.extended_private_key.to_extended_public_key(),
+        )
+    }
+
+    /// Returns the private key of the payment code at the child index.
+    pub fn derive_private_key(&self, index: u32) -> Result<BitcoinPrivateKey<N>, PaymentCodeError> {
+        let path = BitcoinDerivationPath::BIP32(vec![ChildIndex::normal(index)?], PhantomData);
+        Ok(self.extended_private_key.derive(&path)?.to_private_key())
+    }
+
+    /// Returns the private key of the notification address.
+    pub fn notification_private_key(&self) -> Result<BitcoinPrivateKey<N>, PaymentCodeError> {
+        self.derive_private_key(0)
+    }
+
+    /// Returns the public key of the payment at the index sent to the recipient.
+    pub fn send_public_key(
+        &self,
+        recipient: &BitcoinPaymentCode<N>,
+        index: u32,
+    ) -> Result<BitcoinPublicKey<N>, PaymentCodeError> {
+        let public_key = recipient.derive_public_key(index)?;
+        let tweak = payment_tweak(&self.notification_private_key()?, &public_key)?;
+        Ok(public_key.tweak_add(&tweak)?)
+    }
+
+    /// Returns the address of the payment at the index sent to the recipient.
+    pub fn send_address(
+        &self,
+        recipient: &BitcoinPaymentCode<N>,
+        index: u32,
+        format: &BitcoinFormat,
+    ) -> Result<BitcoinAddress<N>, PaymentCodeError> {
+        Ok(self.send_public_key(recipient, index)?.to_address(format)?)
+    }
+
+    /// Returns the private key of the payment at the index received from the sender.
+    pub fn receive_private_key(
+        &self,
+        sender: &BitcoinPaymentCode<N>,
+        index: u32,
+    ) -> Result<BitcoinPrivateKey<N>, PaymentCodeError> {
+        let private_key = self.derive_private_key(index)?;
+        let tweak = payment_tweak(&private_key, &sender.derive_public_key(0)?)?;
+        Ok(private_key.tweak_add(&tweak)?)
+    }
+
+    /// Returns the address of the payment at the index received from the sender.
+    pub fn receive_address(
+        &self,
+        sender: &BitcoinPaymentCode<N>,
+        index: u32,
+        format: &BitcoinFormat,
+    ) -> Result<BitcoinAddress<N>, PaymentCodeError> {
+        Ok(self
+            .receive_private_key(sender, index)?
+            .to_public_key()
+            .to_address(format)?)
+    }
+
+    /// Returns the payment code blinded for the recipient, given the private key and outpoint
+    /// of the designated input, the first input of the notification transaction that exposes
+    /// a public key.
+    pub fn notification_payload(
+        &self,
+        recipient: &BitcoinPaymentCode<N>,
+        designated_private_key: &BitcoinPrivateKey<N>,
+        designated_outpoint: &Outpoint<N>,
+    ) -> Result<[u8; 80], PaymentCodeError> {
+        let shared_secret = Zeroizing::new(designated_private_key.shared_secret(
+            &recipient.derive_public_key(0)?,
+            &BitcoinSharedSecretFormat::X,
+        )?);
+
+        let mut payload = self.to_payment_code().to_bytes();
+        blind(
+            &mut payload,
+            &shared_secret,
+            &serialize_outpoint(designated_outpoint),
+        );
+        Ok(payload)
+    }
+
+    /// Returns the outputs of a notification transaction to the recipient, which pay the amount
+    /// to its notification address and carry the blinded payment code in an OP_RETURN output.
+    pub fn notification_outputs(
+        &self,
+        recipient: &BitcoinPaymentCode<N>,
+        designated_private_key: &BitcoinPrivateKey<N>,
+        designated_outpoint: &Outpoint<N>,
+        amount: BitcoinAmount,
+    ) -> Result<Vec<BitcoinTransactionOutput>, PaymentCodeError> {
+        let payload =
+            self.notification_payload(recipient, designated_private_key, designated_outpoint)?;
+
+        // OP_RETURN OP_PUSHDATA1 80 <payload>
+        let mut script_pub_key = vec![0x6a, 0x4c, 0x50];
+        script_pub_key.extend(&payload[..]);
+
+        Ok(vec![
+            BitcoinTransactionOutput::new(&recipient.notification_address()?, amount)?,
+            BitcoinTransactionOutput {
+                amount: BitcoinAmount::from_satoshi(0)?,
+                script_pub_key,
+            },
+        ])
+    }
+
+    /// Returns the payment code of the sender of a notification transaction to this payment code.
+    pub fn read_notification_transaction(
+        &self,
+        transaction: &BitcoinTransaction<N>,
+    ) -> Result<BitcoinPaymentCode<N>, PaymentCodeError> {
+        let parameters = transaction.parameters();
+        let mut payload = [0u8; 80];
+        match parameters
+            .outputs
+            .iter()
+            .find(|output| match output.script_pub_key.as_slice() {
+                [0x6a, 0x4c, 0x50, payload @ ..] => {
+                    payload.len() == 80 && payload[0] == PAYMENT_CODE_VERSION
+                }
+                _ => false,
+            }) {
+            Some(output) => payload.copy_from_slice(&output.script_pub_key[3..]),
+            None => return Err(PaymentCodeError::MissingNotificationPayload),
+        };
+
+        let (public_key, outpoint) = parameters
+            .inputs
+            .iter()
+            .find_map(|input| {
+                let witness = input.witnesses.last().and_then(|item| {
+                    let mut position = 0;
+                    read_compact_size(item, &mut position)?;
+                    item.get(position..)
+                });
+                let public_key = read_pushes(&input.script_sig)
+                    .and_then(|pushes| pushes.last().cloned())
+                    .and_then(|push| parse_public_key::<N>(&push))
+                    .or_else(|| witness.and_then(parse_public_key))?;
+                Some((public_key, &input.outpoint))
+            })
+            .ok_or(PaymentCodeError::MissingDesignatedInput)?;
+
+        let shared_secret = Zeroizing::new(
+            self.notification_private_key()?
+                .shared_secret(&public_key, &BitcoinSharedSecretFormat::X)?,
+        );
+        blind(&mut payload, &shared_secret, &serialize_outpoint(outpoint));
+        BitcoinPaymentCode::from_bytes(&payload)
+    }
+}
+
+/// Returns the tweak SHA256(S.x) of the shared secret S of a payment.
+fn payment_tweak<N: BitcoinNetwork>(
+    private_key: &BitcoinPrivateKey<N>,
+    public_key: &BitcoinPublicKey<N>,
+) -> Result<[u8; 32], PaymentCodeError> {
+    let shared_secret =
+        Zeroizing::new(private_key.shared_secret(public_key, &BitcoinSharedSecretFormat::X)?);
+    let mut tweak = [0u8; 32];
+    tweak.copy_from_slice(&Sha256::digest(&shared_secret[..]));
+    Ok(tweak)
+}
+
+/// Blinds or unblinds the public key x coordinate and chain code of the payment code with
+/// HMAC-SHA512(outpoint, S.x).
+fn blind(payload: &mut [u8; 80], shared_secret: &[u8; 32], outpoint: &[u8]) {
+    let mut mac = HmacSha512::new_varkey(outpoint).expect("HMAC accepts any key length");
+    mac.input(shared_secret);
+    let mask = Zeroizing::new(mac.result().code().to_vec());
+    for (byte, mask_byte) in payload[3..67].iter_mut().zip(mask.iter()) {
+        *byte ^= mask_byte;
+    }
+}
+
+/// Returns the 36 byte serialization of the outpoint.
+fn serialize_outpoint<N: BitcoinNetwork>(outpoint: &Outpoint<N>) -> Vec<u8> {
+    let mut serialized = outpoint.reverse_transaction_id.clone();
+    serialized.extend(&outpoint.index.to_le_bytes());
+    serialized
+}
+
+/// Returns the public key of a compressed or uncompressed serialization.
+fn parse_public_key<N: BitcoinNetwork>(bytes: &[u8]) -> Option<BitcoinPublicKey<N>> {
+    BitcoinPublicKey::from_slice(bytes, &PublicKeyPolicy::P2PKH).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+
+    type N = Mainnet;
+
+    const ALICE_SEED: &str = "64dca76abc9c6f0cf3d212d248c380c4622c8f93b2c425ec6a5567fd5db57e10d3e6f94a2f6af4ac2edb8998072aad92098db73558c323777abf5bd1082d970a";
+    const ALICE_PAYMENT_CODE: &str = "PM8TJTLJbPRGxSbc8EJi42Wrr6QbNSaSSVJ5Y3E4pbCYiTHUskHg13935Ubb7q8tx9GVbh2UuRnBc3WSyJHhUrw8KhprKnn9eDznYGieTzFcwQRya4GA";
+    const BOB_SEED: &str = "87eaaac5a539ab028df44d9110defbef3797ddb805ca309f61a69ff96dbaa7ab5b24038cf029edec5235d933110f0aea8aeecf939ed14fc20730bba71e4b1110";
+    const BOB_PAYMENT_CODE: &str = "PM8TJS2JxQ5ztXUpBBRnpTbcUXbUHy2T1abfrb3KkAAtMEGNbey4oumH7Hc578WgQJhPjBxteQ5GHHToTYHE3A1w6p7tU6KSoFmWBVbFGjKPisZDbP97";
+    const PAYLOAD: &str = "010002063e4eb95e62791b06c50e1a3a942e1ecaaa9afbbeb324d16ae6821e091611fa96c0cf048f607fe51a0327f5e2528979311c78cb2de0d682c61e1180fc3d543b00000000000000000000000000";
+
+    fn private_payment_code(seed: &str) -> BitcoinPrivatePaymentCode<N> {
+        let master = BitcoinExtendedPrivateKey::<N>::new_master(
+            &hex::decode(seed).unwrap(),
+            &BitcoinFormat::P2PKH,
+        )
+        .unwrap();
+        BitcoinPrivatePaymentCode::from_extended_private_key(&master, 0).unwrap()
+    }
+
+    #[test]
+    fn payment_codes() {
+        let alice = private_payment_code(ALICE_SEED).to_payment_code();
+        let bob = private_payment_code(BOB_SEED).to_payment_code();
+        assert_eq!(ALICE_PAYMENT_CODE, alice.to_string());
+        assert_eq!(BOB_PAYMENT_CODE, bob.to_string());
+        assert_eq!(bob, BitcoinPaymentCode::from_str(BOB_PAYMENT_CODE).unwrap());
+
+        assert_eq!(
+            "1JDdmqFLhpzcUwPeinhJbUPw4Co3aWLyzW",
+            alice.notification_address().unwrap().to_string()
+        );
+        assert_eq!(
+            "1ChvUUvht2hUQufHBXF8NgLhW8SwE2ecGV",
+            bob.notification_address().unwrap().to_string()
+        );
+        assert_eq!(
+            "029b5f290ef2f98a0462ec691f5cc3ae939325f7577fcaf06cfc3b8fc249402156",
+            bob.derive_public_key(2).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn payment_addresses() {
+        let alice = private_payment_code(ALICE_SEED);
+        let bob = private_payment_code(BOB_SEED);
+        let addresses = [
+            "141fi7TY3h936vRUKh1qfUZr8rSBuYbVBK",
+            "12u3Uued2fuko2nY4SoSFGCoGLCBUGPkk6",
+            "1FsBVhT5dQutGwaPePTYMe5qvYqqjxyftc",
+        ];
+        for (index, address) in addresses.iter().enumerate() {
+            let index = index as u32;
+            assert_eq!(
+                *address,
+                alice
+                    .send_address(&bob.to_payment_code(), index, &BitcoinFormat::P2PKH)
+                    .unwrap()
+                    .to_string()
+            );
+            assert_eq!(
+                *address,
+                bob.receive_address(&alice.to_payment_code(), index, &BitcoinFormat::P2PKH)
+                    .unwrap()
+                    .to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn notification_transaction() {
+        let alice = private_payment_code(ALICE_SEED);
+        let bob = private_payment_code(BOB_SEED);
+        let designated_private_key = BitcoinPrivateKey::<N>::from_str(
+            "Kx983SRhAZpAhj7Aac1wUXMJ6XZeyJKqCxJJ49dxEbYCT4a1ozRD",
+        )
+        .unwrap();
+        let transaction_id =
+            hex::decode("86f411ab1c8e70ae8a0795ab7a6757aea6e4d5ae1826fc7b8f00c597d500609c")
+                .unwrap();
+        let outpoint = Outpoint::<N>::new(transaction_id, 1, None, None, None, None).unwrap();
+
+        let payload = alice
+            .notification_payload(&bob.to_payment_code(), &designated_private_key, &outpoint)
+            .unwrap();
+        assert_eq!(PAYLOAD, hex::encode(&payload[..]));
+
+        let outputs = alice
+            .notification_outputs(
+                &bob.to_payment_code(),
+                &designated_private_key,
+                &outpoint,
+                BitcoinAmount::from_satoshi(10000).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            "76a9148066a8e7ee82e5c5b9b7dc1765038340dc5420a988ac",
+            hex::encode(&outputs[0].script_pub_key)
+        );
+        assert_eq!(
+            format!("6a4c50{}", PAYLOAD),
+            hex::encode(&outputs[1].script_pub_key)
+        );
+
+        let transaction = BitcoinTransaction::<N>::from_str("010000000186f411ab1c8e70ae8a0795ab7a6757aea6e4d5ae1826fc7b8f00c597d500609c010000006b483045022100ac8c6dbc482c79e86c18928a8b364923c774bfdbd852059f6b3778f2319b59a7022029d7cc5724e2f41ab1fcfc0ba5a0d4f57ca76f72f19530ba97c860c70a6bf0a801210272d83d8a1fa323feab1c085157a0791b46eba34afb8bfbfaeb3a3fcc3f2c9ad8ffffffff0210270000000000001976a9148066a8e7ee82e5c5b9b7dc1765038340dc5420a988ac1027000000000000536a4c50010002063e4eb95e62791b06c50e1a3a942e1ecaaa9afbbeb324d16ae6821e091611fa96c0cf048f607fe51a0327f5e2528979311c78cb2de0d682c61e1180fc3d543b0000000000000000000000000000000000").unwrap();
+        assert_eq!(
+            alice.to_payment_code(),
+            bob.read_notification_transaction(&transaction).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_payment_code() {
+        let mut invalid = BOB_PAYMENT_CODE.to_string();
+        invalid.pop();
+        invalid.push('8');
+        assert!(BitcoinPaymentCode::<N>::from_str(&invalid).is_err());
+        assert!(BitcoinPaymentCode::<N>::from_bytes(&[0u8; 80]).is_err());
+    }
+}
@@ -11,12 +11,18 @@ use gyu_model::{
 
 use crate::{
     address::BitcoinAddress, format::BitcoinFormat, network::BitcoinNetwork,
-    public_key::BitcoinPublicKey,
+    public_key::BitcoinPublicKey, schnorr, signature::sign,
 };
 
 use rand::Rng;
+use secp256k1::curve::Scalar;
+use sha2::digest::generic_array::{typenum::U33, GenericArray};
+use sha2::digest::{FixedOutput, Input, Reset};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The secret key is cleared on drop by `secp256k1::SecretKey`.
+#[derive(Clone, PartialEq, Eq)]
 pub struct BitcoinPrivateKey<N: BitcoinNetwork> {
     secret_key: secp256k1::SecretKey,
     compressed: bool,
@@ -58,16 +64,192 @@ impl<N: BitcoinNetwork> BitcoinPrivateKey<N> {
         self.secret_key.clone()
     }
 
+    /// Returns a reference to the secret key, for callers that must handle the raw secret.
+    pub fn expose_secret(&self) -> &secp256k1::SecretKey {
+        &self.secret_key
+    }
+
     pub fn is_compressed(&self) -> bool {
         self.compressed
     }
+
+    /// Returns the private key negated if its public key has an odd y coordinate,
+    /// so that it corresponds to the x-only (BIP340) public key.
+    pub fn to_even_y(&self) -> Result<Self, PrivateKeyError> {
+        Ok(Self::from_secp256k1_secret_key(
+            &schnorr::to_even_y_secret_key(&self.secret_key)?,
+            self.compressed,
+        ))
+    }
+
+    /// Returns the x-only (BIP340) serialization of the public key.
+    pub fn to_x_only_public_key(&self) -> [u8; 32] {
+        schnorr::to_x_only(&secp256k1::PublicKey::from_secret_key(&self.secret_key))
+    }
+
+    /// Returns the private key d + tweak modulo the curve order.
+    pub fn tweak_add(&self, tweak: &[u8; 32]) -> Result<Self, PrivateKeyError> {
+        let mut secret_key = self.secret_key.clone();
+        secret_key.tweak_add_assign(&secp256k1::SecretKey::parse(tweak)?)?;
+        Ok(Self::from_secp256k1_secret_key(
+            &secret_key,
+            self.compressed,
+        ))
+    }
+
+    /// Returns the private key d * tweak modulo the curve order.
+    pub fn tweak_mul(&self, tweak: &[u8; 32]) -> Result<Self, PrivateKeyError> {
+        let mut secret_key = self.secret_key.clone();
+        secret_key.tweak_mul_assign(&secp256k1::SecretKey::parse(tweak)?)?;
+        Ok(Self::from_secp256k1_secret_key(
+            &secret_key,
+            self.compressed,
+        ))
+    }
+
+    /// Returns the negated private key -d modulo the curve order.
+    pub fn negate(&self) -> Result<Self, PrivateKeyError> {
+        let scalar: Scalar = self.secret_key.clone().into();
+        let secret_key = secp256k1::SecretKey::parse(&Zeroizing::new((-scalar).b32()))?;
+        Ok(Self::from_secp256k1_secret_key(
+            &secret_key,
+            self.compressed,
+        ))
+    }
+
+    /// Returns the 65 byte recoverable signature r || s || recovery id of the message digest.
+    pub fn sign_recoverable(&self, digest: &[u8; 32]) -> [u8; 65] {
+        let (signature, recovery_id) =
+            sign(&secp256k1::Message::parse(digest), &self.secret_key, None);
+
+        let mut recoverable = [0u8; 65];
+        recoverable[..64].copy_from_slice(&signature.serialize());
+        recoverable[64] = recovery_id.serialize();
+        recoverable
+    }
+
+    /// Returns the ECDH shared secret of the private key and the public key.
+    pub fn shared_secret(
+        &self,
+        public_key: &BitcoinPublicKey<N>,
+        format: &BitcoinSharedSecretFormat,
+    ) -> Result<[u8; 32], PrivateKeyError> {
+        let point = secp256k1::SharedSecret::<SharedPoint>::new(
+            &public_key.to_secp256k1_public_key(),
+            &self.secret_key,
+        )?;
+
+        let mut shared_secret = [0u8; 32];
+        match format {
+            BitcoinSharedSecretFormat::Sha256 => {
+                shared_secret.copy_from_slice(&Sha256::digest(point.as_ref()))
+            }
+            BitcoinSharedSecretFormat::X => shared_secret.copy_from_slice(&point.as_ref()[1..]),
+        };
+        Ok(shared_secret)
+    }
+
+    /// Returns the ECDH shared point d * P of the private key and the public key, as a
+    /// compressed public key. Unlike `BitcoinPublicKey::tweak_mul`, the multiplication
+    /// runs in constant time.
+    pub fn shared_point(
+        &self,
+        public_key: &BitcoinPublicKey<N>,
+    ) -> Result<BitcoinPublicKey<N>, PrivateKeyError> {
+        let point = secp256k1::SharedSecret::<SharedPoint>::new(
+            &public_key.to_secp256k1_public_key(),
+            &self.secret_key,
+        )?;
+        let mut compressed = Zeroizing::new([0u8; 33]);
+        compressed.copy_from_slice(point.as_ref());
+        Ok(BitcoinPublicKey::from_secp256k1_public_key(
+            secp256k1::PublicKey::parse_compressed(&compressed)?,
+            true,
+        ))
+    }
+}
+
+/// A digest that returns its input, the compressed point that the constant time ECDH
+/// of libsecp256k1 hashes, unchanged.
+#[derive(Clone)]
+struct SharedPoint {
+    point: [u8; 33],
+    length: usize,
+}
+
+impl Default for SharedPoint {
+    fn default() -> Self {
+        Self {
+            point: [0u8; 33],
+            length: 0,
+        }
+    }
+}
+
+impl Input for SharedPoint {
+    fn input<B: AsRef<[u8]>>(&mut self, data: B) {
+        for byte in data.as_ref() {
+            if self.length < self.point.len() {
+                self.point[self.length] = *byte;
+                self.length += 1;
+            }
+        }
+    }
+}
+
+impl FixedOutput for SharedPoint {
+    type OutputSize = U33;
+
+    fn fixed_result(self) -> GenericArray<u8, U33> {
+        GenericArray::clone_from_slice(&self.point)
+    }
+}
+
+impl Reset for SharedPoint {
+    fn reset(&mut self) {
+        self.point.zeroize();
+        self.length = 0;
+    }
+}
+
+impl Drop for SharedPoint {
+    fn drop(&mut self) {
+        self.point.zeroize();
+    }
+}
+
+/// Represents the output format of an ECDH shared secret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitcoinSharedSecretFormat {
+    /// SHA256 of the compressed shared point, as in libsecp256k1
+    Sha256,
+    /// The x coordinate of the shared point
+    X,
+}
+
+impl Display for BitcoinSharedSecretFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BitcoinSharedSecretFormat::Sha256 => write!(f, "sha256"),
+            BitcoinSharedSecretFormat::X => write!(f, "x"),
+        }
+    }
+}
+
+impl<N: BitcoinNetwork> fmt::Debug for BitcoinPrivateKey<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BitcoinPrivateKey")
+            .field("secret_key", &format_args!("[REDACTED]"))
+            .field("compressed", &self.compressed)
+            .finish()
+    }
 }
 
 impl<N: BitcoinNetwork> FromStr for BitcoinPrivateKey<N> {
     type Err = PrivateKeyError;
 
     fn from_str(wif: &str) -> Result<Self, Self::Err> {
-        let data = wif.from_base58()?;
+        let data = Zeroizing::new(wif.from_base58()?);
         let len = data.len();
         if len != 37 && len != 38 {
             return Err(PrivateKeyError::InvalidByteLength(len));
@@ -92,11 +274,11 @@ impl<N: BitcoinNetwork> FromStr for BitcoinPrivateKey<N> {
 
 impl<N: BitcoinNetwork> Display for BitcoinPrivateKey<N> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut wif = [0u8; 38];
+        let mut wif = Zeroizing::new([0u8; 38]);
         wif[0] = N::to_private_key_prefix();
         wif[1..33].copy_from_slice(&self.secret_key.serialize());
 
-        let output = if self.compressed {
+        let output = Zeroizing::new(if self.compressed {
             wif[33] = 0x01;
             let sum = &checksum(&wif[0..34])[0..4];
             wif[34..].copy_from_slice(sum);
@@ -105,7 +287,159 @@ impl<N: BitcoinNetwork> Display for BitcoinPrivateKey<N> {
             let sum = &checksum(&wif[0..33])[0..4];
             wif[33..37].copy_from_slice(sum);
             wif[..37].to_base58()
-        };
-        write!(f, "{}", output)
+        });
+        write!(f, "{}", *output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+
+    fn private_key(byte: u8) -> BitcoinPrivateKey<Mainnet> {
+        BitcoinPrivateKey::from_secp256k1_secret_key(
+            &secp256k1::SecretKey::parse(&[byte; 32]).unwrap(),
+            true,
+        )
+    }
+
+    #[test]
+    fn shared_secret() {
+        for i in 1..20 {
+            let (alice, bob) = (private_key(i), private_key(i + 40));
+            let (alice_public_key, bob_public_key) = (alice.to_public_key(), bob.to_public_key());
+            for format in [
+                BitcoinSharedSecretFormat::Sha256,
+                BitcoinSharedSecretFormat::X,
+            ]
+            .iter()
+            {
+                assert_eq!(
+                    alice.shared_secret(&bob_public_key, format).unwrap(),
+                    bob.shared_secret(&alice_public_key, format).unwrap()
+                );
+            }
+
+            let expected = secp256k1::SharedSecret::<Sha256>::new(
+                &bob_public_key.to_secp256k1_public_key(),
+                alice.expose_secret(),
+            )
+            .unwrap();
+            assert_eq!(
+                expected.as_ref(),
+                &alice
+                    .shared_secret(&bob_public_key, &BitcoinSharedSecretFormat::Sha256)
+                    .unwrap()[..]
+            );
+
+            let shared_point = alice.shared_point(&bob_public_key).unwrap();
+            assert_eq!(
+                bob_public_key
+                    .tweak_mul(&alice.expose_secret().serialize())
+                    .unwrap(),
+                shared_point
+            );
+            assert_eq!(
+                shared_point.to_x_only(),
+                alice
+                    .shared_secret(&bob_public_key, &BitcoinSharedSecretFormat::X)
+                    .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn shared_secret_with_one() {
+        let one = BitcoinPrivateKey::<Mainnet>::from_str(
+            "KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn",
+        )
+        .unwrap();
+        let public_key = private_key(7).to_public_key();
+        assert_eq!(public_key, one.shared_point(&public_key).unwrap());
+        assert_eq!(
+            public_key.to_x_only(),
+            one.shared_secret(&public_key, &BitcoinSharedSecretFormat::X)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn tweaks() {
+        for i in 1..20 {
+            let private_key = private_key(i);
+            let public_key = private_key.to_public_key();
+            let tweak = [i + 3; 32];
+            assert_eq!(
+                public_key.tweak_add(&tweak).unwrap(),
+                private_key.tweak_add(&tweak).unwrap().to_public_key()
+            );
+            assert_eq!(
+                public_key.tweak_mul(&tweak).unwrap(),
+                private_key.tweak_mul(&tweak).unwrap().to_public_key()
+            );
+            assert_eq!(
+                public_key.negate(),
+                private_key.negate().unwrap().to_public_key()
+            );
+
+            let even = private_key.to_even_y().unwrap();
+            assert!(even.to_public_key().has_even_y());
+            assert_eq!(
+                private_key.to_x_only_public_key(),
+                even.to_public_key().to_x_only()
+            );
+            assert_eq!(
+                even.to_public_key(),
+                BitcoinPublicKey::from_x_only(&public_key.to_x_only()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn recoverable_signature() {
+        for i in 1..20 {
+            let private_key = private_key(i);
+            let public_key = private_key.to_public_key();
+            let digest = [i + 11; 32];
+            let mut signature = private_key.sign_recoverable(&digest);
+            assert!(signature[64] < 4);
+            assert!(signature[32] < 0x80);
+            assert_eq!(
+                public_key,
+                BitcoinPublicKey::recover(&digest, &signature).unwrap()
+            );
+
+            signature[64] += 27;
+            assert_eq!(
+                public_key,
+                BitcoinPublicKey::recover(&digest, &signature).unwrap()
+            );
+            assert_ne!(
+                Some(public_key),
+                BitcoinPublicKey::recover(&[0x43; 32], &signature).ok()
+            );
+            signature[64] = 4;
+            assert!(BitcoinPublicKey::<Mainnet>::recover(&digest, &signature).is_err());
+        }
+    }
+
+    #[test]
+    fn redacted_debug() {
+        let wif = "KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn";
+        let private_key = BitcoinPrivateKey::<Mainnet>::from_str(wif).unwrap();
+        let secret_key = hex::encode(private_key.expose_secret().serialize());
+        assert_eq!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            secret_key
+        );
+
+        let debug = format!("{:?}", private_key);
+        assert_eq!(
+            "BitcoinPrivateKey { secret_key: [REDACTED], compressed: true }",
+            debug
+        );
+        assert!(!debug.contains(wif));
+        assert_eq!(wif, private_key.to_string());
     }
 }
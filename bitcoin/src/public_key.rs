@@ -1,16 +1,109 @@
 use core::fmt;
+use gyu_model::no_std::*;
 use gyu_model::{
     address::{Address, AddressError},
     public_key::{PublicKey, PublicKeyError},
 };
 
 use core::{fmt::Display, marker::PhantomData, str::FromStr};
+use secp256k1::curve::Jacobian;
 
 use crate::{
     address::BitcoinAddress, format::BitcoinFormat, network::BitcoinNetwork,
-    private_key::BitcoinPrivateKey,
+    private_key::BitcoinPrivateKey, schnorr,
 };
 
+/// Represents the encoding of a serialized public key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PublicKeyEncoding {
+    /// 33 bytes, prefixed with 0x02 or 0x03 for the parity of y
+    Compressed,
+    /// 65 bytes, prefixed with 0x04
+    Uncompressed,
+    /// 65 bytes, prefixed with 0x06 or 0x07 for the parity of y
+    Hybrid,
+}
+
+impl PublicKeyEncoding {
+    /// Returns the encoding of the serialized public key, from its prefix and length.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PublicKeyError> {
+        match (bytes.len(), bytes.first()) {
+            (33, Some(0x02 | 0x03)) => Ok(PublicKeyEncoding::Compressed),
+            (65, Some(0x04)) => Ok(PublicKeyEncoding::Uncompressed),
+            (65, Some(0x06 | 0x07)) => Ok(PublicKeyEncoding::Hybrid),
+            (33 | 65, Some(prefix)) => {
+                Err(PublicKeyError::InvalidPrefix(format!("{:02x}", prefix)))
+            }
+            (length, _) => Err(PublicKeyError::InvalidByteLength(length)),
+        }
+    }
+}
+
+impl Display for PublicKeyEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PublicKeyEncoding::Compressed => write!(f, "compressed"),
+            PublicKeyEncoding::Uncompressed => write!(f, "uncompressed"),
+            PublicKeyEncoding::Hybrid => write!(f, "hybrid"),
+        }
+    }
+}
+
+/// Represents the public key encodings accepted where a public key is parsed or used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicKeyPolicy {
+    pub compressed: bool,
+    pub uncompressed: bool,
+    pub hybrid: bool,
+}
+
+impl PublicKeyPolicy {
+    /// Accepts compressed keys only, as segwit outputs and scripts require.
+    pub const SEGWIT: Self = Self {
+        compressed: true,
+        uncompressed: false,
+        hybrid: false,
+    };
+
+    /// Accepts compressed and uncompressed keys, as standard P2PKH outputs do.
+    pub const P2PKH: Self = Self {
+        compressed: true,
+        uncompressed: true,
+        hybrid: false,
+    };
+
+    /// Accepts every encoding valid under consensus, including hybrid keys.
+    pub const ANY: Self = Self {
+        compressed: true,
+        uncompressed: true,
+        hybrid: true,
+    };
+
+    /// Returns the policy for public keys of addresses in the given format.
+    pub fn for_format(format: &BitcoinFormat) -> Self {
+        match format {
+            BitcoinFormat::P2SH_P2WPKH
+            | BitcoinFormat::P2SH_P2WSH
+            | BitcoinFormat::Bech32
+            | BitcoinFormat::P2WSH => Self::SEGWIT,
+            BitcoinFormat::P2PKH | BitcoinFormat::P2SH | BitcoinFormat::P2TR => Self::P2PKH,
+        }
+    }
+
+    /// Returns an error unless the policy accepts the encoding.
+    pub fn check(&self, encoding: PublicKeyEncoding) -> Result<(), PublicKeyError> {
+        let allowed = match encoding {
+            PublicKeyEncoding::Compressed => self.compressed,
+            PublicKeyEncoding::Uncompressed => self.uncompressed,
+            PublicKeyEncoding::Hybrid => self.hybrid,
+        };
+        match allowed {
+            true => Ok(()),
+            false => Err(PublicKeyError::DisallowedEncoding(encoding.to_string())),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BitcoinPublicKey<N: BitcoinNetwork> {
     public_key: secp256k1::PublicKey,
@@ -54,16 +147,117 @@ impl<N: BitcoinNetwork> BitcoinPublicKey<N> {
     pub fn is_compressed(&self) -> bool {
         self.compressed
     }
+
+    /// Returns the public key of the serialization, if the policy accepts its encoding and
+    /// it is a point on the curve. Hybrid keys are reserialized as uncompressed keys.
+    pub fn from_slice(bytes: &[u8], policy: &PublicKeyPolicy) -> Result<Self, PublicKeyError> {
+        let encoding = PublicKeyEncoding::from_bytes(bytes)?;
+        policy.check(encoding)?;
+
+        let public_key = secp256k1::PublicKey::parse_slice(bytes, None)
+            .map_err(|_| PublicKeyError::InvalidPoint(hex::encode(bytes)))?;
+        Ok(Self::from_secp256k1_public_key(
+            public_key,
+            encoding == PublicKeyEncoding::Compressed,
+        ))
+    }
+
+    /// Returns the public key of the hex serialization, if the policy accepts its encoding
+    /// and it is a point on the curve.
+    pub fn from_hex(public_key: &str, policy: &PublicKeyPolicy) -> Result<Self, PublicKeyError> {
+        Self::from_slice(&hex::decode(public_key)?, policy)
+    }
+
+    /// Returns an error unless the policy accepts the encoding of the public key.
+    pub fn check_policy(&self, policy: &PublicKeyPolicy) -> Result<(), PublicKeyError> {
+        policy.check(match self.compressed {
+            true => PublicKeyEncoding::Compressed,
+            false => PublicKeyEncoding::Uncompressed,
+        })
+    }
+
+    /// Returns the public key with an even y coordinate for the x-only (BIP340) public key.
+    pub fn from_x_only(x_only: &[u8; 32]) -> Result<Self, PublicKeyError> {
+        Ok(Self::from_secp256k1_public_key(
+            schnorr::lift_x(x_only)?,
+            true,
+        ))
+    }
+
+    /// Returns the compressed public key of the point, or None for the point at infinity.
+    pub(crate) fn from_point(point: &Jacobian) -> Option<Self> {
+        let public_key =
+            secp256k1::PublicKey::parse_compressed(&schnorr::serialize_point(point)?).ok()?;
+        Some(Self::from_secp256k1_public_key(public_key, true))
+    }
+
+    /// Returns the point of the public key.
+    pub(crate) fn to_point(&self) -> Jacobian {
+        Jacobian::from_ge(&schnorr::to_affine(&self.public_key))
+    }
+
+    /// Returns the public key of the 65 byte recoverable signature r || s || recovery id of the
+    /// message digest. Recovery ids offset by 27, as in Ethereum, are also accepted.
+    pub fn recover(digest: &[u8; 32], signature: &[u8; 65]) -> Result<Self, PublicKeyError> {
+        let recovery_id = match signature[64] {
+            v @ 27..=30 => v - 27,
+            v => v,
+        };
+        let public_key = secp256k1::recover(
+            &secp256k1::Message::parse(digest),
+            &secp256k1::Signature::parse_slice(&signature[..64])?,
+            &secp256k1::RecoveryId::parse(recovery_id)?,
+        )?;
+        Ok(Self::from_secp256k1_public_key(public_key, true))
+    }
+
+    /// Returns the x-only (BIP340) serialization of the public key.
+    pub fn to_x_only(&self) -> [u8; 32] {
+        schnorr::to_x_only(&self.public_key)
+    }
+
+    /// Returns true if the y coordinate of the public key is even.
+    pub fn has_even_y(&self) -> bool {
+        schnorr::has_even_y(&self.public_key)
+    }
+
+    /// Returns the public key P + tweak * G.
+    pub fn tweak_add(&self, tweak: &[u8; 32]) -> Result<Self, PublicKeyError> {
+        let mut public_key = self.public_key.clone();
+        public_key.tweak_add_assign(&secp256k1::SecretKey::parse(tweak)?)?;
+        Ok(Self::from_secp256k1_public_key(public_key, self.compressed))
+    }
+
+    /// Returns the public key tweak * P. The multiplication is not constant time, so the
+    /// tweak must be public; use `BitcoinPrivateKey::shared_point` for secret scalars.
+    pub fn tweak_mul(&self, tweak: &[u8; 32]) -> Result<Self, PublicKeyError> {
+        let mut public_key = self.public_key.clone();
+        public_key.tweak_mul_assign(&secp256k1::SecretKey::parse(tweak)?)?;
+        Ok(Self::from_secp256k1_public_key(public_key, self.compressed))
+    }
+
+    /// Returns the sum of the public keys P + Q.
+    pub fn combine(&self, other: &Self) -> Result<Self, PublicKeyError> {
+        let public_key =
+            secp256k1::PublicKey::combine(&[self.public_key.clone(), other.public_key.clone()])?;
+        Ok(Self::from_secp256k1_public_key(public_key, self.compressed))
+    }
+
+    /// Returns the negated public key -P.
+    pub fn negate(&self) -> Self {
+        let mut compressed = self.public_key.serialize_compressed();
+        compressed[0] ^= 0x01;
+        match secp256k1::PublicKey::parse_compressed(&compressed) {
+            Ok(public_key) => Self::from_secp256k1_public_key(public_key, self.compressed),
+            Err(_) => unreachable!("the negation of a valid point is a valid point"),
+        }
+    }
 }
 
 impl<N: BitcoinNetwork> FromStr for BitcoinPublicKey<N> {
     type Err = PublicKeyError;
     fn from_str(public_key: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            public_key: secp256k1::PublicKey::parse_slice(&hex::decode(public_key)?, None)?,
-            compressed: public_key.len() == 66,
-            _network: PhantomData,
-        })
+        Self::from_hex(public_key, &PublicKeyPolicy::P2PKH)
     }
 }
 
@@ -81,3 +275,140 @@ impl<N: BitcoinNetwork> Display for BitcoinPublicKey<N> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+
+    type N = Mainnet;
+
+    const X: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    const Y: &str = "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+
+    fn generator(prefix: &str) -> String {
+        match prefix {
+            "02" | "03" => format!("{}{}", prefix, X),
+            _ => format!("{}{}{}", prefix, X, Y),
+        }
+    }
+
+    #[test]
+    fn encodings() {
+        for (prefix, encoding) in [
+            ("02", PublicKeyEncoding::Compressed),
+            ("04", PublicKeyEncoding::Uncompressed),
+            ("06", PublicKeyEncoding::Hybrid),
+        ]
+        .iter()
+        {
+            let bytes = hex::decode(generator(prefix)).unwrap();
+            assert_eq!(*encoding, PublicKeyEncoding::from_bytes(&bytes).unwrap());
+        }
+
+        let bytes = hex::decode(generator("05")).unwrap();
+        assert!(PublicKeyEncoding::from_bytes(&bytes).is_err());
+        let bytes = hex::decode(generator("04")).unwrap();
+        assert!(PublicKeyEncoding::from_bytes(&bytes[1..]).is_err());
+        assert!(PublicKeyEncoding::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn policies() {
+        let compressed = generator("02");
+        let uncompressed = generator("04");
+        let hybrid = generator("06");
+        for (policy, accepted) in [
+            (PublicKeyPolicy::SEGWIT, [true, false, false]),
+            (PublicKeyPolicy::P2PKH, [true, true, false]),
+            (PublicKeyPolicy::ANY, [true, true, true]),
+        ]
+        .iter()
+        {
+            for (public_key, accepted) in [&compressed, &uncompressed, &hybrid]
+                .iter()
+                .zip(accepted.iter())
+            {
+                let result = BitcoinPublicKey::<N>::from_hex(public_key, policy);
+                assert_eq!(*accepted, result.is_ok());
+            }
+        }
+
+        // Hybrid keys are reserialized as uncompressed keys.
+        let public_key = BitcoinPublicKey::<N>::from_hex(&hybrid, &PublicKeyPolicy::ANY).unwrap();
+        assert_eq!(uncompressed, public_key.to_string());
+        assert!(public_key.check_policy(&PublicKeyPolicy::P2PKH).is_ok());
+        assert!(public_key.check_policy(&PublicKeyPolicy::SEGWIT).is_err());
+
+        assert_eq!(
+            PublicKeyPolicy::SEGWIT,
+            PublicKeyPolicy::for_format(&BitcoinFormat::Bech32)
+        );
+        assert_eq!(
+            PublicKeyPolicy::P2PKH,
+            PublicKeyPolicy::for_format(&BitcoinFormat::P2PKH)
+        );
+    }
+
+    #[test]
+    fn invalid_public_keys() {
+        // The hybrid prefix must match the parity of y.
+        let wrong_parity = generator("07");
+        assert!(BitcoinPublicKey::<N>::from_hex(&wrong_parity, &PublicKeyPolicy::ANY).is_err());
+
+        // x = 5 is not the x coordinate of a point on the curve.
+        let off_curve = format!("02{:064x}", 5);
+        match BitcoinPublicKey::<N>::from_hex(&off_curve, &PublicKeyPolicy::ANY) {
+            Err(PublicKeyError::InvalidPoint(_)) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+        let mut off_curve = generator("04");
+        off_curve.replace_range(128.., "b9");
+        assert!(BitcoinPublicKey::<N>::from_hex(&off_curve, &PublicKeyPolicy::ANY).is_err());
+
+        match BitcoinPublicKey::<N>::from_hex(&generator("04"), &PublicKeyPolicy::SEGWIT) {
+            Err(PublicKeyError::DisallowedEncoding(encoding)) => {
+                assert_eq!("uncompressed", encoding)
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(BitcoinPublicKey::<N>::from_str(X).is_err());
+        assert!(BitcoinPublicKey::<N>::from_str(&format!("{}{}", X, Y)).is_err());
+        assert!(BitcoinPublicKey::<N>::from_str(&generator("06")).is_err());
+        assert!(BitcoinPublicKey::<N>::from_str("02zz").is_err());
+    }
+
+    #[test]
+    fn from_str() {
+        for prefix in ["02", "04"].iter() {
+            let public_key = BitcoinPublicKey::<N>::from_str(&generator(prefix)).unwrap();
+            assert_eq!(*prefix == "02", public_key.is_compressed());
+            assert_eq!(generator(prefix), public_key.to_string());
+        }
+    }
+
+    #[test]
+    fn segwit_addresses_require_compressed_keys() {
+        let uncompressed = BitcoinPublicKey::<N>::from_str(&generator("04")).unwrap();
+        assert_eq!(
+            "1EHNa6Q4Jz2uvNExL497mE43ikXhwF6kZm",
+            uncompressed
+                .to_address(&BitcoinFormat::P2PKH)
+                .unwrap()
+                .to_string()
+        );
+        assert!(uncompressed.to_address(&BitcoinFormat::Bech32).is_err());
+        assert!(uncompressed
+            .to_address(&BitcoinFormat::P2SH_P2WPKH)
+            .is_err());
+
+        let compressed = BitcoinPublicKey::<N>::from_str(&generator("02")).unwrap();
+        assert_eq!(
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            compressed
+                .to_address(&BitcoinFormat::Bech32)
+                .unwrap()
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use secp256k1::curve::{Affine, Field, Jacobian, Scalar, ECMULT_CONTEXT};
+use secp256k1::{Error, PublicKey, PublicKeyFormat, SecretKey};
+use sha2::{Digest, Sha256};
+
+/// Returns the BIP340 tagged hash SHA256(SHA256(tag) || SHA256(tag) || data).
+pub fn tagged_hash(tag: &str, data: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+    let mut sha256 = Sha256::new();
+    sha256.input(tag_hash);
+    sha256.input(tag_hash);
+    for input in data {
+        sha256.input(input);
+    }
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&sha256.result());
+    hash
+}
+
+/// Returns the scalar of the 32 bytes reduced modulo the curve order.
+pub(crate) fn scalar_from_bytes(bytes: &[u8; 32]) -> Scalar {
+    let mut scalar = Scalar::default();
+    let _ = scalar.set_b32(bytes);
+    scalar
+}
+
+/// Returns the point of the public key in affine coordinates, normalized.
+pub(crate) fn to_affine(public_key: &PublicKey) -> Affine {
+    let mut point: Affine = public_key.clone().into();
+    point.x.normalize();
+    point.y.normalize();
+    point
+}
+
+/// Returns the point at infinity.
+pub(crate) fn infinity() -> Jacobian {
+    let mut point = Jacobian::default();
+    point.set_infinity();
+    point
+}
+
+/// Returns the point of the 33 byte compressed public key.
+pub(crate) fn parse_point(bytes: &[u8]) -> Result<Jacobian, Error> {
+    let point: Affine = PublicKey::parse_slice(bytes, Some(PublicKeyFormat::Compressed))?.into();
+    Ok(Jacobian::from_ge(&point))
+}
+
+/// Returns the compressed serialization of the point, or None for the point at infinity.
+pub(crate) fn serialize_point(point: &Jacobian) -> Option<[u8; 33]> {
+    if point.is_infinity() {
+        return None;
+    }
+    let mut point = Affine::from_gej(point);
+    point.x.normalize();
+    point.y.normalize();
+
+    let mut serialized = [0u8; 33];
+    serialized[0] = 0x02 | point.y.is_odd() as u8;
+    serialized[1..].copy_from_slice(&point.x.b32());
+    Some(serialized)
+}
+
+/// Returns the x-only (BIP340) serialization of the public key.
+pub fn to_x_only(public_key: &PublicKey) -> [u8; 32] {
+    let mut x_only = [0u8; 32];
+    x_only.copy_from_slice(&public_key.serialize_compressed()[1..]);
+    x_only
+}
+
+/// Returns true if the y coordinate of the public key is even.
+pub fn has_even_y(public_key: &PublicKey) -> bool {
+    public_key.serialize_compressed()[0] == 0x02
+}
+
+/// Returns the public key with the given x coordinate and an even y coordinate.
+pub fn lift_x(x_only: &[u8; 32]) -> Result<PublicKey, Error> {
+    let mut compressed = [0x02u8; 33];
+    compressed[1..].copy_from_slice(x_only);
+    PublicKey::parse_compressed(&compressed)
+}
+
+/// Returns the secret key negated if its public key has an odd y coordinate,
+/// so that it corresponds to the x-only public key.
+pub fn to_even_y_secret_key(secret_key: &SecretKey) -> Result<SecretKey, Error> {
+    match has_even_y(&PublicKey::from_secret_key(secret_key)) {
+        true => Ok(secret_key.clone()),
+        false => {
+            let scalar: Scalar = secret_key.clone().into();
+            SecretKey::parse(&(-scalar).b32())
+        }
+    }
+}
+
+/// Returns the BIP341 TapTweak of the x-only internal key, committing to the merkle root
+/// of the script tree if there is one.
+pub fn tap_tweak(internal_key: &[u8; 32], merkle_root: Option<&[u8; 32]>) -> [u8; 32] {
+    match merkle_root {
+        Some(merkle_root) => tagged_hash("TapTweak", &[internal_key, merkle_root]),
+        None => tagged_hash("TapTweak", &[internal_key]),
+    }
+}
+
+/// Returns the x-only output key of the x-only internal key tweaked as in BIP341, and
+/// whether the output key has an odd y coordinate.
+pub fn tweak_public_key(
+    internal_key: &[u8; 32],
+    merkle_root: Option<&[u8; 32]>,
+) -> Result<([u8; 32], bool), Error> {
+    let tweak = SecretKey::parse(&tap_tweak(internal_key, merkle_root))?;
+    let mut output_key = lift_x(internal_key)?;
+    output_key.tweak_add_assign(&tweak)?;
+    Ok((to_x_only(&output_key), !has_even_y(&output_key)))
+}
+
+/// Returns the secret key of the output key for the internal secret key tweaked as in BIP341.
+pub fn tweak_secret_key(
+    secret_key: &SecretKey,
+    merkle_root: Option<&[u8; 32]>,
+) -> Result<SecretKey, Error> {
+    let internal_key = to_x_only(&PublicKey::from_secret_key(secret_key));
+    let tweak = SecretKey::parse(&tap_tweak(&internal_key, merkle_root))?;
+    let mut output_secret_key = to_even_y_secret_key(secret_key)?;
+    output_secret_key.tweak_add_assign(&tweak)?;
+    Ok(output_secret_key)
+}
+
+/// Returns the BIP340 signature of the 32 byte message with the auxiliary randomness.
+pub fn sign(
+    message: &[u8; 32],
+    secret_key: &SecretKey,
+    aux_rand: &[u8; 32],
+) -> Result<[u8; 64], Error> {
+    let public_key = PublicKey::from_secret_key(secret_key);
+    let secret_key = to_even_y_secret_key(secret_key)?;
+    let public_key_x = to_x_only(&public_key);
+
+    let mut t = tagged_hash("BIP0340/aux", &[aux_rand]);
+    for (byte, secret_byte) in t.iter_mut().zip(secret_key.serialize().iter()) {
+        *byte ^= secret_byte;
+    }
+    let rand = tagged_hash("BIP0340/nonce", &[&t, &public_key_x, message]);
+    let nonce = SecretKey::parse(&scalar_from_bytes(&rand).b32())?;
+
+    let nonce_point = PublicKey::from_secret_key(&nonce);
+    let nonce = to_even_y_secret_key(&nonce)?;
+    let nonce_point_x = to_x_only(&nonce_point);
+
+    let challenge = scalar_from_bytes(&tagged_hash(
+        "BIP0340/challenge",
+        &[&nonce_point_x, &public_key_x, message],
+    ));
+    let secret: Scalar = secret_key.into();
+    let k: Scalar = nonce.into();
+    let s = k + challenge * secret;
+
+    let mut signature = [0u8; 64];
+    signature[0..32].copy_from_slice(&nonce_point_x);
+    signature[32..64].copy_from_slice(&s.b32());
+
+    if !verify(message, &public_key_x, &signature) {
+        return Err(Error::InvalidSignature);
+    }
+    Ok(signature)
+}
+
+/// Verifies the BIP340 signature of the 32 byte message by the x-only public key.
+pub fn verify(message: &[u8; 32], public_key: &[u8; 32], signature: &[u8; 64]) -> bool {
+    let point = match lift_x(public_key) {
+        Ok(public_key) => to_affine(&public_key),
+        Err(_) => return false,
+    };
+
+    let mut r_bytes = [0u8; 32];
+    r_bytes.copy_from_slice(&signature[0..32]);
+    let mut r = Field::default();
+    if !r.set_b32(&r_bytes) {
+        return false;
+    }
+
+    let mut s_bytes = [0u8; 32];
+    s_bytes.copy_from_slice(&signature[32..64]);
+    let mut s = Scalar::default();
+    if bool::from(s.set_b32(&s_bytes)) {
+        return false;
+    }
+
+    let challenge = scalar_from_bytes(&tagged_hash(
+        "BIP0340/challenge",
+        &[&r_bytes, public_key, message],
+    ));
+
+    // R = s * G - e * P
+    let mut nonce_point = Jacobian::default();
+    ECMULT_CONTEXT.ecmult(
+        &mut nonce_point,
+        &Jacobian::from_ge(&point),
+        &-challenge,
+        &s,
+    );
+    if nonce_point.is_infinity() {
+        return false;
+    }
+
+    let mut nonce_point = Affine::from_gej(&nonce_point);
+    nonce_point.x.normalize();
+    nonce_point.y.normalize();
+    !nonce_point.y.is_odd() && nonce_point.x.b32() == r_bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes32(hex: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&hex::decode(hex).unwrap());
+        bytes
+    }
+
+    fn bytes64(hex: &str) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(&hex::decode(hex).unwrap());
+        bytes
+    }
+
+    #[test]
+    fn bip340_signing_vectors() {
+        // (secret key, public key, aux rand, message, signature)
+        let vectors = [
+            (
+                "0000000000000000000000000000000000000000000000000000000000000003",
+                "F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0",
+            ),
+            (
+                "B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF",
+                "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+                "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE33418906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A",
+            ),
+            (
+                "C90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B14E5C9",
+                "DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+                "C87AA53824B4D7AE2EB035A2B5BBBCCC080E76CDC6D1692C4B0B62D798E6D906",
+                "7E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C",
+                "5831AAEED7B44BB74E5EAB94BA9D4294C49BCF2A60728D8B4C200F50DD313C1BAB745879A5AD954A72C45A91C3A51D3C7ADEA98D82F8481E0E1E03674A6F3FB7",
+            ),
+        ];
+
+        for (secret_key, public_key, aux_rand, message, signature) in vectors.iter() {
+            let secret_key = SecretKey::parse_slice(&hex::decode(secret_key).unwrap()).unwrap();
+            let public_key = bytes32(public_key);
+            let message = bytes32(message);
+            let expected = bytes64(signature);
+
+            assert_eq!(
+                public_key,
+                to_x_only(&PublicKey::from_secret_key(&secret_key))
+            );
+            let signature = sign(&message, &secret_key, &bytes32(aux_rand)).unwrap();
+            assert_eq!(&expected[..], &signature[..]);
+            assert!(verify(&message, &public_key, &signature));
+        }
+    }
+
+    #[test]
+    fn bip340_verification_vectors() {
+        // (public key, message, signature, valid)
+        let vectors = [
+            (
+                "D69C3509BB99E412E68B0FE8544E72837DFA30746D8BE2AA65975F29D22DC7B9",
+                "4DF3C3F68FCC83B27E9D42C90431A72499F17875C81A599B566C9889B9696703",
+                "00000000000000000000003B78CE563F89A0ED9414F5AA28AD0D96D6795F9C6376AFB1548AF603B3EB45C9F8207DEE1060CB71C04E80F593060B07D28308D7F4",
+                true,
+            ),
+            (
+                "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+                "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+                "FFF97BD5755EEEA420453A14355235D382F6472F8568A18B2F057A14602975563CC27944640AC607CD107AE10923D9EF7A73C643E166BE5EBEAFA34B1AC553E2",
+                false,
+            ),
+            (
+                "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+                "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+                "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE33418906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0B",
+                false,
+            ),
+        ];
+
+        for (public_key, message, signature, valid) in vectors.iter() {
+            assert_eq!(
+                *valid,
+                verify(&bytes32(message), &bytes32(public_key), &bytes64(signature))
+            );
+        }
+    }
+
+    #[test]
+    fn bip86_tweak() {
+        let internal_key =
+            bytes32("cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115");
+        let (output_key, _) = tweak_public_key(&internal_key, None).unwrap();
+        assert_eq!(
+            "a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c",
+            hex::encode(output_key)
+        );
+    }
+
+    #[test]
+    fn tweak_secret_key_matches_public_key() {
+        let secret_key = SecretKey::parse_slice(
+            &hex::decode("B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF")
+                .unwrap(),
+        )
+        .unwrap();
+        let merkle_root = [7u8; 32];
+        for merkle_root in [None, Some(&merkle_root)].iter() {
+            let tweaked = tweak_secret_key(&secret_key, *merkle_root).unwrap();
+            let (output_key, _) = tweak_public_key(
+                &to_x_only(&PublicKey::from_secret_key(&secret_key)),
+                *merkle_root,
+            )
+            .unwrap();
+            assert_eq!(output_key, to_x_only(&PublicKey::from_secret_key(&tweaked)));
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use hmac::{Hmac, Mac};
+use secp256k1::curve::{Scalar, ECMULT_GEN_CONTEXT};
+use secp256k1::{Message, PublicKey, RecoveryId, SecretKey, Signature};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The maximum size of a low-R DER signature, excluding the sighash byte
+pub const MAX_LOW_R_DER_SIGNATURE_SIZE: usize = 70;
+
+/// Returns HMAC-SHA256 of the concatenated data under the given key.
+fn hmac_sha256(key: &[u8; 32], data: &[&[u8]]) -> [u8; 32] {
+    // HMAC accepts keys of any length
+    let mut mac = HmacSha256::new_varkey(key).expect("HMAC accepts any key length");
+    for input in data {
+        mac.input(input);
+    }
+    let mut output = [0u8; 32];
+    output.copy_from_slice(&mac.result().code());
+    output
+}
+
+/// The RFC6979 HMAC-SHA256 deterministic nonce generator, seeded as in Bitcoin Core
+/// with the secret key, the message and optional extra entropy.
+pub struct Rfc6979 {
+    k: [u8; 32],
+    v: [u8; 32],
+    retry: bool,
+}
+
+impl Rfc6979 {
+    pub fn new(secret_key: &SecretKey, message: &Message, extra_entropy: Option<&[u8; 32]>) -> Self {
+        let secret_key = secret_key.serialize();
+        let message = message.serialize();
+        let extra_entropy: &[u8] = match extra_entropy {
+            Some(extra_entropy) => extra_entropy,
+            None => &[],
+        };
+
+        let mut k = [0u8; 32];
+        let mut v = [1u8; 32];
+        k = hmac_sha256(&k, &[&v, &[0x00], &secret_key, &message, extra_entropy]);
+        v = hmac_sha256(&k, &[&v]);
+        k = hmac_sha256(&k, &[&v, &[0x01], &secret_key, &message, extra_entropy]);
+        v = hmac_sha256(&k, &[&v]);
+
+        Self { k, v, retry: false }
+    }
+
+    /// Returns the next 32 bytes of the generator output.
+    pub fn generate(&mut self) -> [u8; 32] {
+        if self.retry {
+            self.k = hmac_sha256(&self.k, &[&self.v, &[0x00]]);
+            self.v = hmac_sha256(&self.k, &[&self.v]);
+        }
+        self.v = hmac_sha256(&self.k, &[&self.v]);
+        self.retry = true;
+        self.v
+    }
+
+    /// Returns the next candidate nonce that is a valid non-zero scalar.
+    pub fn next_nonce(&mut self) -> Scalar {
+        loop {
+            let mut nonce = Scalar::default();
+            let overflow = bool::from(nonce.set_b32(&self.generate()));
+            if !overflow && !nonce.is_zero() {
+                return nonce;
+            }
+        }
+    }
+}
+
+impl Drop for Rfc6979 {
+    fn drop(&mut self) {
+        self.k = [0u8; 32];
+        self.v = [0u8; 32];
+    }
+}
+
+/// Returns a deterministic ECDSA signature with a low S value, using an RFC6979 nonce
+/// with optional extra entropy.
+pub fn sign(
+    message: &Message,
+    secret_key: &SecretKey,
+    extra_entropy: Option<&[u8; 32]>,
+) -> (Signature, RecoveryId) {
+    let secret: Scalar = secret_key.clone().into();
+    let mut rfc6979 = Rfc6979::new(secret_key, message, extra_entropy);
+    loop {
+        let nonce = rfc6979.next_nonce();
+        // The signing only fails for a zero S value, in which case the next nonce is used
+        if let Ok((r, s, recovery_id)) = ECMULT_GEN_CONTEXT.sign_raw(&secret, &message.0, &nonce) {
+            let signature = Signature { r, s };
+            let recovery_id =
+                RecoveryId::parse(recovery_id).expect("recovery id is always less than 4");
+            return (signature, recovery_id);
+        }
+    }
+}
+
+/// Returns a deterministic ECDSA signature with low R and low S values, grinding the
+/// extra entropy with an incrementing counter as in Bitcoin Core. The DER encoding of
+/// the signature is at most 70 bytes.
+pub fn sign_low_r(message: &Message, secret_key: &SecretKey) -> (Signature, RecoveryId) {
+    let (mut signature, mut recovery_id) = sign(message, secret_key, None);
+
+    let mut counter = 0u32;
+    while !is_low_r(&signature) {
+        counter += 1;
+        let mut extra_entropy = [0u8; 32];
+        extra_entropy[0..4].copy_from_slice(&counter.to_le_bytes());
+
+        let (grinded_signature, grinded_recovery_id) =
+            sign(message, secret_key, Some(&extra_entropy));
+        signature = grinded_signature;
+        recovery_id = grinded_recovery_id;
+    }
+
+    (signature, recovery_id)
+}
+
+/// Returns true if the R value of the signature fits in 32 bytes of DER without padding.
+pub fn is_low_r(signature: &Signature) -> bool {
+    signature.r.b32()[0] < 0x80
+}
+
+/// Returns true if the S value of the signature is at most half the curve order.
+pub fn is_low_s(signature: &Signature) -> bool {
+    !signature.s.is_high()
+}
+
+/// Verifies the ECDSA signature, rejecting signatures with a high S value as Bitcoin
+/// Core's standardness rules do.
+pub fn verify(message: &Message, signature: &Signature, public_key: &PublicKey) -> bool {
+    is_low_s(signature) && secp256k1::verify(message, signature, public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::Digest;
+
+    fn secret_key(hex: &str) -> SecretKey {
+        SecretKey::parse_slice(&hex::decode(hex).unwrap()).unwrap()
+    }
+
+    fn message(text: &str) -> Message {
+        Message::parse_slice(&Sha256::digest(text.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn rfc6979_vectors() {
+        // Deterministic secp256k1 signatures of SHA256(message), as published before low-S
+        // normalization
+        const VECTORS: [(&str, &str, &str); 4] = [
+            (
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "Satoshi Nakamoto",
+                "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d82442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5",
+            ),
+            (
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "All those moments will be lost in time, like tears in rain. Time to die...",
+                "8600dbd41e348fe5c9465ab92d23e3db8b98b873beecd930736488696438cb6b547fe64427496db33bf66019dacbf0039c04199abb0122918601db38a72cfc21",
+            ),
+            (
+                "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140",
+                "Satoshi Nakamoto",
+                "fd567d121db66e382991534ada77a6bd3106f0a1098c231e47993447cd6af2d094c632f14e4379fc1ea610a3df5a375152549736425ee17cebe10abbc2a2826c",
+            ),
+            (
+                "f8b8af8ce3c7cca5e300d33939540c10d45ce001b8f252bfbc57ba0342904181",
+                "Alan Turing",
+                "7063ae83e7f62bbb171798131b4a0564b956930092b33b07b395615d9ec7e15c58dfcc1e00a35e1572f366ffe34ba0fc47db1e7189759b9fb233c5b05ab388ea",
+            ),
+        ];
+        for (secret, text, expected) in VECTORS.iter() {
+            let (secret_key, message) = (secret_key(secret), message(text));
+            let (signature, _) = sign(&message, &secret_key, None);
+            let mut expected = Signature::parse_slice(&hex::decode(expected).unwrap()).unwrap();
+            expected.normalize_s();
+            assert_eq!(expected, signature);
+            assert!(is_low_s(&signature));
+            assert!(verify(
+                &message,
+                &signature,
+                &PublicKey::from_secret_key(&secret_key)
+            ));
+        }
+    }
+
+    #[test]
+    fn extra_entropy() {
+        let secret_key =
+            secret_key("0000000000000000000000000000000000000000000000000000000000000001");
+        let message = message("Satoshi Nakamoto");
+        let (signature, _) = sign(&message, &secret_key, None);
+        let (with_entropy, _) = sign(&message, &secret_key, Some(&[1u8; 32]));
+        assert_ne!(signature, with_entropy);
+        assert_eq!(
+            with_entropy,
+            sign(&message, &secret_key, Some(&[1u8; 32])).0
+        );
+        assert!(verify(
+            &message,
+            &with_entropy,
+            &PublicKey::from_secret_key(&secret_key)
+        ));
+    }
+
+    #[test]
+    fn low_r_grinding() {
+        let secret_key =
+            secret_key("e91671c46231f833a6406ccbea0e3e392c76c167bac1cb013f6f1013980455c2");
+        let public_key = PublicKey::from_secret_key(&secret_key);
+        for i in 0..64u32 {
+            let message = Message::parse_slice(&Sha256::digest(&i.to_le_bytes())).unwrap();
+            let (signature, recovery_id) = sign_low_r(&message, &secret_key);
+            assert!(is_low_r(&signature) && is_low_s(&signature));
+            assert!(signature.serialize_der().as_ref().len() <= MAX_LOW_R_DER_SIGNATURE_SIZE);
+            assert!(verify(&message, &signature, &public_key));
+            assert_eq!(
+                public_key,
+                secp256k1::recover(&message, &signature, &recovery_id).unwrap()
+            );
+            assert_eq!(signature, sign_low_r(&message, &secret_key).0);
+        }
+
+        // High S values are not standard
+        let message = message("Satoshi Nakamoto");
+        let (mut signature, _) = sign(&message, &secret_key, None);
+        signature.s = -signature.s;
+        assert!(!is_low_s(&signature));
+        assert!(!verify(&message, &signature, &public_key));
+    }
+}
@@ -0,0 +1,362 @@
+use crate::network::BitcoinNetwork;
+use crate::private_key::BitcoinPrivateKey;
+use crate::public_key::BitcoinPublicKey;
+use crate::signature::sign_low_r;
+
+use gyu_model::no_std::*;
+use gyu_model::private_key::PrivateKey;
+use gyu_model::signer::{Signer, SignerError};
+
+use core::str::FromStr;
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::thread;
+
+/// A signer holding Bitcoin private keys in memory, indexed by key id.
+#[derive(Clone)]
+pub struct BitcoinSoftwareSigner<N: BitcoinNetwork> {
+    keys: HashMap<String, BitcoinPrivateKey<N>>,
+}
+
+impl<N: BitcoinNetwork> BitcoinSoftwareSigner<N> {
+    pub fn new() -> Self {
+        Self {
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Adds the private key under the given key id, replacing any previous key.
+    pub fn insert(&mut self, key_id: &str, private_key: BitcoinPrivateKey<N>) {
+        self.keys.insert(key_id.into(), private_key);
+    }
+
+    fn private_key(&self, key_id: &str) -> Result<&BitcoinPrivateKey<N>, SignerError> {
+        self.keys
+            .get(key_id)
+            .ok_or_else(|| SignerError::KeyNotFound(key_id.into()))
+    }
+}
+
+impl<N: BitcoinNetwork> Default for BitcoinSoftwareSigner<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: BitcoinNetwork> Signer for BitcoinSoftwareSigner<N> {
+    type KeyId = String;
+    type PublicKey = BitcoinPublicKey<N>;
+
+    fn public_key(&self, key_id: &Self::KeyId) -> Result<Self::PublicKey, SignerError> {
+        Ok(self.private_key(key_id)?.to_public_key())
+    }
+
+    /// Returns the DER encoded low-R ECDSA signature of the 32 byte digest.
+    fn sign_digest(&self, key_id: &Self::KeyId, digest: &[u8]) -> Result<Vec<u8>, SignerError> {
+        if digest.len() != 32 {
+            return Err(SignerError::InvalidDigestLength(digest.len()));
+        }
+        let (signature, _) = sign_low_r(
+            &secp256k1::Message::parse_slice(digest)?,
+            &self.private_key(key_id)?.to_secp256k1_secret_key(),
+        );
+        Ok(signature.serialize_der().as_ref().to_vec())
+    }
+}
+
+/// A signer that requests public keys and signatures from a signing service listening
+/// on a local Unix socket. Each request is a single line, `PUBKEY <key id>` or
+/// `SIGN <key id> <hex digest>`, answered by `OK <hex>` or `ERR <message>`. Only available
+/// on Unix targets.
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub struct BitcoinRemoteSigner<N: BitcoinNetwork> {
+    path: PathBuf,
+    _network: core::marker::PhantomData<N>,
+}
+
+#[cfg(unix)]
+impl<N: BitcoinNetwork> BitcoinRemoteSigner<N> {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            _network: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns the key id if it fits in a request line. Ids with whitespace or control
+    /// characters would split or end the line, so they are rejected before any request.
+    fn key_id(key_id: &str) -> Result<&str, SignerError> {
+        match key_id.is_empty() || key_id.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            true => Err(SignerError::InvalidKeyId(key_id.into())),
+            false => Ok(key_id),
+        }
+    }
+
+    fn request(&self, request: &str) -> Result<Vec<u8>, SignerError> {
+        let mut stream = UnixStream::connect(&self.path)?;
+        stream.write_all(format!("{}\n", request).as_bytes())?;
+
+        let mut response = String::new();
+        BufReader::new(stream).read_line(&mut response)?;
+
+        let mut parts = response.trim_end().splitn(2, ' ');
+        match (parts.next(), parts.next()) {
+            (Some("OK"), Some(payload)) => Ok(hex::decode(payload)?),
+            (Some("ERR"), Some(message)) => Err(SignerError::Message(message.into())),
+            _ => Err(SignerError::Message(format!(
+                "invalid signer response: {}",
+                response.trim_end()
+            ))),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl<N: BitcoinNetwork> Signer for BitcoinRemoteSigner<N> {
+    type KeyId = String;
+    type PublicKey = BitcoinPublicKey<N>;
+
+    fn public_key(&self, key_id: &Self::KeyId) -> Result<Self::PublicKey, SignerError> {
+        let public_key = self.request(&format!("PUBKEY {}", Self::key_id(key_id)?))?;
+        Ok(BitcoinPublicKey::from_str(&hex::encode(public_key))?)
+    }
+
+    fn sign_digest(&self, key_id: &Self::KeyId, digest: &[u8]) -> Result<Vec<u8>, SignerError> {
+        self.request(&format!(
+            "SIGN {} {}",
+            Self::key_id(key_id)?,
+            hex::encode(digest)
+        ))
+    }
+}
+
+/// A mock signing service answering `BitcoinRemoteSigner` requests on a local Unix
+/// socket with the keys of a software signer, for testing remote signing offline.
+#[cfg(unix)]
+pub struct MockRemoteSignerService<N: BitcoinNetwork> {
+    listener: UnixListener,
+    signer: BitcoinSoftwareSigner<N>,
+}
+
+#[cfg(unix)]
+impl<N: BitcoinNetwork> MockRemoteSignerService<N> {
+    pub fn bind<P: AsRef<Path>>(
+        path: P,
+        signer: BitcoinSoftwareSigner<N>,
+    ) -> Result<Self, SignerError> {
+        Ok(Self {
+            listener: UnixListener::bind(path)?,
+            signer,
+        })
+    }
+
+    /// Serves requests on a background thread until the process exits.
+    pub fn spawn(self) -> thread::JoinHandle<()> {
+        thread::spawn(move || self.serve())
+    }
+
+    /// Serves requests until the listener fails.
+    pub fn serve(&self) {
+        for stream in self.listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    // A failed connection only affects its own client
+                    let _ = self.handle(stream);
+                }
+                Err(_) => return,
+            }
+        }
+    }
+
+    fn handle(&self, mut stream: UnixStream) -> Result<(), SignerError> {
+        let mut request = String::new();
+        BufReader::new(&stream).read_line(&mut request)?;
+
+        let response = match self.respond(request.trim_end()) {
+            Ok(payload) => format!("OK {}\n", hex::encode(payload)),
+            Err(error) => format!("ERR {}\n", error),
+        };
+        stream.write_all(response.as_bytes())?;
+        Ok(())
+    }
+
+    fn respond(&self, request: &str) -> Result<Vec<u8>, SignerError> {
+        let parts: Vec<&str> = request.split(' ').collect();
+        match parts.as_slice() {
+            ["PUBKEY", key_id] => Ok(hex::decode(
+                self.signer.public_key(&key_id.to_string())?.to_string(),
+            )?),
+            ["SIGN", key_id, digest] => self
+                .signer
+                .sign_digest(&key_id.to_string(), &hex::decode(digest)?),
+            _ => Err(SignerError::Message(format!("invalid request: {}", request))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::BitcoinAmount;
+    use crate::format::BitcoinFormat;
+    use crate::network::Mainnet;
+    use crate::transaction::*;
+    use gyu_model::transaction::Transaction;
+    use gyu_model::utilities::crypto::hash160;
+
+    const PRIVATE_KEY: &str = "L1uyy5qTuGrVXrmrsvHWHgVzW9kKdrp27wBC7Vs6nZDTF2BRUVwy";
+
+    fn transaction(
+        private_key: &BitcoinPrivateKey<Mainnet>,
+        format: &BitcoinFormat,
+    ) -> BitcoinTransaction<Mainnet> {
+        let address = private_key.to_address(format).unwrap();
+        let redeem_script = match format {
+            BitcoinFormat::P2SH_P2WPKH => Some(
+                [
+                    vec![0x00, 0x14],
+                    hash160(
+                        &private_key
+                            .to_public_key()
+                            .to_secp256k1_public_key()
+                            .serialize_compressed(),
+                    ),
+                ]
+                .concat(),
+            ),
+            _ => None,
+        };
+        let input = BitcoinTransactionInput::<Mainnet>::new(
+            vec![7u8; 32],
+            0,
+            Some(address.clone()),
+            Some(BitcoinAmount(100_000)),
+            redeem_script,
+            None,
+            None,
+            SignatureHash::SIG_ALL,
+        )
+        .unwrap();
+        let output = BitcoinTransactionOutput::new(&address, BitcoinAmount(90_000)).unwrap();
+        BitcoinTransaction::new(&BitcoinTransactionParameters {
+            version: 2,
+            inputs: vec![input],
+            outputs: vec![output],
+            lock_time: 0,
+            segwit_flag: false,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn software_signer() {
+        let private_key = BitcoinPrivateKey::<Mainnet>::from_str(PRIVATE_KEY).unwrap();
+        let mut signer = BitcoinSoftwareSigner::<Mainnet>::new();
+        signer.insert("key", private_key.clone());
+
+        assert_eq!(
+            private_key.to_public_key(),
+            signer.public_key(&"key".into()).unwrap()
+        );
+        assert!(matches!(
+            signer.public_key(&"missing".into()),
+            Err(SignerError::KeyNotFound(_))
+        ));
+        assert!(matches!(
+            signer.sign_digest(&"key".into(), &[0u8; 31]),
+            Err(SignerError::InvalidDigestLength(31))
+        ));
+
+        for format in [
+            BitcoinFormat::P2PKH,
+            BitcoinFormat::P2SH_P2WPKH,
+            BitcoinFormat::Bech32,
+        ]
+        .iter()
+        {
+            let transaction = transaction(&private_key, format);
+            assert_eq!(
+                transaction
+                    .sign(&private_key)
+                    .unwrap()
+                    .to_transaction_bytes()
+                    .unwrap(),
+                transaction
+                    .sign_with_signer(&signer, &"key".into())
+                    .unwrap()
+                    .to_transaction_bytes()
+                    .unwrap()
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remote_signer() {
+        let private_key = BitcoinPrivateKey::<Mainnet>::from_str(PRIVATE_KEY).unwrap();
+        let mut signer = BitcoinSoftwareSigner::<Mainnet>::new();
+        signer.insert("key", private_key.clone());
+
+        let path = std::env::temp_dir().join(format!("gyu-signer-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        MockRemoteSignerService::bind(&path, signer)
+            .unwrap()
+            .spawn();
+        let remote = BitcoinRemoteSigner::<Mainnet>::new(&path);
+
+        assert_eq!(
+            private_key.to_public_key(),
+            remote.public_key(&"key".into()).unwrap()
+        );
+        for format in [BitcoinFormat::P2PKH, BitcoinFormat::Bech32].iter() {
+            let transaction = transaction(&private_key, format);
+            assert_eq!(
+                transaction
+                    .sign(&private_key)
+                    .unwrap()
+                    .to_transaction_bytes()
+                    .unwrap(),
+                transaction
+                    .sign_with_signer(&remote, &"key".into())
+                    .unwrap()
+                    .to_transaction_bytes()
+                    .unwrap()
+            );
+            assert!(transaction
+                .sign_with_signer(&remote, &"missing".into())
+                .is_err());
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remote_signer_key_ids() {
+        // No service listens on the path, so only a request that is never sent can
+        // fail with InvalidKeyId.
+        let remote = BitcoinRemoteSigner::<Mainnet>::new(
+            std::env::temp_dir().join(format!("gyu-no-signer-{}.sock", std::process::id())),
+        );
+        for key_id in ["", "key other", "key\nSIGN key 00", "key\r", "key\tother"].iter() {
+            assert!(matches!(
+                remote.public_key(&key_id.to_string()),
+                Err(SignerError::InvalidKeyId(_))
+            ));
+            assert!(matches!(
+                remote.sign_digest(&key_id.to_string(), &[0u8; 32]),
+                Err(SignerError::InvalidKeyId(_))
+            ));
+        }
+        assert!(matches!(
+            remote.public_key(&"key".into()),
+            Err(SignerError::Crate(..))
+        ));
+    }
+}
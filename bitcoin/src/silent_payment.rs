@@ -0,0 +1,990 @@
+use crate::address::{decode_bech32m, encode_bech32m, BitcoinAddress};
+use crate::derivation_path::BitcoinDerivationPath;
+use crate::extended_private_key::BitcoinExtendedPrivateKey;
+use crate::message::{read_compact_size, read_pushes};
+use crate::network::BitcoinNetwork;
+use crate::private_key::BitcoinPrivateKey;
+use crate::public_key::BitcoinPublicKey;
+use crate::schnorr::tagged_hash;
+use crate::transaction::{BitcoinTransaction, BitcoinTransactionInput, Outpoint};
+use gyu_model::no_std::*;
+use gyu_model::{
+    address::AddressError,
+    derivation_path::ChildIndex,
+    extended_private_key::{ExtendedPrivateKey, ExtendedPrivateKeyError},
+    private_key::{PrivateKey, PrivateKeyError},
+    public_key::PublicKeyError,
+    utilities::crypto::hash160,
+};
+
+use bech32::{u5, FromBase32, ToBase32};
+use core::{fmt, marker::PhantomData, str::FromStr};
+use zeroize::Zeroizing;
+
+/// A label with its tweak and the compressed point of the tweak
+type LabelPoint = (u32, [u8; 32], [u8; 33]);
+
+/// The BIP341 NUMS point H, used as the internal key of script-path-only Taproot outputs
+const NUMS_H: [u8; 32] = [
+    0x50, 0x92, 0x9b, 0x74, 0xc1, 0xa0, 0x49, 0x54, 0xb7, 0x8b, 0x4b, 0x60, 0x35, 0xe9, 0x7a, 0x5e,
+    0x07, 0x8a, 0x5a, 0x0f, 0x28, 0xec, 0x96, 0xd5, 0x47, 0xbf, 0xee, 0x9a, 0xce, 0x80, 0x3a, 0xc0,
+];
+
+#[derive(Debug, Fail)]
+pub enum SilentPaymentError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPrivateKeyError(ExtendedPrivateKeyError),
+
+    #[fail(display = "invalid silent payment address: {}", _0)]
+    InvalidAddress(String),
+
+    #[fail(display = "expected {} previous output scripts, found {}", _0, _1)]
+    InvalidPrevouts(usize, usize),
+
+    #[fail(display = "invalid silent payment address version {}", _0)]
+    InvalidVersion(u8),
+
+    #[fail(display = "missing previous output script public key for input {}", _0)]
+    MissingScriptPublicKey(usize),
+
+    #[fail(display = "no eligible inputs for a silent payment")]
+    NoEligibleInputs,
+
+    #[fail(display = "{}", _0)]
+    PrivateKeyError(PrivateKeyError),
+
+    #[fail(display = "{}", _0)]
+    PublicKeyError(PublicKeyError),
+
+    #[fail(display = "input {} spends a segwit version {} output", _0, _1)]
+    UnsupportedWitnessVersion(usize, u8),
+}
+
+impl From<AddressError> for SilentPaymentError {
+    fn from(error: AddressError) -> Self {
+        SilentPaymentError::AddressError(error)
+    }
+}
+
+impl From<ExtendedPrivateKeyError> for SilentPaymentError {
+    fn from(error: ExtendedPrivateKeyError) -> Self {
+        SilentPaymentError::ExtendedPrivateKeyError(error)
+    }
+}
+
+impl From<PrivateKeyError> for SilentPaymentError {
+    fn from(error: PrivateKeyError) -> Self {
+        SilentPaymentError::PrivateKeyError(error)
+    }
+}
+
+impl From<PublicKeyError> for SilentPaymentError {
+    fn from(error: PublicKeyError) -> Self {
+        SilentPaymentError::PublicKeyError(error)
+    }
+}
+
+impl From<bech32::Error> for SilentPaymentError {
+    fn from(error: bech32::Error) -> Self {
+        SilentPaymentError::Crate("bech32", format!("{:?}", error))
+    }
+}
+
+impl From<secp256k1::Error> for SilentPaymentError {
+    fn from(error: secp256k1::Error) -> Self {
+        SilentPaymentError::Crate("libsecp256k1", format!("{:?}", error))
+    }
+}
+
+/// Represents a BIP352 silent payment address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinSilentPaymentAddress<N: BitcoinNetwork> {
+    scan_public_key: BitcoinPublicKey<N>,
+    spend_public_key: BitcoinPublicKey<N>,
+}
+
+impl<N: BitcoinNetwork> BitcoinSilentPaymentAddress<N> {
+    pub fn new(
+        scan_public_key: BitcoinPublicKey<N>,
+        spend_public_key: BitcoinPublicKey<N>,
+    ) -> Self {
+        Self {
+            scan_public_key,
+            spend_public_key,
+        }
+    }
+
+    pub fn scan_public_key(&self) -> &BitcoinPublicKey<N> {
+        &self.scan_public_key
+    }
+
+    pub fn spend_public_key(&self) -> &BitcoinPublicKey<N> {
+        &self.spend_public_key
+    }
+}
+
+impl<N: BitcoinNetwork> FromStr for BitcoinSilentPaymentAddress<N> {
+    type Err = SilentPaymentError;
+
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        let (prefix, data) = decode_bech32m(address)?;
+        let _ = N::from_silent_payment_prefix(&prefix)?;
+
+        let version = match data.first() {
+            Some(version) => version.to_u8(),
+            None => return Err(SilentPaymentError::InvalidAddress(address.into())),
+        };
+        let payload = Vec::from_base32(&data[1..])?;
+        // Future versions may append data, which is ignored by version 0 readers.
+        match version {
+            0 if payload.len() == 66 => {}
+            1..=30 if payload.len() >= 66 => {}
+            31 => return Err(SilentPaymentError::InvalidVersion(version)),
+            _ => return Err(SilentPaymentError::InvalidAddress(address.into())),
+        };
+
+        let mut scan_public_key = [0u8; 33];
+        scan_public_key.copy_from_slice(&payload[0..33]);
+        let mut spend_public_key = [0u8; 33];
+        spend_public_key.copy_from_slice(&payload[33..66]);
+
+        Ok(Self::new(
+            BitcoinPublicKey::from_secp256k1_public_key(
+                secp256k1::PublicKey::parse_compressed(&scan_public_key)?,
+                true,
+            ),
+            BitcoinPublicKey::from_secp256k1_public_key(
+                secp256k1::PublicKey::parse_compressed(&spend_public_key)?,
+                true,
+            ),
+        ))
+    }
+}
+
+impl<N: BitcoinNetwork> fmt::Display for BitcoinSilentPaymentAddress<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut payload = self
+            .scan_public_key
+            .to_secp256k1_public_key()
+            .serialize_compressed()
+            .to_vec();
+        payload.extend(
+            &self
+                .spend_public_key
+                .to_secp256k1_public_key()
+                .serialize_compressed(),
+        );
+
+        let mut data = vec![u5::try_from_u8(0).map_err(|_| fmt::Error)?];
+        data.extend_from_slice(&payload.to_base32());
+        match encode_bech32m(N::to_silent_payment_prefix(), &data) {
+            Ok(address) => write!(f, "{}", address),
+            Err(_) => Err(fmt::Error),
+        }
+    }
+}
+
+/// Represents an output of a transaction that pays a silent payment address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinSilentPaymentOutput<N: BitcoinNetwork> {
+    /// The index of the output in the transaction
+    pub vout: u32,
+    /// The x-only Taproot output key
+    pub output_key: [u8; 32],
+    /// The tweak added to the spend private key, including the label tweak
+    pub tweak: [u8; 32],
+    /// The label of the address that was paid, if any
+    pub label: Option<u32>,
+    /// The private key that spends the output key with a Taproot key path spend
+    pub private_key: BitcoinPrivateKey<N>,
+}
+
+/// Represents the scan and spend private keys of a silent payment receiver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcoinSilentPaymentKeys<N: BitcoinNetwork> {
+    scan_private_key: BitcoinPrivateKey<N>,
+    spend_private_key: BitcoinPrivateKey<N>,
+}
+
+impl<N: BitcoinNetwork> BitcoinSilentPaymentKeys<N> {
+    pub fn new(
+        scan_private_key: BitcoinPrivateKey<N>,
+        spend_private_key: BitcoinPrivateKey<N>,
+    ) -> Self {
+        Self {
+            scan_private_key,
+            spend_private_key,
+        }
+    }
+
+    /// Returns the scan key at m/352'/coin'/account'/1'/0 and the spend key at
+    /// m/352'/coin'/account'/0'/0 of the master extended private key.
+    pub fn from_extended_private_key(
+        extended_private_key: &BitcoinExtendedPrivateKey<N>,
+        account: u32,
+    ) -> Result<Self, SilentPaymentError> {
+        // Reject account indices that are already hardened.
+        ChildIndex::normal(account).map_err(ExtendedPrivateKeyError::from)?;
+
+        let derive = |key_type: u32| -> Result<BitcoinPrivateKey<N>, SilentPaymentError> {
+            let path = BitcoinDerivationPath::<N>::BIP32(
+                vec![
+                    ChildIndex::Hardened(352),
+                    N::HD_COIN_TYPE,
+                    ChildIndex::Hardened(account),
+                    ChildIndex::Hardened(key_type),
+                    ChildIndex::Normal(0),
+                ],
+                PhantomData,
+            );
+            Ok(extended_private_key.derive(&path)?.to_private_key())
+        };
+
+        Ok(Self::new(derive(1)?, derive(0)?))
+    }
+
+    pub fn scan_private_key(&self) -> &BitcoinPrivateKey<N> {
+        &self.scan_private_key
+    }
+
+    pub fn spend_private_key(&self) -> &BitcoinPrivateKey<N> {
+        &self.spend_private_key
+    }
+
+    /// Returns the unlabeled silent payment address.
+    pub fn to_address(&self) -> BitcoinSilentPaymentAddress<N> {
+        BitcoinSilentPaymentAddress::new(
+            self.scan_private_key.to_public_key(),
+            self.spend_private_key.to_public_key(),
+        )
+    }
+
+    /// Returns the silent payment address with the label. Label 0 is reserved for change.
+    pub fn to_labeled_address(
+        &self,
+        label: u32,
+    ) -> Result<BitcoinSilentPaymentAddress<N>, SilentPaymentError> {
+        Ok(BitcoinSilentPaymentAddress::new(
+            self.scan_private_key.to_public_key(),
+            self.spend_private_key
+                .to_public_key()
+                .tweak_add(&self.label_tweak(label))?,
+        ))
+    }
+
+    /// Returns the tweak hash_BIP0352/Label(b_scan || m) of the label.
+    pub fn label_tweak(&self, label: u32) -> [u8; 32] {
+        let scan_private_key = Zeroizing::new(self.scan_private_key.expose_secret().serialize());
+        tagged_hash(
+            "BIP0352/Label",
+            &[&scan_private_key[..], &label.to_be_bytes()],
+        )
+    }
+
+    /// Returns the outputs of the transaction that pay this receiver, under any of the labels,
+    /// given the script public keys of the outputs spent by its inputs. Transactions spending
+    /// a segwit version 2 or later output have no silent payment outputs.
+    pub fn scan(
+        &self,
+        transaction: &BitcoinTransaction<N>,
+        prevout_script_pub_keys: &[Vec<u8>],
+        labels: &[u32],
+    ) -> Result<Vec<BitcoinSilentPaymentOutput<N>>, SilentPaymentError> {
+        let parameters = transaction.parameters();
+        if parameters.inputs.len() != prevout_script_pub_keys.len() {
+            return Err(SilentPaymentError::InvalidPrevouts(
+                parameters.inputs.len(),
+                prevout_script_pub_keys.len(),
+            ));
+        }
+        if prevout_script_pub_keys
+            .iter()
+            .any(|script_pub_key| future_witness_version(script_pub_key).is_some())
+        {
+            return Ok(vec![]);
+        }
+
+        let public_keys = parameters
+            .inputs
+            .iter()
+            .zip(prevout_script_pub_keys)
+            .filter_map(|(input, script_pub_key)| input_public_key(input, script_pub_key))
+            .collect::<Vec<BitcoinPublicKey<N>>>();
+        let public_key = match public_keys.split_first() {
+            Some((first, rest)) => rest
+                .iter()
+                .try_fold(first.clone(), |sum, public_key| sum.combine(public_key)),
+            None => return Ok(vec![]),
+        };
+        // The input public keys may cancel out, in which case there is nothing to scan for.
+        let public_key = match public_key {
+            Ok(public_key) => public_key,
+            Err(_) => return Ok(vec![]),
+        };
+
+        let outpoints = parameters.inputs.iter().map(|input| &input.outpoint);
+        let input_hash = input_hash(&smallest_outpoint(outpoints), &public_key);
+        let shared_secret = self
+            .scan_private_key
+            .shared_point(&public_key.tweak_mul(&input_hash)?)?;
+
+        let label_points = labels
+            .iter()
+            .map(|label| {
+                let tweak = self.label_tweak(*label);
+                let point =
+                    secp256k1::PublicKey::from_secret_key(&secp256k1::SecretKey::parse(&tweak)?)
+                        .serialize_compressed();
+                Ok((*label, tweak, point))
+            })
+            .collect::<Result<Vec<LabelPoint>, SilentPaymentError>>()?;
+
+        let mut outputs = parameters
+            .outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(vout, output)| match output.script_pub_key.as_slice() {
+                [0x51, 0x20, output_key @ ..] if output_key.len() == 32 => {
+                    let mut x_only = [0u8; 32];
+                    x_only.copy_from_slice(output_key);
+                    Some((vout as u32, x_only))
+                }
+                _ => None,
+            })
+            .collect::<Vec<(u32, [u8; 32])>>();
+
+        let spend_public_key = self.spend_private_key.to_public_key();
+        let mut found = vec![];
+        let mut k = 0u32;
+        loop {
+            let t_k = shared_secret_tweak(&shared_secret, k);
+            let p_k = spend_public_key.tweak_add(&t_k)?;
+            let negated_p_k = p_k.negate();
+
+            let mut matched = None;
+            for (position, (vout, output_key)) in outputs.iter().enumerate() {
+                if *output_key == p_k.to_x_only() {
+                    matched = Some((position, *vout, *output_key, None));
+                    break;
+                }
+
+                // A labeled output is P_k + label * G for one of the labels, up to the sign
+                // of the output key.
+                let output = BitcoinPublicKey::<N>::from_x_only(output_key)?;
+                for candidate in &[output.clone(), output.negate()] {
+                    let label_point = match candidate.combine(&negated_p_k) {
+                        Ok(label_point) => label_point.to_secp256k1_public_key(),
+                        Err(_) => continue,
+                    };
+                    let label_point = label_point.serialize_compressed();
+                    if let Some((label, tweak, _)) = label_points
+                        .iter()
+                        .find(|(_, _, point)| point[..] == label_point[..])
+                    {
+                        matched = Some((position, *vout, *output_key, Some((*label, *tweak))));
+                        break;
+                    }
+                }
+                if matched.is_some() {
+                    break;
+                }
+            }
+
+            let (position, vout, output_key, label) = match matched {
+                Some(matched) => matched,
+                None => break,
+            };
+            outputs.remove(position);
+
+            let mut tweak = secp256k1::SecretKey::parse(&t_k)?;
+            if let Some((_, label_tweak)) = &label {
+                tweak.tweak_add_assign(&secp256k1::SecretKey::parse(label_tweak)?)?;
+            }
+            let tweak = tweak.serialize();
+
+            found.push(BitcoinSilentPaymentOutput {
+                vout,
+                output_key,
+                tweak,
+                label: label.map(|(label, _)| label),
+                private_key: self.spend_private_key.tweak_add(&tweak)?,
+            });
+            k += 1;
+        }
+
+        Ok(found)
+    }
+}
+
+/// Returns the P2TR addresses paying each of the silent payment addresses, in order, from a
+/// transaction spending the outpoints. The private keys of the eligible inputs are given in the
+/// order of the outpoints, and the outpoints must include their script public keys. Spending a
+/// segwit version 2 or later output is an error, as receivers skip such transactions.
+pub fn create_outputs<N: BitcoinNetwork>(
+    outpoints: &[Outpoint<N>],
+    private_keys: &[Option<BitcoinPrivateKey<N>>],
+    recipients: &[BitcoinSilentPaymentAddress<N>],
+) -> Result<Vec<BitcoinAddress<N>>, SilentPaymentError> {
+    if outpoints.len() != private_keys.len() {
+        return Err(SilentPaymentError::InvalidPrevouts(
+            outpoints.len(),
+            private_keys.len(),
+        ));
+    }
+    for (index, outpoint) in outpoints.iter().enumerate() {
+        if let Some(version) = outpoint
+            .script_pub_key
+            .as_ref()
+            .and_then(|script_pub_key| future_witness_version(script_pub_key))
+        {
+            return Err(SilentPaymentError::UnsupportedWitnessVersion(
+                index, version,
+            ));
+        }
+    }
+
+    let mut private_key: Option<BitcoinPrivateKey<N>> = None;
+    for (index, (outpoint, input_private_key)) in outpoints.iter().zip(private_keys).enumerate() {
+        let input_private_key = match input_private_key {
+            Some(input_private_key) => input_private_key,
+            None => continue,
+        };
+        let script_pub_key = match &outpoint.script_pub_key {
+            Some(script_pub_key) => script_pub_key,
+            None => return Err(SilentPaymentError::MissingScriptPublicKey(index)),
+        };
+
+        let input_private_key = match script_pub_key.as_slice() {
+            [0x51, 0x20, ..] if script_pub_key.len() == 34 => input_private_key.to_even_y()?,
+            [0x00, 0x14, ..] if script_pub_key.len() == 22 => input_private_key.clone(),
+            [0xa9, 0x14, .., 0x87] if script_pub_key.len() == 23 => input_private_key.clone(),
+            [0x76, 0xa9, 0x14, .., 0x88, 0xac] if script_pub_key.len() == 25 => {
+                match input_private_key.is_compressed() {
+                    true => input_private_key.clone(),
+                    false => continue,
+                }
+            }
+            _ => continue,
+        };
+
+        private_key = Some(match private_key {
+            Some(private_key) => private_key.tweak_add(&Zeroizing::new(
+                input_private_key.expose_secret().serialize(),
+            ))?,
+            None => input_private_key,
+        });
+    }
+    let private_key = private_key.ok_or(SilentPaymentError::NoEligibleInputs)?;
+
+    let input_hash = input_hash(
+        &smallest_outpoint(outpoints.iter()),
+        &private_key.to_public_key(),
+    );
+    let private_key = private_key.tweak_mul(&input_hash)?;
+
+    // The outputs to the spend keys sharing a scan key are numbered k = 0, 1, ...
+    let mut shared_secrets: Vec<(BitcoinPublicKey<N>, BitcoinPublicKey<N>, u32)> = vec![];
+    let mut addresses = vec![];
+    for recipient in recipients {
+        let position = match shared_secrets.iter().position(|(scan_public_key, _, _)| {
+            scan_public_key
+                .to_secp256k1_public_key()
+                .serialize_compressed()
+                == recipient
+                    .scan_public_key
+                    .to_secp256k1_public_key()
+                    .serialize_compressed()
+        }) {
+            Some(position) => position,
+            None => {
+                let shared_secret = private_key.shared_point(&recipient.scan_public_key)?;
+                shared_secrets.push((recipient.scan_public_key.clone(), shared_secret, 0));
+                shared_secrets.len() - 1
+            }
+        };
+        let (_, shared_secret, k) = &mut shared_secrets[position];
+
+        let t_k = shared_secret_tweak(shared_secret, *k);
+        let output_key = recipient.spend_public_key.tweak_add(&t_k)?.to_x_only();
+        addresses.push(BitcoinAddress::p2tr_output_key(&output_key)?);
+        *k += 1;
+    }
+
+    Ok(addresses)
+}
+
+/// Returns the lexicographically smallest serialized outpoint.
+fn smallest_outpoint<'a, N: BitcoinNetwork + 'a>(
+    outpoints: impl Iterator<Item = &'a Outpoint<N>>,
+) -> Vec<u8> {
+    outpoints
+        .map(|outpoint| {
+            let mut serialized = outpoint.reverse_transaction_id.clone();
+            serialized.extend(&outpoint.index.to_le_bytes());
+            serialized
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+/// Returns the tweak hash_BIP0352/Inputs(outpoint_L || A).
+fn input_hash<N: BitcoinNetwork>(
+    smallest_outpoint: &[u8],
+    public_key: &BitcoinPublicKey<N>,
+) -> [u8; 32] {
+    tagged_hash(
+        "BIP0352/Inputs",
+        &[
+            smallest_outpoint,
+            &public_key.to_secp256k1_public_key().serialize_compressed(),
+        ],
+    )
+}
+
+/// Returns the tweak hash_BIP0352/SharedSecret(ecdh_shared_secret || k).
+fn shared_secret_tweak<N: BitcoinNetwork>(shared_secret: &BitcoinPublicKey<N>, k: u32) -> [u8; 32] {
+    tagged_hash(
+        "BIP0352/SharedSecret",
+        &[
+            &shared_secret
+                .to_secp256k1_public_key()
+                .serialize_compressed(),
+            &k.to_be_bytes(),
+        ],
+    )
+}
+
+/// Returns the witness version of a segwit version 2 to 16 output, OP_2..OP_16 followed by a
+/// push of a 2 to 40 byte program.
+fn future_witness_version(script_pub_key: &[u8]) -> Option<u8> {
+    match script_pub_key {
+        [opcode @ 0x52..=0x60, length, program @ ..]
+            if *length as usize == program.len() && (2..=40).contains(&program.len()) =>
+        {
+            Some(opcode - 0x50)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the public key of an input eligible for silent payments, given the script public key
+/// of the output it spends.
+fn input_public_key<N: BitcoinNetwork>(
+    input: &BitcoinTransactionInput<N>,
+    script_pub_key: &[u8],
+) -> Option<BitcoinPublicKey<N>> {
+    // Witness items are stored with their length prefix.
+    let mut witness = input
+        .witnesses
+        .iter()
+        .map(|item| {
+            let mut position = 0;
+            let length = read_compact_size(item, &mut position)?;
+            match item.len() == position + length {
+                true => Some(&item[position..]),
+                false => None,
+            }
+        })
+        .collect::<Option<Vec<&[u8]>>>()?;
+
+    let compressed_public_key = |bytes: &[u8]| -> Option<BitcoinPublicKey<N>> {
+        let mut compressed = [0u8; 33];
+        match bytes.len() {
+            33 => compressed.copy_from_slice(bytes),
+            _ => return None,
+        };
+        let public_key = secp256k1::PublicKey::parse_compressed(&compressed).ok()?;
+        Some(BitcoinPublicKey::from_secp256k1_public_key(
+            public_key, true,
+        ))
+    };
+
+    match script_pub_key {
+        // P2TR
+        [0x51, 0x20, output_key @ ..] if output_key.len() == 32 => {
+            if witness.len() > 1 && witness.last()?.first() == Some(&0x50) {
+                witness.pop();
+            }
+            if witness.is_empty() {
+                return None;
+            }
+            // Script path spends from the NUMS internal key are skipped.
+            if witness.len() > 1 && witness.last()?.get(1..33)? == &NUMS_H[..] {
+                return None;
+            }
+            let mut x_only = [0u8; 32];
+            x_only.copy_from_slice(output_key);
+            BitcoinPublicKey::from_x_only(&x_only).ok()
+        }
+        // P2WPKH
+        [0x00, 0x14, ..] if script_pub_key.len() == 22 => compressed_public_key(witness.last()?),
+        // P2SH-P2WPKH
+        [0xa9, 0x14, .., 0x87] if script_pub_key.len() == 23 => {
+            let redeem_script = read_pushes(&input.script_sig)?.last()?.clone();
+            match redeem_script.as_slice() {
+                [0x00, 0x14, ..] if redeem_script.len() == 22 => {
+                    compressed_public_key(witness.last()?)
+                }
+                _ => None,
+            }
+        }
+        // P2PKH
+        [0x76, 0xa9, 0x14, .., 0x88, 0xac] if script_pub_key.len() == 25 => {
+            let script_sig = &input.script_sig;
+            (33..=script_sig.len())
+                .rev()
+                .map(|end| &script_sig[end - 33..end])
+                .filter(|public_key| hash160(public_key)[..] == script_pub_key[3..23])
+                .find_map(compressed_public_key)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+
+    type N = Mainnet;
+
+    fn private_key(hex: &str) -> BitcoinPrivateKey<N> {
+        BitcoinPrivateKey::from_secp256k1_secret_key(
+            &secp256k1::SecretKey::parse_slice(&hex::decode(hex).unwrap()).unwrap(),
+            true,
+        )
+    }
+
+    fn outpoint(transaction_id: &str, index: u32, script_pub_key: &str) -> Outpoint<N> {
+        let mut reverse_transaction_id = hex::decode(transaction_id).unwrap();
+        reverse_transaction_id.reverse();
+        Outpoint {
+            reverse_transaction_id,
+            index,
+            amount: None,
+            script_pub_key: Some(hex::decode(script_pub_key).unwrap()),
+            redeem_script: None,
+            address: None,
+        }
+    }
+
+    /// An outpoint spent with a script sig and witness
+    type Input<'a> = (&'a Outpoint<N>, Vec<u8>, Vec<Vec<u8>>);
+
+    /// Returns a transaction spending the outpoints with the script sigs and witnesses, with
+    /// an output for each script public key.
+    fn transaction(inputs: &[Input], script_pub_keys: &[Vec<u8>]) -> BitcoinTransaction<N> {
+        let mut transaction = vec![0x02, 0x00, 0x00, 0x00, 0x00, 0x01, inputs.len() as u8];
+        for (outpoint, script_sig, _) in inputs {
+            transaction.extend(&outpoint.reverse_transaction_id);
+            transaction.extend(&outpoint.index.to_le_bytes());
+            transaction.push(script_sig.len() as u8);
+            transaction.extend(script_sig);
+            transaction.extend(&[0xff; 4]);
+        }
+        transaction.push(script_pub_keys.len() as u8);
+        for script_pub_key in script_pub_keys {
+            transaction.extend(&1000u64.to_le_bytes());
+            transaction.push(script_pub_key.len() as u8);
+            transaction.extend(script_pub_key);
+        }
+        for (_, _, witness) in inputs {
+            transaction.push(witness.len() as u8);
+            for item in witness {
+                transaction.push(item.len() as u8);
+                transaction.extend(item);
+            }
+        }
+        transaction.extend(&[0x00; 4]);
+        BitcoinTransaction::from_str(&hex::encode(transaction)).unwrap()
+    }
+
+    /// Returns a P2PKH script sig with a placeholder signature and the public key.
+    fn p2pkh_script_sig(private_key: &BitcoinPrivateKey<N>) -> Vec<u8> {
+        let public_key = private_key
+            .to_public_key()
+            .to_secp256k1_public_key()
+            .serialize_compressed();
+        [vec![0x47], vec![0x30; 71], vec![0x21], public_key.to_vec()].concat()
+    }
+
+    fn p2tr_script_pub_key(output_key: &[u8]) -> Vec<u8> {
+        [&[0x51, 0x20][..], output_key].concat()
+    }
+
+    #[test]
+    fn bip352_simple_send() {
+        let outpoints = [
+            outpoint(
+                "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16",
+                0,
+                "76a91419c2f3ae0ca3b642bd3e49598b8da89f50c1416188ac",
+            ),
+            outpoint(
+                "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d",
+                0,
+                "76a914d9317c66f54ff0a152ec50b1d19c25be50c8e15988ac",
+            ),
+        ];
+        let input_private_keys = [
+            private_key("eadc78165ff1f8ea94ad7cfdc54990738a4c53f6e0507b42154201b8e5dff3b1"),
+            private_key("93f5ed907ad5b2bdbbdcb5d9116ebc0a4e1f92f910d5260237fa45a9408aad16"),
+        ];
+        let keys = BitcoinSilentPaymentKeys::new(
+            private_key("0f694e068028a717f8af6b9411f9a133dd3565258714cc226594b34db90c1f2c"),
+            private_key("9d6ad855ce3417ef84e836892e5a56392bfba05fa5d97ccea30e266f540e08b3"),
+        );
+        let address = "sp1qqgste7k9hx0qftg6qmwlkqtwuy6cycyavzmzj85c6qdfhjdpdjtdgqjuexzk6murw56suy3e0rd2cgqvycxttddwsvgxe2usfpxumr70xc9pkqwv";
+        let output_key = "3e9fce73d4e77a4809908e3c3a2e54ee147b9312dc5044a193d1fc85de46e3c1";
+
+        assert_eq!(address, keys.to_address().to_string());
+        let recipient = BitcoinSilentPaymentAddress::<N>::from_str(address).unwrap();
+        assert_eq!(keys.to_address(), recipient);
+
+        let outputs = create_outputs(
+            &outpoints,
+            &[
+                Some(input_private_keys[0].clone()),
+                Some(input_private_keys[1].clone()),
+            ],
+            &[recipient],
+        )
+        .unwrap();
+        assert_eq!(1, outputs.len());
+        assert_eq!(
+            output_key,
+            hex::encode(outputs[0].witness_program().unwrap().program)
+        );
+
+        let transaction = transaction(
+            &[
+                (
+                    &outpoints[0],
+                    p2pkh_script_sig(&input_private_keys[0]),
+                    vec![],
+                ),
+                (
+                    &outpoints[1],
+                    p2pkh_script_sig(&input_private_keys[1]),
+                    vec![],
+                ),
+            ],
+            &[p2tr_script_pub_key(&hex::decode(output_key).unwrap())],
+        );
+        let prevout_script_pub_keys = outpoints
+            .iter()
+            .map(|outpoint| outpoint.script_pub_key.clone().unwrap())
+            .collect::<Vec<Vec<u8>>>();
+        let found = keys
+            .scan(&transaction, &prevout_script_pub_keys, &[])
+            .unwrap();
+        assert_eq!(1, found.len());
+        assert_eq!(0, found[0].vout);
+        assert_eq!(output_key, hex::encode(found[0].output_key));
+        assert_eq!(None, found[0].label);
+        assert_eq!(
+            found[0].output_key,
+            found[0].private_key.to_public_key().to_x_only()
+        );
+    }
+
+    #[test]
+    fn labels_and_multiple_recipients() {
+        let key = |byte: u8| private_key(&hex::encode([byte; 32]));
+        let keys = BitcoinSilentPaymentKeys::new(key(0x11), key(0x22));
+        let other_keys = BitcoinSilentPaymentKeys::new(key(0x66), key(0x77));
+        let address = keys.to_address();
+        let labeled_address = keys.to_labeled_address(1).unwrap();
+        assert_ne!(address, labeled_address);
+        assert_eq!(address.scan_public_key(), labeled_address.scan_public_key());
+        assert_eq!(
+            labeled_address,
+            BitcoinSilentPaymentAddress::from_str(&labeled_address.to_string()).unwrap()
+        );
+
+        let public_key = |byte: u8| {
+            key(byte)
+                .to_public_key()
+                .to_secp256k1_public_key()
+                .serialize_compressed()
+        };
+        let p2wpkh = [vec![0x00, 0x14], hash160(&public_key(0x33))].concat();
+        let p2tr = p2tr_script_pub_key(&public_key(0x44)[1..]);
+        let p2pkh = [
+            vec![0x76, 0xa9, 0x14],
+            hash160(&public_key(0x55)),
+            vec![0x88, 0xac],
+        ]
+        .concat();
+        let outpoints = [
+            outpoint(&"a1".repeat(32), 3, &hex::encode(&p2wpkh)),
+            outpoint(&"a1".repeat(32), 1, &hex::encode(&p2tr)),
+            outpoint(&"b2".repeat(32), 0, &hex::encode(&p2pkh)),
+        ];
+
+        let outputs = create_outputs(
+            &outpoints,
+            &[Some(key(0x33)), Some(key(0x44)), Some(key(0x55))],
+            &[
+                address.clone(),
+                address.clone(),
+                labeled_address,
+                other_keys.to_address(),
+            ],
+        )
+        .unwrap();
+        let output_keys = outputs
+            .iter()
+            .map(|output| output.witness_program().unwrap().program)
+            .collect::<Vec<Vec<u8>>>();
+        assert_ne!(output_keys[0], output_keys[1]);
+
+        let transaction = transaction(
+            &[
+                (
+                    &outpoints[0],
+                    vec![],
+                    vec![vec![0x30; 71], public_key(0x33).to_vec()],
+                ),
+                (&outpoints[1], vec![], vec![vec![0x01; 64]]),
+                (&outpoints[2], p2pkh_script_sig(&key(0x55)), vec![]),
+            ],
+            &output_keys
+                .iter()
+                .map(|output_key| p2tr_script_pub_key(output_key))
+                .collect::<Vec<Vec<u8>>>(),
+        );
+        let prevouts = [p2wpkh.clone(), p2tr, p2pkh.clone()];
+
+        let found = keys.scan(&transaction, &prevouts, &[0, 1]).unwrap();
+        assert_eq!(3, found.len());
+        assert_eq!(
+            1,
+            found
+                .iter()
+                .filter(|output| output.label == Some(1))
+                .count()
+        );
+        for output in &found {
+            assert_eq!(
+                output_keys[output.vout as usize],
+                output.output_key.to_vec()
+            );
+            assert_eq!(
+                output.output_key,
+                output.private_key.to_public_key().to_x_only()
+            );
+        }
+
+        // Without the label only the unlabeled outputs are found.
+        assert_eq!(2, keys.scan(&transaction, &prevouts, &[]).unwrap().len());
+
+        let found = other_keys.scan(&transaction, &prevouts, &[]).unwrap();
+        assert_eq!(1, found.len());
+        assert_eq!(3, found[0].vout);
+
+        // Other prevouts give another shared secret.
+        let prevouts = [p2wpkh.clone(), p2wpkh, p2pkh];
+        assert!(keys.scan(&transaction, &prevouts, &[]).unwrap().is_empty());
+        assert!(keys.scan(&transaction, &prevouts[..2], &[]).is_err());
+    }
+
+    #[test]
+    fn future_witness_version() {
+        let key = |byte: u8| private_key(&hex::encode([byte; 32]));
+        let keys = BitcoinSilentPaymentKeys::new(key(0x11), key(0x22));
+        let public_key = key(0x33)
+            .to_public_key()
+            .to_secp256k1_public_key()
+            .serialize_compressed();
+        let p2wpkh = [vec![0x00, 0x14], hash160(&public_key)].concat();
+        let p2wsh = [vec![0x00, 0x20], vec![0x01; 32]].concat();
+        let segwit_v2 = [vec![0x52, 0x20], vec![0x01; 32]].concat();
+        let outpoints = [
+            outpoint(&"a1".repeat(32), 0, &hex::encode(&p2wpkh)),
+            outpoint(&"a1".repeat(32), 1, &hex::encode(&p2wsh)),
+        ];
+
+        let outputs =
+            create_outputs(&outpoints, &[Some(key(0x33)), None], &[keys.to_address()]).unwrap();
+        let transaction = transaction(
+            &[
+                (
+                    &outpoints[0],
+                    vec![],
+                    vec![vec![0x30; 71], public_key.to_vec()],
+                ),
+                (&outpoints[1], vec![], vec![vec![0x51]]),
+            ],
+            &[p2tr_script_pub_key(
+                &outputs[0].witness_program().unwrap().program,
+            )],
+        );
+        let prevouts = [p2wpkh.clone(), p2wsh];
+        assert_eq!(1, keys.scan(&transaction, &prevouts, &[]).unwrap().len());
+
+        // Spending a segwit version 2 output, the sender must not pay and the receiver skips.
+        let prevouts = [p2wpkh, segwit_v2.clone()];
+        assert!(keys.scan(&transaction, &prevouts, &[]).unwrap().is_empty());
+        let outpoints = [
+            outpoints[0].clone(),
+            outpoint(&"a1".repeat(32), 1, &hex::encode(&segwit_v2)),
+        ];
+        assert!(matches!(
+            create_outputs(&outpoints, &[Some(key(0x33)), None], &[keys.to_address()]),
+            Err(SilentPaymentError::UnsupportedWitnessVersion(1, 2))
+        ));
+    }
+
+    #[test]
+    fn invalid_address() {
+        let keys = BitcoinSilentPaymentKeys::new(
+            private_key(&"11".repeat(32)),
+            private_key(&"22".repeat(32)),
+        );
+        let address = keys.to_address().to_string();
+        assert!(address.starts_with("sp1q"));
+        let mut corrupted = address.clone().into_bytes();
+        corrupted[10] = if corrupted[10] == b'q' { b'p' } else { b'q' };
+        assert!(
+            BitcoinSilentPaymentAddress::<N>::from_str(&String::from_utf8(corrupted).unwrap())
+                .is_err()
+        );
+        assert!(
+            BitcoinSilentPaymentAddress::<N>::from_str(&address.replacen("sp1", "tsp1", 1))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn from_extended_private_key() {
+        let master = BitcoinExtendedPrivateKey::<N>::new_master(
+            &[7u8; 64],
+            &crate::format::BitcoinFormat::P2PKH,
+        )
+        .unwrap();
+        let keys = BitcoinSilentPaymentKeys::from_extended_private_key(&master, 0).unwrap();
+        let derive = |key_type: u32| {
+            let path = vec![
+                ChildIndex::Hardened(352),
+                ChildIndex::Hardened(0),
+                ChildIndex::Hardened(0),
+                ChildIndex::Hardened(key_type),
+                ChildIndex::Normal(0),
+            ];
+            master
+                .derive(&BitcoinDerivationPath::BIP32(path, PhantomData))
+                .unwrap()
+                .to_private_key()
+        };
+        assert_eq!(&derive(1), keys.scan_private_key());
+        assert_eq!(&derive(0), keys.spend_private_key());
+        assert!(BitcoinSilentPaymentKeys::from_extended_private_key(&master, 1 << 31).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::format::BitcoinFormat;
+
+/// Represents a SLIP-132 registered pair of extended key version bytes
+/// https://github.com/satoshilabs/slips/blob/master/slip-0132.md
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slip132Version {
+    /// The name of the network of the version bytes
+    pub network: &'static str,
+    /// The address format of keys serialized with the version bytes
+    pub format: BitcoinFormat,
+    /// The script type encoded in the version bytes
+    pub script_type: &'static str,
+    pub public_prefix: &'static str,
+    pub public_version: [u8; 4],
+    pub private_prefix: &'static str,
+    pub private_version: [u8; 4],
+}
+
+/// The registry of extended key version bytes, by network and script type
+pub const SLIP132_VERSIONS: &[Slip132Version] = &[
+    Slip132Version {
+        network: "mainnet",
+        format: BitcoinFormat::P2PKH,
+        script_type: "P2PKH or P2SH",
+        public_prefix: "xpub",
+        public_version: [0x04, 0x88, 0xB2, 0x1E],
+        private_prefix: "xprv",
+        private_version: [0x04, 0x88, 0xAD, 0xE4],
+    },
+    Slip132Version {
+        network: "mainnet",
+        format: BitcoinFormat::P2SH_P2WPKH,
+        script_type: "P2WPKH nested in P2SH",
+        public_prefix: "ypub",
+        public_version: [0x04, 0x9D, 0x7C, 0xB2],
+        private_prefix: "yprv",
+        private_version: [0x04, 0x9D, 0x78, 0x78],
+    },
+    Slip132Version {
+        network: "mainnet",
+        format: BitcoinFormat::Bech32,
+        script_type: "P2WPKH",
+        public_prefix: "zpub",
+        public_version: [0x04, 0xB2, 0x47, 0x46],
+        private_prefix: "zprv",
+        private_version: [0x04, 0xB2, 0x43, 0x0C],
+    },
+    Slip132Version {
+        network: "mainnet",
+        format: BitcoinFormat::P2SH_P2WSH,
+        script_type: "multi-signature P2WSH nested in P2SH",
+        public_prefix: "Ypub",
+        public_version: [0x02, 0x95, 0xB4, 0x3F],
+        private_prefix: "Yprv",
+        private_version: [0x02, 0x95, 0xB0, 0x05],
+    },
+    Slip132Version {
+        network: "mainnet",
+        format: BitcoinFormat::P2WSH,
+        script_type: "multi-signature P2WSH",
+        public_prefix: "Zpub",
+        public_version: [0x02, 0xAA, 0x7E, 0xD3],
+        private_prefix: "Zprv",
+        private_version: [0x02, 0xAA, 0x7A, 0x99],
+    },
+    Slip132Version {
+        network: "testnet",
+        format: BitcoinFormat::P2PKH,
+        script_type: "P2PKH or P2SH",
+        public_prefix: "tpub",
+        public_version: [0x04, 0x35, 0x87, 0xCF],
+        private_prefix: "tprv",
+        private_version: [0x04, 0x35, 0x83, 0x94],
+    },
+    Slip132Version {
+        network: "testnet",
+        format: BitcoinFormat::P2SH_P2WPKH,
+        script_type: "P2WPKH nested in P2SH",
+        public_prefix: "upub",
+        public_version: [0x04, 0x4A, 0x52, 0x62],
+        private_prefix: "uprv",
+        private_version: [0x04, 0x4A, 0x4E, 0x28],
+    },
+    Slip132Version {
+        network: "testnet",
+        format: BitcoinFormat::Bech32,
+        script_type: "P2WPKH",
+        public_prefix: "vpub",
+        public_version: [0x04, 0x5F, 0x1C, 0xF6],
+        private_prefix: "vprv",
+        private_version: [0x04, 0x5F, 0x18, 0xBC],
+    },
+    Slip132Version {
+        network: "testnet",
+        format: BitcoinFormat::P2SH_P2WSH,
+        script_type: "multi-signature P2WSH nested in P2SH",
+        public_prefix: "Upub",
+        public_version: [0x02, 0x42, 0x89, 0xEF],
+        private_prefix: "Uprv",
+        private_version: [0x02, 0x42, 0x85, 0xB5],
+    },
+    Slip132Version {
+        network: "testnet",
+        format: BitcoinFormat::P2WSH,
+        script_type: "multi-signature P2WSH",
+        public_prefix: "Vpub",
+        public_version: [0x02, 0x57, 0x54, 0x83],
+        private_prefix: "Vprv",
+        private_version: [0x02, 0x57, 0x50, 0x48],
+    },
+];
+
+impl Slip132Version {
+    /// Returns the version bytes of extended keys of the format on the network. P2SH and
+    /// P2TR keys have no version bytes of their own and use those of P2PKH.
+    pub fn from_format(network: &str, format: &BitcoinFormat) -> Option<&'static Self> {
+        let format = match format {
+            BitcoinFormat::P2SH | BitcoinFormat::P2TR => &BitcoinFormat::P2PKH,
+            format => format,
+        };
+        SLIP132_VERSIONS
+            .iter()
+            .find(|version| version.network == network && version.format == *format)
+    }
+
+    /// Returns the registry entry of the extended public key version bytes.
+    pub fn from_public_version(version: &[u8]) -> Option<&'static Self> {
+        SLIP132_VERSIONS
+            .iter()
+            .find(|entry| version.get(0..4) == Some(&entry.public_version[..]))
+    }
+
+    /// Returns the registry entry of the extended private key version bytes.
+    pub fn from_private_version(version: &[u8]) -> Option<&'static Self> {
+        SLIP132_VERSIONS
+            .iter()
+            .find(|entry| version.get(0..4) == Some(&entry.private_version[..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extended_private_key::BitcoinExtendedPrivateKey;
+    use crate::extended_public_key::BitcoinExtendedPublicKey;
+    use crate::network::Mainnet;
+    use gyu_model::extended_private_key::ExtendedPrivateKey;
+    use gyu_model::utilities::crypto::checksum;
+
+    use base58::ToBase58;
+    use core::str::FromStr;
+
+    type N = Mainnet;
+
+    /// The BIP84 account m/84'/0'/0' of the mnemonic "abandon abandon ... about".
+    const ZPUB: &str = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+    const XPUB: &str = "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V";
+
+    /// Returns the base58 serialization of an extended key with the version bytes.
+    fn serialize(version: &[u8; 4], filler: u8) -> String {
+        let mut data = version.to_vec();
+        data.extend_from_slice(&[filler; 74]);
+        let checksum = checksum(&data);
+        data.extend_from_slice(&checksum[0..4]);
+        data.to_base58()
+    }
+
+    #[test]
+    fn registry_prefixes() {
+        for version in SLIP132_VERSIONS {
+            for filler in [0x00, 0xff].iter() {
+                assert!(
+                    serialize(&version.public_version, *filler).starts_with(version.public_prefix)
+                );
+                assert!(serialize(&version.private_version, *filler)
+                    .starts_with(version.private_prefix));
+            }
+            assert_eq!(
+                Some(version),
+                Slip132Version::from_public_version(&version.public_version)
+            );
+            assert_eq!(
+                Some(version),
+                Slip132Version::from_private_version(&version.private_version)
+            );
+            assert_eq!(
+                Some(version),
+                Slip132Version::from_format(version.network, &version.format)
+            );
+        }
+
+        assert_eq!(
+            Slip132Version::from_format("mainnet", &BitcoinFormat::P2PKH),
+            Slip132Version::from_format("mainnet", &BitcoinFormat::P2TR)
+        );
+        assert_eq!(None, Slip132Version::from_public_version(&[0, 0, 0, 0]));
+        assert_eq!(None, Slip132Version::from_private_version(&[0x04, 0x88]));
+    }
+
+    #[test]
+    fn convert_extended_public_keys() {
+        let zpub = BitcoinExtendedPublicKey::<N>::from_str(ZPUB).unwrap();
+        assert_eq!("zpub", zpub.slip132_version().unwrap().public_prefix);
+        assert_eq!("P2WPKH", zpub.slip132_version().unwrap().script_type);
+
+        let xpub = zpub.to_format(&BitcoinFormat::P2PKH).unwrap();
+        assert_eq!(XPUB, xpub.to_string());
+
+        for (format, prefix) in [
+            (BitcoinFormat::P2SH_P2WPKH, "ypub"),
+            (BitcoinFormat::P2SH_P2WSH, "Ypub"),
+            (BitcoinFormat::P2WSH, "Zpub"),
+        ]
+        .iter()
+        {
+            let converted = zpub.to_format(format).unwrap().to_string();
+            assert!(converted.starts_with(prefix));
+
+            let parsed = BitcoinExtendedPublicKey::<N>::from_str(&converted).unwrap();
+            assert_eq!(*format, parsed.format());
+            assert_eq!(converted, parsed.to_string());
+            assert_eq!(zpub, parsed.to_format(&BitcoinFormat::Bech32).unwrap());
+        }
+
+        // Testnet version bytes are rejected on mainnet
+        let tpub = "tpubD6NzVbkrYhZ4XgiXtGrdW5XDAPFCL9h7we1vwNCpn8tGbBcgfVYjXyhWo4E1xkh56hjod1RhGjxbaTLV3X4FyWuejifB9jusQ46QzG87VKp";
+        assert!(BitcoinExtendedPublicKey::<N>::from_str(tpub).is_err());
+    }
+
+    #[test]
+    fn convert_extended_private_keys() {
+        let xprv =
+            BitcoinExtendedPrivateKey::<N>::new_master(&[7u8; 32], &BitcoinFormat::P2PKH).unwrap();
+        for format in [
+            BitcoinFormat::P2SH_P2WPKH,
+            BitcoinFormat::P2SH_P2WSH,
+            BitcoinFormat::P2WSH,
+            BitcoinFormat::Bech32,
+        ]
+        .iter()
+        {
+            let converted = xprv.to_format(format).unwrap();
+            let serialized = converted.to_string();
+            assert!(serialized.starts_with(converted.slip132_version().unwrap().private_prefix));
+
+            let parsed = BitcoinExtendedPrivateKey::<N>::from_str(&serialized).unwrap();
+            assert_eq!(*format, parsed.format());
+            assert_eq!(
+                xprv.to_string(),
+                parsed.to_format(&BitcoinFormat::P2PKH).unwrap().to_string()
+            );
+        }
+    }
+}
@@ -0,0 +1,14 @@
+//! Keys shared by the tests of several modules.
+
+use crate::extended_private_key::BitcoinExtendedPrivateKey;
+use crate::format::BitcoinFormat;
+use crate::network::Mainnet;
+use gyu_model::extended_private_key::ExtendedPrivateKey;
+
+/// The seed of the BIP39 mnemonic "abandon abandon ... about" with no passphrase.
+pub const SEED: &str = "5eb00bbddcf069084889a8ab9155568165f5c453ccb85e70811aaed6f6da5fc19a5ac40b389cd370d086206dec8aa6c43daea6690f20ad3d8d48b2d2ce9e38e4";
+
+/// Returns the mainnet master key of the seed in the format.
+pub fn master(format: &BitcoinFormat) -> BitcoinExtendedPrivateKey<Mainnet> {
+    BitcoinExtendedPrivateKey::new_master(&hex::decode(SEED).unwrap(), format).unwrap()
+}
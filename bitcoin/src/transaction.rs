@@ -4,6 +4,8 @@ use crate::format::BitcoinFormat;
 use crate::network::BitcoinNetwork;
 use crate::private_key::BitcoinPrivateKey;
 use crate::public_key::BitcoinPublicKey;
+use crate::schnorr::{self, tagged_hash, tweak_secret_key};
+use crate::signature::{sign_low_r, verify};
 use crate::witness_program::WitnessProgram;
 use core::fmt;
 use core::str::FromStr;
@@ -13,6 +15,8 @@ use base58::FromBase58;
 use bech32::{Bech32, FromBase32};
 
 use gyu_model::private_key::PrivateKey;
+use gyu_model::public_key::PublicKey;
+use gyu_model::signer::{Signer, SignerError};
 use gyu_model::transaction::Transaction;
 use gyu_model::transaction::TransactionError;
 use gyu_model::transaction::TransactionId;
@@ -31,13 +35,13 @@ pub fn variable_length_integer(value: u64) -> Result<Vec<u8>, TransactionError>
 
 pub fn read_variable_length_integer<R: Read>(mut reader: R) -> Result<usize, TransactionError> {
     let mut flag = [0u8; 1];
-    reader.read(&mut flag)?;
+    reader.read_exact(&mut flag)?;
 
     match flag[0] {
         0..=252 => Ok(flag[0] as usize),
         0xfd => {
             let mut size = [0u8; 2];
-            reader.read(&mut size)?;
+            reader.read_exact(&mut size)?;
             match u16::from_le_bytes(size) {
                 s if s < 253 => {
                     return Err(TransactionError::InvalidVariableSizeInteger(s as usize))
@@ -47,7 +51,7 @@ pub fn read_variable_length_integer<R: Read>(mut reader: R) -> Result<usize, Tra
         }
         0xfe => {
             let mut size = [0u8; 4];
-            reader.read(&mut size)?;
+            reader.read_exact(&mut size)?;
             match u32::from_le_bytes(size) {
                 s if s < 65536 => {
                     return Err(TransactionError::InvalidVariableSizeInteger(s as usize))
@@ -57,7 +61,7 @@ pub fn read_variable_length_integer<R: Read>(mut reader: R) -> Result<usize, Tra
         }
         _ => {
             let mut size = [0u8; 8];
-            reader.read(&mut size)?;
+            reader.read_exact(&mut size)?;
             match u64::from_le_bytes(size) {
                 s if s < 4294967296 => {
                     return Err(TransactionError::InvalidVariableSizeInteger(s as usize))
@@ -116,7 +120,7 @@ pub fn create_script_pub_key<N: BitcoinNetwork>(
             script_bytes.extend(script);
             Ok(script_bytes)
         }
-        BitcoinFormat::P2SH_P2WPKH => {
+        BitcoinFormat::P2SH | BitcoinFormat::P2SH_P2WPKH | BitcoinFormat::P2SH_P2WSH => {
             let script_bytes = &address.to_string().from_base58()?;
             let script_hash = script_bytes[1..(script_bytes.len() - 4)].to_vec();
 
@@ -136,12 +140,16 @@ pub fn create_script_pub_key<N: BitcoinNetwork>(
 
             Ok(WitnessProgram::new(&program_bytes)?.to_scriptpubkey())
         }
+        BitcoinFormat::P2TR => Ok(address.witness_program()?.to_scriptpubkey()),
     }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 #[allow(non_camel_case_types)]
 pub enum SignatureHash {
+    /// Taproot only, signs as SIG_ALL without appending the sighash byte
+    SIGHASH_DEFAULT = 0x00,
+
     SIG_ALL = 0x01,
 
     SIG_NONE = 0x02,
@@ -158,6 +166,7 @@ pub enum SignatureHash {
 impl fmt::Display for SignatureHash {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            SignatureHash::SIGHASH_DEFAULT => write!(f, "SIGHASH_DEFAULT"),
             SignatureHash::SIG_ALL => write!(f, "SIG_HASH"),
             SignatureHash::SIG_NONE => write!(f, "SIG_NONE"),
             SignatureHash::SIG_SINGLE => write!(f, "SIG_SINGLE"),
@@ -196,6 +205,7 @@ pub enum Opcode {
     OP_CHECKSIG = 0xac,
     OP_EQUAL = 0x87,
     OP_EQUALVERIFY = 0x88,
+    OP_CHECKMULTISIG = 0xae,
 }
 
 impl fmt::Display for Opcode {
@@ -206,6 +216,7 @@ impl fmt::Display for Opcode {
             Opcode::OP_CHECKSIG => write!(f, "OP_CHECKSIG"),
             Opcode::OP_EQUAL => write!(f, "OP_EQUAL"),
             Opcode::OP_EQUALVERIFY => write!(f, "OP_EQUALVERIFY"),
+            Opcode::OP_CHECKMULTISIG => write!(f, "OP_CHECKMULTISIG"),
         }
     }
 }
@@ -268,10 +279,38 @@ impl<N: BitcoinNetwork> Outpoint<N> {
                         },
                         None => return Err(TransactionError::InvalidInputs("P2SH_P2WPKH".into())),
                     },
+                    BitcoinFormat::P2SH => match redeem_script {
+                        Some(redeem_script) => match script_pub_key[0] != Opcode::OP_HASH160 as u8
+                            || script_pub_key[script_pub_key.len() - 1] != Opcode::OP_EQUAL as u8
+                        {
+                            true => {
+                                return Err(TransactionError::InvalidScriptPubKey("P2SH".into()))
+                            }
+                            false => Some(redeem_script),
+                        },
+                        None => return Err(TransactionError::InvalidInputs("P2SH".into())),
+                    },
+                    BitcoinFormat::P2SH_P2WSH => match redeem_script {
+                        Some(redeem_script) => match script_pub_key[0] != Opcode::OP_HASH160 as u8
+                            || script_pub_key[script_pub_key.len() - 1] != Opcode::OP_EQUAL as u8
+                        {
+                            true => {
+                                return Err(TransactionError::InvalidScriptPubKey(
+                                    "P2SH_P2WSH".into(),
+                                ))
+                            }
+                            false => Some(redeem_script),
+                        },
+                        None => return Err(TransactionError::InvalidInputs("P2SH_P2WSH".into())),
+                    },
                     BitcoinFormat::Bech32 => match redeem_script.is_some() {
                         true => return Err(TransactionError::InvalidInputs("Bech32".into())),
                         false => None,
                     },
+                    BitcoinFormat::P2TR => match redeem_script.is_some() {
+                        true => return Err(TransactionError::InvalidInputs("P2TR".into())),
+                        false => None,
+                    },
                 };
 
                 (Some(script_pub_key), redeem_script)
@@ -348,8 +387,8 @@ impl<N: BitcoinNetwork> BitcoinTransactionInput<N> {
         let mut vin = [0u8; 4];
         let mut sequence = [0u8; 4];
 
-        reader.read(&mut transaction_hash)?;
-        reader.read(&mut vin)?;
+        reader.read_exact(&mut transaction_hash)?;
+        reader.read_exact(&mut vin)?;
 
         let outpoint = Outpoint::<N>::new(
             transaction_hash.to_vec(),
@@ -362,16 +401,19 @@ impl<N: BitcoinNetwork> BitcoinTransactionInput<N> {
 
         let script_sig: Vec<u8> = BitcoinVector::read(&mut reader, |s| {
             let mut byte = [0u8; 1];
-            s.read(&mut byte)?;
+            s.read_exact(&mut byte)?;
             Ok(byte[0])
         })?;
 
-        reader.read(&mut sequence)?;
+        reader.read_exact(&mut sequence)?;
 
-        let script_sig_len = read_variable_length_integer(&script_sig[..])?;
+        let script_sig_len = match script_sig.is_empty() {
+            true => 0,
+            false => read_variable_length_integer(&script_sig[..])?,
+        };
         let sighash_code = SignatureHash::from_byte(&match script_sig_len {
             0 => 0x01,
-            length => script_sig[length],
+            length => *script_sig.get(length).unwrap_or(&0x01),
         });
 
         Ok(Self {
@@ -396,6 +438,7 @@ impl<N: BitcoinNetwork> BitcoinTransactionInput<N> {
                 0 => match &self.outpoint.address {
                     Some(address) => match address.format() {
                         BitcoinFormat::Bech32 => input.extend(vec![0x00]),
+                        BitcoinFormat::P2TR => input.extend(vec![0x00]),
                         BitcoinFormat::P2WSH => input.extend(vec![0x00]),
                         _ => {
                             let script_pub_key = match &self.outpoint.script_pub_key {
@@ -441,11 +484,11 @@ impl BitcoinTransactionOutput {
 
     pub fn read<R: Read>(mut reader: &mut R) -> Result<Self, TransactionError> {
         let mut amount = [0u8; 8];
-        reader.read(&mut amount)?;
+        reader.read_exact(&mut amount)?;
 
         let script_pub_key: Vec<u8> = BitcoinVector::read(&mut reader, |s| {
             let mut byte = [0u8; 1];
-            s.read(&mut byte)?;
+            s.read_exact(&mut byte)?;
             Ok(byte[0])
         })?;
 
@@ -471,6 +514,18 @@ pub struct BitcoinTransactionId {
 }
 
 impl TransactionId for BitcoinTransactionId {}
+
+impl BitcoinTransactionId {
+    /// Returns the transaction id in display (big-endian) byte order.
+    pub fn txid(&self) -> &[u8] {
+        &self.txid
+    }
+
+    /// Returns the witness transaction id in display (big-endian) byte order.
+    pub fn wtxid(&self) -> &[u8] {
+        &self.wtxid
+    }
+}
 impl fmt::Display for BitcoinTransactionId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", &hex::encode(&self.txid))
@@ -489,13 +544,13 @@ pub struct BitcoinTransactionParameters<N: BitcoinNetwork> {
 impl<N: BitcoinNetwork> BitcoinTransactionParameters<N> {
     pub fn read<R: Read>(mut reader: R) -> Result<Self, TransactionError> {
         let mut version = [0u8; 4];
-        reader.read(&mut version)?;
+        reader.read_exact(&mut version)?;
 
         let mut inputs = BitcoinVector::read(&mut reader, BitcoinTransactionInput::<N>::read)?;
         let segwit_flag = match inputs.is_empty() {
             true => {
                 let mut flag = [0u8; 1];
-                reader.read(&mut flag)?;
+                reader.read_exact(&mut flag)?;
                 match flag[0] {
                     1 => {
                         inputs =
@@ -514,14 +569,19 @@ impl<N: BitcoinNetwork> BitcoinTransactionParameters<N> {
                 let witness: Vec<Vec<u8>> = BitcoinVector::read(&mut reader, |s| {
                     let (size, witness) = BitcoinVector::read_witness(s, |sr| {
                         let mut byte = [0u8; 1];
-                        sr.read(&mut byte)?;
+                        sr.read_exact(&mut byte)?;
                         Ok(byte[0])
                     })?;
                     Ok([variable_length_integer(size as u64)?, witness?].concat())
                 })?;
                 if witness.len() > 0 {
-                    input.sighash_code =
-                        SignatureHash::from_byte(&witness[0][&witness[0].len() - 1]);
+                    input.sighash_code = match witness.as_slice() {
+                        // A lone 64 byte signature is a taproot key path SIGHASH_DEFAULT spend
+                        [signature] if signature.len() == 65 && signature[0] == 64 => {
+                            SignatureHash::SIGHASH_DEFAULT
+                        }
+                        _ => SignatureHash::from_byte(&witness[0][&witness[0].len() - 1]),
+                    };
                     input.is_signed = true;
                 }
                 input.witnesses = witness;
@@ -529,7 +589,7 @@ impl<N: BitcoinNetwork> BitcoinTransactionParameters<N> {
         }
 
         let mut lock_time = [0u8; 4];
-        reader.read(&mut lock_time)?;
+        reader.read_exact(&mut lock_time)?;
 
         let transaction_parameters = BitcoinTransactionParameters::<N> {
             version: u32::from_le_bytes(version),
@@ -563,6 +623,139 @@ impl<N: BitcoinNetwork> Transaction for BitcoinTransaction<N> {
     }
 
     fn sign(&self, private_key: &Self::PrivateKey) -> Result<Self, TransactionError> {
+        let secret_key = private_key.to_secp256k1_secret_key();
+        self.sign_with(&private_key.to_public_key(), |transaction_hash, format| {
+            match format {
+                BitcoinFormat::P2TR => {
+                    // Key path spend with the BIP86 tweaked key, deterministic with zero
+                    // auxiliary randomness as BIP340 permits
+                    let mut message = [0u8; 32];
+                    message.copy_from_slice(transaction_hash);
+                    let secret_key = tweak_secret_key(&secret_key, None)?;
+                    Ok(schnorr::sign(&message, &secret_key, &[0u8; 32])?.to_vec())
+                }
+                _ => {
+                    // Deterministic with low R and low S for a predictable size
+                    let (signature, _) = sign_low_r(
+                        &secp256k1::Message::parse_slice(transaction_hash)?,
+                        &secret_key,
+                    );
+                    Ok(signature.serialize_der().as_ref().to_vec())
+                }
+            }
+        })
+    }
+
+    fn from_transaction_bytes(transaction: &Vec<u8>) -> Result<Self, TransactionError> {
+        Self::read(&transaction[..])
+    }
+
+    fn to_transaction_bytes(&self) -> Result<Vec<u8>, TransactionError> {
+        let mut transaction = self.parameters.version.to_le_bytes().to_vec();
+
+        if self.parameters.segwit_flag {
+            transaction.extend(vec![0x00, 0x01]);
+        }
+
+        transaction.extend(variable_length_integer(self.parameters.inputs.len() as u64)?);
+
+        let mut has_witness = false;
+        for input in &self.parameters.inputs {
+            if !has_witness {
+                has_witness = input.witnesses.len() > 0;
+            }
+            transaction.extend(input.serialize(!input.is_signed)?);
+        }
+
+        transaction.extend(variable_length_integer(
+            self.parameters.outputs.len() as u64
+        )?);
+        for output in &self.parameters.outputs {
+            transaction.extend(output.serialize()?);
+        }
+
+        if has_witness {
+            for input in &self.parameters.inputs {
+                match input.witnesses.len() {
+                    0 => transaction.extend(vec![0x00]),
+                    _ => {
+                        transaction.extend(variable_length_integer(input.witnesses.len() as u64)?);
+                        for witness in &input.witnesses {
+                            transaction.extend(witness);
+                        }
+                    }
+                };
+            }
+        }
+
+        transaction.extend(&self.parameters.lock_time.to_le_bytes());
+        Ok(transaction)
+    }
+
+    fn to_transaction_id(&self) -> Result<Self::TransactionId, TransactionError> {
+        let mut txid = Sha256::digest(&Sha256::digest(
+            &self.to_transaction_bytes_without_witness()?,
+        ))
+        .to_vec();
+
+        let mut wtxid = Sha256::digest(&Sha256::digest(&self.to_transaction_bytes()?)).to_vec();
+
+        txid.reverse();
+        wtxid.reverse();
+
+        Ok(Self::TransactionId { txid, wtxid })
+    }
+}
+
+impl<N: BitcoinNetwork> BitcoinTransaction<N> {
+    pub fn read<R: Read>(reader: R) -> Result<Self, TransactionError> {
+        Ok(Self {
+            parameters: BitcoinTransactionParameters::read(reader)?,
+        })
+    }
+
+    /// Signs the inputs spendable by the key with the given id, using any signer that
+    /// returns DER encoded ECDSA signatures. Signatures are verified against the
+    /// signer's public key before they are added to the transaction. P2TR inputs are
+    /// not supported, as the signer would need to sign with the tweaked key.
+    pub fn sign_with_signer<S>(&self, signer: &S, key_id: &S::KeyId) -> Result<Self, TransactionError>
+    where
+        S: Signer<PublicKey = BitcoinPublicKey<N>>,
+    {
+        let public_key = signer.public_key(key_id)?;
+        self.sign_with(&public_key, |transaction_hash, format| {
+            if *format == BitcoinFormat::P2TR {
+                return Err(TransactionError::UnsupportedPreimage(format.to_string()));
+            }
+            let signature = signer.sign_digest(key_id, transaction_hash)?;
+            let message = secp256k1::Message::parse_slice(transaction_hash)?;
+            let signature = match secp256k1::Signature::parse_der(&signature) {
+                Ok(signature) => signature,
+                Err(_) => {
+                    return Err(SignerError::InvalidSignature(hex::encode(&signature)).into())
+                }
+            };
+            match verify(&message, &signature, &public_key.to_secp256k1_public_key()) {
+                true => Ok(signature.serialize_der().as_ref().to_vec()),
+                false => Err(SignerError::InvalidSignature(hex::encode(
+                    signature.serialize_der().as_ref(),
+                ))
+                .into()),
+            }
+        })
+    }
+
+    /// Signs the inputs spendable by the public key, producing the signature of each
+    /// input's transaction hash with the given function, DER encoded ECDSA for all
+    /// formats but P2TR, which takes a BIP340 signature.
+    fn sign_with<F>(
+        &self,
+        public_key: &BitcoinPublicKey<N>,
+        sign_digest: F,
+    ) -> Result<Self, TransactionError>
+    where
+        F: Fn(&[u8], &BitcoinFormat) -> Result<Vec<u8>, TransactionError>,
+    {
         let mut transaction = self.clone();
         for (vin, input) in self.parameters.inputs.iter().enumerate() {
             let address = match &input.outpoint.address {
@@ -579,31 +772,61 @@ impl<N: BitcoinNetwork> Transaction for BitcoinTransaction<N> {
                     let c_address = BitcoinAddress::<N>::p2wsh(&input_script)?;
                     address == &c_address
                 }
-                _ => address == &private_key.to_address(&address.format())?,
+                BitcoinFormat::P2SH_P2WSH => {
+                    let input_script = match &input.outpoint.redeem_script {
+                        Some(redeem_script) => redeem_script.clone(),
+                        None => return Err(TransactionError::InvalidInputs("P2SH_P2WSH".into())),
+                    };
+                    address == &BitcoinAddress::<N>::p2sh_p2wsh(&input_script)?
+                }
+                BitcoinFormat::P2SH => {
+                    let input_script = match &input.outpoint.redeem_script {
+                        Some(redeem_script) => redeem_script.clone(),
+                        None => return Err(TransactionError::InvalidInputs("P2SH".into())),
+                    };
+                    address == &BitcoinAddress::<N>::p2sh(&input_script)?
+                }
+                _ => address == &public_key.to_address(&address.format())?,
             };
 
             if address_is_valid && !transaction.parameters.inputs[vin].is_signed {
+                if input.sighash_code == SignatureHash::SIGHASH_DEFAULT
+                    && address.format() != BitcoinFormat::P2TR
+                {
+                    return Err(TransactionError::InvalidInputs(
+                        input.sighash_code.to_string(),
+                    ));
+                }
+
                 // Transaction hash
-                let preimage = match &address.format() {
+                let transaction_hash = match &address.format() {
+                    BitcoinFormat::P2SH => {
+                        return Err(TransactionError::UnsupportedPreimage("P2SH".into()))
+                    }
                     BitcoinFormat::P2PKH => {
-                        transaction.p2pkh_hash_preimage(vin, input.sighash_code)?
+                        let preimage = transaction.p2pkh_hash_preimage(vin, input.sighash_code)?;
+                        Sha256::digest(&Sha256::digest(&preimage)).to_vec()
+                    }
+                    BitcoinFormat::P2TR => {
+                        let preimage =
+                            transaction.taproot_hash_preimage(vin, input.sighash_code)?;
+                        tagged_hash("TapSighash", &[&preimage]).to_vec()
+                    }
+                    _ => {
+                        let preimage = transaction.segwit_hash_preimage(vin, input.sighash_code)?;
+                        Sha256::digest(&Sha256::digest(&preimage)).to_vec()
                     }
-                    _ => transaction.segwit_hash_preimage(vin, input.sighash_code)?,
                 };
-                let transaction_hash = Sha256::digest(&Sha256::digest(&preimage));
 
                 // Signature
-                let (signature, _) = secp256k1::sign(
-                    &secp256k1::Message::parse_slice(&transaction_hash)?,
-                    &private_key.to_secp256k1_secret_key(),
-                );
-                let mut signature = signature.serialize_der().as_ref().to_vec();
-                signature.push((input.sighash_code as u32).to_le_bytes()[0]);
+                let mut signature = sign_digest(&transaction_hash, &address.format())?;
+                if input.sighash_code != SignatureHash::SIGHASH_DEFAULT {
+                    signature.push((input.sighash_code as u32).to_le_bytes()[0]);
+                }
                 let signature =
                     [variable_length_integer(signature.len() as u64)?, signature].concat();
 
                 // Public key
-                let public_key = private_key.to_public_key();
                 let public_key_bytes = match (&address.format(), public_key.is_compressed()) {
                     (BitcoinFormat::P2PKH, false) => {
                         public_key.to_secp256k1_public_key().serialize().to_vec()
@@ -621,19 +844,32 @@ impl<N: BitcoinNetwork> Transaction for BitcoinTransaction<N> {
                             [signature.clone(), public_key].concat();
                         transaction.parameters.inputs[vin].is_signed = true;
                     }
-                    BitcoinFormat::P2WSH => {
+                    BitcoinFormat::P2SH => {
+                        return Err(TransactionError::UnsupportedPreimage("P2SH".into()))
+                    }
+                    BitcoinFormat::P2WSH | BitcoinFormat::P2SH_P2WSH => {
                         let input_script = match &input.outpoint.redeem_script {
                             Some(redeem_script) => redeem_script.clone(),
                             None => return Err(TransactionError::InvalidInputs("P2WSH".into())),
                         };
 
+                        // P2SH_P2WSH pushes the P2WSH witness program as its redeem script
+                        let script_sig = match address.format() {
+                            BitcoinFormat::P2SH_P2WSH => {
+                                let mut witness_program = vec![0x00, 0x20];
+                                witness_program.extend(Sha256::digest(&input_script));
+                                [vec![witness_program.len() as u8], witness_program].concat()
+                            }
+                            _ => vec![],
+                        };
+
                         let ser_input_script = [
                             variable_length_integer(input_script.len() as u64)?,
                             input_script,
                         ]
                         .concat();
                         transaction.parameters.segwit_flag = true;
-                        transaction.parameters.inputs[vin].script_sig = vec![];
+                        transaction.parameters.inputs[vin].script_sig = script_sig;
                         // TODO: (jaakinyele) Generalize to a vec of additional witnesses
                         let (other_signature, is_other_sig_first) =
                             match transaction.parameters.inputs[vin]
@@ -697,76 +933,76 @@ impl<N: BitcoinNetwork> Transaction for BitcoinTransaction<N> {
                             .append(&mut vec![signature.clone(), public_key]);
                         transaction.parameters.inputs[vin].is_signed = true;
                     }
+                    BitcoinFormat::P2TR => {
+                        transaction.parameters.segwit_flag = true;
+                        transaction.parameters.inputs[vin]
+                            .witnesses
+                            .append(&mut vec![signature.clone()]);
+                        transaction.parameters.inputs[vin].is_signed = true;
+                    }
                 };
             }
         }
         Ok(transaction)
     }
 
-    fn from_transaction_bytes(transaction: &Vec<u8>) -> Result<Self, TransactionError> {
-        Ok(Self {
-            parameters: Self::TransactionParameters::read(&transaction[..])?,
-        })
+    /// Returns the parameters of the transaction.
+    pub fn parameters(&self) -> &BitcoinTransactionParameters<N> {
+        &self.parameters
     }
 
-    fn to_transaction_bytes(&self) -> Result<Vec<u8>, TransactionError> {
-        let mut transaction = self.parameters.version.to_le_bytes().to_vec();
-
-        if self.parameters.segwit_flag {
-            transaction.extend(vec![0x00, 0x01]);
-        }
+    /// Returns the BIP341 signature hash of a key path spend of the P2TR input, for keys
+    /// that sign outside of the transaction, such as MuSig2 aggregate keys.
+    pub fn taproot_signature_hash(&self, vin: usize) -> Result<[u8; 32], TransactionError> {
+        let input = match self.parameters.inputs.get(vin) {
+            Some(input) => input,
+            None => return Err(TransactionError::InvalidInputs(format!("input {}", vin))),
+        };
+        let preimage = self.taproot_hash_preimage(vin, input.sighash_code)?;
+        Ok(tagged_hash("TapSighash", &[&preimage]))
+    }
 
-        transaction.extend(variable_length_integer(self.parameters.inputs.len() as u64)?);
+    /// Adds the BIP340 signature of a key path spend as the witness of the P2TR input,
+    /// after verifying it against the output key of the input's address.
+    pub fn add_taproot_key_path_signature(
+        &self,
+        vin: usize,
+        signature: &[u8; 64],
+    ) -> Result<Self, TransactionError> {
+        let transaction_hash = self.taproot_signature_hash(vin)?;
+        let input = &self.parameters.inputs[vin];
 
-        let mut has_witness = false;
-        for input in &self.parameters.inputs {
-            if !has_witness {
-                has_witness = input.witnesses.len() > 0;
+        let program = match &input.outpoint.address {
+            Some(address) if address.format() == BitcoinFormat::P2TR => {
+                address.witness_program()?.program
             }
-            transaction.extend(input.serialize(!input.is_signed)?);
-        }
-
-        transaction.extend(variable_length_integer(
-            self.parameters.outputs.len() as u64
-        )?);
-        for output in &self.parameters.outputs {
-            transaction.extend(output.serialize()?);
+            _ => return Err(TransactionError::InvalidInputs("P2TR".into())),
+        };
+        let mut output_key = [0u8; 32];
+        match program.len() {
+            32 => output_key.copy_from_slice(&program),
+            _ => return Err(TransactionError::InvalidInputs("P2TR".into())),
+        };
+        if !schnorr::verify(&transaction_hash, &output_key, signature) {
+            return Err(TransactionError::Message(format!(
+                "invalid key path signature of input {}",
+                vin
+            )));
         }
 
-        if has_witness {
-            for input in &self.parameters.inputs {
-                match input.witnesses.len() {
-                    0 => transaction.extend(vec![0x00]),
-                    _ => {
-                        transaction.extend(variable_length_integer(input.witnesses.len() as u64)?);
-                        for witness in &input.witnesses {
-                            transaction.extend(witness);
-                        }
-                    }
-                };
-            }
+        let mut signature = signature.to_vec();
+        if input.sighash_code != SignatureHash::SIGHASH_DEFAULT {
+            signature.push((input.sighash_code as u32).to_le_bytes()[0]);
         }
+        let signature = [variable_length_integer(signature.len() as u64)?, signature].concat();
 
-        transaction.extend(&self.parameters.lock_time.to_le_bytes());
+        let mut transaction = self.clone();
+        transaction.parameters.segwit_flag = true;
+        transaction.parameters.inputs[vin].witnesses = vec![signature];
+        transaction.parameters.inputs[vin].is_signed = true;
         Ok(transaction)
     }
 
-    fn to_transaction_id(&self) -> Result<Self::TransactionId, TransactionError> {
-        let mut txid = Sha256::digest(&Sha256::digest(
-            &self.to_transaction_bytes_without_witness()?,
-        ))
-        .to_vec();
-
-        let mut wtxid = Sha256::digest(&Sha256::digest(&self.to_transaction_bytes()?)).to_vec();
-
-        txid.reverse();
-        wtxid.reverse();
-
-        Ok(Self::TransactionId { txid, wtxid })
-    }
-}
-
-impl<N: BitcoinNetwork> BitcoinTransaction<N> {
     pub fn p2pkh_hash_preimage(
         &self,
         vin: usize,
@@ -819,7 +1055,7 @@ impl<N: BitcoinNetwork> BitcoinTransaction<N> {
                 Some(script) => script[1..].to_vec(),
                 None => return Err(TransactionError::MissingOutpointScriptPublicKey),
             },
-            BitcoinFormat::P2WSH => match &input.outpoint.redeem_script {
+            BitcoinFormat::P2WSH | BitcoinFormat::P2SH_P2WSH => match &input.outpoint.redeem_script {
                 Some(redeem_script) => redeem_script.to_vec(),
                 None => return Err(TransactionError::InvalidInputs("P2WSH".into())),
             },
@@ -830,10 +1066,16 @@ impl<N: BitcoinNetwork> BitcoinTransaction<N> {
             BitcoinFormat::P2PKH => {
                 return Err(TransactionError::UnsupportedPreimage("P2PKH".into()))
             }
+            BitcoinFormat::P2SH => {
+                return Err(TransactionError::UnsupportedPreimage("P2SH".into()))
+            }
+            BitcoinFormat::P2TR => {
+                return Err(TransactionError::UnsupportedPreimage("P2TR".into()))
+            }
         };
 
         let mut script_code = vec![];
-        if format == BitcoinFormat::P2WSH {
+        if format == BitcoinFormat::P2WSH || format == BitcoinFormat::P2SH_P2WSH {
             script_code.extend(script);
         } else {
             script_code.push(Opcode::OP_DUP as u8);
@@ -870,6 +1112,89 @@ impl<N: BitcoinNetwork> BitcoinTransaction<N> {
         Ok(preimage)
     }
 
+    /// Returns the BIP341 signature message of a key path spend of the input, prefixed
+    /// with the sighash epoch. The amounts and script public keys of all outpoints are
+    /// committed to, so they must all be known.
+    pub fn taproot_hash_preimage(
+        &self,
+        vin: usize,
+        sighash: SignatureHash,
+    ) -> Result<Vec<u8>, TransactionError> {
+        let sighash_byte = sighash as u8;
+        let anyone_can_pay = sighash_byte & 0x80 == 0x80;
+        let output_type = match sighash_byte & 0x03 {
+            0x00 => SignatureHash::SIG_ALL as u8,
+            output_type => output_type,
+        };
+
+        let mut prev_outputs = vec![];
+        let mut amounts = vec![];
+        let mut script_pub_keys = vec![];
+        let mut sequences = vec![];
+        let mut outputs = vec![];
+
+        for input in &self.parameters.inputs {
+            prev_outputs.extend(&input.outpoint.reverse_transaction_id);
+            prev_outputs.extend(&input.outpoint.index.to_le_bytes());
+            match &input.outpoint.amount {
+                Some(amount) => amounts.extend(&amount.0.to_le_bytes()),
+                None => return Err(TransactionError::MissingOutpointAmount),
+            };
+            match &input.outpoint.script_pub_key {
+                Some(script) => {
+                    script_pub_keys.extend(variable_length_integer(script.len() as u64)?);
+                    script_pub_keys.extend(script);
+                }
+                None => return Err(TransactionError::MissingOutpointScriptPublicKey),
+            };
+            sequences.extend(&input.sequence);
+        }
+
+        for output in &self.parameters.outputs {
+            outputs.extend(&output.serialize()?);
+        }
+
+        // Sighash epoch and type
+        let mut preimage = vec![0x00, sighash_byte];
+        preimage.extend(&self.parameters.version.to_le_bytes());
+        preimage.extend(&self.parameters.lock_time.to_le_bytes());
+        if !anyone_can_pay {
+            preimage.extend(Sha256::digest(&prev_outputs));
+            preimage.extend(Sha256::digest(&amounts));
+            preimage.extend(Sha256::digest(&script_pub_keys));
+            preimage.extend(Sha256::digest(&sequences));
+        }
+        if output_type == SignatureHash::SIG_ALL as u8 {
+            preimage.extend(Sha256::digest(&outputs));
+        }
+
+        // Key path spend without an annex
+        preimage.push(0x00);
+
+        let input = &self.parameters.inputs[vin];
+        match anyone_can_pay {
+            true => {
+                preimage.extend(&input.outpoint.reverse_transaction_id);
+                preimage.extend(&input.outpoint.index.to_le_bytes());
+                preimage.extend(&amounts[8 * vin..8 * (vin + 1)]);
+                let script_pub_key = input.outpoint.script_pub_key.clone().unwrap_or_default();
+                preimage.extend(variable_length_integer(script_pub_key.len() as u64)?);
+                preimage.extend(script_pub_key);
+                preimage.extend(&input.sequence);
+            }
+            false => preimage.extend(&(vin as u32).to_le_bytes()),
+        };
+
+        if output_type == SignatureHash::SIG_SINGLE as u8 {
+            match self.parameters.outputs.get(vin) {
+                Some(output) => preimage.extend(Sha256::digest(&output.serialize()?)),
+                None => return Err(TransactionError::InvalidInputs("SIG_SINGLE".into())),
+            };
+        }
+
+        Ok(preimage)
+    }
+
     fn to_transaction_bytes_without_witness(&self) -> Result<Vec<u8>, TransactionError> {
         let mut transaction = self.parameters.version.to_le_bytes().to_vec();
 
@@ -911,3 +1236,136 @@ impl<N: BitcoinNetwork> FromStr for BitcoinTransaction<N> {
         Self::from_transaction_bytes(&hex::decode(s)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+
+    /// Returns a transaction spending one input with the script sig to an empty output.
+    fn transaction_with_script_sig(script_sig: &str) -> String {
+        format!(
+            "0100000001{}00000000{:02x}{}ffffffff010000000000000000{}00000000",
+            "07".repeat(32),
+            script_sig.len() / 2,
+            script_sig,
+            "00"
+        )
+    }
+
+    #[test]
+    fn signature_hash_of_script_sig() {
+        let transaction =
+            BitcoinTransaction::<Mainnet>::from_str(&transaction_with_script_sig("023003"))
+                .unwrap();
+        assert_eq!(
+            SignatureHash::SIG_SINGLE,
+            transaction.parameters().inputs[0].sighash_code
+        );
+
+        // The push claims two bytes of signature and sighash, but only one follows
+        let transaction =
+            BitcoinTransaction::<Mainnet>::from_str(&transaction_with_script_sig("0230"))
+                .unwrap();
+        assert_eq!(
+            SignatureHash::SIG_ALL,
+            transaction.parameters().inputs[0].sighash_code
+        );
+
+        // A script sig that does not start with a signature push, such as OP_TRUE
+        let transaction =
+            BitcoinTransaction::<Mainnet>::from_str(&transaction_with_script_sig("51")).unwrap();
+        assert_eq!(
+            SignatureHash::SIG_ALL,
+            transaction.parameters().inputs[0].sighash_code
+        );
+    }
+
+    /// Returns a segwit transaction spending one input with the witness to an empty output.
+    fn transaction_with_witness(witness: &[&str]) -> String {
+        let items: String = witness
+            .iter()
+            .map(|item| format!("{:02x}{}", item.len() / 2, item))
+            .collect();
+        format!(
+            "01000000000101{}0000000000ffffffff010000000000000000{}{:02x}{}00000000",
+            "07".repeat(32),
+            "00",
+            witness.len(),
+            items
+        )
+    }
+
+    #[test]
+    fn signature_hash_of_witness() {
+        let public_key = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let transaction = BitcoinTransaction::<Mainnet>::from_str(&transaction_with_witness(&[
+            "300083",
+            public_key,
+        ]))
+        .unwrap();
+        assert_eq!(
+            SignatureHash::SIGHASH_SINGLE_SIGHASH_ANYONECANPAY,
+            transaction.parameters().inputs[0].sighash_code
+        );
+
+        // 0x00 is not a signature hash type of segwit v0 signatures
+        let transaction = BitcoinTransaction::<Mainnet>::from_str(&transaction_with_witness(&[
+            "300000",
+            public_key,
+        ]))
+        .unwrap();
+        assert_eq!(
+            SignatureHash::SIG_ALL,
+            transaction.parameters().inputs[0].sighash_code
+        );
+
+        // A taproot key path signature without a sighash byte
+        let transaction =
+            BitcoinTransaction::<Mainnet>::from_str(&transaction_with_witness(&[&"00".repeat(64)]))
+                .unwrap();
+        assert_eq!(
+            SignatureHash::SIGHASH_DEFAULT,
+            transaction.parameters().inputs[0].sighash_code
+        );
+    }
+
+    /// Checks that an outpoint of the script hash address of the script is accepted with
+    /// its script pub key, and rejected if either its first or its last opcode is wrong.
+    fn check_script_hash_outpoint(address: BitcoinAddress<Mainnet>, script: Vec<u8>) {
+        let outpoint = |script_pub_key: Option<Vec<u8>>| {
+            Outpoint::new(
+                vec![7u8; 32],
+                0,
+                Some(address.clone()),
+                None,
+                Some(script.clone()),
+                script_pub_key,
+            )
+        };
+
+        let script_pub_key = create_script_pub_key::<Mainnet>(&address).unwrap();
+        assert!(outpoint(None).is_ok());
+        assert!(outpoint(Some(script_pub_key.clone())).is_ok());
+
+        let mut wrong_first = script_pub_key.clone();
+        wrong_first[0] = Opcode::OP_DUP as u8;
+        assert!(outpoint(Some(wrong_first)).is_err());
+        let mut wrong_last = script_pub_key;
+        *wrong_last.last_mut().unwrap() = Opcode::OP_CHECKSIG as u8;
+        assert!(outpoint(Some(wrong_last)).is_err());
+    }
+
+    #[test]
+    fn script_hash_outpoint_script_pub_key() {
+        let script = hex::decode(
+            "51210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179851ae",
+        )
+        .unwrap();
+        check_script_hash_outpoint(
+            BitcoinAddress::<Mainnet>::p2sh_p2wsh(&script).unwrap(),
+            script.clone(),
+        );
+        check_script_hash_outpoint(BitcoinAddress::<Mainnet>::p2sh(&script).unwrap(), script);
+    }
+}
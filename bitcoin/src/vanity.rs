@@ -0,0 +1,206 @@
+use crate::address::BitcoinAddress;
+use crate::format::BitcoinFormat;
+use crate::network::BitcoinNetwork;
+use crate::private_key::BitcoinPrivateKey;
+use crate::public_key::BitcoinPublicKey;
+use gyu_model::no_std::*;
+use gyu_model::{
+    address::Address,
+    private_key::PrivateKey,
+    vanity::{self, VanityError, VanityMatch, VanityOptions, VanityPattern},
+};
+
+use base58::ToBase58;
+use rand::{Rng, SeedableRng};
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BECH32_ALPHABET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Returns the expected number of attempts to find a key whose address in the format
+/// matches the pattern, or None for regular expressions.
+pub fn difficulty<N: BitcoinNetwork>(
+    pattern: &VanityPattern,
+    format: &BitcoinFormat,
+) -> Result<Option<f64>, VanityError> {
+    match format {
+        BitcoinFormat::P2PKH
+        | BitcoinFormat::P2SH
+        | BitcoinFormat::P2SH_P2WPKH
+        | BitcoinFormat::P2SH_P2WSH => {
+            // The version byte fixes the leading characters shared by all addresses
+            let version = format.to_address_prefix::<N>()[0];
+            let (mut lowest, mut highest) = ([0x00u8; 25], [0xffu8; 25]);
+            lowest[0] = version;
+            highest[0] = version;
+            let (lowest, highest) = (lowest.to_base58(), highest.to_base58());
+            let fixed_prefix: String = lowest
+                .chars()
+                .zip(highest.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect();
+            pattern.difficulty(BASE58_ALPHABET, &fixed_prefix)
+        }
+        BitcoinFormat::P2WSH | BitcoinFormat::Bech32 | BitcoinFormat::P2TR => {
+            let hrp = BitcoinFormat::Bech32.to_address_prefix::<N>();
+            let version = match format {
+                BitcoinFormat::P2TR => 'p',
+                _ => 'q',
+            };
+            let fixed_prefix = format!("{}1{}", String::from_utf8_lossy(&hrp), version);
+            pattern.difficulty(BECH32_ALPHABET, &fixed_prefix)
+        }
+    }
+}
+
+/// Searches for a private key whose address in the format matches the pattern, on all
+/// cores unless the options say otherwise.
+pub fn generate<N: BitcoinNetwork, R: Rng + SeedableRng + Send>(
+    rng: &mut R,
+    format: &BitcoinFormat,
+    pattern: &VanityPattern,
+    options: &VanityOptions,
+) -> Result<VanityMatch<BitcoinPrivateKey<N>, BitcoinAddress<N>>, VanityError> {
+    let options = with_difficulty::<N>(pattern, format, options)?;
+    vanity::generate(rng, format, pattern, &options)
+}
+
+/// Searches for a partial private key which, added to the private key of the requester's
+/// public key, gives a key whose address matches the pattern. The searcher learns
+/// neither the requester's private key nor the private key of the vanity address.
+pub fn split_key_search<N: BitcoinNetwork, R: Rng + SeedableRng + Send>(
+    rng: &mut R,
+    public_key: &BitcoinPublicKey<N>,
+    format: &BitcoinFormat,
+    pattern: &VanityPattern,
+    options: &VanityOptions,
+) -> Result<VanityMatch<BitcoinPrivateKey<N>, BitcoinAddress<N>>, VanityError> {
+    let options = with_difficulty::<N>(pattern, format, options)?;
+    vanity::search(rng, pattern, &options, |rng| {
+        let partial_private_key = BitcoinPrivateKey::<N>::new(rng)?;
+        let public_key = public_key.tweak_add(&partial_private_key.expose_secret().serialize())?;
+        let address = BitcoinAddress::from_public_key(&public_key, format)?;
+        Ok((partial_private_key, address))
+    })
+}
+
+/// Returns the private key of a split-key vanity address, the sum of the requester's
+/// private key and the partial private key found by the searcher.
+pub fn combine_split_key<N: BitcoinNetwork>(
+    private_key: &BitcoinPrivateKey<N>,
+    partial_private_key: &BitcoinPrivateKey<N>,
+) -> Result<BitcoinPrivateKey<N>, VanityError> {
+    Ok(private_key.tweak_add(&partial_private_key.expose_secret().serialize())?)
+}
+
+/// Returns the options with the difficulty of the pattern filled in, rejecting patterns
+/// that no address in the format can match.
+fn with_difficulty<'a, N: BitcoinNetwork>(
+    pattern: &VanityPattern,
+    format: &BitcoinFormat,
+    options: &VanityOptions<'a>,
+) -> Result<VanityOptions<'a>, VanityError> {
+    let difficulty = difficulty::<N>(pattern, format)?;
+    Ok(VanityOptions {
+        difficulty: options.difficulty.or(difficulty),
+        ..options.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Mainnet;
+    use rand_xorshift::XorShiftRng;
+
+    type N = Mainnet;
+
+    fn options() -> VanityOptions<'static> {
+        VanityOptions {
+            threads: 2,
+            max_attempts: Some(1_000_000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn difficulty() {
+        let prefix = VanityPattern::prefix("1A", false);
+        assert_eq!(
+            Some(58.0),
+            super::difficulty::<N>(&prefix, &BitcoinFormat::P2PKH).unwrap()
+        );
+        let prefix = VanityPattern::prefix("3A", false);
+        assert_eq!(
+            Some(58.0),
+            super::difficulty::<N>(&prefix, &BitcoinFormat::P2SH_P2WPKH).unwrap()
+        );
+        let prefix = VanityPattern::prefix("bc1qqq", false);
+        assert_eq!(
+            Some(1024.0),
+            super::difficulty::<N>(&prefix, &BitcoinFormat::Bech32).unwrap()
+        );
+        let prefix = VanityPattern::prefix("bc1pq", false);
+        assert_eq!(
+            Some(32.0),
+            super::difficulty::<N>(&prefix, &BitcoinFormat::P2TR).unwrap()
+        );
+        let suffix = VanityPattern::suffix("q", true);
+        assert_eq!(
+            Some(32.0),
+            super::difficulty::<N>(&suffix, &BitcoinFormat::P2WSH).unwrap()
+        );
+
+        let impossible = VanityPattern::prefix("1A", false);
+        assert!(super::difficulty::<N>(&impossible, &BitcoinFormat::Bech32).is_err());
+        let impossible = VanityPattern::prefix("bc1qb", false);
+        assert!(super::difficulty::<N>(&impossible, &BitcoinFormat::Bech32).is_err());
+        let impossible = VanityPattern::suffix("0", false);
+        assert!(super::difficulty::<N>(&impossible, &BitcoinFormat::P2PKH).is_err());
+    }
+
+    #[test]
+    fn generate() {
+        let mut rng = XorShiftRng::seed_from_u64(39);
+        for (format, pattern) in [
+            (BitcoinFormat::P2PKH, VanityPattern::prefix("1a", true)),
+            (BitcoinFormat::Bech32, VanityPattern::suffix("q", false)),
+            (
+                BitcoinFormat::P2TR,
+                VanityPattern::regex("^bc1p[02]", false).unwrap(),
+            ),
+        ]
+        .iter()
+        {
+            let found = super::generate::<N, _>(&mut rng, format, pattern, &options()).unwrap();
+            assert!(pattern.matches(&found.address.to_string()));
+            assert_eq!(found.address, found.key.to_address(format).unwrap());
+        }
+
+        let impossible = VanityPattern::prefix("bc1b", false);
+        assert!(
+            super::generate::<N, _>(&mut rng, &BitcoinFormat::Bech32, &impossible, &options())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn split_key() {
+        let mut rng = XorShiftRng::seed_from_u64(40);
+        let private_key = BitcoinPrivateKey::<N>::new(&mut rng).unwrap();
+        let pattern = VanityPattern::prefix("bc1qa", false);
+        let format = BitcoinFormat::Bech32;
+
+        let found = split_key_search(
+            &mut rng,
+            &private_key.to_public_key(),
+            &format,
+            &pattern,
+            &options(),
+        )
+        .unwrap();
+        let combined = combine_split_key(&private_key, &found.key).unwrap();
+        assert_eq!(found.address, combined.to_address(&format).unwrap());
+        assert!(pattern.matches(&found.address.to_string()));
+    }
+}
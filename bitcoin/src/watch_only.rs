@@ -0,0 +1,372 @@
+use crate::address::BitcoinAddress;
+use crate::batch::{derive_address_range, BatchAddress, BatchError, BatchOptions};
+use crate::derivation_path::BitcoinDerivationPath;
+use crate::extended_public_key::BitcoinExtendedPublicKey;
+use crate::format::BitcoinFormat;
+use crate::network::BitcoinNetwork;
+use gyu_model::no_std::*;
+use gyu_model::{
+    address::AddressError,
+    derivation_path::{ChildIndex, DerivationPath, DerivationPathError},
+    extended_public_key::{ExtendedPublicKey, ExtendedPublicKeyError},
+    public_key::PublicKey,
+};
+
+use core::{fmt, marker::PhantomData, ops::Range, str::FromStr};
+
+/// The child index of the receive chain of an account
+pub const RECEIVE_CHAIN: u32 = 0;
+/// The child index of the change chain of an account
+pub const CHANGE_CHAIN: u32 = 1;
+
+#[derive(Debug, Fail)]
+pub enum WatchOnlyError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}", _0)]
+    BatchError(BatchError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "{}", _0)]
+    DerivationPathError(DerivationPathError),
+
+    #[fail(display = "{}", _0)]
+    ExtendedPublicKeyError(ExtendedPublicKeyError),
+
+    #[fail(
+        display = "the path {} has hardened steps, which need the extended private key",
+        _0
+    )]
+    HardenedPath(String),
+
+    #[fail(display = "expected an account key at depth {}, found depth {}", _0, _1)]
+    InvalidDepth(usize, u8),
+
+    #[fail(display = "unsupported watch-only format: {}", _0)]
+    UnsupportedFormat(BitcoinFormat),
+}
+
+impl From<AddressError> for WatchOnlyError {
+    fn from(error: AddressError) -> Self {
+        WatchOnlyError::AddressError(error)
+    }
+}
+
+impl From<BatchError> for WatchOnlyError {
+    fn from(error: BatchError) -> Self {
+        WatchOnlyError::BatchError(error)
+    }
+}
+
+impl From<DerivationPathError> for WatchOnlyError {
+    fn from(error: DerivationPathError) -> Self {
+        WatchOnlyError::DerivationPathError(error)
+    }
+}
+
+impl From<ExtendedPublicKeyError> for WatchOnlyError {
+    fn from(error: ExtendedPublicKeyError) -> Self {
+        WatchOnlyError::ExtendedPublicKeyError(error)
+    }
+}
+
+/// A wallet that holds only the extended public key of an account, and derives its
+/// receive and change addresses in the format of the key, as in bc1 addresses for a zpub.
+/// Export the account key with `BitcoinMnemonic::to_account_extended_public_key`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchOnlyWallet<N: BitcoinNetwork> {
+    extended_public_key: BitcoinExtendedPublicKey<N>,
+    receive: BitcoinExtendedPublicKey<N>,
+    change: BitcoinExtendedPublicKey<N>,
+    options: BatchOptions,
+}
+
+impl<N: BitcoinNetwork> WatchOnlyWallet<N> {
+    /// Returns the wallet of the account key, with the format of its version bytes.
+    pub fn new(extended_public_key: &BitcoinExtendedPublicKey<N>) -> Result<Self, WatchOnlyError> {
+        Self::with_format(extended_public_key, &extended_public_key.format())
+    }
+
+    /// Returns the wallet of the account key with the format, for formats that share their
+    /// version bytes with another, as P2TR keys serialized as an xpub. The key must be at
+    /// the depth of the account path of the format, as in m/84'/0'/0'.
+    pub fn with_format(
+        extended_public_key: &BitcoinExtendedPublicKey<N>,
+        format: &BitcoinFormat,
+    ) -> Result<Self, WatchOnlyError> {
+        match format {
+            BitcoinFormat::P2PKH
+            | BitcoinFormat::P2SH_P2WPKH
+            | BitcoinFormat::Bech32
+            | BitcoinFormat::P2TR => {}
+            _ => return Err(WatchOnlyError::UnsupportedFormat(format.clone())),
+        };
+        let depth = BitcoinDerivationPath::<N>::account(format, 0)?.to_vec()?.len();
+        if extended_public_key.depth() as usize != depth {
+            return Err(WatchOnlyError::InvalidDepth(
+                depth,
+                extended_public_key.depth(),
+            ));
+        }
+        let extended_public_key = extended_public_key.to_format(format)?;
+        let chain = |index: u32| -> Result<BitcoinExtendedPublicKey<N>, WatchOnlyError> {
+            Ok(extended_public_key.derive(&BitcoinDerivationPath::BIP32(
+                vec![ChildIndex::normal(index)?],
+                PhantomData,
+            ))?)
+        };
+        Ok(Self {
+            receive: chain(RECEIVE_CHAIN)?,
+            change: chain(CHANGE_CHAIN)?,
+            extended_public_key: extended_public_key.clone(),
+            options: BatchOptions {
+                format: format.clone(),
+                parallel: false,
+            },
+        })
+    }
+
+    /// Returns the wallet that derives address ranges on all cores. Has no effect without
+    /// the `parallel` feature.
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.options.parallel = parallel;
+        self
+    }
+
+    pub fn extended_public_key(&self) -> &BitcoinExtendedPublicKey<N> {
+        &self.extended_public_key
+    }
+
+    pub fn format(&self) -> BitcoinFormat {
+        self.options.format.clone()
+    }
+
+    /// Returns the receive address at the index, as in m/84'/0'/0'/0/{index}.
+    pub fn receive_address(&self, index: u32) -> Result<BitcoinAddress<N>, WatchOnlyError> {
+        self.address(&self.receive, index)
+    }
+
+    /// Returns the change address at the index, as in m/84'/0'/0'/1/{index}.
+    pub fn change_address(&self, index: u32) -> Result<BitcoinAddress<N>, WatchOnlyError> {
+        self.address(&self.change, index)
+    }
+
+    /// Returns the receive addresses in the index range, with their output scripts.
+    pub fn receive_addresses(
+        &self,
+        indices: Range<u32>,
+    ) -> Result<Vec<BatchAddress<N>>, WatchOnlyError> {
+        Ok(derive_address_range(&self.receive, indices, &self.options)?)
+    }
+
+    /// Returns the change addresses in the index range, with their output scripts.
+    pub fn change_addresses(
+        &self,
+        indices: Range<u32>,
+    ) -> Result<Vec<BatchAddress<N>>, WatchOnlyError> {
+        Ok(derive_address_range(&self.change, indices, &self.options)?)
+    }
+
+    /// Returns the extended public key at the path relative to the account key. Paths with
+    /// hardened steps are rejected, since they need the extended private key.
+    pub fn derive(
+        &self,
+        path: &BitcoinDerivationPath<N>,
+    ) -> Result<BitcoinExtendedPublicKey<N>, WatchOnlyError> {
+        if path.to_vec()?.iter().any(ChildIndex::is_hardened) {
+            return Err(WatchOnlyError::HardenedPath(path.to_string()));
+        }
+        Ok(self.extended_public_key.derive(path)?)
+    }
+
+    /// Returns the address at the path relative to the account key, in the wallet format.
+    pub fn derive_address(
+        &self,
+        path: &BitcoinDerivationPath<N>,
+    ) -> Result<BitcoinAddress<N>, WatchOnlyError> {
+        Ok(self
+            .derive(path)?
+            .to_public_key()
+            .to_address(&self.options.format)?)
+    }
+
+    fn address(
+        &self,
+        chain: &BitcoinExtendedPublicKey<N>,
+        index: u32,
+    ) -> Result<BitcoinAddress<N>, WatchOnlyError> {
+        Ok(chain
+            .derive(&BitcoinDerivationPath::BIP32(
+                vec![ChildIndex::normal(index)?],
+                PhantomData,
+            ))?
+            .to_public_key()
+            .to_address(&self.options.format)?)
+    }
+}
+
+impl<N: BitcoinNetwork> FromStr for WatchOnlyWallet<N> {
+    type Err = WatchOnlyError;
+
+    /// Returns the wallet of an xpub, ypub or zpub string, with the format of its version bytes.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(&BitcoinExtendedPublicKey::from_str(s)?)
+    }
+}
+
+impl<N: BitcoinNetwork> fmt::Display for WatchOnlyWallet<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extended_public_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mnemonic::BitcoinMnemonic;
+    use crate::network::Mainnet;
+    use crate::test_vectors::master;
+    use crate::wordlist::english::English;
+    use gyu_model::extended_private_key::ExtendedPrivateKey;
+    use gyu_model::mnemonic::MnemonicExtended;
+
+    type N = Mainnet;
+
+    /// The account zpub of the BIP84 test vectors, at m/84'/0'/0'
+    const ZPUB: &str = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+
+    #[test]
+    fn bip84_vectors() {
+        let wallet = WatchOnlyWallet::<N>::from_str(ZPUB).unwrap();
+        assert_eq!(BitcoinFormat::Bech32, wallet.format());
+        assert_eq!(ZPUB, wallet.to_string());
+        assert_eq!(
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            wallet.receive_address(0).unwrap().to_string()
+        );
+        assert_eq!(
+            "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g",
+            wallet.receive_address(1).unwrap().to_string()
+        );
+        assert_eq!(
+            "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el",
+            wallet.change_address(0).unwrap().to_string()
+        );
+
+        let receive = wallet.receive_addresses(0..2).unwrap();
+        assert_eq!(wallet.receive_address(1).unwrap(), receive[1].address);
+        let change = wallet.change_addresses(0..1).unwrap();
+        assert_eq!(wallet.change_address(0).unwrap(), change[0].address);
+
+        let path = BitcoinDerivationPath::from_str("m/1/0").unwrap();
+        assert_eq!(
+            wallet.change_address(0).unwrap(),
+            wallet.derive_address(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn account_extended_public_key() {
+        let master = master(&BitcoinFormat::P2PKH);
+        let zpub = master
+            .to_account_extended_public_key(&BitcoinFormat::Bech32, 0)
+            .unwrap();
+        assert_eq!(ZPUB, zpub.to_string());
+        assert_eq!(3, zpub.depth());
+        assert_eq!("[73c5da0a/84'/0'/0']", zpub.origin().unwrap().to_string());
+
+        // A mnemonic exports the account key of its master key.
+        let mnemonic = BitcoinMnemonic::<N, English>::from_entropy(&[0u8; 16]).unwrap();
+        assert_eq!(
+            mnemonic
+                .to_extended_private_key(None)
+                .unwrap()
+                .to_account_extended_public_key(&BitcoinFormat::Bech32, 0)
+                .unwrap(),
+            mnemonic
+                .to_account_extended_public_key(None, &BitcoinFormat::Bech32, 0)
+                .unwrap()
+        );
+
+        // Each format exports the key of its account path with its own version bytes, and
+        // the wallet derives the same addresses as the private keys.
+        for (format, path) in [
+            (BitcoinFormat::P2PKH, "m/44'/0'/1'/0/3"),
+            (BitcoinFormat::P2SH_P2WPKH, "m/49'/0'/1'/0/3"),
+            (BitcoinFormat::Bech32, "m/84'/0'/1'/0/3"),
+        ]
+        .iter()
+        {
+            let account = master.to_account_extended_public_key(format, 1).unwrap();
+            let wallet = WatchOnlyWallet::<N>::from_str(&account.to_string()).unwrap();
+            assert_eq!(*format, wallet.format());
+            let key = master
+                .derive(&BitcoinDerivationPath::from_str(path).unwrap())
+                .unwrap();
+            assert_eq!(
+                key.to_address(format).unwrap(),
+                wallet.receive_address(3).unwrap()
+            );
+        }
+
+        let child = master
+            .derive(&BitcoinDerivationPath::from_str("m/0").unwrap())
+            .unwrap();
+        assert!(child
+            .to_account_extended_public_key(&BitcoinFormat::Bech32, 0)
+            .is_err());
+    }
+
+    #[test]
+    fn p2tr_with_format() {
+        // P2TR account keys serialize as an xpub, so their format is given explicitly.
+        let master = master(&BitcoinFormat::P2PKH);
+        let account = master
+            .to_account_extended_public_key(&BitcoinFormat::P2TR, 0)
+            .unwrap();
+        let xpub = BitcoinExtendedPublicKey::<N>::from_str(&account.to_string()).unwrap();
+        let wallet = WatchOnlyWallet::with_format(&xpub, &BitcoinFormat::P2TR).unwrap();
+        assert_eq!(
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            wallet.receive_address(0).unwrap().to_string()
+        );
+        assert!(WatchOnlyWallet::with_format(&xpub, &BitcoinFormat::P2WSH).is_err());
+    }
+
+    #[test]
+    fn account_depth() {
+        let master = master(&BitcoinFormat::P2PKH);
+        for path in ["m", "m/84'/0'", "m/84'/0'/0'/0"].iter() {
+            let key = master
+                .derive(&BitcoinDerivationPath::from_str(path).unwrap())
+                .unwrap()
+                .to_format(&BitcoinFormat::Bech32)
+                .unwrap()
+                .to_extended_public_key();
+            assert!(matches!(
+                WatchOnlyWallet::new(&key),
+                Err(WatchOnlyError::InvalidDepth(3, _))
+            ));
+        }
+    }
+
+    #[test]
+    fn hardened_paths() {
+        let wallet = WatchOnlyWallet::<N>::from_str(ZPUB).unwrap();
+        let path = BitcoinDerivationPath::from_str("m/0/1'").unwrap();
+        assert!(matches!(
+            wallet.derive(&path),
+            Err(WatchOnlyError::HardenedPath(_))
+        ));
+        assert!(wallet.derive_address(&path).is_err());
+
+        // The extended public key itself names the hardened child it cannot derive.
+        match wallet.extended_public_key().derive(&path) {
+            Err(ExtendedPublicKeyError::HardenedChildIndex(index)) => assert_eq!("1'", index),
+            result => panic!("expected a hardened child index error, found {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use crate::wordlist::BitcoinWordlist;
+use gyu_model::{wordlist::bip39::FRENCH, wordlist::Wordlist};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct French;
+
+impl Wordlist for French {}
+
+impl BitcoinWordlist for French {
+    const WORDLIST: &'static str = FRENCH;
+}
@@ -0,0 +1,11 @@
+use crate::wordlist::BitcoinWordlist;
+use gyu_model::{wordlist::bip39::ITALIAN, wordlist::Wordlist};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Italian;
+
+impl Wordlist for Italian {}
+
+impl BitcoinWordlist for Italian {
+    const WORDLIST: &'static str = ITALIAN;
+}
@@ -0,0 +1,12 @@
+use crate::wordlist::BitcoinWordlist;
+use gyu_model::{wordlist::bip39::JAPANESE, wordlist::Wordlist};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Japanese;
+
+impl Wordlist for Japanese {}
+
+impl BitcoinWordlist for Japanese {
+    const WORDLIST: &'static str = JAPANESE;
+    const SEPARATOR: &'static str = "\u{3000}";
+}
@@ -0,0 +1,11 @@
+use crate::wordlist::BitcoinWordlist;
+use gyu_model::{wordlist::bip39::KOREAN, wordlist::Wordlist};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Korean;
+
+impl Wordlist for Korean {}
+
+impl BitcoinWordlist for Korean {
+    const WORDLIST: &'static str = KOREAN;
+}
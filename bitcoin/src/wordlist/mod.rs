@@ -6,8 +6,16 @@ pub mod chinese_traditional;
 
 pub mod english;
 
+pub mod french;
+pub mod italian;
+pub mod japanese;
+pub mod korean;
+pub mod spanish;
+
 pub trait BitcoinWordlist: Wordlist {
     const WORDLIST: &'static str;
+    /// The separator of the words of a phrase, an ideographic space for Japanese.
+    const SEPARATOR: &'static str = " ";
 
     fn get(index: usize) -> Result<String, WordlistError> {
         if index >= 2048 {
@@ -0,0 +1,11 @@
+use crate::wordlist::BitcoinWordlist;
+use gyu_model::{wordlist::bip39::SPANISH, wordlist::Wordlist};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Spanish;
+
+impl Wordlist for Spanish {}
+
+impl BitcoinWordlist for Spanish {
+    const WORDLIST: &'static str = SPANISH;
+}
@@ -1,7 +1,11 @@
 use crate::format::EthereumFormat;
 use crate::private_key::EthereumPrivateKey;
 use crate::public_key::EthereumPublicKey;
-use gyu_model::{address::Address, utilities::to_hex_string, AddressError, PrivateKey};
+use gyu_model::{
+    address::{Address, AddressError},
+    private_key::PrivateKey,
+    utilities::to_hex_string,
+};
 
 use core::{convert::TryFrom, fmt, str::FromStr};
 use regex::Regex;
@@ -98,7 +102,7 @@ impl fmt::Display for EthereumAddress {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use wagyu_model::public_key::PublicKey;
+    use gyu_model::public_key::PublicKey;
 
     fn test_from_private_key(expected_address: &str, private_key: &EthereumPrivateKey) {
         let address =
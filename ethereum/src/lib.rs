@@ -1 +1,6 @@
 pub mod address;
+pub mod format;
+pub mod private_key;
+pub mod public_key;
+#[cfg(feature = "std")]
+pub mod vanity;
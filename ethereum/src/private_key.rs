@@ -0,0 +1,140 @@
+use crate::address::EthereumAddress;
+use crate::format::EthereumFormat;
+use crate::public_key::EthereumPublicKey;
+use gyu_model::{
+    address::{Address, AddressError},
+    private_key::{PrivateKey, PrivateKeyError},
+    public_key::PublicKey,
+    utilities::to_hex_string,
+};
+
+use core::{fmt, str::FromStr};
+use rand::Rng;
+
+/// Represents an Ethereum private key. The secret key is cleared on drop by
+/// `secp256k1::SecretKey`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EthereumPrivateKey(secp256k1::SecretKey);
+
+impl PrivateKey for EthereumPrivateKey {
+    type Address = EthereumAddress;
+    type Format = EthereumFormat;
+    type PublicKey = EthereumPublicKey;
+
+    /// Returns a randomly-generated Ethereum private key.
+    fn new<R: Rng>(rng: &mut R) -> Result<Self, PrivateKeyError> {
+        Ok(Self(secp256k1::SecretKey::random(rng)))
+    }
+
+    /// Returns the public key of the corresponding Ethereum private key.
+    fn to_public_key(&self) -> Self::PublicKey {
+        EthereumPublicKey::from_private_key(self)
+    }
+
+    /// Returns the address of the corresponding Ethereum private key.
+    fn to_address(&self, format: &Self::Format) -> Result<Self::Address, AddressError> {
+        EthereumAddress::from_private_key(self, format)
+    }
+}
+
+impl EthereumPrivateKey {
+    /// Returns a private key given a secp256k1 secret key.
+    pub fn from_secp256k1_secret_key(secret_key: &secp256k1::SecretKey) -> Self {
+        Self(secret_key.clone())
+    }
+
+    /// Returns a secp256k1 secret key.
+    pub fn to_secp256k1_secret_key(&self) -> secp256k1::SecretKey {
+        self.0.clone()
+    }
+
+    /// Returns the 65 byte recoverable signature r || s || recovery id of the message digest,
+    /// with a low s and a recovery id of 0 or 1.
+    pub fn sign_recoverable(&self, digest: &[u8; 32]) -> [u8; 65] {
+        let (signature, recovery_id) = secp256k1::sign(&secp256k1::Message::parse(digest), &self.0);
+
+        let mut recoverable = [0u8; 65];
+        recoverable[..64].copy_from_slice(&signature.serialize());
+        recoverable[64] = recovery_id.serialize();
+        recoverable
+    }
+}
+
+impl FromStr for EthereumPrivateKey {
+    type Err = PrivateKeyError;
+
+    /// Returns the private key of a 64 character hex string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 64 {
+            return Err(PrivateKeyError::InvalidCharacterLength(s.len()));
+        }
+
+        let secret_key = hex::decode(s)
+            .map_err(|error| PrivateKeyError::Crate("hex", format!("{:?}", error)))?;
+        Ok(Self(secp256k1::SecretKey::parse_slice(&secret_key)?))
+    }
+}
+
+impl fmt::Debug for EthereumPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("EthereumPrivateKey")
+            .field(&format_args!("[REDACTED]"))
+            .finish()
+    }
+}
+
+impl fmt::Display for EthereumPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", to_hex_string(&self.0.serialize()).to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIVATE_KEY: &str = "f89f23eaeac18252fedf81bb8318d3c111d48c19b0680dcf6e0a8d5136caf287";
+
+    #[test]
+    fn from_str() {
+        let private_key = EthereumPrivateKey::from_str(PRIVATE_KEY).unwrap();
+        assert_eq!(PRIVATE_KEY, private_key.to_string());
+        assert_eq!(
+            "0x9141B7539E7902872095C408BfA294435e2b8c8a",
+            private_key
+                .to_address(&EthereumFormat::Standard)
+                .unwrap()
+                .to_string()
+        );
+
+        assert!(EthereumPrivateKey::from_str(&PRIVATE_KEY[1..]).is_err());
+        assert!(EthereumPrivateKey::from_str(&"g".repeat(64)).is_err());
+        assert!(EthereumPrivateKey::from_str(&"0".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn redacted_debug() {
+        let private_key = EthereumPrivateKey::from_str(PRIVATE_KEY).unwrap();
+        let debug = format!("{:?}", private_key);
+        assert_eq!("EthereumPrivateKey([REDACTED])", debug);
+    }
+
+    #[test]
+    fn sign_recoverable() {
+        let private_key = EthereumPrivateKey::from_str(PRIVATE_KEY).unwrap();
+        let public_key = private_key.to_public_key();
+        for byte in 0..16u8 {
+            let digest = [byte; 32];
+            let signature = private_key.sign_recoverable(&digest);
+            assert!(signature[64] < 2);
+            assert_eq!(signature, private_key.sign_recoverable(&digest));
+
+            // The s value is in the lower half of the curve order.
+            assert!(signature[32] < 0x80);
+            assert_eq!(
+                public_key,
+                EthereumPublicKey::recover(&digest, &signature).unwrap()
+            );
+        }
+    }
+}
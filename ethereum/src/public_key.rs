@@ -0,0 +1,154 @@
+use crate::address::EthereumAddress;
+use crate::format::EthereumFormat;
+use crate::private_key::EthereumPrivateKey;
+use gyu_model::{
+    address::{Address, AddressError},
+    public_key::{PublicKey, PublicKeyError},
+};
+
+use core::{fmt, str::FromStr};
+
+/// Represents an Ethereum public key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthereumPublicKey(secp256k1::PublicKey);
+
+impl PublicKey for EthereumPublicKey {
+    type Address = EthereumAddress;
+    type Format = EthereumFormat;
+    type PrivateKey = EthereumPrivateKey;
+
+    /// Returns the public key corresponding to the given private key.
+    fn from_private_key(private_key: &Self::PrivateKey) -> Self {
+        Self(secp256k1::PublicKey::from_secret_key(
+            &private_key.to_secp256k1_secret_key(),
+        ))
+    }
+
+    /// Returns the address of the corresponding private key.
+    fn to_address(&self, format: &Self::Format) -> Result<Self::Address, AddressError> {
+        EthereumAddress::from_public_key(self, format)
+    }
+}
+
+impl EthereumPublicKey {
+    /// Returns a public key given a secp256k1 public key.
+    pub fn from_secp256k1_public_key(public_key: secp256k1::PublicKey) -> Self {
+        Self(public_key)
+    }
+
+    /// Returns a secp256k1 public key.
+    pub fn to_secp256k1_public_key(&self) -> secp256k1::PublicKey {
+        self.0.clone()
+    }
+
+    /// Returns the public key of the 65 byte recoverable signature r || s || v of the message
+    /// digest, as in `ecrecover`. The recovery id v may be offset by 27.
+    pub fn recover(digest: &[u8; 32], signature: &[u8; 65]) -> Result<Self, PublicKeyError> {
+        let recovery_id = match signature[64] {
+            v @ 27..=30 => v - 27,
+            v => v,
+        };
+        let public_key = secp256k1::recover(
+            &secp256k1::Message::parse(digest),
+            &secp256k1::Signature::parse_slice(&signature[..64])?,
+            &secp256k1::RecoveryId::parse(recovery_id)?,
+        )?;
+        Ok(Self(public_key))
+    }
+}
+
+impl FromStr for EthereumPublicKey {
+    type Err = PublicKeyError;
+
+    /// Returns the public key of the 128 character hex string of its uncompressed point,
+    /// without the 04 prefix.
+    fn from_str(public_key: &str) -> Result<Self, Self::Err> {
+        if public_key.len() != 128 {
+            return Err(PublicKeyError::InvalidCharacterLength(public_key.len()));
+        }
+
+        let public_key = hex::decode(format!("04{}", public_key))?;
+        Ok(Self(secp256k1::PublicKey::parse_slice(
+            &public_key,
+            Some(secp256k1::PublicKeyFormat::Full),
+        )?))
+    }
+}
+
+impl fmt::Display for EthereumPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in &self.0.serialize()[1..] {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gyu_model::private_key::PrivateKey;
+
+    const PRIVATE_KEY: &str = "f89f23eaeac18252fedf81bb8318d3c111d48c19b0680dcf6e0a8d5136caf287";
+    const ADDRESS: &str = "0x9141B7539E7902872095C408BfA294435e2b8c8a";
+
+    #[test]
+    fn from_str() {
+        let public_key = EthereumPrivateKey::from_str(PRIVATE_KEY)
+            .unwrap()
+            .to_public_key();
+        let string = public_key.to_string();
+        assert_eq!(128, string.len());
+        assert_eq!(public_key, EthereumPublicKey::from_str(&string).unwrap());
+        assert_eq!(
+            ADDRESS,
+            EthereumPublicKey::from_str(&string)
+                .unwrap()
+                .to_address(&EthereumFormat::Standard)
+                .unwrap()
+                .to_string()
+        );
+
+        assert!(EthereumPublicKey::from_str(&string[2..]).is_err());
+        assert!(EthereumPublicKey::from_str(&"0".repeat(128)).is_err());
+    }
+
+    #[test]
+    fn recover() {
+        let private_key = EthereumPrivateKey::from_str(PRIVATE_KEY).unwrap();
+        let digest = [0x42u8; 32];
+        let mut signature = private_key.sign_recoverable(&digest);
+
+        let public_key = EthereumPublicKey::recover(&digest, &signature).unwrap();
+        assert_eq!(
+            ADDRESS,
+            public_key
+                .to_address(&EthereumFormat::Standard)
+                .unwrap()
+                .to_string()
+        );
+
+        // ecrecover takes v = 27 + recovery id.
+        signature[64] += 27;
+        assert_eq!(
+            public_key,
+            EthereumPublicKey::recover(&digest, &signature).unwrap()
+        );
+
+        // The other recovery id gives another key, and an invalid one gives none.
+        signature[64] = 27 + ((signature[64] - 27) ^ 1);
+        assert_ne!(
+            public_key,
+            EthereumPublicKey::recover(&digest, &signature).unwrap()
+        );
+        signature[64] = 4;
+        assert!(EthereumPublicKey::recover(&digest, &signature).is_err());
+
+        // A different digest recovers a different key.
+        let signature = private_key.sign_recoverable(&digest);
+        assert_ne!(
+            Some(public_key),
+            EthereumPublicKey::recover(&[0x43u8; 32], &signature).ok()
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::address::EthereumAddress;
+use crate::format::EthereumFormat;
+use crate::private_key::EthereumPrivateKey;
+use crate::public_key::EthereumPublicKey;
+use gyu_model::{
+    address::Address,
+    private_key::{PrivateKey, PrivateKeyError},
+    vanity::{self, VanityError, VanityMatch, VanityOptions, VanityPattern},
+};
+
+use rand::{Rng, SeedableRng};
+
+const HEX_ALPHABET: &str = "0123456789abcdef";
+
+/// Returns the expected number of attempts to find a key whose address matches the
+/// pattern, or None for regular expressions. Under the EIP-55 checksum a letter is upper
+/// case half of the time, so each letter of a case-sensitive pattern doubles the difficulty.
+pub fn difficulty(pattern: &VanityPattern) -> Result<Option<f64>, VanityError> {
+    let (text, case_insensitive, fixed_prefix) = match pattern {
+        VanityPattern::Prefix(text, case_insensitive) => (text, *case_insensitive, "0x"),
+        VanityPattern::Suffix(text, case_insensitive) => (text, *case_insensitive, ""),
+        VanityPattern::Regex(_) => return Ok(None),
+    };
+
+    let fixed = text.len().min(fixed_prefix.len());
+    if !case_insensitive && text.get(..fixed) != fixed_prefix.get(..fixed) {
+        return Err(VanityError::ImpossiblePattern(text.clone()));
+    }
+
+    let lowercase = VanityPattern::Prefix(text.to_lowercase(), true);
+    let difficulty = lowercase.difficulty(HEX_ALPHABET, fixed_prefix)?;
+    let letters = match case_insensitive {
+        true => 0,
+        false => text
+            .get(fixed..)
+            .unwrap_or("")
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .count(),
+    };
+    Ok(difficulty.map(|difficulty| difficulty * 2f64.powi(letters as i32)))
+}
+
+/// Searches for a private key whose address matches the pattern, on all cores unless the
+/// options say otherwise.
+pub fn generate<R: Rng + SeedableRng + Send>(
+    rng: &mut R,
+    pattern: &VanityPattern,
+    options: &VanityOptions,
+) -> Result<VanityMatch<EthereumPrivateKey, EthereumAddress>, VanityError> {
+    let options = with_difficulty(pattern, options)?;
+    vanity::generate(rng, &EthereumFormat::Standard, pattern, &options)
+}
+
+/// Searches for a partial private key which, added to the private key of the requester's
+/// public key, gives a key whose address matches the pattern. The searcher learns
+/// neither the requester's private key nor the private key of the vanity address.
+pub fn split_key_search<R: Rng + SeedableRng + Send>(
+    rng: &mut R,
+    public_key: &EthereumPublicKey,
+    pattern: &VanityPattern,
+    options: &VanityOptions,
+) -> Result<VanityMatch<EthereumPrivateKey, EthereumAddress>, VanityError> {
+    let options = with_difficulty(pattern, options)?;
+    vanity::search(rng, pattern, &options, |rng| {
+        let partial_private_key = EthereumPrivateKey::new(rng)?;
+        let mut point = public_key.to_secp256k1_public_key();
+        point
+            .tweak_add_assign(&partial_private_key.to_secp256k1_secret_key())
+            .map_err(PrivateKeyError::from)?;
+        let address = EthereumAddress::from_public_key(
+            &EthereumPublicKey::from_secp256k1_public_key(point),
+            &EthereumFormat::Standard,
+        )?;
+        Ok((partial_private_key, address))
+    })
+}
+
+/// Returns the private key of a split-key vanity address, the sum of the requester's
+/// private key and the partial private key found by the searcher.
+pub fn combine_split_key(
+    private_key: &EthereumPrivateKey,
+    partial_private_key: &EthereumPrivateKey,
+) -> Result<EthereumPrivateKey, VanityError> {
+    let mut secret_key = private_key.to_secp256k1_secret_key();
+    secret_key
+        .tweak_add_assign(&partial_private_key.to_secp256k1_secret_key())
+        .map_err(PrivateKeyError::from)?;
+    Ok(EthereumPrivateKey::from_secp256k1_secret_key(&secret_key))
+}
+
+/// Returns the options with the difficulty of the pattern filled in, rejecting patterns
+/// that no address can match.
+fn with_difficulty<'a>(
+    pattern: &VanityPattern,
+    options: &VanityOptions<'a>,
+) -> Result<VanityOptions<'a>, VanityError> {
+    let difficulty = difficulty(pattern)?;
+    Ok(VanityOptions {
+        difficulty: options.difficulty.or(difficulty),
+        ..options.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xorshift::XorShiftRng;
+
+    fn options() -> VanityOptions<'static> {
+        VanityOptions {
+            threads: 2,
+            max_attempts: Some(1_000_000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn difficulty() {
+        let pattern = VanityPattern::prefix("0xbee", true);
+        assert_eq!(Some(4096.0), super::difficulty(&pattern).unwrap());
+        let pattern = VanityPattern::prefix("0xBee", false);
+        assert_eq!(Some(32768.0), super::difficulty(&pattern).unwrap());
+        let pattern = VanityPattern::suffix("A1", false);
+        assert_eq!(Some(512.0), super::difficulty(&pattern).unwrap());
+        let pattern = VanityPattern::regex("bee", true).unwrap();
+        assert_eq!(None, super::difficulty(&pattern).unwrap());
+
+        assert!(super::difficulty(&VanityPattern::prefix("0X1", false)).is_err());
+        assert!(super::difficulty(&VanityPattern::prefix("0xg", true)).is_err());
+        assert!(super::difficulty(&VanityPattern::suffix("x", true)).is_err());
+    }
+
+    #[test]
+    fn generate() {
+        let mut rng = XorShiftRng::seed_from_u64(39);
+        for pattern in [
+            VanityPattern::prefix("0xab", true),
+            VanityPattern::suffix("eF", false),
+            VanityPattern::regex("^0x0.*0$", false).unwrap(),
+        ]
+        .iter()
+        {
+            let found = super::generate(&mut rng, pattern, &options()).unwrap();
+            assert!(pattern.matches(&found.address.to_string()));
+            assert_eq!(
+                found.address,
+                found.key.to_address(&EthereumFormat::Standard).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn split_key() {
+        let mut rng = XorShiftRng::seed_from_u64(40);
+        let private_key = EthereumPrivateKey::new(&mut rng).unwrap();
+        let pattern = VanityPattern::suffix("be", true);
+
+        let found =
+            split_key_search(&mut rng, &private_key.to_public_key(), &pattern, &options()).unwrap();
+        let combined = combine_split_key(&private_key, &found.key).unwrap();
+        assert_eq!(
+            found.address,
+            combined.to_address(&EthereumFormat::Standard).unwrap()
+        );
+        assert!(pattern.matches(&found.address.to_string()));
+    }
+}
@@ -137,3 +137,9 @@ impl From<rand_core::Error> for AddressError {
         AddressError::Crate("rand", format!("{:?}", error))
     }
 }
+
+impl From<secp256k1::Error> for AddressError {
+    fn from(error: secp256k1::Error) -> Self {
+        AddressError::Crate("libsecp256k1", format!("{:?}", error))
+    }
+}
@@ -18,8 +18,16 @@ pub enum DerivationPathError {
     ExpectedBIP32Path,
     #[fail(display = "expected BIP44 path")]
     ExpectedBIP44Path,
+    #[fail(display = "expected BIP45 path")]
+    ExpectedBIP45Path,
+    #[fail(display = "expected BIP48 path")]
+    ExpectedBIP48Path,
     #[fail(display = "expected BIP49 path")]
     ExpectedBIP49Path,
+    #[fail(display = "expected BIP84 path")]
+    ExpectedBIP84Path,
+    #[fail(display = "expected BIP86 path")]
+    ExpectedBIP86Path,
     #[fail(display = "expected valid Ethereum derivation path")]
     ExpectedVaildEthereumDerivationPath,
     #[fail(display = "expected ZIP32 path")]
@@ -52,7 +60,7 @@ impl ChildIndex {
     }
     pub fn hardened(index: u32) -> Result<Self, DerivationPathError> {
         if index & (1 << 31) == 0 {
-            Ok(ChildIndex::Normal(index))
+            Ok(ChildIndex::Hardened(index))
         } else {
             Err(DerivationPathError::InvalidChildNumber(index))
         }
@@ -99,7 +107,11 @@ impl FromStr for ChildIndex {
     type Err = DerivationPathError;
     fn from_str(inp: &str) -> Result<Self, Self::Err> {
         Ok(
-            match inp.chars().last().map_or(false, |l| l == '\'' || l == 'h') {
+            match inp
+                .chars()
+                .last()
+                .map_or(false, |l| l == '\'' || l == 'h' || l == 'H')
+            {
                 true => Self::hardened(
                     inp[0..inp.len() - 1]
                         .parse()
@@ -59,6 +59,9 @@ pub enum ExtendedPrivateKeyError {
     )]
     InvalidChecksum(String, String),
 
+    #[fail(display = "invalid key origin: {}", _0)]
+    InvalidKeyOrigin(String),
+
     #[fail(display = "invalid version bytes: {:?}", _0)]
     InvalidVersionBytes(Vec<u8>),
 
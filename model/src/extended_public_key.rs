@@ -37,6 +37,12 @@ pub enum ExtendedPublicKeyError {
     #[fail(display = "{}", _0)]
     DerivationPathError(DerivationPathError),
 
+    #[fail(
+        display = "cannot derive the hardened child {} from an extended public key",
+        _0
+    )]
+    HardenedChildIndex(String),
+
     #[fail(display = "invalid byte length: {}", _0)]
     InvalidByteLength(usize),
 
@@ -52,6 +58,9 @@ pub enum ExtendedPublicKeyError {
     )]
     InvalidChildNumber(u32, u32),
 
+    #[fail(display = "invalid key origin: {}", _0)]
+    InvalidKeyOrigin(String),
+
     #[fail(display = "invalid version bytes: {:?}", _0)]
     InvalidVersionBytes(Vec<u8>),
 
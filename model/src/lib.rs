@@ -19,6 +19,10 @@ pub mod private_key;
 
 pub mod public_key;
 
+pub mod signer;
+
+pub mod slip10;
+
 pub mod extended_private_key;
 
 pub mod extended_public_key;
@@ -30,3 +34,6 @@ pub mod mnemonic;
 pub mod wordlist;
 
 pub mod transaction;
+
+#[cfg(feature = "std")]
+pub mod vanity;
@@ -27,12 +27,18 @@ pub enum PublicKeyError {
     #[fail(display = "{}: {}", _0, _1)]
     Crate(&'static str, String),
 
+    #[fail(display = "{} public keys are not allowed here", _0)]
+    DisallowedEncoding(String),
+
     #[fail(display = "invalid byte length : {}", _0)]
     InvalidByteLength(usize),
 
     #[fail(display = "invalid character length: {}", _0)]
     InvalidCharacterLength(usize),
 
+    #[fail(display = "invalid public key point: {}", _0)]
+    InvalidPoint(String),
+
     #[fail(display = "invalid public key prefix: {:?}", _0)]
     InvalidPrefix(String),
 
@@ -0,0 +1,72 @@
+use crate::public_key::{PublicKey, PublicKeyError};
+
+use crate::no_std::*;
+use core::fmt::Debug;
+
+/// A source of signatures over message digests, for keys that may be held outside
+/// of the process, such as in a hardware or remote signing service.
+pub trait Signer {
+    type KeyId: Clone + Debug;
+    type PublicKey: PublicKey;
+
+    /// Returns the public key of the key with the given id.
+    fn public_key(&self, key_id: &Self::KeyId) -> Result<Self::PublicKey, SignerError>;
+
+    /// Returns the signature of the digest by the key with the given id,
+    /// in the signature encoding of the chain.
+    fn sign_digest(&self, key_id: &Self::KeyId, digest: &[u8]) -> Result<Vec<u8>, SignerError>;
+}
+
+#[derive(Debug, Fail)]
+pub enum SignerError {
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "invalid digest length: {}", _0)]
+    InvalidDigestLength(usize),
+
+    #[fail(display = "invalid key id: {:?}", _0)]
+    InvalidKeyId(String),
+
+    #[fail(display = "invalid signature: {}", _0)]
+    InvalidSignature(String),
+
+    #[fail(display = "key not found: {}", _0)]
+    KeyNotFound(String),
+
+    #[fail(display = "{}", _0)]
+    Message(String),
+
+    #[fail(display = "{}", _0)]
+    PublicKeyError(PublicKeyError),
+}
+
+impl From<crate::no_std::io::Error> for SignerError {
+    fn from(error: crate::no_std::io::Error) -> Self {
+        SignerError::Crate("crate::no_std::io", format!("{:?}", error))
+    }
+}
+
+impl From<&'static str> for SignerError {
+    fn from(msg: &'static str) -> Self {
+        SignerError::Message(msg.into())
+    }
+}
+
+impl From<PublicKeyError> for SignerError {
+    fn from(error: PublicKeyError) -> Self {
+        SignerError::PublicKeyError(error)
+    }
+}
+
+impl From<hex::FromHexError> for SignerError {
+    fn from(error: hex::FromHexError) -> Self {
+        SignerError::Crate("hex", format!("{:?}", error))
+    }
+}
+
+impl From<secp256k1::Error> for SignerError {
+    fn from(error: secp256k1::Error) -> Self {
+        SignerError::Crate("libsecp256k1", format!("{:?}", error))
+    }
+}
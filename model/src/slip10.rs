@@ -0,0 +1,567 @@
+use crate::derivation_path::{ChildIndex, DerivationPathError};
+use crate::extended_private_key::ExtendedPrivateKeyError;
+use crate::extended_public_key::ExtendedPublicKeyError;
+use crate::utilities::crypto::hash160;
+
+use core::{fmt, marker::PhantomData};
+use hmac::{Hmac, Mac};
+use p256::elliptic_curve::{group::Group, sec1::ToEncodedPoint, Field, PrimeField};
+use sha2::Sha512;
+use zeroize::{Zeroize, Zeroizing};
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// A curve of SLIP-10 hierarchical derivation
+/// https://github.com/satoshilabs/slips/blob/master/slip-0010.md
+pub trait Slip10Curve: Copy + Clone + fmt::Debug + PartialEq + Eq + Send + Sync + 'static {
+    /// The name of the curve
+    const NAME: &'static str;
+    /// The HMAC key of master key generation, such as "ed25519 seed"
+    const SEED_KEY: &'static [u8];
+
+    /// Returns true if the curve supports normal, non-hardened, derivation.
+    fn supports_normal_derivation() -> bool;
+
+    /// Returns true if the bytes are a valid private key.
+    fn is_valid_private_key(private_key: &[u8; 32]) -> bool;
+
+    /// Returns the 33 byte serialized public key of the private key.
+    fn to_public_key(private_key: &[u8; 32]) -> [u8; 33];
+
+    /// Returns the child private key of the parent private key and the left half of the
+    /// HMAC, or None if the half is not a valid key, in which case SLIP-10 derivation retries.
+    fn child_private_key(private_key: &[u8; 32], tweak: &[u8; 32]) -> Option<[u8; 32]>;
+
+    /// Returns the child public key of the parent public key and the left half of the HMAC,
+    /// or None if the half is not a valid key. Only called if normal derivation is supported.
+    fn child_public_key(public_key: &[u8; 33], tweak: &[u8; 32]) -> Option<[u8; 33]>;
+}
+
+/// The ed25519 curve, which only supports hardened derivation. Public keys are serialized
+/// as a zero byte followed by the 32 byte ed25519 public key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Ed25519;
+
+impl Slip10Curve for Ed25519 {
+    const NAME: &'static str = "ed25519";
+    const SEED_KEY: &'static [u8] = b"ed25519 seed";
+
+    fn supports_normal_derivation() -> bool {
+        false
+    }
+
+    fn is_valid_private_key(_: &[u8; 32]) -> bool {
+        true
+    }
+
+    fn to_public_key(private_key: &[u8; 32]) -> [u8; 33] {
+        let mut public_key = [0u8; 33];
+        // Every 32 byte string is an ed25519 private key
+        if let Ok(secret_key) = ed25519_dalek::SecretKey::from_bytes(private_key) {
+            public_key[1..].copy_from_slice(ed25519_dalek::PublicKey::from(&secret_key).as_bytes());
+        }
+        public_key
+    }
+
+    fn child_private_key(_: &[u8; 32], tweak: &[u8; 32]) -> Option<[u8; 32]> {
+        Some(*tweak)
+    }
+
+    fn child_public_key(_: &[u8; 33], _: &[u8; 32]) -> Option<[u8; 33]> {
+        None
+    }
+}
+
+/// The NIST P-256 curve, also known as secp256r1 and nist256p1. Public keys are serialized
+/// in compressed SEC1 form.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NistP256;
+
+impl NistP256 {
+    /// Returns the scalar of the bytes, if they are below the order of the curve.
+    fn to_scalar(bytes: &[u8; 32]) -> Option<p256::Scalar> {
+        Option::from(p256::Scalar::from_repr((*bytes).into()))
+    }
+}
+
+impl Slip10Curve for NistP256 {
+    const NAME: &'static str = "nist256p1";
+    const SEED_KEY: &'static [u8] = b"Nist256p1 seed";
+
+    fn supports_normal_derivation() -> bool {
+        true
+    }
+
+    fn is_valid_private_key(private_key: &[u8; 32]) -> bool {
+        Self::to_scalar(private_key).is_some_and(|scalar| !bool::from(scalar.is_zero()))
+    }
+
+    fn to_public_key(private_key: &[u8; 32]) -> [u8; 33] {
+        let scalar = Self::to_scalar(private_key).unwrap_or_default();
+        let point = (p256::ProjectivePoint::GENERATOR * scalar).to_affine();
+        let mut public_key = [0u8; 33];
+        public_key.copy_from_slice(point.to_encoded_point(true).as_bytes());
+        public_key
+    }
+
+    fn child_private_key(private_key: &[u8; 32], tweak: &[u8; 32]) -> Option<[u8; 32]> {
+        let child = Self::to_scalar(tweak)? + Self::to_scalar(private_key)?;
+        match bool::from(child.is_zero()) {
+            true => None,
+            false => Some(child.to_repr().into()),
+        }
+    }
+
+    fn child_public_key(public_key: &[u8; 33], tweak: &[u8; 32]) -> Option<[u8; 33]> {
+        let parent = p256::PublicKey::from_sec1_bytes(public_key).ok()?;
+        let child =
+            parent.to_projective() + p256::ProjectivePoint::GENERATOR * Self::to_scalar(tweak)?;
+        if bool::from(child.is_identity()) {
+            return None;
+        }
+        let mut public_key = [0u8; 33];
+        public_key.copy_from_slice(child.to_affine().to_encoded_point(true).as_bytes());
+        Some(public_key)
+    }
+}
+
+/// A SLIP-10 extended private key on the curve. The chain code and private key are cleared
+/// on drop.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Slip10ExtendedPrivateKey<C: Slip10Curve> {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_index: ChildIndex,
+    chain_code: [u8; 32],
+    private_key: [u8; 32],
+    _curve: PhantomData<C>,
+}
+
+impl<C: Slip10Curve> Slip10ExtendedPrivateKey<C> {
+    /// Returns the extended private key at the path from the master key of the seed.
+    pub fn new(seed: &[u8], path: &[ChildIndex]) -> Result<Self, ExtendedPrivateKeyError> {
+        Self::new_master(seed)?.derive(path)
+    }
+
+    /// Returns the master extended private key of the seed, such as the seed of a BIP39
+    /// mnemonic.
+    pub fn new_master(seed: &[u8]) -> Result<Self, ExtendedPrivateKeyError> {
+        let mut data = Zeroizing::new(seed.to_vec());
+        loop {
+            let mut mac = HmacSha512::new_varkey(C::SEED_KEY)?;
+            mac.input(&data);
+            let (private_key, chain_code) = split(&mac.result().code());
+            if C::is_valid_private_key(&private_key) {
+                return Ok(Self {
+                    depth: 0,
+                    parent_fingerprint: [0u8; 4],
+                    child_index: ChildIndex::Normal(0),
+                    chain_code: *chain_code,
+                    private_key: *private_key,
+                    _curve: PhantomData,
+                });
+            }
+            // An invalid key is hashed again
+            data = Zeroizing::new([&private_key[..], &chain_code[..]].concat());
+        }
+    }
+
+    /// Returns the extended private key at the path from this key. Curves without normal
+    /// derivation only accept hardened indices.
+    pub fn derive(&self, path: &[ChildIndex]) -> Result<Self, ExtendedPrivateKeyError> {
+        let mut extended_private_key = self.clone();
+        for index in path.iter() {
+            if extended_private_key.depth == 255 {
+                return Err(ExtendedPrivateKeyError::MaximumChildDepthReached(
+                    extended_private_key.depth,
+                ));
+            }
+            if index.is_normal() && !C::supports_normal_derivation() {
+                return Err(DerivationPathError::ExpectedHardenedPath.into());
+            }
+
+            let public_key = extended_private_key.to_public_key();
+            let mut data = Zeroizing::new(Vec::with_capacity(37));
+            match index {
+                ChildIndex::Normal(_) => data.extend_from_slice(&public_key),
+                ChildIndex::Hardened(_) => {
+                    data.push(0u8);
+                    data.extend_from_slice(&extended_private_key.private_key);
+                }
+            }
+            let (private_key, chain_code) = loop {
+                data.extend_from_slice(&u32::from(*index).to_be_bytes());
+                let mut mac = HmacSha512::new_varkey(&extended_private_key.chain_code)?;
+                mac.input(&data);
+                let (tweak, chain_code) = split(&mac.result().code());
+                if let Some(private_key) =
+                    C::child_private_key(&extended_private_key.private_key, &tweak)
+                {
+                    break (private_key, *chain_code);
+                }
+                // An invalid child is derived again from the right half of the HMAC
+                data.clear();
+                data.push(1u8);
+                data.extend_from_slice(&*chain_code);
+            };
+
+            extended_private_key = Self {
+                depth: extended_private_key.depth + 1,
+                parent_fingerprint: fingerprint(&public_key),
+                child_index: *index,
+                chain_code,
+                private_key,
+                _curve: PhantomData,
+            };
+        }
+        Ok(extended_private_key)
+    }
+
+    pub fn to_extended_public_key(&self) -> Slip10ExtendedPublicKey<C> {
+        Slip10ExtendedPublicKey {
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_index: self.child_index,
+            chain_code: self.chain_code,
+            public_key: self.to_public_key(),
+            _curve: PhantomData,
+        }
+    }
+
+    /// Returns the 33 byte serialized public key.
+    pub fn to_public_key(&self) -> [u8; 33] {
+        C::to_public_key(&self.private_key)
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    pub fn child_index(&self) -> ChildIndex {
+        self.child_index
+    }
+
+    /// Returns the fingerprint, the first 4 bytes of the hash160 of the serialized public key.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.to_public_key())
+    }
+
+    /// Returns a reference to the chain code, borrowed rather than copied out of the zeroized key.
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    /// Returns a reference to the private key, for callers that must handle the raw secret.
+    pub fn expose_private_key(&self) -> &[u8; 32] {
+        &self.private_key
+    }
+}
+
+impl<C: Slip10Curve> Drop for Slip10ExtendedPrivateKey<C> {
+    fn drop(&mut self) {
+        self.chain_code.zeroize();
+        self.private_key.zeroize();
+    }
+}
+
+impl<C: Slip10Curve> fmt::Debug for Slip10ExtendedPrivateKey<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Slip10ExtendedPrivateKey")
+            .field("curve", &C::NAME)
+            .field("depth", &self.depth)
+            .field("parent_fingerprint", &self.parent_fingerprint)
+            .field("child_index", &self.child_index)
+            .field("chain_code", &format_args!("[REDACTED]"))
+            .field("private_key", &format_args!("[REDACTED]"))
+            .finish()
+    }
+}
+
+/// A SLIP-10 extended public key on the curve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slip10ExtendedPublicKey<C: Slip10Curve> {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_index: ChildIndex,
+    chain_code: [u8; 32],
+    public_key: [u8; 33],
+    _curve: PhantomData<C>,
+}
+
+impl<C: Slip10Curve> Slip10ExtendedPublicKey<C> {
+    /// Returns the extended public key at the normal path from this key, on curves that
+    /// support normal derivation.
+    pub fn derive(&self, path: &[ChildIndex]) -> Result<Self, ExtendedPublicKeyError> {
+        if !C::supports_normal_derivation() {
+            return Err(DerivationPathError::ExpectedHardenedPath.into());
+        }
+        let mut extended_public_key = self.clone();
+        for index in path.iter() {
+            if extended_public_key.depth == 255 {
+                return Err(ExtendedPublicKeyError::MaximumChildDepthReached(
+                    extended_public_key.depth,
+                ));
+            }
+            if index.is_hardened() {
+                return Err(ExtendedPublicKeyError::HardenedChildIndex(
+                    index.to_string(),
+                ));
+            }
+
+            let mut data = extended_public_key.public_key.to_vec();
+            let (public_key, chain_code) = loop {
+                data.extend_from_slice(&u32::from(*index).to_be_bytes());
+                let mut mac = HmacSha512::new_varkey(&extended_public_key.chain_code)?;
+                mac.input(&data);
+                let (tweak, chain_code) = split(&mac.result().code());
+                if let Some(public_key) =
+                    C::child_public_key(&extended_public_key.public_key, &tweak)
+                {
+                    break (public_key, *chain_code);
+                }
+                // An invalid child is derived again from the right half of the HMAC
+                data = [&[1u8][..], &chain_code[..]].concat();
+            };
+
+            extended_public_key = Self {
+                depth: extended_public_key.depth + 1,
+                parent_fingerprint: fingerprint(&extended_public_key.public_key),
+                child_index: *index,
+                chain_code,
+                public_key,
+                _curve: PhantomData,
+            };
+        }
+        Ok(extended_public_key)
+    }
+
+    /// Returns the 33 byte serialized public key.
+    pub fn to_public_key(&self) -> [u8; 33] {
+        self.public_key
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    pub fn child_index(&self) -> ChildIndex {
+        self.child_index
+    }
+
+    pub fn chain_code(&self) -> [u8; 32] {
+        self.chain_code
+    }
+
+    /// Returns the fingerprint, the first 4 bytes of the hash160 of the serialized public key.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.public_key)
+    }
+}
+
+/// Returns the left and right halves of the HMAC.
+fn split(hmac: &[u8]) -> (Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>) {
+    let (mut left, mut right) = (Zeroizing::new([0u8; 32]), Zeroizing::new([0u8; 32]));
+    left.copy_from_slice(&hmac[..32]);
+    right.copy_from_slice(&hmac[32..64]);
+    (left, right)
+}
+
+/// Returns the first 4 bytes of the hash160 of the serialized public key.
+fn fingerprint(public_key: &[u8; 33]) -> [u8; 4] {
+    let mut fingerprint = [0u8; 4];
+    fingerprint.copy_from_slice(&hash160(public_key)[0..4]);
+    fingerprint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The seed of the first SLIP-10 test vector
+    const SEED: &str = "000102030405060708090a0b0c0d0e0f";
+
+    fn seed() -> Vec<u8> {
+        hex::decode(SEED).unwrap()
+    }
+
+    /// Checks the chain code, private key and public key of the key at the path.
+    fn check<C: Slip10Curve>(
+        path: &[ChildIndex],
+        chain_code: &str,
+        private_key: &str,
+        public_key: &str,
+    ) {
+        let key = Slip10ExtendedPrivateKey::<C>::new(&seed(), path).unwrap();
+        assert_eq!(path.len(), key.depth() as usize);
+        assert_eq!(chain_code, hex::encode(key.chain_code()));
+        assert_eq!(private_key, hex::encode(key.expose_private_key()));
+        assert_eq!(public_key, hex::encode(&key.to_public_key()[..]));
+    }
+
+    #[test]
+    fn ed25519_vector_1() {
+        check::<Ed25519>(
+            &[],
+            "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb",
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+            "00a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed",
+        );
+
+        let master = Slip10ExtendedPrivateKey::<Ed25519>::new_master(&seed()).unwrap();
+        let child = master.derive(&[ChildIndex::Hardened(0)]).unwrap();
+        assert_eq!("ddebc675", hex::encode(child.parent_fingerprint()));
+        assert_eq!(
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+            hex::encode(child.expose_private_key())
+        );
+        assert_eq!(
+            "008c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c",
+            hex::encode(&child.to_public_key()[..])
+        );
+
+        let path = [
+            ChildIndex::Hardened(0),
+            ChildIndex::Hardened(1),
+            ChildIndex::Hardened(2),
+            ChildIndex::Hardened(2),
+            ChildIndex::Hardened(1000000000),
+        ];
+        let key = Slip10ExtendedPrivateKey::<Ed25519>::new(&seed(), &path).unwrap();
+        assert_eq!(
+            "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+            hex::encode(key.expose_private_key())
+        );
+        assert_eq!(
+            "003c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a",
+            hex::encode(&key.to_public_key()[..])
+        );
+    }
+
+    #[test]
+    fn ed25519_hardened_only() {
+        let master = Slip10ExtendedPrivateKey::<Ed25519>::new_master(&seed()).unwrap();
+        assert!(master
+            .derive(&[ChildIndex::Hardened(0), ChildIndex::Normal(1)])
+            .is_err());
+        // Ed25519 has no public derivation.
+        assert!(master.to_extended_public_key().derive(&[]).is_err());
+    }
+
+    #[test]
+    fn nist256p1_vector_1() {
+        check::<NistP256>(
+            &[],
+            "beeb672fe4621673f722f38529c07392fecaa61015c80c34f29ce8b41b3cb6ea",
+            "612091aaa12e22dd2abef664f8a01a82cae99ad7441b7ef8110424915c268bc2",
+            "0266874dc6ade47b3ecd096745ca09bcd29638dd52c2c12117b11ed3e458cfa9e8",
+        );
+
+        let child =
+            Slip10ExtendedPrivateKey::<NistP256>::new(&seed(), &[ChildIndex::Hardened(0)]).unwrap();
+        assert_eq!("be6105b5", hex::encode(child.parent_fingerprint()));
+        assert_eq!(
+            "6939694369114c67917a182c59ddb8cafc3004e63ca5d3b84403ba8613debc0c",
+            hex::encode(child.expose_private_key())
+        );
+        assert_eq!(
+            "0384610f5ecffe8fda089363a41f56a5c7ffc1d81b59a612d0d649b2d22355590c",
+            hex::encode(&child.to_public_key()[..])
+        );
+
+        let grandchild = child.derive(&[ChildIndex::Normal(1)]).unwrap();
+        assert_eq!(
+            "284e9d38d07d21e4e281b645089a94f4cf5a5a81369acf151a1c3a57f18b2129",
+            hex::encode(grandchild.expose_private_key())
+        );
+        assert_eq!(
+            "03526c63f8d0b4bbbf9c80df553fe66742df4676b241dabefdef67733e070f6844",
+            hex::encode(&grandchild.to_public_key()[..])
+        );
+        assert_eq!(
+            grandchild.to_extended_public_key(),
+            child
+                .to_extended_public_key()
+                .derive(&[ChildIndex::Normal(1)])
+                .unwrap()
+        );
+
+        let path = [
+            ChildIndex::Hardened(0),
+            ChildIndex::Normal(1),
+            ChildIndex::Hardened(2),
+            ChildIndex::Normal(2),
+            ChildIndex::Normal(1000000000),
+        ];
+        let key = Slip10ExtendedPrivateKey::<NistP256>::new(&seed(), &path).unwrap();
+        assert_eq!(
+            "21c4f269ef0a5fd1badf47eeacebeeaa3de22eb8e5b0adcd0f27dd99d34d0119",
+            hex::encode(key.expose_private_key())
+        );
+        match key
+            .to_extended_public_key()
+            .derive(&[ChildIndex::Hardened(1)])
+        {
+            Err(ExtendedPublicKeyError::HardenedChildIndex(index)) => assert_eq!("1'", index),
+            result => panic!("expected a hardened child index error, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn nist256p1_retry_derivation() {
+        // The SLIP-10 vector where the child key of m/28578' is invalid and derivation
+        // retries
+        let key =
+            Slip10ExtendedPrivateKey::<NistP256>::new(&seed(), &[ChildIndex::Hardened(28578)])
+                .unwrap();
+        assert_eq!(
+            "e94c8ebe30c2250a14713212f6449b20f3329105ea15b652ca5bdfc68f6c65c2",
+            hex::encode(key.chain_code())
+        );
+        assert_eq!(
+            "06f0db126f023755d0b8d86d4591718a5210dd8d024e3e14b6159d63f53aa669",
+            hex::encode(key.expose_private_key())
+        );
+
+        let child = key.derive(&[ChildIndex::Normal(33941)]).unwrap();
+        assert_eq!(
+            "9e87fe95031f14736774cd82f25fd885065cb7c358c1edf813c72af535e83071",
+            hex::encode(child.chain_code())
+        );
+        assert_eq!(
+            "092154eed4af83e078ff9b84322015aefe5769e31270f62c3f66c33888335f3a",
+            hex::encode(child.expose_private_key())
+        );
+        assert_eq!(
+            child.to_extended_public_key(),
+            key.to_extended_public_key()
+                .derive(&[ChildIndex::Normal(33941)])
+                .unwrap()
+        );
+
+        // The SLIP-10 vector where the master key is invalid and derivation retries
+        let seed = hex::decode("a7305bc8df8d0951f0cb224c0e95d7707cbdf2c6ce7e8d481fec69c7ff5e9446")
+            .unwrap();
+        let master = Slip10ExtendedPrivateKey::<NistP256>::new_master(&seed).unwrap();
+        assert_eq!(
+            "3b8c18469a4634517d6d0b65448f8e6c62091b45540a1743c5846be55d47d88f",
+            hex::encode(master.expose_private_key())
+        );
+    }
+
+    #[test]
+    fn redacted_debug() {
+        let key = Slip10ExtendedPrivateKey::<Ed25519>::new_master(&seed()).unwrap();
+        let debug = format!("{:?}", key);
+        assert!(debug.contains("[REDACTED]"));
+        assert!(!debug.contains(&hex::encode(key.expose_private_key())));
+        assert!(!debug.contains(&hex::encode(key.chain_code())));
+    }
+}
@@ -10,6 +10,7 @@ use crate::{
     format::Format,
     private_key::{PrivateKey, PrivateKeyError},
     public_key::PublicKey,
+    signer::SignerError,
 };
 
 pub trait TransactionId:
@@ -133,6 +134,9 @@ pub enum TransactionError {
     #[fail(display = "{}", _0)]
     PrivateKeyError(PrivateKeyError),
 
+    #[fail(display = "{}", _0)]
+    SignerError(SignerError),
+
     #[fail(display = "Joinsplits are not supported")]
     UnsupportedJoinsplits,
 
@@ -181,6 +185,12 @@ impl From<PrivateKeyError> for TransactionError {
     }
 }
 
+impl From<SignerError> for TransactionError {
+    fn from(error: SignerError) -> Self {
+        TransactionError::SignerError(error)
+    }
+}
+
 impl From<base58::FromBase58Error> for TransactionError {
     fn from(error: base58::FromBase58Error) -> Self {
         TransactionError::Crate("base58", format!("{:?}", error))
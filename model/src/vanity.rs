@@ -0,0 +1,437 @@
+use crate::address::{Address, AddressError};
+use crate::private_key::{PrivateKey, PrivateKeyError};
+use crate::public_key::PublicKeyError;
+
+use crate::no_std::*;
+use core::fmt::Display;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use rand::{Rng, SeedableRng};
+use regex::{Regex, RegexBuilder};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A pattern that vanity addresses are searched for
+#[derive(Debug, Clone)]
+pub enum VanityPattern {
+    Prefix(String, bool),
+    Suffix(String, bool),
+    Regex(Regex),
+}
+
+impl VanityPattern {
+    /// Returns a pattern matching addresses that start with the given string.
+    pub fn prefix(pattern: &str, case_insensitive: bool) -> Self {
+        VanityPattern::Prefix(pattern.into(), case_insensitive)
+    }
+
+    /// Returns a pattern matching addresses that end with the given string.
+    pub fn suffix(pattern: &str, case_insensitive: bool) -> Self {
+        VanityPattern::Suffix(pattern.into(), case_insensitive)
+    }
+
+    /// Returns a pattern matching addresses that contain a match of the regular expression.
+    pub fn regex(pattern: &str, case_insensitive: bool) -> Result<Self, VanityError> {
+        Ok(VanityPattern::Regex(
+            RegexBuilder::new(pattern)
+                .case_insensitive(case_insensitive)
+                .build()?,
+        ))
+    }
+
+    /// Returns true if the address matches the pattern.
+    pub fn matches(&self, address: &str) -> bool {
+        match self {
+            VanityPattern::Prefix(pattern, case_insensitive) => address
+                .get(..pattern.len())
+                .is_some_and(|start| equals(start, pattern, *case_insensitive)),
+            VanityPattern::Suffix(pattern, case_insensitive) => address
+                .len()
+                .checked_sub(pattern.len())
+                .and_then(|index| address.get(index..))
+                .is_some_and(|end| equals(end, pattern, *case_insensitive)),
+            VanityPattern::Regex(regex) => regex.is_match(address),
+        }
+    }
+
+    /// Returns the expected number of attempts to find a match among addresses whose
+    /// characters are drawn uniformly from the alphabet after a fixed prefix, or None for
+    /// regular expressions, whose difficulty is not estimated.
+    pub fn difficulty(
+        &self,
+        alphabet: &str,
+        fixed_prefix: &str,
+    ) -> Result<Option<f64>, VanityError> {
+        let (pattern, case_insensitive, fixed_prefix) = match self {
+            VanityPattern::Prefix(pattern, case_insensitive) => {
+                (pattern, *case_insensitive, fixed_prefix)
+            }
+            VanityPattern::Suffix(pattern, case_insensitive) => (pattern, *case_insensitive, ""),
+            VanityPattern::Regex(_) => return Ok(None),
+        };
+        let impossible = || VanityError::ImpossiblePattern(pattern.clone());
+
+        let fixed = pattern.len().min(fixed_prefix.len());
+        match (pattern.get(..fixed), fixed_prefix.get(..fixed)) {
+            (Some(start), Some(fixed_start)) if equals(start, fixed_start, case_insensitive) => (),
+            _ => return Err(impossible()),
+        };
+
+        let mut difficulty = 1.0;
+        for character in pattern[fixed..].chars() {
+            let matching = alphabet
+                .chars()
+                .filter(|candidate| match case_insensitive {
+                    true => candidate.eq_ignore_ascii_case(&character),
+                    false => *candidate == character,
+                })
+                .count();
+            if matching == 0 {
+                return Err(impossible());
+            }
+            difficulty *= alphabet.chars().count() as f64 / matching as f64;
+        }
+        Ok(Some(difficulty))
+    }
+}
+
+/// The options of a vanity address search
+#[derive(Clone)]
+pub struct VanityOptions<'a> {
+    /// The number of threads to search on, or 0 to use all cores.
+    pub threads: usize,
+    /// The number of attempts after which the search gives up, if any.
+    pub max_attempts: Option<u64>,
+    /// The expected number of attempts, used to estimate progress.
+    pub difficulty: Option<f64>,
+    /// The interval at which progress is reported.
+    pub progress_interval: Duration,
+    /// The callback that progress is reported to.
+    pub progress: Option<&'a (dyn Fn(&VanityProgress) + Sync)>,
+}
+
+impl<'a> Default for VanityOptions<'a> {
+    fn default() -> Self {
+        Self {
+            threads: 0,
+            max_attempts: None,
+            difficulty: None,
+            progress_interval: Duration::from_secs(1),
+            progress: None,
+        }
+    }
+}
+
+/// The progress of a vanity address search
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VanityProgress {
+    pub attempts: u64,
+    pub elapsed: Duration,
+    /// The number of attempts per second.
+    pub rate: f64,
+    pub difficulty: Option<f64>,
+    /// The probability that a match would have been found by now.
+    pub probability: Option<f64>,
+    /// The expected time until a match is found with 50% probability, from the start.
+    pub expected_time: Option<Duration>,
+}
+
+impl VanityProgress {
+    fn new(attempts: u64, elapsed: Duration, difficulty: Option<f64>) -> Self {
+        let rate = match elapsed.as_secs_f64() {
+            seconds if seconds > 0.0 => attempts as f64 / seconds,
+            _ => 0.0,
+        };
+        let probability =
+            difficulty.map(|difficulty| 1.0 - (1.0 - 1.0 / difficulty).powf(attempts as f64));
+        let expected_time = difficulty
+            .filter(|_| rate > 0.0)
+            .map(|difficulty| (difficulty * core::f64::consts::LN_2 / rate).min(1e12))
+            .map(Duration::from_secs_f64);
+        Self {
+            attempts,
+            elapsed,
+            rate,
+            difficulty,
+            probability,
+            expected_time,
+        }
+    }
+}
+
+/// A key found by a vanity address search, with its address
+#[derive(Debug, Clone)]
+pub struct VanityMatch<K, A> {
+    pub key: K,
+    pub address: A,
+    pub attempts: u64,
+    pub elapsed: Duration,
+}
+
+/// Searches for a private key whose address in the given format matches the pattern,
+/// on all cores unless the options say otherwise.
+pub fn generate<A: Address, R: Rng + SeedableRng + Send>(
+    rng: &mut R,
+    format: &A::Format,
+    pattern: &VanityPattern,
+    options: &VanityOptions,
+) -> Result<VanityMatch<A::PrivateKey, A>, VanityError> {
+    search(rng, pattern, options, |rng| {
+        let private_key = A::PrivateKey::new(rng)?;
+        let address = A::from_private_key(&private_key, format)?;
+        Ok((private_key, address))
+    })
+}
+
+/// Searches for a candidate whose address matches the pattern, running the candidate
+/// generator on each thread with its own generator seeded from the given one.
+pub fn search<K, A, R, F>(
+    rng: &mut R,
+    pattern: &VanityPattern,
+    options: &VanityOptions,
+    candidate: F,
+) -> Result<VanityMatch<K, A>, VanityError>
+where
+    K: Send,
+    A: Display + Send,
+    R: Rng + SeedableRng + Send,
+    F: Fn(&mut R) -> Result<(K, A), VanityError> + Sync,
+{
+    let threads = match options.threads {
+        0 => thread::available_parallelism().map_or(1, |threads| threads.get()),
+        threads => threads,
+    };
+    let rngs = (0..threads)
+        .map(|_| R::from_rng(&mut *rng))
+        .collect::<Result<Vec<R>, _>>()
+        .map_err(|error| VanityError::Crate("rand", format!("{:?}", error)))?;
+
+    let attempts = AtomicU64::new(0);
+    let running = AtomicUsize::new(threads);
+    let found = AtomicBool::new(false);
+    let result = Mutex::new(None);
+    let start = Instant::now();
+
+    thread::scope(|scope| {
+        for mut rng in rngs {
+            let (attempts, running, found, result, candidate) =
+                (&attempts, &running, &found, &result, &candidate);
+            scope.spawn(move || {
+                while !found.load(Ordering::Relaxed) {
+                    let attempt = attempts.fetch_add(1, Ordering::Relaxed);
+                    if options.max_attempts.is_some_and(|max| attempt >= max) {
+                        break;
+                    }
+                    let outcome = candidate(&mut rng);
+                    let done = match &outcome {
+                        Ok((_, address)) => pattern.matches(&address.to_string()),
+                        Err(_) => true,
+                    };
+                    if done && !found.swap(true, Ordering::SeqCst) {
+                        *result.lock().unwrap() = Some(outcome);
+                    }
+                }
+                running.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+
+        if let Some(progress) = options.progress {
+            let mut reported = Instant::now();
+            while !found.load(Ordering::Relaxed) && running.load(Ordering::SeqCst) > 0 {
+                thread::sleep(options.progress_interval.min(Duration::from_millis(10)));
+                if reported.elapsed() >= options.progress_interval {
+                    reported = Instant::now();
+                    progress(&VanityProgress::new(
+                        attempts.load(Ordering::Relaxed),
+                        start.elapsed(),
+                        options.difficulty,
+                    ));
+                }
+            }
+        }
+    });
+
+    let attempts = attempts.into_inner();
+    match result.into_inner().unwrap() {
+        Some(Ok((key, address))) => Ok(VanityMatch {
+            key,
+            address,
+            attempts: options
+                .max_attempts
+                .map_or(attempts, |max| attempts.min(max)),
+            elapsed: start.elapsed(),
+        }),
+        Some(Err(error)) => Err(error),
+        None => Err(VanityError::Exhausted(
+            options.max_attempts.unwrap_or(attempts),
+        )),
+    }
+}
+
+/// Returns true if the strings are equal, ignoring ASCII case if requested.
+fn equals(a: &str, b: &str, case_insensitive: bool) -> bool {
+    match case_insensitive {
+        true => a.eq_ignore_ascii_case(b),
+        false => a == b,
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum VanityError {
+    #[fail(display = "{}", _0)]
+    AddressError(AddressError),
+
+    #[fail(display = "{}: {}", _0, _1)]
+    Crate(&'static str, String),
+
+    #[fail(display = "no match found in {} attempts", _0)]
+    Exhausted(u64),
+
+    #[fail(display = "no address can match the pattern: {}", _0)]
+    ImpossiblePattern(String),
+
+    #[fail(display = "invalid pattern: {}", _0)]
+    InvalidPattern(String),
+
+    #[fail(display = "{}", _0)]
+    PrivateKeyError(PrivateKeyError),
+
+    #[fail(display = "{}", _0)]
+    PublicKeyError(PublicKeyError),
+}
+
+impl From<AddressError> for VanityError {
+    fn from(error: AddressError) -> Self {
+        VanityError::AddressError(error)
+    }
+}
+
+impl From<PrivateKeyError> for VanityError {
+    fn from(error: PrivateKeyError) -> Self {
+        VanityError::PrivateKeyError(error)
+    }
+}
+
+impl From<PublicKeyError> for VanityError {
+    fn from(error: PublicKeyError) -> Self {
+        VanityError::PublicKeyError(error)
+    }
+}
+
+impl From<regex::Error> for VanityError {
+    fn from(error: regex::Error) -> Self {
+        VanityError::InvalidPattern(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn matches() {
+        let address = "1BoatSLRHtKNngkdXEeobR76b53LETtpyT";
+        assert!(VanityPattern::prefix("1Boat", false).matches(address));
+        assert!(!VanityPattern::prefix("1boat", false).matches(address));
+        assert!(VanityPattern::prefix("1boat", true).matches(address));
+        assert!(VanityPattern::suffix("tpyT", false).matches(address));
+        assert!(VanityPattern::suffix("TPYT", true).matches(address));
+        assert!(
+            !VanityPattern::suffix("1BoatSLRHtKNngkdXEeobR76b53LETtpyTx", true).matches(address)
+        );
+        assert!(VanityPattern::regex("^1b.*t$", true)
+            .unwrap()
+            .matches(address));
+        assert!(!VanityPattern::regex("^1b.*t$", false)
+            .unwrap()
+            .matches(address));
+        assert!(VanityPattern::regex("(", false).is_err());
+
+        // Patterns never match inside a multibyte character.
+        assert!(!VanityPattern::prefix("a", false).matches("\u{e9}a"));
+        assert!(!VanityPattern::suffix("a", false).matches("a\u{e9}"));
+    }
+
+    #[test]
+    fn difficulty() {
+        let alphabet = "0123456789abcdef";
+        let prefix = VanityPattern::prefix("0xbee", false);
+        assert_eq!(Some(4096.0), prefix.difficulty(alphabet, "0x").unwrap());
+        let suffix = VanityPattern::suffix("BEE", true);
+        assert_eq!(Some(4096.0), suffix.difficulty(alphabet, "0x").unwrap());
+        let regex = VanityPattern::regex("bee", false).unwrap();
+        assert_eq!(None, regex.difficulty(alphabet, "0x").unwrap());
+
+        assert!(VanityPattern::prefix("1x", false)
+            .difficulty(alphabet, "0x")
+            .is_err());
+        assert!(VanityPattern::prefix("0xg", false)
+            .difficulty(alphabet, "0x")
+            .is_err());
+        assert!(VanityPattern::suffix("B", false)
+            .difficulty(alphabet, "")
+            .is_err());
+    }
+
+    #[test]
+    fn progress() {
+        let progress = VanityProgress::new(1000, Duration::from_secs(2), Some(1000.0));
+        assert_eq!(500.0, progress.rate);
+        assert!((progress.probability.unwrap() - 0.632).abs() < 0.001);
+        assert_eq!(
+            Duration::from_secs_f64(1000.0 * core::f64::consts::LN_2 / 500.0),
+            progress.expected_time.unwrap()
+        );
+
+        let progress = VanityProgress::new(0, Duration::from_secs(0), None);
+        assert_eq!(0.0, progress.rate);
+        assert_eq!(None, progress.probability);
+        assert_eq!(None, progress.expected_time);
+    }
+
+    #[test]
+    fn search() {
+        let mut rng = StdRng::seed_from_u64(39);
+        let options = VanityOptions {
+            threads: 4,
+            ..Default::default()
+        };
+        let found = super::search(
+            &mut rng,
+            &VanityPattern::suffix("7", false),
+            &options,
+            |rng| {
+                let value: u64 = rng.gen();
+                Ok((value, value % 10))
+            },
+        )
+        .unwrap();
+        assert_eq!(7, found.key % 10);
+        assert_eq!(7, found.address);
+        assert!(found.attempts >= 1);
+
+        let options = VanityOptions {
+            threads: 2,
+            max_attempts: Some(100),
+            ..Default::default()
+        };
+        match super::search(
+            &mut rng,
+            &VanityPattern::prefix("x", false),
+            &options,
+            |_| Ok(((), 0)),
+        ) {
+            Err(VanityError::Exhausted(100)) => (),
+            result => panic!("unexpected result {:?}", result.map(|found| found.attempts)),
+        }
+
+        let error = super::search::<(), u8, _, _>(
+            &mut rng,
+            &VanityPattern::prefix("x", false),
+            &options,
+            |_| Err(VanityError::InvalidPattern("x".into())),
+        );
+        assert!(error.is_err());
+    }
+}